@@ -0,0 +1,30 @@
+//! Benchmark of the HTML emission on documents with many sibling components.
+//! Compare with `cargo bench --no-default-features --features bench` to see the speedup of parallel emission
+
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion};
+
+/// Generates document with the given number of top-level components
+fn document(count: usize) -> String {
+    let mut code = String::new();
+    for index in 0..count {
+        code.push_str(&format!(
+            "box[horizontal, x_align = \"center\"] {{\n    header[2](Section {index})\n    paragraph(Some text of the section {index})\n    list {{\n        @(First)\n        @(Second)\n    }}\n}}\n"
+        ));
+    }
+
+    code
+}
+
+fn emission(c: &mut Criterion) {
+    let mut group = c.benchmark_group("emission");
+    for count in [100, 1_000, 10_000] {
+        let ir = markerml::generate_ir(&document(count), true).unwrap();
+        group.bench_with_input(BenchmarkId::from_parameter(count), &ir, |b, ir| {
+            b.iter(|| markerml::markerml_backend::generate_html(ir).unwrap())
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, emission);
+criterion_main!(benches);
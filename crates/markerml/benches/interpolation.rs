@@ -0,0 +1,27 @@
+//! Micro-benchmark of the string interpolation in the HTML emission
+
+use criterion::{criterion_group, criterion_main, Criterion};
+
+/// Generates document, where every text and string is interpolated from several segments
+fn document(count: usize) -> String {
+    let mut code = String::from(
+        "component entry[default name: string, title: string] {\n    box[x_align = \"center\"] {\n        header[3](${title} of ${name})\n        @(Hello, ${name}! This is the ${title}.)\n    }\n}\n",
+    );
+    for index in 0..count {
+        code.push_str(&format!(
+            "entry[\"user {index}\", title = \"entry {index}\"]\n"
+        ));
+    }
+
+    code
+}
+
+fn interpolation(c: &mut Criterion) {
+    let ir = markerml::generate_ir(&document(1_000), true).unwrap();
+    c.bench_function("interpolation", |b| {
+        b.iter(|| markerml::markerml_backend::generate_html(&ir).unwrap())
+    });
+}
+
+criterion_group!(benches, interpolation);
+criterion_main!(benches);
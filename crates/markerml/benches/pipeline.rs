@@ -0,0 +1,104 @@
+//! Benchmarks of every compilation stage on large generated documents
+
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+use markerml::{markerml_backend, markerml_frontend, markerml_middleend};
+
+/// Numbers of top-level entries in the generated documents.
+/// Every entry contains a dozen of components
+const SIZES: [usize; 2] = [1_000, 10_000];
+/// Number of top-level entries in the document of about 10 MB,
+/// which shows allocation overhead of the identifiers and cloned trees
+const LARGE_SIZE: usize = 30_000;
+
+/// Generates document with custom components, nested boxes, lists and interpolated strings
+fn document(count: usize) -> String {
+    let mut code = String::from(
+        "// Generated document\ncomponent card[default title: string, text body] {\n    box[vertical] {\n        header[3](${title})\n        paragraph(${body})\n    }\n}\n",
+    );
+    for index in 0..count {
+        code.push_str(&format!(
+            r#"box[horizontal, x_align = "center", y_align = "start"] {{
+    card["Card {index}"](Body of the card {index})
+    list[ordered] {{
+        @(First item)
+        #["https://example.com/{index}"](Link {index})
+        image["image_{index}.png"]
+    }}
+    box {{
+        paragraph(Paragraph {index})
+        header[2](Header {index})
+    }}
+}}
+"#
+        ));
+    }
+
+    code
+}
+
+fn stages(c: &mut Criterion) {
+    for count in SIZES {
+        let code = document(count);
+        let mut group = c.benchmark_group(format!("pipeline/{count}"));
+        group.sample_size(10);
+        group.throughput(Throughput::Bytes(code.len() as u64));
+
+        group.bench_function("lex", |b| {
+            b.iter(|| markerml_frontend::tokens::tokens(&code).unwrap().count())
+        });
+        group.bench_function("parse", |b| {
+            b.iter(|| markerml_frontend::parse(&code).unwrap())
+        });
+
+        let ast = markerml_frontend::parse(&code).unwrap();
+        group.bench_function("ir", |b| {
+            b.iter(|| markerml_middleend::generate_ir(ast.clone()).unwrap())
+        });
+
+        let ir = markerml_middleend::generate_ir(ast).unwrap();
+        group.bench_function("expansion", |b| {
+            b.iter(|| markerml_middleend::expand_components(ir.clone()).unwrap())
+        });
+
+        let ir = markerml_middleend::expand_components(ir).unwrap();
+        group.bench_function("emission", |b| {
+            b.iter(|| markerml_backend::generate_html(&ir).unwrap())
+        });
+
+        group.finish();
+    }
+}
+
+fn end_to_end(c: &mut Criterion) {
+    let mut group = c.benchmark_group("end_to_end");
+    group.sample_size(10);
+    for count in SIZES {
+        let code = document(count);
+        group.throughput(Throughput::Bytes(code.len() as u64));
+        group.bench_with_input(BenchmarkId::new("stages", count), &code, |b, code| {
+            b.iter(|| markerml::parse(code).unwrap())
+        });
+        let options = markerml::Options {
+            single_pass: true,
+            ..markerml::Options::default()
+        };
+        group.bench_with_input(BenchmarkId::new("single_pass", count), &code, |b, code| {
+            b.iter(|| markerml::parse_with_options(code, &options).unwrap())
+        });
+    }
+    group.finish();
+}
+
+fn large_document(c: &mut Criterion) {
+    let code = document(LARGE_SIZE);
+    let mut group = c.benchmark_group("large_document");
+    group.sample_size(10);
+    group.throughput(Throughput::Bytes(code.len() as u64));
+    group.bench_function("parse_and_emit", |b| {
+        b.iter(|| markerml::parse(&code).unwrap())
+    });
+    group.finish();
+}
+
+criterion_group!(benches, stages, end_to_end, large_document);
+criterion_main!(benches);
@@ -0,0 +1,239 @@
+use crate::{
+    lints, ComponentLibrary, CssMode, Diagnostics, MarkermlError, MarkermlWarning, Options,
+};
+use markerml_backend::html_generator::HtmlGenerator;
+use markerml_backend::{manifest, BackendWarning};
+use markerml_frontend::ast;
+use markerml_frontend::span::{Position, Span};
+use markerml_middleend::analysis;
+use markerml_middleend::component_expander::ComponentExpander;
+use markerml_middleend::ir_generator::IrGenerator;
+use markerml_middleend::typecheck::TypeChecker;
+use std::collections::HashMap;
+use std::hash::{DefaultHasher, Hash, Hasher};
+use std::ops::Range;
+
+/// Converter of the repeatedly changing document, such as the one that is being edited.
+///
+/// Markup of every top-level component is cached by its code,
+/// so only the changed components go through IR generation, expansion and emission.
+/// Cached markup is reused while constants, component definitions and libraries stay the same,
+/// and components before it have the same header ids.
+/// Styles collected with [`CssMode::Stylesheet`] aren't cached, so the whole document is converted
+pub struct IncrementalConverter {
+    options: Options,
+    /// Markup of the top-level components by the hash of their code and context
+    cache: HashMap<u64, CachedComponent>,
+}
+
+/// Markup of the top-level component along with ids and warnings, generated for it
+struct CachedComponent {
+    html: String,
+    ids: Vec<String>,
+    /// Explicit ids of the anchors, checked for duplicates along with the rest of the document
+    anchors: Vec<(String, Span)>,
+    warnings: Vec<MarkermlWarning>,
+    /// Position of the component in the code, when the warnings were generated
+    position: Range<usize>,
+}
+
+impl IncrementalConverter {
+    /// Creates new instance with the given options
+    pub fn new(options: Options) -> Self {
+        IncrementalConverter {
+            options,
+            cache: HashMap::new(),
+        }
+    }
+
+    /// Replaces component libraries, available in the document.
+    /// Cached markup is reused only if the libraries are the same
+    pub fn set_components(&mut self, components: Vec<ComponentLibrary>) {
+        self.options.components = components;
+    }
+
+    /// Converts given code into HTML, reusing markup of the components
+    /// that haven't changed since the previous conversion
+    pub fn convert(&mut self, code: &str) -> Result<String, MarkermlError> {
+        Ok(self.convert_with_diagnostics(code)?.html)
+    }
+
+    /// Converts given code into HTML same as [`Self::convert`], collecting warnings.
+    /// Warnings of the unchanged components are reused along with their markup
+    pub fn convert_with_diagnostics(&mut self, code: &str) -> Result<Diagnostics, MarkermlError> {
+        if self.options.html.css == CssMode::Stylesheet {
+            return crate::parse_with_diagnostics(code, &self.options);
+        }
+
+        let options = &self.options;
+        let ast =
+            markerml_frontend::parse(code).map_err(|err| MarkermlError::from_parser(&err, code))?;
+        let mut context = DefaultHasher::new();
+
+        let mut ir_generator = IrGenerator::default();
+        let mut constants = Vec::new();
+        let mut definitions = Vec::new();
+        let mut components = Vec::new();
+        for library in &options.components {
+            // Libraries are identified by their hash, since spans point into their code
+            library.hash.hash(&mut context);
+        }
+        for def in crate::library_definitions(options, &ast) {
+            definitions.push(ir_generator.generate_component_definition(def.clone())?);
+        }
+        for item in ast.items {
+            match item {
+                ast::ModuleItem::Constant(constant) => {
+                    hash_item(code, &constant.span, options.source_spans, &mut context);
+                    constants.push(ir_generator.generate_constant(constant)?);
+                }
+                ast::ModuleItem::Component(component) => components.push(component),
+                ast::ModuleItem::ComponentDefinition(def) => {
+                    hash_item(code, &def.span, options.source_spans, &mut context);
+                    let def = ir_generator.generate_component_definition(def)?;
+                    markerml_backend::check_definition(&def)?;
+                    definitions.push(def);
+                }
+            }
+        }
+        let context = context.finish();
+
+        let checker = TypeChecker::new(&definitions);
+        for def in &definitions {
+            checker.check_definition(def)?;
+        }
+        let used = lints::used_components(&components);
+        let unused =
+            analysis::unused_definitions(&definitions, used.iter().map(String::as_str).collect());
+        let constant_names = constants
+            .iter()
+            .map(|constant| constant.name.as_str())
+            .collect();
+        let ambiguous = analysis::ambiguous_flags(&definitions, &constant_names);
+        let mut warnings = lints::local_warnings(options, unused)
+            .chain(lints::local_warnings(options, ambiguous))
+            .collect::<Vec<_>>();
+        let variables = options
+            .vars
+            .iter()
+            .map(|(name, value)| (name.clone(), value.into()))
+            .collect();
+        let mut expander =
+            ComponentExpander::with_definitions(definitions.iter().cloned(), variables);
+        for constant in constants {
+            expander.define_constant(constant)?;
+        }
+        if let Some(messages) = &options.messages {
+            expander = expander.with_messages(messages.clone());
+        }
+        let mut generator = HtmlGenerator::streaming(&options.html);
+        if options.source_spans {
+            generator = generator.with_source_spans(code);
+        }
+
+        let mut cache = HashMap::with_capacity(components.len());
+        let mut ids = DefaultHasher::new();
+        let mut anchors = Vec::new();
+        let mut content = String::new();
+        for component in components {
+            let mut key = DefaultHasher::new();
+            context.hash(&mut key);
+            ids.finish().hash(&mut key);
+            hash_item(code, &component.span, options.source_spans, &mut key);
+            let key = key.finish();
+
+            let position = component.span.start.offset()..component.span.end.offset();
+            // Page head is stored in the generator, so head components are applied every time
+            let is_head = manifest::HEAD_COMPONENTS.contains(&component.name.as_str());
+            let cached = match self.cache.remove(&key).filter(|_| !is_head) {
+                Some(mut cached) => {
+                    generator.reserve_ids(&cached.ids);
+                    for warning in &mut cached.warnings {
+                        move_warning(warning, &cached.position, position.start);
+                    }
+                    for (_, span) in &mut cached.anchors {
+                        move_span(span, &cached.position, position.start);
+                    }
+                    cached.position = position;
+                    cached
+                }
+                None => {
+                    let component = ir_generator.generate_component(component)?;
+                    checker.check_component(&component)?;
+                    let mut html = String::new();
+                    let mut anchors = Vec::new();
+                    let mut warnings = Vec::new();
+                    for component in expander.expand_top_level(component)? {
+                        lints::lint_component(options, &component, &mut anchors, &mut warnings);
+                        generator.write_component(&component, &mut html)?;
+                    }
+
+                    CachedComponent {
+                        html,
+                        ids: generator.take_generated_ids(),
+                        anchors,
+                        warnings: warnings.into_iter().map(MarkermlWarning::from).collect(),
+                        position,
+                    }
+                }
+            };
+            cached.ids.hash(&mut ids);
+            content.push_str(&cached.html);
+            warnings.extend(cached.warnings.iter().cloned());
+            anchors.extend(cached.anchors.iter().cloned());
+            cache.insert(key, cached);
+        }
+        // Only components of the latest version are kept
+        self.cache = cache;
+        let mut duplicates = Vec::new();
+        lints::lint_duplicate_ids(&anchors, &mut duplicates);
+        warnings.extend(duplicates.into_iter().map(MarkermlWarning::from));
+        lints::sort_warnings(&mut warnings);
+        let warnings = lints::apply_levels(code, options, warnings)?;
+
+        if options.fragment {
+            return Ok(Diagnostics {
+                html: content,
+                warnings,
+            });
+        }
+        let mut html = String::with_capacity(content.len());
+        generator.write_page_start(&mut html);
+        generator.write_main(&content, &mut html);
+        HtmlGenerator::write_page_end(&mut html);
+
+        Ok(Diagnostics { html, warnings })
+    }
+}
+
+/// Hashes code of the item, along with its position if it's emitted
+fn hash_item(code: &str, span: &Span, has_position: bool, hasher: &mut impl Hasher) {
+    let start = span.start.offset();
+    code.get(start..span.end.offset()).hash(hasher);
+    if has_position {
+        start.hash(hasher);
+    }
+}
+
+/// Moves span of the cached warning along with its component, which could move in the code.
+/// Spans outside of the component, such as the ones in definitions, are kept
+fn move_warning(warning: &mut MarkermlWarning, from: &Range<usize>, to: usize) {
+    let span = match warning {
+        // Warnings of the IR analysis aren't cached
+        MarkermlWarning::Middleend(_) => return,
+        MarkermlWarning::Backend(BackendWarning::UnknownProperty(warning)) => &mut warning.span,
+        MarkermlWarning::Backend(BackendWarning::InvalidLink(warning)) => &mut warning.span,
+        MarkermlWarning::Backend(BackendWarning::MissingLinkTarget(warning)) => &mut warning.span,
+        // Duplicate ids are checked for the whole document, so they aren't cached
+        MarkermlWarning::Backend(BackendWarning::DuplicateId(_)) => return,
+    };
+    move_span(span, from, to);
+}
+
+/// Moves the span along with its component, if it's inside of the component
+fn move_span(span: &mut Span, from: &Range<usize>, to: usize) {
+    if from.contains(&span.start.offset()) && span.end.offset() <= from.end {
+        span.start = Position::new(span.start.offset() - from.start + to);
+        span.end = Position::new(span.end.offset() - from.start + to);
+    }
+}
@@ -4,9 +4,21 @@
 //! MarkerML stands for Marker Markup Language.
 //! It's a simple language for formatting and layouting
 //! text similar to HTML.
-//!
-//! Note that custom components are not yet implemented
-//! in the backend library.
+//! [`parse_with_diagnostics`] also returns warnings, such as properties
+//! that builtin components don't understand (`unknown_property`)
+//! or component definitions that are never used (`unused_definition`).
+//! Flags of the definitions, named as a variable in scope, are reported as well (`ambiguous_flag`).
+//! Links with empty URL, malformed or `javascript:` scheme are reported too (`invalid_link`),
+//! and with [`Options::link_base`] also relative links to missing files (`missing_link_target`).
+//! Anchors with the same explicit id are reported as well (`duplicate_id`).
+//! Lints can be silenced or turned into errors with [`Options::lints`]
+//! or with `// markerml-allow(unknown_property)` comment, that applies to its line and the next one.
+//! [`Pipeline`] runs the stages one at a time: [`Pipeline::parse_ast`], [`Pipeline::lower_ir`]
+//! and [`Pipeline::emit_html`], with hooks to inspect or transform the [`ir`] between them.
+//! Hooks can apply rewrites, implemented with [`ir::Transform`], or walk the IR with [`ir::Visitor`].
+//! Documents can also be generated from data without building the code from strings:
+//! [`convert_ast`] accepts the [`ast`] constructed with [`ast::builder`],
+//! or deserialized from JSON with the `serde` feature.
 //!
 //! # Syntax
 //! Here is an overview of the syntax.
@@ -39,6 +51,48 @@
 //! }
 //! ```
 //!
+//! Newlines of the multiline text are rendered as spaces. With [`HtmlGeneratorOptions::preserve_newlines`]
+//! single newlines become line breaks, and blank lines split `paragraph` and `quote`
+//! into separate paragraphs. Newlines after the opening bracket and before the closing one are skipped.
+//! ```markerml
+//! paragraph(
+//!     Roses are red,
+//!     violets are blue
+//!
+//!     Second stanza
+//! )
+//! ```
+//!
+//! Strings and text can contain escape sequences: `\\` for a backslash,
+//! `\"`, `\(`, `\)` and `\$` for the characters that otherwise end the string
+//! or text or start interpolation, `\n` for a newline, `\t` for a tab
+//! and `\u{1F600}` for any unicode character by its hex code point.
+//! Any other character after a backslash is an error.
+//! ```markerml
+//! paragraph(f\(x\) costs \$5 \u{2014} see "C:\\docs")
+//! ```
+//!
+//! Text can also be raw: enclosed in backticks instead of parentheses.
+//! Raw text is kept verbatim, so it can contain parentheses and `${`,
+//! and multiline raw text keeps its newlines, except for the indentation
+//! common to all lines. Use more backticks to include backticks into the text.
+//! ```markerml
+//! code`let x = f(y);`
+//! code_block[language = "rust"] ```
+//!     fn main() {
+//!         println!("Hello");
+//!     }
+//!     ```
+//! ```
+//!
+//! Text can contain inline components: `$` followed by the component name,
+//! optional properties and optional text without whitespace between them.
+//! Only `@`, `#`, `code`, `break` and `anchor`,
+//! along with custom components that expand into them, can be inside the text.
+//! ```markerml
+//! paragraph(See $#["/docs"](the $@[bold](docs))$break or run $code(markerml --help))
+//! ```
+//!
 //! ## Properties
 //! More about properties. Properties contain optional
 //! default property that doesn't need to be named,
@@ -57,9 +111,26 @@
 //! // Flag and named properties can be combined
 //! box[x_align = "center", vertical] {}
 //!
+//! // Flag can be explicitly disabled with `'!'`, which is the same as setting it to `false`.
+//! // This overrides `bool` properties of the custom components, that default to `true`
+//! card[!shadow] {}
+//!
+//! // Builtin flags also accept bool values, passed on from the custom component
+//! box[horizontal = ${is_wide}] {}
+//!
+//! // Properties of builtin components prefixed with `attr:` are passed through
+//! // to the generated element as HTML attributes with escaped values.
+//! // Attribute with `true` value is emitted empty, and with `false` is skipped
+//! box[attr:data-test = "hero", attr:hidden = ${is_hidden}] {}
+//!
 //! // Example of variable interpolation
 //! box[x_align = ${align}] {}
 //! ```
+//! Bare identifier in properties is always a flag, even if there is a variable with the same name,
+//! and variables are always passed with `${...}`. So inside `component card[title: string]`,
+//! `box[title]` sets flag `title`, while `box[${title}]` passes the variable as the default property
+//! and `box[title = ${title}]` as the named one. Flags, named as a property of the definition
+//! or as a constant, are reported with `ambiguous_flag` warning.
 //!
 //! ## Identifiers
 //! Identifier must begin with ascii alphabetic character
@@ -71,10 +142,12 @@
 //!
 //! ## Types
 //! There are several types in this language:
-//! - `int` - integers like 0, 42, or -252
+//! - `int` - integers like 0, 42, or -252, also written in hex `0xff`,
+//!   binary `0b1010` or with underscores `1_000_000`
 //! - `bool` - `true` or `false`
 //! - `string` - "Text inside quotes", might also
 //!   have interpolated variables like: "Hello, ${user_name}"
+//!   or localized messages like: "${t:menu.home}"
 //!
 //! - `slot` and `slot[]` for component composition
 //!
@@ -96,14 +169,80 @@
 //! }
 //! ```
 //!
+//! Component can be defined only once in the module, and can't have the name
+//! of the builtin component, such as `box` or `repeat`.
+//! Definitions of the module take precedence over the ones from [`Options::components`].
+//!
+//! Instances of the custom components are type checked:
+//! values must match declared types, properties without default values
+//! must be specified, and unknown properties are rejected.
+//!
+//! Children of the component instance fill its `slot` or `slot[]` property.
+//! They are rendered in place of the components named after the property.
+//! `slot` accepts single child, while `slot[]` accepts any number of them.
+//! ```markerml
+//! component card[default title: string, content: slot[]] {
+//!     box[vertical] {
+//!         header[2](${title})
+//!         content
+//!     }
+//! }
+//!
+//! card["Card title"] {
+//!     paragraph(First paragraph)
+//!     paragraph(Second paragraph)
+//! }
+//! ```
+//!
 //! ## Modules
 //! Module is a top-level entity that is a sequence
-//! of components and component definitions.
+//! of constants, components and component definitions.
 //! That's what was used in previous examples.
 //!
+//! ## Constants
+//! Constant is declared at the top level of the module with the `let` keyword
+//! and is available for `${...}` interpolation in the whole module,
+//! including component definitions. Constant can refer to the constants declared before it.
+//! Constants take precedence over externally supplied variables with the same name.
+//! ```markerml
+//! let accent = "darkred"
+//! let title = "MarkerML"
+//!
+//! header[color = ${accent}](Welcome to ${title})
+//! ```
+//!
+//! ## Formatting
+//! Interpolated variables inside strings and text can have a format specifier
+//! after a colon: optional alignment `<`, `^` or `>`, `0` for zero padding, width
+//! and `.` followed by precision. Integers and strings with decimal numbers are aligned
+//! to the right and can be padded with zeros or given the number of fractional digits,
+//! which rounds the number. Other values are aligned to the left.
+//! ```markerml
+//! let price = "19.995"
+//! let count = 7
+//! paragraph(${price:.2} for ${count:03} items)
+//! ```
+//!
+//! ## Localization
+//! Strings and text might contain keys of localized messages,
+//! which are replaced with messages from the catalog of the current locale.
+//! This way the same document can be compiled into multiple languages.
+//! ```markerml
+//! header(${t:welcome})
+//! #["/"](${t:menu.home})
+//! ```
+//!
 //! ## Comments
 //! These examples make heavy use of the comments,
 //! which are lines that begin with `//` and then ignored.
+//! Block comments are enclosed in `/*` and `*/`, can span multiple lines and be nested.
+//! ```markerml
+//! box /* horizontal */ {
+//!     /* Disabled for now:
+//!     paragraph(Draft) /* TODO */
+//!     */
+//! }
+//! ```
 //!
 //! ## Whitespaces
 //! Most of the syntax elements can also be separated
@@ -118,16 +257,42 @@
 //! - `horizontal`
 //! - `x_align: string = "start" | "center" | "end"`. Default: `"start"`
 //! - `y_align: string = "start" | "center" | "end"`. Default: `"start"`
+//! - `gap`: length of the space between children
+//!
+//! Box also accepts `padding` and `margin` like other components, see [style properties](#style-properties).
+//! ```markerml
+//! box[horizontal, gap = 8, padding = "1em"] {
+//!     @(First)
+//!     @(Second)
+//! }
+//! ```
 //!
 //! ## Text
 //! Name: `@` \
 //! Properties:
+//! - `bold`, `italic`, `underline`, `strike`, `mono`
 //! - `text content`
 //!
+//! Flags wrap the text into `<strong>`, `<em>`, `<u>`, `<s>` and `<code>` respectively.
+//! Other flags are rejected.
+//! ```markerml
+//! @[bold, italic](Important)
+//! ```
+//!
 //! ## Image
 //! Name: `image` \
 //! Properties:
-//! - `default url: string`
+//! - `default src: string`, also accepted as `url`
+//! - `alt: string`
+//! - `width: integer`
+//! - `height: integer`
+//! - `fit`
+//!
+//! Width and height are given in pixels. When only one of them is set and the image size is known,
+//! the other one keeps the aspect ratio. `fit` scales the image to fit into its box.
+//! ```markerml
+//! image["logo.png", alt = "Logo", width = 200, fit]
+//! ```
 //!
 //! ## Link
 //! Name: `#` \
@@ -135,6 +300,25 @@
 //! - `default url: string`
 //! - `text name`
 //!
+//! Link starting with `#` points to the header or the anchor within the document,
+//! and gets the same prefix as their ids.
+//! ```markerml
+//! #["#getting-started"](Getting started)
+//! ```
+//!
+//! ## Anchor
+//! Name: `anchor` \
+//! Properties:
+//! - `default id: string`
+//!
+//! Target of the links within the document, emitted as `<a>` with the given id.
+//! Headers skip ids of the anchors, though when components are converted one by one,
+//! as in the [`Options::single_pass`] mode or by the [`IncrementalConverter`],
+//! only ids of the anchors before the header are known. Can't have children or text.
+//! ```markerml
+//! anchor["details"]
+//! ```
+//!
 //! ## List
 //! Name: `list` \
 //! Properties:
@@ -147,18 +331,156 @@
 //! Properties:
 //! - `default level: integer = 1`
 //!
+//! Header gets id derived from its text, so it can be linked to, like `#getting-started`.
+//! Repeated ids get numeric suffix, and all ids can be prefixed with
+//! [`HtmlGeneratorOptions::id_prefix`] to embed multiple documents into one page.
+//!
 //! ## Paragraph
 //! Name: `paragraph` \
 //! Properties:
 //! - `text content`
 //!
-//! # Grammar
+//! Paragraph with raw text is emitted as `<pre>`, so its newlines and spaces are preserved,
+//! which is useful for ASCII art.
+//!
+//! ## Code
+//! Name: `code` \
+//! Properties:
+//! - `text content`
+//!
+//! ## Code block
+//! Name: `code_block` \
+//! Properties:
+//! - `language: string`
+//! - `text content`
+//!
+//! Code block is emitted as `<pre><code class="language-...">`. Its text is usually raw,
+//! so newlines are preserved.
+//!
+//! ## Quote
+//! Name: `quote` \
+//! Properties:
+//! - `cite: string`
+//! - `text content` or `children: slot[]`
+//!
+//! Quote contains either text or child components. Source of the quotation
+//! is emitted as the `cite` attribute.
+//! ```markerml
+//! quote[cite = "https://example.com"](Simplicity is prerequisite for reliability)
+//! ```
+//!
+//!
+//! ## Divider
+//! Name: `divider` \
+//! Horizontal rule between the blocks. Can't have children or text.
+//!
+//! ## Break
+//! Name: `break` \
+//! Line break. Can't have children or text, and doesn't accept style properties.
+//!
+//! ## Page break
+//! Name: `page_break` \
+//! Starts new page when the document is printed, see [`PrintOptions`].
+//! Can't have children or text, and doesn't accept style properties.
+//!
+//! ## Page
+//! Name: `page` \
+//! Properties:
+//! - `default title: string`
+//! - `description: string`
+//! - `lang: string`
+//! - `favicon: string`
+//! - `charset: string = "utf-8"`
+//! - `theme: string = "light" | "dark" | "plain"`, see [`Theme`]
+//!
+//! Metadata for the head of the page. It isn't emitted as content, must be at the top level,
+//! and can't have children or text. Title and theme set with [`Options`] take precedence.
+//! ```markerml
+//! page["My notes", lang = "en", theme = "dark", description = "Things worth remembering"]
+//! ```
+//!
+//! ## Stylesheet
+//! Name: `stylesheet` \
+//! Properties:
+//! - `default href: string`
+//!
+//! External stylesheet, linked from the head of the page after the ones from [`Options`].
+//! Must be at the top level, and can't have children or text.
+//!
+//! ## Script
+//! Name: `script` \
+//! Properties:
+//! - `default src: string`
+//! - `defer: flag`
+//! - `async: flag`
+//! - `module: flag` - script is loaded as an ES module
+//!
+//! External script, loaded from the head of the page. Must be at the top level,
+//! and can't have children or text.
+//! ```markerml
+//! stylesheet["style.css"]
+//! script["app.js", defer]
+//! ```
+//!
+//!
+//! ## Repeat
+//! Name: `repeat` \
+//! Properties:
+//! - `default count: int`
+//! - `index: string = "index"`
+//! - `children: slot[]`
+//!
+//! Children are repeated `count` times, with the index of the repetition, starting from zero,
+//! available for interpolation as `${index}`. Nested repetitions can name their index differently.
+//! Repetition itself isn't emitted, so it doesn't accept style properties.
+//! Count can't exceed 10000, and all repetitions of the document together
+//! can't produce more than a million components.
+//! ```markerml
+//! repeat[3, index = "row"] {
+//!     box[horizontal] { repeat[2] { @(${row}:${index}) } }
+//! }
+//! ```
+//!
+//! ## Style properties
+//! All built-in components also accept style properties,
+//! which are emitted as inline CSS, or collected into a `<style>` block
+//! with generated class names with [`CssMode::Stylesheet`]:
+//! - `color: string`
+//! - `background: string`
+//! - `padding`, `margin`, `width`, `height`, `font_size`: lengths, given
+//!   either as strings like `"2em"` or as integers, which are treated as pixels.
+//!   Only `margin` can be negative
+//! - `grow: integer`, `shrink: integer`: how much the component grows or shrinks
+//!   inside of a `box`, relative to its siblings
+//! - `align_self: string = "start" | "center" | "end"`: alignment of the component
+//!   inside of a `box`, overriding the alignment of the box across its direction
+//! ```markerml
+//! box[padding = 16, background = "#eee"] {
+//!     paragraph[color = "gray", font_size = "0.8em"](Note)
+//! }
+//! box[horizontal] {
+//!     box[grow = 1] { @(Content) }
+//!     box[align_self = "center"] { @(Sidebar) }
+//! }
 //! ```
+//!
+//! # Grammar
+//! ```text
 //! WHITESPACE = _{ (" " | "\t" | NEWLINE)+ }
 //!
-//! COMMENT = _{ "//" ~ (!NEWLINE ~ ANY)* ~ NEWLINE }
+//! COMMENT = _{ line_comment | block_comment }
 //!
-//! integer = @{ "-"? ~ ASCII_DIGIT+ }
+//! line_comment = _{ "//" ~ (!NEWLINE ~ ANY)* ~ NEWLINE }
+//!
+//! block_comment = _{ "/*" ~ (block_comment | !"*/" ~ ANY)* ~ "*/" }
+//!
+//! integer = @{
+//!     "-"? ~ (
+//!         "0x" ~ ASCII_HEX_DIGIT ~ ("_"? ~ ASCII_HEX_DIGIT)*
+//!       | "0b" ~ ASCII_BIN_DIGIT ~ ("_"? ~ ASCII_BIN_DIGIT)*
+//!       | ASCII_DIGIT ~ ("_"? ~ ASCII_DIGIT)*
+//!     )
+//! }
 //!
 //! bool = @{ "true" | "false" }
 //!
@@ -166,31 +488,49 @@
 //!
 //! literal_newline = @{ NEWLINE ~ (" " | "\t")* }
 //!
-//! string_literal_segment = @{ (!("$" | "\"" | NEWLINE) ~ ANY)+ }
+//! escape_sequence = @{ "\\" ~ ("\\" | "\"" | "(" | ")" | "$" | "n" | "t" | "u{" ~ ASCII_HEX_DIGIT{1, 6} ~ "}") }
+//!
+//! string_literal_segment = @{ (!("$" | "\"" | "\\" | NEWLINE) ~ ANY)+ }
+//!
+//! text_literal_segment = @{ (!("$" | ")" | "\\" | NEWLINE) ~ ANY)+ }
+//!
+//! format_spec = @{ ("<" | "^" | ">") ~ format_size? | format_size }
+//!
+//! format_size = _{ ASCII_DIGIT+ ~ ("." ~ ASCII_DIGIT+)? | "." ~ ASCII_DIGIT+ }
+//!
+//! variable_interpolation = { "${" ~ identifier ~ (":" ~ format_spec)? ~ "}" }
 //!
-//! text_literal_segment = @{ (!("$" | ")" | NEWLINE) ~ ANY)+ }
+//! message_key = @{ identifier ~ ("." ~ identifier)* }
 //!
-//! variable_interpolation = { "${" ~ identifier ~ "}" }
+//! message_interpolation = { "${" ~ "t:" ~ message_key ~ "}" }
 //!
-//! string_segment = ${ literal_newline | variable_interpolation | string_literal_segment }
+//! string_segment = ${ literal_newline | escape_sequence | message_interpolation | variable_interpolation | string_literal_segment }
 //!
-//! text_segment = ${ literal_newline | variable_interpolation | text_literal_segment }
+//! text_segment = ${ literal_newline | escape_sequence | message_interpolation | variable_interpolation | inline_component | text_literal_segment }
 //!
 //! string = @{ "\"" ~ string_segment* ~ "\"" }
 //!
 //! text = @{ "(" ~ text_segment* ~ ")" }
 //!
+//! raw_text_content = @{ (!PEEK ~ ANY)* }
+//!
+//! raw_text = ${ PUSH("`"+) ~ raw_text_content ~ POP }
+//!
 //! value = { variable_interpolation | bool | string | integer }
 //!
 //! component_name = { "@" | "#" | identifier }
 //!
 //! default_property = { value }
 //!
-//! named_property = { identifier ~ "=" ~ value }
+//! attribute_name = @{ "attr:" ~ (ASCII_ALPHA | "_") ~ (ASCII_ALPHANUMERIC | "_" | "-" | ":" | ".")* }
+//!
+//! named_property = { (attribute_name | identifier) ~ "=" ~ value }
 //!
 //! flag_property = { identifier }
 //!
-//! property = { named_property | flag_property }
+//! negated_flag_property = { "!" ~ identifier }
+//!
+//! property = { named_property | flag_property | negated_flag_property }
 //!
 //! properties_list = _{ property ~ ("," ~ property)* }
 //!
@@ -198,7 +538,13 @@
 //!
 //! children = { "{" ~ component* ~ "}" }
 //!
-//! component = { component_name ~ properties? ~ children? ~ text? }
+//! component = { component_name ~ properties? ~ children? ~ (text | raw_text)? }
+//!
+//! inline_component_name = ${ "$" ~ component_name }
+//!
+//! inline_properties = !{ properties }
+//!
+//! inline_component = ${ inline_component_name ~ inline_properties? ~ text? }
 //!
 //! ty = @{ "string" | "int" | "bool" | "slot[]" | "slot" }
 //!
@@ -216,26 +562,50 @@
 //!
 //! component_definition = { "component" ~ identifier ~ properties_definition? ~ children? }
 //!
-//! module_item = _{ component_definition | component }
+//! constant = { "let" ~ identifier ~ "=" ~ value }
+//!
+//! module_item = _{ constant | component_definition | component }
 //!
 //! module = { SOI ~ module_item* ~ EOI}
 //! ```
 //!
 
+pub use incremental::IncrementalConverter;
+pub use lints::{DeniedWarningError, DiagnosticLevel, LINTS};
 pub use markerml_backend;
+pub use markerml_backend::manifest::{
+    ComponentManifest, ContentKind, PropertyManifest, PropertyType,
+};
+pub use markerml_backend::{CssMode, HtmlGeneratorOptions, ImageInfo, PrintOptions, Theme};
 pub use markerml_frontend;
+pub use markerml_frontend::ast;
 pub use markerml_middleend;
+pub use markerml_middleend::{ir, Span};
+pub use pipeline::{IrHook, Pipeline};
 
+use markerml_backend::html_generator::HtmlGenerator;
+use markerml_middleend::analysis;
+use markerml_middleend::component_expander::ComponentExpander;
+use markerml_middleend::ir_generator::IrGenerator;
+use markerml_middleend::typecheck::TypeChecker;
 use miette::Diagnostic;
+use std::collections::{HashMap, HashSet};
+use std::hash::{DefaultHasher, Hash, Hasher};
+use std::path::PathBuf;
 use thiserror::Error;
 
+mod incremental;
+mod lints;
+mod pipeline;
+
 /// Error type that encompasses all errors that might
 /// occur while parsing code and generating HTML
 #[derive(Debug, Error, Diagnostic)]
 pub enum MarkermlError {
     /// Error from the parser stage
     #[error(transparent)]
-    Parser(#[from] Box<markerml_frontend::ParserError>),
+    #[diagnostic(transparent)]
+    Parser(#[from] markerml_frontend::SyntaxError),
     /// Error from the Intermediate Representation generation stage
     #[error(transparent)]
     #[diagnostic(transparent)]
@@ -244,13 +614,342 @@ pub enum MarkermlError {
     #[error(transparent)]
     #[diagnostic(transparent)]
     Backend(#[from] markerml_backend::BackendError),
+    /// Warning of the lint, that is denied in the options
+    #[error(transparent)]
+    #[diagnostic(transparent)]
+    DeniedWarning(#[from] DeniedWarningError),
+}
+
+/// Warning that doesn't prevent the conversion, such as unknown property of the builtin component
+#[derive(Debug, Clone, Error, Diagnostic)]
+pub enum MarkermlWarning {
+    /// Warning from the Intermediate Representation analysis
+    #[error(transparent)]
+    #[diagnostic(transparent)]
+    Middleend(#[from] markerml_middleend::IrWarning),
+    /// Warning from the HTML emitting stage
+    #[error(transparent)]
+    #[diagnostic(transparent)]
+    Backend(#[from] markerml_backend::BackendWarning),
+}
+
+/// Converted HTML along with the warnings, collected during the conversion
+#[derive(Debug, Clone, Default)]
+pub struct Diagnostics {
+    /// Generated HTML
+    pub html: String,
+    /// Warnings in the order of the code
+    pub warnings: Vec<MarkermlWarning>,
+}
+
+impl MarkermlError {
+    /// Creates syntax error from the parser error of the given code
+    pub fn from_parser(err: &markerml_frontend::ParserError, code: &str) -> Self {
+        MarkermlError::Parser(markerml_frontend::SyntaxError::new(err, code))
+    }
+}
+
+/// Options of the conversion
+#[derive(Debug, Clone, Default)]
+pub struct Options {
+    /// Component libraries, whose definitions are available in the module,
+    /// unless the module defines component with the same name
+    pub components: Vec<ComponentLibrary>,
+    /// Variables, available for `${...}` interpolation in the whole module
+    pub vars: HashMap<String, Value>,
+    /// Catalog of localized messages, used for `${t:...}` interpolation.
+    /// Keys of the nested messages are joined with dots, like `menu.home`.
+    /// Messages are replaced with their keys if the catalog isn't specified
+    pub messages: Option<HashMap<String, String>>,
+    /// Whether to emit markup without the surrounding page
+    pub fragment: bool,
+    /// Whether to emit `data-mml-span="line:column-line:column"` attribute on every element
+    /// with position of its component in the code, so that tooling can map elements back to it
+    pub source_spans: bool,
+    /// Options of the HTML generation, such as page title, pretty-printing, print layout,
+    /// styles and theme. Page has no default styles unless the theme is chosen
+    pub html: HtmlGeneratorOptions,
+    /// Whether to process top-level components one by one, generating IR,
+    /// expanding and emitting each of them before moving to the next,
+    /// without storing IR of the whole module.
+    /// Intended for hosts that compile many small trusted templates.
+    /// Output is the same as with the separate stages
+    pub single_pass: bool,
+    /// Levels of the lints by their names from [`LINTS`], such as `unknown_property`.
+    /// Lints that aren't listed are reported as warnings
+    pub lints: HashMap<String, DiagnosticLevel>,
+    /// Directory, against which relative links are resolved
+    /// to report the ones to missing files. Links aren't checked if it's missing
+    pub link_base: Option<PathBuf>,
+}
+
+/// Parsed component library, which can be reused for many modules
+#[derive(Debug, Clone)]
+pub struct ComponentLibrary {
+    definitions: Vec<markerml_frontend::ast::ModuleItem<markerml_frontend::Span>>,
+    /// Hash of the library code, which identifies the definitions
+    hash: u64,
+}
+
+impl ComponentLibrary {
+    /// Parses and checks given code of the component library.
+    /// Only component definitions are kept
+    pub fn parse(code: &str) -> Result<Self, MarkermlError> {
+        check(code)?;
+        let module =
+            markerml_frontend::parse(code).map_err(|err| MarkermlError::from_parser(&err, code))?;
+        let definitions = module
+            .items
+            .into_iter()
+            .filter(|item| {
+                matches!(
+                    item,
+                    markerml_frontend::ast::ModuleItem::ComponentDefinition(_)
+                )
+            })
+            .collect();
+
+        Ok(ComponentLibrary {
+            definitions,
+            hash: hash_of(code),
+        })
+    }
+}
+
+/// Value of the externally supplied variable
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Value {
+    String(String),
+    Integer(i64),
+    Bool(bool),
+}
+
+impl From<&Value> for ir::Value<Span> {
+    fn from(value: &Value) -> Self {
+        match value {
+            Value::String(value) => ir::ValueKind::String(ir::StringValue::from_literal(value)),
+            Value::Integer(value) => ir::ValueKind::Integer(*value),
+            Value::Bool(value) => ir::ValueKind::Bool(*value),
+        }
+        .into()
+    }
 }
 
 /// Converts given MarkerML code into HTML
 pub fn parse(code: &str) -> Result<String, MarkermlError> {
-    let ast = markerml_frontend::parse(code)?;
+    let ast =
+        markerml_frontend::parse(code).map_err(|err| MarkermlError::from_parser(&err, code))?;
     let ir = markerml_middleend::generate_ir(ast)?;
-    let html = markerml_backend::generate_html(ir)?;
+    pipeline::check_definitions(&ir)?;
+    markerml_middleend::typecheck(&ir)?;
+    let ir = markerml_middleend::expand_components(ir)?;
+    let html = markerml_backend::generate_html(&ir)?;
 
     Ok(html)
 }
+
+/// Returns description of the builtin components, their properties, types and defaults.
+/// It follows the implementation of the backend, so tools can stay in sync with it
+pub fn builtin_manifest() -> &'static [ComponentManifest] {
+    markerml_backend::builtin_manifest()
+}
+
+/// Checks given MarkerML code for errors, running parser, Intermediate Representation
+/// generation and type checking stages without emitting HTML
+pub fn check(code: &str) -> Result<(), MarkermlError> {
+    let ast =
+        markerml_frontend::parse(code).map_err(|err| MarkermlError::from_parser(&err, code))?;
+    let ir = markerml_middleend::generate_ir(ast)?;
+    pipeline::check_definitions(&ir)?;
+    markerml_middleend::typecheck(&ir)?;
+    markerml_middleend::expand_components(ir)?;
+
+    Ok(())
+}
+
+/// Formats given MarkerML code into canonical form, preserving comments
+pub fn format(code: &str) -> Result<String, MarkermlError> {
+    markerml_frontend::format(code).map_err(|err| MarkermlError::from_parser(&err, code))
+}
+
+/// Generates Intermediate Representation from the given MarkerML code.
+/// Custom components are replaced with their definitions if `expand` is set
+pub fn generate_ir(
+    code: &str,
+    expand: bool,
+) -> Result<markerml_middleend::ir::Module<markerml_middleend::Span>, MarkermlError> {
+    let ast =
+        markerml_frontend::parse(code).map_err(|err| MarkermlError::from_parser(&err, code))?;
+    let ir = markerml_middleend::generate_ir(ast)?;
+    pipeline::check_definitions(&ir)?;
+    if !expand {
+        return Ok(ir);
+    }
+
+    Ok(markerml_middleend::expand_components(ir)?)
+}
+
+/// Converts given MarkerML code into HTML markup
+/// without the surrounding page, so it can be embedded into a layout
+pub fn parse_fragment(code: &str) -> Result<String, MarkermlError> {
+    parse_with_options(
+        code,
+        &Options {
+            fragment: true,
+            ..Options::default()
+        },
+    )
+}
+
+/// Converts given MarkerML code into HTML with the given options
+pub fn parse_with_options(code: &str, options: &Options) -> Result<String, MarkermlError> {
+    Ok(parse_with_diagnostics(code, options)?.html)
+}
+
+/// Converts externally constructed AST into HTML with the given options,
+/// so that documents can be generated from data without building the code from strings.
+/// AST can be made with [`ast::builder`] or deserialized from JSON with the `serde` feature
+pub fn convert_ast(module: ast::Module<Span>, options: &Options) -> Result<String, MarkermlError> {
+    Ok(Pipeline::new(options).run_ast(module)?.html)
+}
+
+/// Converts given MarkerML code into HTML with the given options,
+/// collecting warnings, such as unknown properties of the builtin components
+pub fn parse_with_diagnostics(code: &str, options: &Options) -> Result<Diagnostics, MarkermlError> {
+    let pipeline = Pipeline::new(options);
+    if !options.single_pass {
+        return pipeline.run(code);
+    }
+
+    let ast = pipeline.parse_ast(code)?;
+    parse_single_pass(code, ast, pipeline::variables(options), options)
+}
+
+/// Converts the module, fusing IR generation, expansion and emission of every top-level component
+fn parse_single_pass(
+    code: &str,
+    ast: ast::Module<Span>,
+    variables: HashMap<String, ir::Value<Span>>,
+    options: &Options,
+) -> Result<Diagnostics, MarkermlError> {
+    let mut ir_generator = IrGenerator::default();
+    let mut constants = Vec::new();
+    let mut definitions = Vec::new();
+    let mut components = Vec::new();
+    for item in ast.items {
+        match item {
+            ast::ModuleItem::Constant(constant) => {
+                constants.push(ir_generator.generate_constant(constant)?)
+            }
+            ast::ModuleItem::Component(component) => components.push(component),
+            ast::ModuleItem::ComponentDefinition(def) => {
+                let def = ir_generator.generate_component_definition(def)?;
+                markerml_backend::check_definition(&def)?;
+                definitions.push(def);
+            }
+        }
+    }
+    let checker = TypeChecker::new(&definitions);
+    for def in &definitions {
+        checker.check_definition(def)?;
+    }
+    let used = lints::used_components(&components);
+    let unused =
+        analysis::unused_definitions(&definitions, used.iter().map(String::as_str).collect());
+    let constant_names = constants
+        .iter()
+        .map(|constant| constant.name.as_str())
+        .collect();
+    let ambiguous = analysis::ambiguous_flags(&definitions, &constant_names);
+    let mut warnings = lints::local_warnings(options, unused)
+        .chain(lints::local_warnings(options, ambiguous))
+        .collect::<Vec<_>>();
+    let mut expander = ComponentExpander::with_definitions(definitions.iter().cloned(), variables);
+    for constant in constants {
+        expander.define_constant(constant)?;
+    }
+    if let Some(messages) = &options.messages {
+        expander = expander.with_messages(messages.clone());
+    }
+    let mut generator = HtmlGenerator::streaming(&options.html);
+    if options.source_spans {
+        generator = generator.with_source_spans(code);
+    }
+
+    let mut content = String::new();
+    let mut backend_warnings = Vec::new();
+    let mut ids = Vec::new();
+    for component in components {
+        let component = ir_generator.generate_component(component)?;
+        checker.check_component(&component)?;
+        for component in expander.expand_top_level(component)? {
+            lints::lint_component(options, &component, &mut ids, &mut backend_warnings);
+            generator.write_component(&component, &mut content)?;
+        }
+    }
+    lints::lint_duplicate_ids(&ids, &mut backend_warnings);
+    warnings.extend(backend_warnings.into_iter().map(MarkermlWarning::from));
+    lints::sort_warnings(&mut warnings);
+    let warnings = lints::apply_levels(code, options, warnings)?;
+
+    // Page head is written after the content, since it includes the collected classes
+    if options.fragment {
+        let mut html = String::with_capacity(content.len());
+        generator.write_classes(&mut html);
+        html.push_str(&content);
+        return Ok(Diagnostics { html, warnings });
+    }
+    let mut html = String::with_capacity(content.len());
+    generator.write_page_start(&mut html);
+    generator.write_main(&content, &mut html);
+    HtmlGenerator::write_page_end(&mut html);
+
+    Ok(Diagnostics { html, warnings })
+}
+
+/// Converts given MarkerML code into HTML, making given
+/// variables available for `${...}` interpolation in the whole module.
+/// Use of an undefined variable is reported along with the names of the available ones
+pub fn parse_with_vars(code: &str, vars: &HashMap<String, Value>) -> Result<String, MarkermlError> {
+    parse_with_options(
+        code,
+        &Options {
+            vars: vars.clone(),
+            ..Options::default()
+        },
+    )
+}
+
+/// Returns definitions of the component libraries, except the ones
+/// that the module defines itself, since they take precedence
+fn library_definitions<'a>(
+    options: &'a Options,
+    module: &ast::Module<Span>,
+) -> Vec<&'a ast::ComponentDefinition<Span>> {
+    let local = module
+        .items
+        .iter()
+        .filter_map(|item| match item {
+            ast::ModuleItem::ComponentDefinition(def) => Some(def.name.as_str()),
+            ast::ModuleItem::Constant(_) | ast::ModuleItem::Component(_) => None,
+        })
+        .collect::<HashSet<_>>();
+
+    options
+        .components
+        .iter()
+        .flat_map(|library| &library.definitions)
+        .filter_map(|item| match item {
+            ast::ModuleItem::ComponentDefinition(def) if !local.contains(def.name.as_str()) => {
+                Some(def)
+            }
+            _ => None,
+        })
+        .collect()
+}
+
+fn hash_of(value: impl Hash) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    value.hash(&mut hasher);
+    hasher.finish()
+}
@@ -0,0 +1,214 @@
+use crate::{MarkermlError, MarkermlWarning, Options};
+use markerml_backend::{lint, BackendWarning};
+use markerml_frontend::ast::{self, Visitor};
+use markerml_frontend::format::collect_comments;
+use markerml_frontend::SourceMap;
+use markerml_middleend::error::IrWarning;
+use markerml_middleend::{ir, Span};
+use miette::{Diagnostic, LabeledSpan, Severity, SourceCode};
+use std::collections::HashSet;
+use std::fmt;
+use thiserror::Error;
+
+/// Names of the lints, whose level can be configured
+pub const LINTS: &[&str] = &[
+    "unknown_property",
+    "unused_definition",
+    "ambiguous_flag",
+    "invalid_link",
+    "missing_link_target",
+    "duplicate_id",
+];
+
+/// Prefix of the comment, that allows the lints on its line and the next one
+const ALLOW_COMMENT: &str = "markerml-allow(";
+
+/// Level of the lint, that decides how its warnings are reported
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum DiagnosticLevel {
+    /// Warnings are silenced
+    Allow,
+    /// Warnings are reported without failing the conversion
+    #[default]
+    Warn,
+    /// Warnings are turned into errors
+    Deny,
+}
+
+impl MarkermlWarning {
+    /// Returns name of the lint, that produced the warning
+    pub fn lint(&self) -> &'static str {
+        match self {
+            MarkermlWarning::Middleend(IrWarning::UnusedDefinition(_)) => "unused_definition",
+            MarkermlWarning::Middleend(IrWarning::AmbiguousFlag(_)) => "ambiguous_flag",
+            MarkermlWarning::Backend(BackendWarning::UnknownProperty(_)) => "unknown_property",
+            MarkermlWarning::Backend(BackendWarning::InvalidLink(_)) => "invalid_link",
+            MarkermlWarning::Backend(BackendWarning::MissingLinkTarget(_)) => "missing_link_target",
+            MarkermlWarning::Backend(BackendWarning::DuplicateId(_)) => "duplicate_id",
+        }
+    }
+}
+
+/// Warning of the denied lint, reported as an error
+#[derive(Debug, Error)]
+#[error(transparent)]
+pub struct DeniedWarningError {
+    pub warning: MarkermlWarning,
+}
+
+impl Diagnostic for DeniedWarningError {
+    fn code<'a>(&'a self) -> Option<Box<dyn fmt::Display + 'a>> {
+        self.warning.code()
+    }
+
+    fn severity(&self) -> Option<Severity> {
+        Some(Severity::Error)
+    }
+
+    fn help<'a>(&'a self) -> Option<Box<dyn fmt::Display + 'a>> {
+        self.warning.help()
+    }
+
+    fn source_code(&self) -> Option<&dyn SourceCode> {
+        self.warning.source_code()
+    }
+
+    fn labels(&self) -> Option<Box<dyn Iterator<Item = LabeledSpan> + '_>> {
+        self.warning.labels()
+    }
+}
+
+/// Applies levels of the lints from the options and the `// markerml-allow(...)` comments
+/// of the code, failing on the first warning of the denied lint
+pub(crate) fn apply_levels(
+    code: &str,
+    options: &Options,
+    warnings: Vec<MarkermlWarning>,
+) -> Result<Vec<MarkermlWarning>, MarkermlError> {
+    if warnings.is_empty() {
+        return Ok(warnings);
+    }
+
+    let allowed = allowed_regions(code);
+    let mut reported = Vec::with_capacity(warnings.len());
+    for warning in warnings {
+        let lint = warning.lint();
+        let offset = offset(&warning);
+        let is_allowed = offset.is_some_and(|offset| {
+            allowed.iter().any(|(start, end, lints)| {
+                (*start..*end).contains(&offset) && lints.iter().any(|name| name == lint)
+            })
+        });
+        if is_allowed {
+            continue;
+        }
+
+        match options.lints.get(lint).copied().unwrap_or_default() {
+            DiagnosticLevel::Allow => {}
+            DiagnosticLevel::Warn => reported.push(warning),
+            DiagnosticLevel::Deny => return Err(DeniedWarningError { warning }.into()),
+        }
+    }
+
+    Ok(reported)
+}
+
+pub(crate) use lint::lint_duplicate_ids;
+
+/// Checks the expanded component with the lints of the backend,
+/// including targets of the relative links if their base directory is given.
+/// Explicit ids of the anchors are collected, so that they are checked for the whole document
+/// with [`lint_duplicate_ids`]
+pub(crate) fn lint_component(
+    options: &Options,
+    component: &ir::Component<Span>,
+    ids: &mut Vec<(String, Span)>,
+    warnings: &mut Vec<BackendWarning>,
+) {
+    lint::lint_component(component, warnings);
+    lint::anchor_ids(component, ids);
+    if let Some(base) = &options.link_base {
+        lint::lint_link_targets(component, base, warnings);
+    }
+}
+
+/// Orders warnings by their position in the code
+pub(crate) fn sort_warnings(warnings: &mut [MarkermlWarning]) {
+    warnings.sort_by_key(offset);
+}
+
+/// Converts warnings of the IR analysis, skipping the ones about definitions of the component
+/// libraries, since their spans point into the code of the library
+pub(crate) fn local_warnings(
+    options: &Options,
+    warnings: Vec<IrWarning>,
+) -> impl Iterator<Item = MarkermlWarning> + '_ {
+    let libraries = options
+        .components
+        .iter()
+        .flat_map(|library| &library.definitions)
+        .filter_map(|item| match item {
+            ast::ModuleItem::ComponentDefinition(def) => Some(def.name.as_str()),
+            ast::ModuleItem::Constant(_) | ast::ModuleItem::Component(_) => None,
+        })
+        .collect::<HashSet<_>>();
+
+    warnings
+        .into_iter()
+        .filter(move |warning| match warning {
+            IrWarning::UnusedDefinition(warning) => !libraries.contains(warning.name.as_str()),
+            IrWarning::AmbiguousFlag(warning) => !libraries.contains(warning.definition.as_str()),
+        })
+        .map(MarkermlWarning::from)
+}
+
+/// Returns names of the components, used in the given top-level components
+pub(crate) fn used_components<'a>(
+    components: impl IntoIterator<Item = &'a ast::Component<Span>>,
+) -> HashSet<String> {
+    struct Names(HashSet<String>);
+
+    impl Visitor<Span> for Names {
+        fn visit_component(&mut self, component: &ast::Component<Span>) {
+            self.0.insert(component.name.name.clone());
+            ast::visit::walk_component(self, component);
+        }
+    }
+
+    let mut names = Names(HashSet::new());
+    for component in components {
+        names.visit_component(component);
+    }
+
+    names.0
+}
+
+fn offset(warning: &MarkermlWarning) -> Option<usize> {
+    warning
+        .labels()
+        .and_then(|mut labels| labels.next())
+        .map(|label| label.offset())
+}
+
+/// Returns regions of the code along with the lints, allowed by the comments.
+/// Region spans from the start of the comment line to the end of the next line
+fn allowed_regions(code: &str) -> Vec<(usize, usize, Vec<String>)> {
+    let source_map = SourceMap::new(code);
+    collect_comments(code)
+        .into_iter()
+        .filter_map(|comment| {
+            let text = comment.text.trim_start_matches('/').trim();
+            let lints = text.strip_prefix(ALLOW_COMMENT)?.strip_suffix(')')?;
+            let lints = lints
+                .split(',')
+                .map(|lint| lint.trim().to_owned())
+                .collect();
+
+            let line = source_map.line_index(comment.span.start.offset());
+            let line_start = source_map.line_range(line).start;
+            let next_line_end = source_map.line_range(line + 1).end;
+
+            Some((line_start, next_line_end, lints))
+        })
+        .collect()
+}
@@ -0,0 +1,202 @@
+use crate::{lints, Diagnostics, MarkermlError, MarkermlWarning, Options};
+use markerml_backend::html_generator::HtmlGenerator;
+use markerml_frontend::ast;
+use markerml_middleend::component_expander::ComponentExpander;
+use markerml_middleend::{ir, Span};
+use std::collections::HashMap;
+use std::fmt;
+
+/// Hook, that inspects or transforms the IR between the stages
+pub type IrHook<'o> = Box<dyn Fn(&mut ir::Module<Span>) -> Result<(), MarkermlError> + 'o>;
+
+/// Conversion split into separately callable stages:
+/// [`Pipeline::parse_ast`], [`Pipeline::lower_ir`] and [`Pipeline::emit_html`].
+/// Stages are always run separately, regardless of [`Options::single_pass`]
+pub struct Pipeline<'o> {
+    options: &'o Options,
+    ir_hooks: Vec<IrHook<'o>>,
+    expanded_ir_hooks: Vec<IrHook<'o>>,
+}
+
+impl<'o> Pipeline<'o> {
+    pub fn new(options: &'o Options) -> Self {
+        Pipeline {
+            options,
+            ir_hooks: Vec::new(),
+            expanded_ir_hooks: Vec::new(),
+        }
+    }
+
+    /// Adds hook, that is called with the generated IR before type checking and expansion,
+    /// so it still contains custom components and their definitions
+    pub fn with_ir_hook(
+        mut self,
+        hook: impl Fn(&mut ir::Module<Span>) -> Result<(), MarkermlError> + 'o,
+    ) -> Self {
+        self.ir_hooks.push(Box::new(hook));
+        self
+    }
+
+    /// Adds hook, that is called with the expanded IR right before emitting HTML
+    pub fn with_expanded_ir_hook(
+        mut self,
+        hook: impl Fn(&mut ir::Module<Span>) -> Result<(), MarkermlError> + 'o,
+    ) -> Self {
+        self.expanded_ir_hooks.push(Box::new(hook));
+        self
+    }
+
+    /// Parses the code into AST, prepending definitions of the component libraries.
+    /// Definitions of the module take precedence over the ones with the same name from the libraries
+    pub fn parse_ast(&self, code: &str) -> Result<ast::Module<Span>, MarkermlError> {
+        let ast =
+            markerml_frontend::parse(code).map_err(|err| MarkermlError::from_parser(&err, code))?;
+
+        Ok(self.with_library_definitions(ast))
+    }
+
+    fn with_library_definitions(&self, mut ast: ast::Module<Span>) -> ast::Module<Span> {
+        let mut items = crate::library_definitions(self.options, &ast)
+            .into_iter()
+            .map(|def| ast::ModuleItem::ComponentDefinition(def.clone()))
+            .collect::<Vec<_>>();
+        items.append(&mut ast.items);
+        ast.items = items;
+
+        ast
+    }
+
+    /// Generates IR from the AST, type checks it and expands custom components,
+    /// calling the hooks on the way
+    pub fn lower_ir(&self, ast: ast::Module<Span>) -> Result<ir::Module<Span>, MarkermlError> {
+        self.lower(ast, None)
+    }
+
+    /// Lowers the AST same as [`Self::lower_ir`], collecting warnings of the IR analysis,
+    /// such as unused component definitions.
+    /// Code is used for the `markerml-allow` comments
+    pub fn lower_ir_with_diagnostics(
+        &self,
+        code: &str,
+        ast: ast::Module<Span>,
+    ) -> Result<(ir::Module<Span>, Vec<MarkermlWarning>), MarkermlError> {
+        let mut warnings = Vec::new();
+        let ir = self.lower(ast, Some(&mut warnings))?;
+        let warnings = lints::apply_levels(code, self.options, warnings)?;
+
+        Ok((ir, warnings))
+    }
+
+    fn lower(
+        &self,
+        ast: ast::Module<Span>,
+        warnings: Option<&mut Vec<MarkermlWarning>>,
+    ) -> Result<ir::Module<Span>, MarkermlError> {
+        let mut ir = markerml_middleend::generate_ir(ast)?;
+        for hook in &self.ir_hooks {
+            hook(&mut ir)?;
+        }
+        check_definitions(&ir)?;
+        markerml_middleend::typecheck(&ir)?;
+        if let Some(warnings) = warnings {
+            let analysis = markerml_middleend::analysis::lint_module(&ir);
+            warnings.extend(lints::local_warnings(self.options, analysis));
+        }
+
+        let mut expander = ComponentExpander::with_variables(ir, variables(self.options));
+        if let Some(messages) = &self.options.messages {
+            expander = expander.with_messages(messages.clone());
+        }
+        let mut ir = expander.expand()?;
+        for hook in &self.expanded_ir_hooks {
+            hook(&mut ir)?;
+        }
+
+        Ok(ir)
+    }
+
+    /// Emits HTML from the expanded IR, collecting warnings.
+    /// Code is used for the source spans and the `markerml-allow` comments
+    pub fn emit_html(
+        &self,
+        code: &str,
+        ir: &ir::Module<Span>,
+    ) -> Result<Diagnostics, MarkermlError> {
+        let mut warnings = Vec::new();
+        let mut ids = Vec::new();
+        for item in &ir.items {
+            if let ir::ModuleItem::Component(component) = item {
+                lints::lint_component(self.options, component, &mut ids, &mut warnings);
+            }
+        }
+        lints::lint_duplicate_ids(&ids, &mut warnings);
+        let warnings = warnings.into_iter().map(MarkermlWarning::from).collect();
+        let warnings = lints::apply_levels(code, self.options, warnings)?;
+
+        let mut generator = HtmlGenerator::with_options(ir, &self.options.html);
+        if self.options.source_spans {
+            generator = generator.with_source_spans(code);
+        }
+        let html = if self.options.fragment {
+            generator.generate_fragment()?
+        } else {
+            generator.generate()?
+        };
+
+        Ok(Diagnostics { html, warnings })
+    }
+
+    /// Runs all stages on the code
+    pub fn run(&self, code: &str) -> Result<Diagnostics, MarkermlError> {
+        let ast = self.parse_ast(code)?;
+        self.run_parsed(code, ast)
+    }
+
+    /// Runs the stages after parsing on the externally constructed AST,
+    /// such as the one made with [`ast::builder`] or deserialized from JSON.
+    /// Definitions of the component libraries are prepended same as in [`Self::parse_ast`]
+    pub fn run_ast(&self, ast: ast::Module<Span>) -> Result<Diagnostics, MarkermlError> {
+        let ast = self.with_library_definitions(ast);
+        self.run_parsed("", ast)
+    }
+
+    fn run_parsed(&self, code: &str, ast: ast::Module<Span>) -> Result<Diagnostics, MarkermlError> {
+        let (ir, mut warnings) = self.lower_ir_with_diagnostics(code, ast)?;
+        let mut diagnostics = self.emit_html(code, &ir)?;
+        warnings.append(&mut diagnostics.warnings);
+        lints::sort_warnings(&mut warnings);
+        diagnostics.warnings = warnings;
+
+        Ok(diagnostics)
+    }
+}
+
+impl fmt::Debug for Pipeline<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Pipeline")
+            .field("options", &self.options)
+            .field("ir_hooks", &self.ir_hooks.len())
+            .field("expanded_ir_hooks", &self.expanded_ir_hooks.len())
+            .finish()
+    }
+}
+
+/// Checks that definitions of the module don't shadow builtin components
+pub(crate) fn check_definitions(ir: &ir::Module<Span>) -> Result<(), MarkermlError> {
+    for item in &ir.items {
+        if let ir::ModuleItem::ComponentDefinition(def) = item {
+            markerml_backend::check_definition(def)?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Converts externally supplied variables into IR values
+pub(crate) fn variables(options: &Options) -> HashMap<String, ir::Value<Span>> {
+    options
+        .vars
+        .iter()
+        .map(|(name, value)| (name.clone(), value.into()))
+        .collect()
+}
@@ -0,0 +1,678 @@
+#[cfg(test)]
+mod test {
+    use anyhow::Result;
+    use markerml::markerml_backend::{BackendError, BackendWarning};
+    use markerml::*;
+    use std::path::PathBuf;
+
+    fn fragment_options() -> Options {
+        Options {
+            fragment: true,
+            ..Options::default()
+        }
+    }
+
+    fn fragment(code: &str) -> Result<String> {
+        Ok(parse_with_options(code, &fragment_options())?)
+    }
+
+    fn backend_error(code: &str) -> BackendError {
+        match parse_with_options(code, &fragment_options()) {
+            Err(MarkermlError::Backend(err)) => err,
+            Err(err) => panic!("Unexpected error: {err}"),
+            Ok(html) => panic!("Unexpected success: {html}"),
+        }
+    }
+
+    fn lints(code: &str, options: &Options) -> Result<Vec<&'static str>> {
+        let diagnostics = parse_with_diagnostics(code, options)?;
+
+        Ok(diagnostics
+            .warnings
+            .iter()
+            .map(MarkermlWarning::lint)
+            .collect())
+    }
+
+    #[test]
+    fn empty_elements() -> Result<()> {
+        assert_eq!(
+            fragment("box {}")?,
+            r#"<div style="display: flex; flex-direction: column"></div>"#
+        );
+        assert_eq!(fragment("list {}")?, "<ul></ul>");
+        assert_eq!(fragment("divider")?, "<hr/>");
+        assert_eq!(
+            parse("")?,
+            "<!DOCTYPE html><html><head></head><body><main></main></body></html>"
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn reserved_names() {
+        let code = "component box { @(a) }\nbox";
+        assert!(matches!(
+            parse(code),
+            Err(MarkermlError::Backend(BackendError::ReservedComponentName(
+                _
+            )))
+        ));
+        assert!(matches!(
+            check(code),
+            Err(MarkermlError::Backend(BackendError::ReservedComponentName(
+                _
+            )))
+        ));
+        assert!(matches!(
+            generate_ir(code, false),
+            Err(MarkermlError::Backend(BackendError::ReservedComponentName(
+                _
+            )))
+        ));
+    }
+
+    #[test]
+    fn parallel_emission() -> Result<()> {
+        let code = "header(Same)\n".repeat(100);
+        let expected = (1..=100)
+            .map(|index| match index {
+                1 => r#"<h1 id="same">Same</h1>"#.to_owned(),
+                index => format!(r#"<h1 id="same-{index}">Same</h1>"#),
+            })
+            .collect::<String>();
+        assert_eq!(fragment(&code)?, expected);
+
+        Ok(())
+    }
+
+    const GREETING: &str = r#"
+        let name = "World"
+        component greeting[text who] { header(Hello ${who}) }
+        page["Greeting"]
+        greeting(${name})
+        repeat[2] { paragraph(${index}) }
+    "#;
+
+    #[test]
+    fn single_pass() -> Result<()> {
+        let options = Options {
+            single_pass: true,
+            ..Options::default()
+        };
+        assert_eq!(parse_with_options(GREETING, &options)?, parse(GREETING)?);
+
+        Ok(())
+    }
+
+    #[test]
+    fn incremental() -> Result<()> {
+        let expected = parse(GREETING)?;
+        let mut converter = IncrementalConverter::new(Options::default());
+        assert_eq!(converter.convert(GREETING)?, expected);
+        assert_eq!(converter.convert(GREETING)?, expected);
+
+        let changed = GREETING.replace("World", "There");
+        assert_eq!(converter.convert(&changed)?, parse(&changed)?);
+
+        Ok(())
+    }
+
+    #[test]
+    fn header_ids() -> Result<()> {
+        assert_eq!(
+            fragment("header[1](Hello world)\nheader[2](Hello world)")?,
+            r#"<h1 id="hello-world">Hello world</h1><h2 id="hello-world-2">Hello world</h2>"#
+        );
+
+        let mut options = fragment_options();
+        options.html.id_prefix = "doc-".to_owned();
+        assert_eq!(
+            parse_with_options("header(Hi)", &options)?,
+            r#"<h1 id="doc-hi">Hi</h1>"#
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn image_dimensions() -> Result<()> {
+        let mut options = fragment_options();
+        options.html.images.insert(
+            "a.png".to_owned(),
+            ImageInfo {
+                width: 100,
+                height: 50,
+                variants: vec![("a-50.png".to_owned(), 50)],
+            },
+        );
+        assert_eq!(
+            parse_with_options(r#"image["a.png"]"#, &options)?,
+            r#"<img src="a.png" width="100" height="50" srcset="a-50.png 50w, a.png 100w"/>"#
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn slots() -> Result<()> {
+        let code = r#"
+            component frame[content: slot[]] { box { content } }
+            frame { @(a) @(b) }
+            frame
+        "#;
+        assert_eq!(
+            fragment(code)?,
+            concat!(
+                r#"<div style="display: flex; flex-direction: column"><span>a</span><span>b</span></div>"#,
+                r#"<div style="display: flex; flex-direction: column"></div>"#
+            )
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn style_properties() -> Result<()> {
+        assert_eq!(
+            fragment(r#"paragraph[padding = 4, width = "50%"](x)"#)?,
+            r#"<p style="padding: 4px; width: 50%">x</p>"#
+        );
+        assert!(matches!(
+            backend_error(r#"box[padding = "1px;}"]"#),
+            BackendError::InvalidStyleValue(_)
+        ));
+
+        Ok(())
+    }
+
+    #[test]
+    fn css_stylesheet() -> Result<()> {
+        let mut options = fragment_options();
+        options.html.css = CssMode::Stylesheet;
+        assert_eq!(
+            parse_with_options("box { @(a) }\nbox { @(b) }", &options)?,
+            concat!(
+                "<style>.mml-aec0e644 { display: flex; flex-direction: column }</style>",
+                r#"<div class="mml-aec0e644"><span>a</span></div>"#,
+                r#"<div class="mml-aec0e644"><span>b</span></div>"#
+            )
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn html_options() -> Result<()> {
+        let mut options = Options::default();
+        options.html.title = Some("<T>".to_owned());
+        options.html.styles = vec!["p{}".to_owned()];
+        assert_eq!(
+            parse_with_options("@(a)", &options)?,
+            concat!(
+                "<!DOCTYPE html><html><head><title>&lt;T&gt;</title><style>p{}</style></head>",
+                "<body><main><span>a</span></main></body></html>"
+            )
+        );
+
+        let mut options = fragment_options();
+        options.html.pretty = true;
+        assert_eq!(
+            parse_with_options("box { @(a) paragraph(b) }", &options)?,
+            "<div style=\"display: flex; flex-direction: column\">\n  <span>a</span>\n  <p>b</p>\n</div>\n"
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn fragment_output() -> Result<()> {
+        assert_eq!(
+            parse("@(a)")?,
+            "<!DOCTYPE html><html><head></head><body><main><span>a</span></main></body></html>"
+        );
+        assert_eq!(parse_fragment("@(a)")?, "<span>a</span>");
+
+        Ok(())
+    }
+
+    #[test]
+    fn source_spans() -> Result<()> {
+        let mut options = fragment_options();
+        options.source_spans = true;
+        assert_eq!(
+            parse_with_options("box {\n  @(a)\n}", &options)?,
+            r#"<div style="display: flex; flex-direction: column" data-mml-span="1:1-3:2"><span data-mml-span="2:3-2:7">a</span></div>"#
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn repeat() -> Result<()> {
+        assert_eq!(
+            fragment("repeat[2] { @(${index}) }")?,
+            "<span>0</span><span>1</span>"
+        );
+        assert_eq!(
+            fragment(
+                r#"repeat[2, index = "row"] { box[horizontal] { repeat[2] { @(${row}:${index}) } } }"#
+            )?,
+            concat!(
+                r#"<div style="display: flex; flex-direction: row"><span>0:0</span><span>0:1</span></div>"#,
+                r#"<div style="display: flex; flex-direction: row"><span>1:0</span><span>1:1</span></div>"#
+            )
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn code() -> Result<()> {
+        assert_eq!(fragment("code(let x = 1;)")?, "<code>let x = 1;</code>");
+        assert_eq!(
+            fragment("code_block[language = \"rust\"] ```\n    fn main() {}\n    ```")?,
+            r#"<pre><code class="language-rust">fn main() {}</code></pre>"#
+        );
+        assert!(matches!(
+            backend_error("code_block[language = \"a b\"] ```\n    x\n    ```"),
+            BackendError::InvalidLanguage(_)
+        ));
+
+        Ok(())
+    }
+
+    #[test]
+    fn quote() -> Result<()> {
+        assert_eq!(
+            fragment(r#"quote[cite = "https://q"](Quote)"#)?,
+            r#"<blockquote cite="https://q">Quote</blockquote>"#
+        );
+        assert_eq!(
+            fragment(r#"quote[cite = "\"><script>"](q)"#)?,
+            r#"<blockquote cite="&quot;&gt;&lt;script&gt;">q</blockquote>"#
+        );
+        assert_eq!(fragment(r#"#["a\"b"](q)"#)?, r#"<a href="a&quot;b">q</a>"#);
+
+        Ok(())
+    }
+
+    #[test]
+    fn divider_and_break() -> Result<()> {
+        assert_eq!(fragment("divider")?, "<hr/>");
+        assert_eq!(fragment("paragraph(a$break b)")?, "<p>a<br/> b</p>");
+        assert!(matches!(
+            backend_error("divider(text)"),
+            BackendError::UnexpectedContent(_)
+        ));
+
+        Ok(())
+    }
+
+    #[test]
+    fn text_flags() -> Result<()> {
+        assert_eq!(
+            fragment("@[bold, italic](x)")?,
+            "<strong><em>x</em></strong>"
+        );
+        assert_eq!(
+            fragment("@[underline, strike, mono](x)")?,
+            "<u><s><code>x</code></s></u>"
+        );
+        assert!(matches!(
+            backend_error("@[shiny](x)"),
+            BackendError::UnknownFlag(_)
+        ));
+
+        Ok(())
+    }
+
+    #[test]
+    fn image() -> Result<()> {
+        assert_eq!(
+            fragment(r#"image["a.png", alt = "Alt", width = 10]"#)?,
+            r#"<img src="a.png" alt="Alt" width="10"/>"#
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn alignment() -> Result<()> {
+        assert_eq!(
+            fragment(r#"box[horizontal, x_align = "center", y_align = "end"] { @[grow = 1](a) }"#)?,
+            r#"<div style="display: flex; flex-direction: row; justify-content: center; align-items: end"><span style="flex-grow: 1">a</span></div>"#
+        );
+        assert!(matches!(
+            backend_error(r#"box[x_align = "middle"]"#),
+            BackendError::InvalidAlignValue(_)
+        ));
+        assert!(matches!(
+            backend_error("box[vertical, horizontal]"),
+            BackendError::ConflictingFlags(_)
+        ));
+
+        Ok(())
+    }
+
+    #[test]
+    fn spacing() -> Result<()> {
+        assert_eq!(
+            fragment(r#"box[gap = "4px", padding = "1em", margin = 2] { @(a) }"#)?,
+            r#"<div style="display: flex; flex-direction: column; gap: 4px; padding: 1em; margin: 2px"><span>a</span></div>"#
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn print() -> Result<()> {
+        let mut options = Options::default();
+        options.html.print = Some(PrintOptions::default());
+        assert_eq!(
+            parse_with_options("@(a)\npage_break\n@(b)", &options)?,
+            concat!(
+                "<!DOCTYPE html><html><head><style>@page { size: A4; margin: 2cm } ",
+                "@media print { body { margin: 0 } h1, h2, h3, h4, h5, h6 { break-after: avoid } ",
+                "pre, blockquote, img { break-inside: avoid } }</style></head><body><main>",
+                r#"<span>a</span><div style="break-after: page"></div><span>b</span>"#,
+                "</main></body></html>"
+            )
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn backend_errors() {
+        assert!(matches!(
+            backend_error("header[7](x)"),
+            BackendError::InvalidHeaderLevel(_)
+        ));
+        assert!(matches!(
+            backend_error("unknown_thing"),
+            BackendError::UnknownComponent(_)
+        ));
+        assert!(matches!(
+            backend_error("paragraph"),
+            BackendError::TextMissing(_)
+        ));
+        assert!(matches!(
+            backend_error("image[alt = \"a\"]"),
+            BackendError::RequiredDefaultPropertyMissing(_)
+        ));
+    }
+
+    #[test]
+    fn unknown_properties() -> Result<()> {
+        let diagnostics = parse_with_diagnostics("box[foo = 1, bold] {}", &fragment_options())?;
+        assert_eq!(
+            diagnostics.html,
+            r#"<div style="display: flex; flex-direction: column"></div>"#
+        );
+        assert_eq!(
+            lints("box[foo = 1, bold] {}", &fragment_options())?,
+            ["unknown_property", "unknown_property"]
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn lint_levels() -> Result<()> {
+        let code = "box[foo = 1]\n// markerml-allow(unknown_property)\nbox[bar = 1]";
+        assert_eq!(lints(code, &fragment_options())?, ["unknown_property"]);
+
+        let mut options = fragment_options();
+        options
+            .lints
+            .insert("unknown_property".to_owned(), DiagnosticLevel::Allow);
+        assert!(lints(code, &options)?.is_empty());
+
+        options
+            .lints
+            .insert("unknown_property".to_owned(), DiagnosticLevel::Deny);
+        assert!(matches!(
+            parse_with_diagnostics(code, &options),
+            Err(MarkermlError::DeniedWarning(_))
+        ));
+
+        Ok(())
+    }
+
+    #[test]
+    fn unused_definitions() -> Result<()> {
+        let code = "component used { @(a) }\ncomponent unused { @(b) }\nused";
+        assert_eq!(lints(code, &fragment_options())?, ["unused_definition"]);
+
+        Ok(())
+    }
+
+    #[test]
+    fn attributes() -> Result<()> {
+        assert_eq!(
+            fragment(r#"box[attr:data-test = "hero", attr:hidden = true, attr:off = false] {}"#)?,
+            r#"<div style="display: flex; flex-direction: column" data-test="hero" hidden=""></div>"#
+        );
+        assert_eq!(
+            fragment(r#"@[attr:title = "<q>"](a)"#)?,
+            r#"<span title="&lt;q&gt;">a</span>"#
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn page_head() -> Result<()> {
+        assert_eq!(
+            parse("page[\"Notes\", lang = \"en\", description = \"About\"]\n@(a)")?,
+            concat!(
+                r#"<!DOCTYPE html><html lang="en"><head><meta charset="utf-8"><title>Notes</title>"#,
+                r#"<meta name="description" content="About"></head>"#,
+                "<body><main><span>a</span></main></body></html>"
+            )
+        );
+        assert!(matches!(
+            backend_error(r#"box { page["Nested"] }"#),
+            BackendError::NestedPage(_)
+        ));
+
+        Ok(())
+    }
+
+    #[test]
+    fn stylesheets_and_scripts() -> Result<()> {
+        assert_eq!(
+            parse("stylesheet[\"s.css\"]\nscript[\"app.js\", defer]\n@(a)")?,
+            concat!(
+                r#"<!DOCTYPE html><html><head><link href="s.css" rel="stylesheet">"#,
+                r#"<script src="app.js" defer=""></script></head>"#,
+                "<body><main><span>a</span></main></body></html>"
+            )
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn themes() -> Result<()> {
+        let style = format!("<style>{}</style>", Theme::Dark.css());
+        assert!(parse("page[theme = \"dark\"]\n@(a)")?.contains(&style));
+
+        let mut options = Options::default();
+        options.html.theme = Some(Theme::Dark);
+        assert!(parse_with_options("@(a)", &options)?.contains(&style));
+
+        assert!(matches!(
+            backend_error(r#"page[theme = "neon"]"#),
+            BackendError::InvalidTheme(_)
+        ));
+
+        Ok(())
+    }
+
+    #[test]
+    fn anchors() -> Result<()> {
+        assert_eq!(
+            fragment(r##"anchor["top"] #["#top"](Top)"##)?,
+            r##"<a id="top"></a><a href="#top">Top</a>"##
+        );
+        assert_eq!(
+            fragment(r##"#["#intro"](intro) header(Intro)"##)?,
+            r##"<a href="#intro">intro</a><h1 id="intro">Intro</h1>"##
+        );
+
+        let mut options = fragment_options();
+        options.html.id_prefix = "doc-".to_owned();
+        assert_eq!(
+            parse_with_options(r##"header(Hi) #["#hi"](x)"##, &options)?,
+            r##"<h1 id="doc-hi">Hi</h1><a href="#doc-hi">x</a>"##
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn duplicate_anchor_ids() -> Result<()> {
+        let code = r#"anchor["a"] box { anchor["a"] } anchor["b"]"#;
+        let mut options = Options::default();
+        assert_eq!(lints(code, &options)?, ["duplicate_id"]);
+        options.single_pass = true;
+        assert_eq!(lints(code, &options)?, ["duplicate_id"]);
+
+        // Spans of the cached anchors move along with their components
+        let mut converter = IncrementalConverter::new(Options::default());
+        converter.convert_with_diagnostics(&format!("@(x) {code}"))?;
+        let code = format!("@(xyz) {code}");
+        let diagnostics = converter.convert_with_diagnostics(&code)?;
+        let [MarkermlWarning::Backend(BackendWarning::DuplicateId(warning))] =
+            diagnostics.warnings.as_slice()
+        else {
+            panic!("Unexpected warnings: {:?}", diagnostics.warnings);
+        };
+        assert_eq!(warning.first.start.offset(), code.find(r#""a""#).unwrap());
+        assert_eq!(warning.span.start.offset(), code.rfind(r#""a""#).unwrap());
+
+        Ok(())
+    }
+
+    #[test]
+    fn image_sources() -> Result<()> {
+        let mut options = fragment_options();
+        options
+            .html
+            .image_sources
+            .insert("a.png".to_owned(), "data:x".to_owned());
+        assert_eq!(
+            parse_with_options(r#"image["a.png"] image["b.png"]"#, &options)?,
+            r#"<img src="data:x"/><img src="b.png"/>"#
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn link_validation() -> Result<()> {
+        let code = r#"#["javascript:alert(1)"](a) #["https://a.com"](b) #[""](c)"#;
+        assert_eq!(
+            lints(code, &fragment_options())?,
+            ["invalid_link", "invalid_link"]
+        );
+
+        let mut options = fragment_options();
+        options.link_base = Some(PathBuf::from(env!("CARGO_MANIFEST_DIR")));
+        assert_eq!(
+            lints(r#"#["Cargo.toml"](a) #["missing.md"](b)"#, &options)?,
+            ["missing_link_target"]
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn inline_components() -> Result<()> {
+        assert_eq!(
+            fragment(r#"paragraph(See $#["https://a"](the $@[bold](docs)))"#)?,
+            r#"<p>See <a href="https://a">the <strong>docs</strong></a></p>"#
+        );
+        assert!(matches!(
+            backend_error("@(a $box(x))"),
+            BackendError::NotInlineComponent(_)
+        ));
+
+        Ok(())
+    }
+
+    #[test]
+    fn formatting() -> Result<()> {
+        assert_eq!(
+            fragment("let x = \"3.14159\"\nlet n = 7\nparagraph(${x:.2} [${x:>8}] ${n:03})")?,
+            "<p>3.14 [ 3.14159] 007</p>"
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn preserve_newlines() -> Result<()> {
+        let mut options = fragment_options();
+        options.html.preserve_newlines = true;
+        let code = "paragraph[attr:data-x = \"1\"](\n    Roses are red,\n    violets are $@[bold](blue)\n\n    Second stanza\n)";
+        assert_eq!(
+            parse_with_options(code, &options)?,
+            r#"<p data-x="1">Roses are red,<br/>violets are <strong>blue</strong></p><p data-x="1">Second stanza</p>"#
+        );
+        assert_eq!(
+            parse_with_options("quote(One\n\n    Two)", &options)?,
+            "<blockquote><p>One</p><p>Two</p></blockquote>"
+        );
+        assert_eq!(
+            parse_with_options("@(a\nb)", &options)?,
+            "<span>a<br/>b</span>"
+        );
+        assert_eq!(fragment("@(a\nb)")?, "<span>a b</span>");
+
+        Ok(())
+    }
+
+    #[test]
+    fn option_stylesheets() -> Result<()> {
+        let mut options = Options::default();
+        options.html.stylesheets = vec!["x\".css".to_owned()];
+        assert_eq!(
+            parse_with_options("@(a)", &options)?,
+            concat!(
+                r#"<!DOCTYPE html><html><head><link href="x&quot;.css" rel="stylesheet"></head>"#,
+                "<body><main><span>a</span></main></body></html>"
+            )
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn nonce() -> Result<()> {
+        let mut options = Options::default();
+        options.html.nonce = Some("a\"b".to_owned());
+        options.html.styles = vec!["p{}".to_owned()];
+        assert_eq!(
+            parse_with_options(r#"script["app.js"] @(a)"#, &options)?,
+            concat!(
+                r#"<!DOCTYPE html><html><head><style nonce="a&quot;b">p{}</style>"#,
+                r#"<script src="app.js" nonce="a&quot;b"></script></head>"#,
+                "<body><main><span>a</span></main></body></html>"
+            )
+        );
+
+        let mut options = fragment_options();
+        options.html.nonce = Some("a\"b".to_owned());
+        options.html.css = CssMode::Stylesheet;
+        assert_eq!(
+            parse_with_options("@[padding = 1](a)", &options)?,
+            r#"<style nonce="a&quot;b">.mml-e7b114d7 { padding: 1px }</style><span class="mml-e7b114d7">a</span>"#
+        );
+
+        Ok(())
+    }
+}
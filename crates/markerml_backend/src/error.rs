@@ -17,10 +17,57 @@ pub enum BackendError {
     #[error(transparent)]
     #[diagnostic(transparent)]
     TypeMismatch(#[from] TypeMismatchError),
-    #[error("Unimplemented")]
-    Unimplemented,
-    #[error("TODO")]
-    Todo,
+    /// Value of the style property isn't valid CSS value
+    #[error(transparent)]
+    #[diagnostic(transparent)]
+    InvalidStyleValue(#[from] InvalidStyleValueError),
+    /// Language of the code block isn't valid class name
+    #[error(transparent)]
+    #[diagnostic(transparent)]
+    InvalidLanguage(#[from] InvalidLanguageError),
+    /// Void component has children or text
+    #[error(transparent)]
+    #[diagnostic(transparent)]
+    UnexpectedContent(#[from] UnexpectedContentError),
+    /// Component doesn't accept the flag
+    #[error(transparent)]
+    #[diagnostic(transparent)]
+    UnknownFlag(#[from] UnknownFlagError),
+    /// Mutually exclusive flags are set on the same component
+    #[error(transparent)]
+    #[diagnostic(transparent)]
+    ConflictingFlags(#[from] ConflictingFlagsError),
+    /// Header level is outside of the 1 to 6 range
+    #[error(transparent)]
+    #[diagnostic(transparent)]
+    InvalidHeaderLevel(#[from] InvalidHeaderLevelError),
+    /// Alignment property has unknown value
+    #[error(transparent)]
+    #[diagnostic(transparent)]
+    InvalidAlignValue(#[from] InvalidAlignValueError),
+    /// Component is neither builtin nor defined
+    #[error(transparent)]
+    #[diagnostic(transparent)]
+    UnknownComponent(#[from] UnknownComponentError),
+    /// Component definition has the name of the builtin component
+    #[error(transparent)]
+    #[diagnostic(transparent)]
+    ReservedComponentName(#[from] ReservedComponentNameError),
+    /// Page head component is given inside of another component
+    #[error(transparent)]
+    #[diagnostic(transparent)]
+    NestedPage(#[from] NestedPageError),
+    /// Theme of the page isn't one of the built-in themes
+    #[error(transparent)]
+    #[diagnostic(transparent)]
+    InvalidTheme(#[from] InvalidThemeError),
+    /// Component inside the text isn't an inline one
+    #[error(transparent)]
+    #[diagnostic(transparent)]
+    NotInlineComponent(#[from] NotInlineComponentError),
+    /// Generated HTML couldn't be written into the output
+    #[error("Couldn't write generated HTML")]
+    Io(#[from] std::io::Error),
 }
 
 #[derive(Debug, Error, Diagnostic)]
@@ -52,3 +99,243 @@ pub struct TypeMismatchError {
     #[label("Value defined here")]
     pub span: Span,
 }
+
+#[derive(Debug, Error, Diagnostic)]
+#[error("Invalid value '{value}' of the style property '{name}'")]
+pub struct InvalidStyleValueError {
+    /// Name of the property
+    pub name: String,
+    /// Value of the property
+    pub value: String,
+    /// Description of the values that the property accepts
+    #[help]
+    pub help: String,
+    /// Span of the value
+    #[label("Value defined here")]
+    pub span: Span,
+}
+
+#[derive(Debug, Error, Diagnostic)]
+#[error("Invalid language '{value}' of the code block")]
+#[diagnostic(help("Use name of the language like `rust` or `c++`"))]
+pub struct InvalidLanguageError {
+    /// Value of the property
+    pub value: String,
+    /// Span of the value
+    #[label("Value defined here")]
+    pub span: Span,
+}
+
+#[derive(Debug, Error, Diagnostic)]
+#[error("Component '{name}' can't have children or text")]
+#[diagnostic(help("Remove children or text from the component"))]
+pub struct UnexpectedContentError {
+    /// Name of the component
+    pub name: String,
+    /// Span of the component name
+    #[label("Component")]
+    pub component: Span,
+    /// Span of the text or the first child
+    #[label("Content")]
+    pub content: Span,
+}
+
+#[derive(Debug, Error, Diagnostic)]
+#[error("Unknown theme '{value}' of the page")]
+pub struct InvalidThemeError {
+    /// Value of the property
+    pub value: String,
+    /// Names of the built-in themes
+    #[help]
+    pub help: String,
+    /// Span of the value
+    #[label("Value defined here")]
+    pub span: Span,
+}
+
+#[derive(Debug, Error, Diagnostic)]
+#[error("Component '{name}' of the page head must be at the top level")]
+#[diagnostic(help("Move the component out of its parent"))]
+pub struct NestedPageError {
+    /// Name of the component
+    pub name: String,
+    /// Span of the component
+    #[label("Component")]
+    pub span: Span,
+}
+
+#[derive(Debug, Error, Diagnostic)]
+#[error("Component '{name}' can't be inside the text")]
+#[diagnostic(help("{help}"))]
+pub struct NotInlineComponentError {
+    /// Name of the component
+    pub name: String,
+    /// Inline components, that can be used instead
+    pub help: String,
+    /// Span of the component
+    #[label("Component")]
+    pub span: Span,
+}
+
+#[derive(Debug, Error, Diagnostic)]
+#[error("Unknown flag '{name}' of the text")]
+#[diagnostic(help("Text accepts `bold`, `italic`, `underline`, `strike` and `mono` flags"))]
+pub struct UnknownFlagError {
+    /// Name of the flag
+    pub name: String,
+    /// Span of the flag
+    #[label("Flag used here")]
+    pub span: Span,
+}
+
+#[derive(Debug, Error, Diagnostic)]
+#[error("Unknown component '{name}'")]
+pub struct UnknownComponentError {
+    /// Name of the component
+    pub name: String,
+    /// Name of the closest builtin or defined component
+    pub suggestion: Option<String>,
+    /// Suggestion to use the closest component
+    #[help]
+    pub help: Option<String>,
+    /// Span of the component name
+    #[label("Component used here")]
+    pub span: Span,
+}
+
+#[derive(Debug, Error, Diagnostic)]
+#[error("Component '{name}' is builtin and can't be redefined")]
+#[diagnostic(help("Rename the component, for example to `my_{name}`"))]
+pub struct ReservedComponentNameError {
+    /// Name of the component
+    pub name: String,
+    /// Span with name of the component definition
+    #[label("Defined here")]
+    pub span: Span,
+}
+
+#[derive(Debug, Error, Diagnostic)]
+#[error("Flags '{first}' and '{second}' can't be used together")]
+#[diagnostic(help("Remove one of the flags"))]
+pub struct ConflictingFlagsError {
+    /// Name of the first flag
+    pub first: String,
+    /// Name of the second flag
+    pub second: String,
+    /// Span of the first flag
+    #[label("First flag")]
+    pub first_span: Span,
+    /// Span of the second flag
+    #[label("Conflicting flag")]
+    pub second_span: Span,
+}
+
+#[derive(Debug, Error, Diagnostic)]
+#[error("Invalid header level {level}")]
+#[diagnostic(help("Header level must be between 1 and 6"))]
+pub struct InvalidHeaderLevelError {
+    /// Value of the level
+    pub level: i64,
+    /// Span of the value
+    #[label("Value defined here")]
+    pub span: Span,
+}
+
+#[derive(Debug, Error, Diagnostic)]
+#[error("Invalid value '{value}' of the alignment property '{name}'")]
+#[diagnostic(help("Use one of `start`, `center` or `end`"))]
+pub struct InvalidAlignValueError {
+    /// Name of the property
+    pub name: String,
+    /// Value of the property
+    pub value: String,
+    /// Span of the value
+    #[label("Value defined here")]
+    pub span: Span,
+}
+
+/// Represents HTML generation warning, that doesn't prevent the generation
+#[derive(Debug, Clone, Error, Diagnostic)]
+pub enum BackendWarning {
+    /// Builtin component has property that it doesn't understand
+    #[error(transparent)]
+    #[diagnostic(transparent)]
+    UnknownProperty(#[from] UnknownPropertyWarning),
+    /// URL of the link is empty or has malformed scheme
+    #[error(transparent)]
+    #[diagnostic(transparent)]
+    InvalidLink(#[from] InvalidLinkWarning),
+    /// Relative link points to the file, that doesn't exist
+    #[error(transparent)]
+    #[diagnostic(transparent)]
+    MissingLinkTarget(#[from] MissingLinkTargetWarning),
+    /// Explicit id of the anchor is already used by another anchor
+    #[error(transparent)]
+    #[diagnostic(transparent)]
+    DuplicateId(#[from] DuplicateIdWarning),
+}
+
+#[derive(Debug, Clone, Error, Diagnostic)]
+#[error("Unknown property '{name}' of the component '{component}'")]
+#[diagnostic(severity(Warning), code(unknown_property))]
+pub struct UnknownPropertyWarning {
+    /// Name of the component
+    pub component: String,
+    /// Name of the property
+    pub name: String,
+    /// Name of the closest property of the component
+    pub suggestion: Option<String>,
+    /// Suggestion to use the closest property
+    #[help]
+    pub help: Option<String>,
+    /// Span of the property name
+    #[label("Property used here")]
+    pub span: Span,
+}
+
+#[derive(Debug, Clone, Error, Diagnostic)]
+#[error("Invalid URL '{url}' of the link")]
+#[diagnostic(severity(Warning), code(invalid_link))]
+pub struct InvalidLinkWarning {
+    /// URL of the link
+    pub url: String,
+    /// Description of the problem
+    #[help]
+    pub help: String,
+    /// Span of the URL
+    #[label("URL defined here")]
+    pub span: Span,
+}
+
+#[derive(Debug, Clone, Error, Diagnostic)]
+#[error("Link points to missing file '{path}'")]
+#[diagnostic(
+    severity(Warning),
+    code(missing_link_target),
+    help("Path of the link is relative to the document")
+)]
+pub struct MissingLinkTargetWarning {
+    /// Path of the file, as given by the link
+    pub path: String,
+    /// Span of the URL
+    #[label("URL defined here")]
+    pub span: Span,
+}
+
+#[derive(Debug, Clone, Error, Diagnostic)]
+#[error("Duplicate id '{id}' of the anchor")]
+#[diagnostic(
+    severity(Warning),
+    code(duplicate_id),
+    help("Links to the id lead only to the first anchor")
+)]
+pub struct DuplicateIdWarning {
+    /// Id of the anchor
+    pub id: String,
+    /// Span of the repeated id
+    #[label("Id used again here")]
+    pub span: Span,
+    /// Span of the first use of the id
+    #[label("First used here")]
+    pub first: Span,
+}
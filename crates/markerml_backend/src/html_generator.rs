@@ -1,52 +1,732 @@
 use crate::error::*;
-use build_html::{Html, HtmlChild, HtmlContainer, HtmlElement, HtmlPage, HtmlTag};
-use itertools::{Either, Itertools};
-use markerml_middleend::{ir, Span};
-use std::collections::HashSet;
-
-/// HTML string generator
-pub struct HtmlGenerator {
-    ir: Option<ir::Module<Span>>,
-    definitions: HashSet<ir::ComponentDefinition<Span>>,
+use crate::ids::IdGenerator;
+use crate::manifest;
+use crate::theme::Theme;
+use build_html::{escape_html, Html, HtmlChild, HtmlElement, HtmlPage, HtmlTag};
+use markerml_middleend::suggestions::closest_name;
+use markerml_middleend::{ir, SourceMap, Span};
+#[cfg(feature = "parallel")]
+use rayon::prelude::*;
+use std::borrow::Cow;
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::fmt::{self, Write};
+use std::io;
+use std::str::FromStr;
+use std::sync::{LazyLock, Mutex, MutexGuard, PoisonError};
+
+/// Options of the HTML generation
+#[derive(Debug, Clone, Default)]
+pub struct HtmlGeneratorOptions {
+    /// URLs of the stylesheets, linked from the page head
+    pub stylesheets: Vec<String>,
+    /// CSS, inlined into the page head after the theme
+    pub styles: Vec<String>,
+    /// Built-in theme
+    pub theme: Option<Theme>,
+    /// Prefix of the generated ids, such as ids of the headers,
+    /// so that multiple documents can be embedded into one page
+    pub id_prefix: String,
+    /// Nonce of the generated `<style>` blocks and `<script>` tags, so that the page
+    /// can be served under strict Content-Security-Policy.
+    /// Inline `style` attributes of the elements aren't covered by it,
+    /// unless they are collected with [`CssMode::Stylesheet`]
+    pub nonce: Option<String>,
+    /// Known images by their source, whose dimensions and resized variants are emitted
+    pub images: BTreeMap<String, ImageInfo>,
+    /// Sources, that are emitted instead of the given sources of the images,
+    /// such as data URIs of the inlined images or paths of the copied ones
+    pub image_sources: BTreeMap<String, String>,
+    /// How styles of the components are emitted
+    pub css: CssMode,
+    /// Title of the page
+    pub title: Option<String>,
+    /// Whether to put nested elements on separate indented lines instead of minified markup
+    pub pretty: bool,
+    /// Whether newlines of the multiline text are kept: single newlines are emitted
+    /// as line breaks and blank lines as paragraph breaks, instead of spaces
+    pub preserve_newlines: bool,
+    /// Page layout of the print-oriented document. Page is emitted for screen if it's missing
+    pub print: Option<PrintOptions>,
+}
+
+/// Page layout of the print-oriented document,
+/// which is split into pages by the `page_break` components
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PrintOptions {
+    /// CSS page size, such as `A4`, `letter` or `210mm 297mm`
+    pub page_size: String,
+    /// CSS margin of the pages
+    pub margin: String,
+}
+
+impl Default for PrintOptions {
+    fn default() -> Self {
+        PrintOptions {
+            page_size: "A4".to_owned(),
+            margin: "2cm".to_owned(),
+        }
+    }
 }
 
-impl HtmlGenerator {
+impl PrintOptions {
+    /// Returns print CSS with the page layout, which also keeps
+    /// headers with the following content and doesn't split code blocks, quotes and images
+    pub fn css(&self) -> String {
+        format!(
+            "@page {{ size: {}; margin: {} }} \
+             @media print {{ body {{ margin: 0 }} \
+             h1, h2, h3, h4, h5, h6 {{ break-after: avoid }} \
+             pre, blockquote, img {{ break-inside: avoid }} }}",
+            self.page_size, self.margin
+        )
+    }
+}
+
+/// Way of emitting styles of the components, such as layout of the boxes and style properties
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum CssMode {
+    /// Styles are emitted as `style` attributes of the elements
+    #[default]
+    Inline,
+    /// Styles are collected into a `<style>` block with generated class names,
+    /// which deduplicates repeated styles and is covered by the nonce
+    Stylesheet,
+}
+
+impl CssMode {
+    pub const ALL: [CssMode; 2] = [CssMode::Inline, CssMode::Stylesheet];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            CssMode::Inline => "inline",
+            CssMode::Stylesheet => "stylesheet",
+        }
+    }
+}
+
+impl fmt::Display for CssMode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+impl FromStr for CssMode {
+    type Err = String;
+
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        CssMode::ALL
+            .into_iter()
+            .find(|mode| mode.name() == name)
+            .ok_or_else(|| {
+                let names = CssMode::ALL.map(CssMode::name).join(", ");
+                format!("Unknown CSS mode '{name}', expected one of: {names}")
+            })
+    }
+}
+
+/// Dimensions and resized variants of the image, which prevent layout shift
+/// and let the browser choose the size
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ImageInfo {
+    pub width: u32,
+    pub height: u32,
+    /// Sources of the resized variants along with their widths
+    pub variants: Vec<(String, u32)>,
+}
+
+/// Estimated length of the element markup without the text, such as tags and styles
+const ELEMENT_SIZE: usize = 48;
+/// End of the page markup, after which no content is added
+const PAGE_END: &str = "</body></html>";
+/// Estimated length of the page markup without the content and styles
+const PAGE_SIZE: usize = 128;
+/// Flags of the text component along with the elements they wrap the text into
+const TEXT_FLAG_TAGS: &[(&str, &str)] = &[
+    ("bold", "strong"),
+    ("italic", "em"),
+    ("underline", "u"),
+    ("strike", "s"),
+    ("mono", "code"),
+];
+/// Attribute with the position of the component in the source
+const SPAN_ATTRIBUTE: &str = "data-mml-span";
+/// Default values of the builtin component properties from the manifest,
+/// given as the component name, property name and the value
+static DEFAULT_VALUES: LazyLock<Vec<(&str, &str, ir::Value<Span>)>> = LazyLock::new(|| {
+    manifest::BUILTIN_COMPONENTS
+        .iter()
+        .flat_map(|component| {
+            component.properties.iter().filter_map(|property| {
+                let value = property.default_value?;
+                let kind = match property.ty {
+                    manifest::PropertyType::Integer => ir::ValueKind::Integer(value.parse().ok()?),
+                    manifest::PropertyType::String | manifest::PropertyType::Length => {
+                        ir::ValueKind::String(ir::StringValue::from_literal(value))
+                    }
+                    manifest::PropertyType::Flag => return None,
+                };
+                Some((component.name, property.name, kind.spanned(Span::default())))
+            })
+        })
+        .collect()
+});
+/// Indentation of the nested elements in the pretty-printed markup
+const INDENT: &str = "  ";
+
+/// Characters that can't appear in the style values,
+/// since they would end the declaration or the attribute
+const INVALID_STYLE_CHARACTERS: &[char] = &[';', '"', '\'', '<', '>', '{', '}'];
+
+/// Options used when none are given
+static DEFAULT_OPTIONS: HtmlGeneratorOptions = HtmlGeneratorOptions {
+    stylesheets: Vec::new(),
+    styles: Vec::new(),
+    theme: None,
+    id_prefix: String::new(),
+    nonce: None,
+    images: BTreeMap::new(),
+    image_sources: BTreeMap::new(),
+    css: CssMode::Inline,
+    title: None,
+    pretty: false,
+    preserve_newlines: false,
+    print: None,
+};
+
+/// Ids of the components, assigned in the document order before the emission,
+/// so that they don't depend on the order of the parallel emission
+struct AssignedIds {
+    generator: IdGenerator,
+    /// Ids by address of the component, which is borrowed for the whole emission
+    ids: HashMap<usize, String>,
+    /// Ids generated since they were last taken
+    generated: Vec<String>,
+}
+
+/// Metadata and assets of the page head, given by the top-level head components
+#[derive(Debug, Default)]
+struct PageHead {
+    title: Option<String>,
+    description: Option<String>,
+    lang: Option<String>,
+    favicon: Option<String>,
+    charset: Option<String>,
+    theme: Option<Theme>,
+    stylesheets: Vec<String>,
+    scripts: Vec<Script>,
+}
+
+/// External script of the page head
+#[derive(Debug)]
+struct Script {
+    src: String,
+    is_deferred: bool,
+    is_async: bool,
+    is_module: bool,
+}
+
+/// HTML string generator, borrowing the IR for the whole emission
+pub struct HtmlGenerator<'a> {
+    items: &'a [ir::ModuleItem<Span>],
+    options: &'a HtmlGeneratorOptions,
+    ids: Mutex<AssignedIds>,
+    /// Styles by their class name, which are collected with [`CssMode::Stylesheet`].
+    /// Names are derived from the styles, so they don't depend on the order of the emission
+    classes: Mutex<BTreeMap<String, String>>,
+    /// Source code, whose positions are emitted for every component
+    source: Option<SourceMap<'a>>,
+    head: Mutex<PageHead>,
+}
+
+impl<'a> HtmlGenerator<'a> {
     /// Creates new instance from the given IR
-    pub fn new(ir: ir::Module<Span>) -> Self {
+    pub fn new(ir: &'a ir::Module<Span>) -> Self {
+        Self::with_options(ir, &DEFAULT_OPTIONS)
+    }
+
+    /// Creates new instance from the given IR and generation options
+    pub fn with_options(ir: &'a ir::Module<Span>, options: &'a HtmlGeneratorOptions) -> Self {
+        HtmlGenerator {
+            items: &ir.items,
+            options,
+            ids: Self::create_ids(options),
+            classes: Mutex::default(),
+            source: None,
+            head: Mutex::default(),
+        }
+    }
+
+    /// Creates new instance without stored IR, which emits
+    /// components one by one with [`HtmlGenerator::write_component`]
+    pub fn streaming(options: &'a HtmlGeneratorOptions) -> Self {
         HtmlGenerator {
-            ir: Some(ir),
-            definitions: HashSet::new(),
+            items: &[],
+            options,
+            ids: Self::create_ids(options),
+            classes: Mutex::default(),
+            source: None,
+            head: Mutex::default(),
         }
     }
 
-    /// Generates HTML from the stored IR
-    pub fn generate(mut self) -> Result<String, BackendError> {
-        let module = self.ir.take().unwrap();
-        let root = self.emit_module(module)?;
+    /// Emits `data-mml-span="line:column-line:column"` attribute on the element of every component
+    /// with its position in the given source, so that tooling can map elements back to the code.
+    /// Components, expanded from the definitions, point into the definition body
+    pub fn with_source_spans(mut self, source: &'a str) -> Self {
+        self.source = Some(SourceMap::new(source));
+        self
+    }
+
+    fn create_ids(options: &HtmlGeneratorOptions) -> Mutex<AssignedIds> {
+        Mutex::new(AssignedIds {
+            generator: IdGenerator::new(options.id_prefix.as_str()),
+            ids: HashMap::new(),
+            generated: Vec::new(),
+        })
+    }
 
-        Ok(root.to_html_string())
+    /// Generates HTML page from the stored IR
+    pub fn generate(&self) -> Result<String, BackendError> {
+        let mut content = String::with_capacity(self.estimate_size());
+        for child in self.emit_module()? {
+            self.write_child(&child, &mut content);
+        }
+
+        let mut html = String::with_capacity(PAGE_SIZE + content.len());
+        self.write_page_start(&mut html);
+        self.write_main(&content, &mut html);
+        Self::write_page_end(&mut html);
+
+        Ok(html)
     }
 
-    fn emit_module(&mut self, module: ir::Module<Span>) -> Result<HtmlPage, BackendError> {
-        let mut items = Vec::new();
+    /// Writes HTML page from the stored IR into the given writer, emitting top-level components
+    /// one by one, so that markup of the whole module isn't kept in memory.
+    /// With [`CssMode::Stylesheet`] the content is still generated at once,
+    /// since the collected classes precede it
+    pub fn generate_to_writer(&self, writer: &mut impl io::Write) -> Result<(), BackendError> {
+        if self.options.css == CssMode::Stylesheet {
+            writer.write_all(self.generate()?.as_bytes())?;
+            return Ok(());
+        }
 
-        let (components, definitions): (Vec<_>, HashSet<_>) =
-            module.items.into_iter().partition_map(|item| match item {
-                ir::ModuleItem::Component(component) => Either::Left(component),
-                ir::ModuleItem::ComponentDefinition(def) => Either::Right(def),
-            });
-        self.definitions = definitions;
+        let components = self.content_components()?;
+        self.assign_ids(components.iter().copied());
 
-        for component in components {
-            items.push(self.emit_component(&component, None)?);
+        let mut html = String::with_capacity(PAGE_SIZE);
+        self.write_page_start(&mut html);
+        if components.is_empty() {
+            self.write_main("", &mut html);
+        } else if self.options.pretty {
+            html.push_str("<main>\n");
+        } else {
+            html.push_str("<main>");
+        }
+        writer.write_all(html.as_bytes())?;
+
+        for component in &components {
+            html.clear();
+            self.write_child(&self.emit_component(component, None)?, &mut html);
+            writer.write_all(html.as_bytes())?;
         }
 
+        html.clear();
+        if !components.is_empty() {
+            html.push_str("</main>");
+            if self.options.pretty {
+                html.push('\n');
+            }
+        }
+        Self::write_page_end(&mut html);
+        writer.write_all(html.as_bytes())?;
+
+        Ok(())
+    }
+
+    /// Returns sources of the images, referenced by the stored IR
+    pub fn image_sources(&self) -> BTreeSet<String> {
+        fn collect(component: &ir::Component<Span>, sources: &mut BTreeSet<String>) {
+            if component.name.as_str() == "image" {
+                let properties = HtmlGenerator::effective_properties(component);
+                if let Some(Ok(src)) = properties.get("src").map(HtmlGenerator::cast_to_string) {
+                    sources.insert(src.into_owned());
+                }
+            }
+            for child in component_children(component) {
+                collect(child, sources);
+            }
+        }
+
+        let mut sources = BTreeSet::new();
+        for item in self.items {
+            if let ir::ModuleItem::Component(component) = item {
+                collect(component, &mut sources);
+            }
+        }
+
+        sources
+    }
+
+    /// Writes beginning of the page with styles, after which the content goes.
+    /// Includes the classes, collected from the components written so far
+    pub fn write_page_start(&self, html: &mut String) {
         let mut page = HtmlPage::new();
-        let mut main = HtmlElement::new(HtmlTag::Main);
-        main.children = items;
-        page.add_raw(main.to_html_string());
+        let head = self.lock_head();
+        if let Some(charset) = &head.charset {
+            page.add_meta([("charset", escape_html(charset).as_str())]);
+        }
+        if let Some(title) = self.options.title.as_ref().or(head.title.as_ref()) {
+            page.add_title(escape_html(title));
+        }
+        if let Some(description) = &head.description {
+            page.add_meta([
+                ("name", "description"),
+                ("content", &escape_html(description)),
+            ]);
+        }
+        if let Some(favicon) = &head.favicon {
+            page.add_head_link(escape_html(favicon), "icon");
+        }
+        let nonce = self.options.nonce.as_deref().map(escape_html);
+        let nonce = nonce.as_deref().map(|nonce| ("nonce", nonce));
+        if let Some(theme) = self.options.theme.or(head.theme) {
+            page.add_style_attr(theme.css(), nonce);
+        }
+        if let Some(print) = &self.options.print {
+            page.add_style_attr(print.css(), nonce);
+        }
+        for stylesheet in &self.options.stylesheets {
+            page.add_stylesheet(escape_html(stylesheet));
+        }
+        for stylesheet in &head.stylesheets {
+            page.add_stylesheet(escape_html(stylesheet));
+        }
+        for style in &self.options.styles {
+            page.add_style_attr(style, nonce);
+        }
+        let classes = self.take_classes();
+        if !classes.is_empty() {
+            page.add_style_attr(classes, nonce);
+        }
+        for script in &head.scripts {
+            let attributes = [
+                script.is_module.then_some(("type", "module")),
+                script.is_deferred.then_some(("defer", "")),
+                script.is_async.then_some(("async", "")),
+                nonce,
+            ];
+            page.add_script_link_attr(escape_html(&script.src), attributes.into_iter().flatten());
+        }
+
+        // Page is rendered without the content, which is then written
+        // directly into the output to avoid copying large markup
+        let mut page = page.to_html_string();
+        if let Some(lang) = &head.lang {
+            page = page.replacen(
+                "<html>",
+                &format!("<html lang=\"{}\">", escape_html(lang)),
+                1,
+            );
+        }
+        let start = page.strip_suffix(PAGE_END).unwrap_or(&page);
+        html.push_str(start);
+        if self.options.pretty {
+            html.push('\n');
+        }
+    }
+
+    /// Writes `<main>` element with the given content of the page
+    pub fn write_main(&self, content: &str, html: &mut String) {
+        if content.is_empty() {
+            html.push_str("<main></main>");
+        } else if self.options.pretty {
+            let _ = write!(html, "<main>\n{content}</main>");
+        } else {
+            let _ = write!(html, "<main>{content}</main>");
+        }
+        if self.options.pretty {
+            html.push('\n');
+        }
+    }
+
+    /// Writes end of the page, started with [`HtmlGenerator::write_page_start`]
+    pub fn write_page_end(html: &mut String) {
+        html.push_str(PAGE_END);
+    }
+
+    /// Writes markup of the single component.
+    /// Generated ids stay unique across the written components
+    pub fn write_component(
+        &self,
+        component: &ir::Component<Span>,
+        html: &mut String,
+    ) -> Result<(), BackendError> {
+        if manifest::HEAD_COMPONENTS.contains(&component.name.as_str()) {
+            return self.apply_head(component);
+        }
+        self.assign_ids([component]);
+        let child = self.emit_component(component, None);
+        // Component might be dropped after this call, so its address can be reused
+        self.lock_ids().ids.clear();
+        self.write_child(&child?, html);
+
+        Ok(())
+    }
+
+    /// Takes ids, generated for the components written since the last call
+    pub fn take_generated_ids(&self) -> Vec<String> {
+        std::mem::take(&mut self.lock_ids().generated)
+    }
+
+    /// Marks ids as used, as if the components they were generated for were written.
+    /// Allows to reuse previously written markup, keeping ids of the following components
+    pub fn reserve_ids(&self, ids: &[String]) {
+        let mut assigned = self.lock_ids();
+        for id in ids {
+            assigned.generator.reserve(id);
+        }
+    }
+
+    /// Writes `<style>` block with the classes, collected from the components
+    /// written so far, if there are any. Written classes are not included again
+    pub fn write_classes(&self, html: &mut String) {
+        let classes = self.take_classes();
+        if classes.is_empty() {
+            return;
+        }
+
+        html.push_str("<style");
+        if let Some(nonce) = &self.options.nonce {
+            let _ = write!(html, " nonce=\"{}\"", escape_html(nonce));
+        }
+        let _ = write!(html, ">{classes}</style>");
+        if self.options.pretty {
+            html.push('\n');
+        }
+    }
+
+    /// Generates only the component markup from the stored IR,
+    /// without the page and `<main>` wrappers. Page styles are not included,
+    /// except for the `<style>` block with the collected classes
+    pub fn generate_fragment(&self) -> Result<String, BackendError> {
+        let children = self.emit_module()?;
+
+        let mut html = String::with_capacity(self.estimate_size());
+        self.write_classes(&mut html);
+        for child in &children {
+            self.write_child(child, &mut html);
+        }
+
+        Ok(html)
+    }
+
+    /// Writes markup of the emitted top-level element
+    fn write_child(&self, child: &HtmlChild, html: &mut String) {
+        if self.options.pretty {
+            write_pretty(child, 0, html);
+        } else {
+            let _ = write!(html, "{child}");
+        }
+    }
+
+    /// Estimates length of the generated markup from the number of components
+    /// and length of their text, so that the output is allocated at once
+    fn estimate_size(&self) -> usize {
+        fn component_size(component: &ir::Component<Span>) -> usize {
+            let text = component
+                .text
+                .iter()
+                .flat_map(|text| &text.segments)
+                .chain(
+                    component
+                        .properties
+                        .default
+                        .iter()
+                        .chain(
+                            component
+                                .properties
+                                .named_properties
+                                .iter()
+                                .map(|prop| &prop.value),
+                        )
+                        .filter_map(|value| match &value.kind {
+                            ir::ValueKind::String(string) => Some(&string.segments),
+                            _ => None,
+                        })
+                        .flatten(),
+                )
+                .map(|segment| match &segment.kind {
+                    ir::InterpolationSegmentKind::Literal(literal) => literal.len(),
+                    ir::InterpolationSegmentKind::Variable(_)
+                    | ir::InterpolationSegmentKind::Formatted(_) => 0,
+                    ir::InterpolationSegmentKind::Message(key) => key.len(),
+                    ir::InterpolationSegmentKind::Component(component) => component_size(component),
+                    ir::InterpolationSegmentKind::Newline => 1,
+                })
+                .sum::<usize>();
+
+            ELEMENT_SIZE + text + component.children.iter().map(component_size).sum::<usize>()
+        }
+
+        self.items
+            .iter()
+            .map(|item| match item {
+                ir::ModuleItem::Component(component) => component_size(component),
+                ir::ModuleItem::Constant(_) | ir::ModuleItem::ComponentDefinition(_) => 0,
+            })
+            .sum::<usize>()
+            + ELEMENT_SIZE
+    }
+
+    /// Emits top-level components. They are independent,
+    /// so with the `parallel` feature they are emitted in parallel, preserving the order
+    fn emit_module(&self) -> Result<Vec<HtmlChild>, BackendError> {
+        let components = self.content_components()?;
+        self.assign_ids(components.iter().copied());
+
+        #[cfg(feature = "parallel")]
+        let components = components.into_par_iter();
+        #[cfg(not(feature = "parallel"))]
+        let components = components.into_iter();
+
+        components
+            .map(|component| self.emit_component(component, None))
+            .collect()
+    }
+
+    /// Returns top-level components of the page content,
+    /// applying the head components to the page head instead
+    fn content_components(&self) -> Result<Vec<&'a ir::Component<Span>>, BackendError> {
+        let mut components = Vec::new();
+        for item in self.items {
+            match item {
+                ir::ModuleItem::Component(component)
+                    if manifest::HEAD_COMPONENTS.contains(&component.name.as_str()) =>
+                {
+                    self.apply_head(component)?;
+                }
+                ir::ModuleItem::Component(component) => components.push(component),
+                ir::ModuleItem::Constant(_) | ir::ModuleItem::ComponentDefinition(_) => {}
+            }
+        }
+
+        Ok(components)
+    }
+
+    /// Stores the top-level head component, which is written with the page head.
+    /// Metadata of the later `page` components takes precedence,
+    /// while stylesheets and scripts are included in the order of the components
+    fn apply_head(&self, component: &ir::Component<Span>) -> Result<(), BackendError> {
+        Self::check_no_content(component)?;
+        let properties = Self::effective_properties(component);
+        let get = |name| {
+            properties
+                .get(name)
+                .map(|value| Self::cast_to_string(value).map(Cow::into_owned))
+                .transpose()
+        };
+        let get_required = |name| {
+            Self::get_required_property(component, &properties, name)
+                .and_then(|value| Self::cast_to_string(value).map(Cow::into_owned))
+        };
+
+        let mut head = self.lock_head();
+        let head = &mut *head;
+        match component.name.as_str() {
+            "stylesheet" => {
+                head.stylesheets.push(get_required("href")?);
+                return Ok(());
+            }
+            "script" => {
+                head.scripts.push(Script {
+                    src: get_required("src")?,
+                    is_deferred: properties.has_flag("defer"),
+                    is_async: properties.has_flag("async"),
+                    is_module: properties.has_flag("module"),
+                });
+                return Ok(());
+            }
+            _ => {}
+        }
+        for (field, name) in [
+            (&mut head.title, "title"),
+            (&mut head.description, "description"),
+            (&mut head.lang, "lang"),
+            (&mut head.favicon, "favicon"),
+            (&mut head.charset, "charset"),
+        ] {
+            if let Some(value) = get(name)? {
+                *field = Some(value);
+            }
+        }
+        if let Some(value) = properties.get("theme") {
+            let name = Self::cast_to_string(value)?;
+            let theme = name.parse().map_err(|_| InvalidThemeError {
+                value: name.into_owned(),
+                help: format!("Use one of: {}", manifest::THEME_VALUES.join(", ")),
+                span: value.span.clone(),
+            })?;
+            head.theme = Some(theme);
+        }
 
-        Ok(page)
+        Ok(())
+    }
+
+    /// Assigns ids to the given components and their children in the document order.
+    /// Ids of the anchors are claimed first, so that headers don't take them
+    fn assign_ids<'c>(&self, components: impl IntoIterator<Item = &'c ir::Component<Span>>) {
+        fn claim(assigned: &mut AssignedIds, component: &ir::Component<Span>) {
+            if component.name.as_str() == "anchor" {
+                let properties = HtmlGenerator::effective_properties(component);
+                // Invalid anchor is reported during the emission
+                if let Some(Ok(name)) = properties.get("id").map(HtmlGenerator::cast_to_string) {
+                    let id = assigned.generator.claim(&name);
+                    assigned.generated.push(id.clone());
+                    assigned.ids.insert(component_address(component), id);
+                }
+            }
+            for child in component_children(component) {
+                claim(assigned, child);
+            }
+        }
+
+        fn assign(assigned: &mut AssignedIds, component: &ir::Component<Span>) {
+            if component.name.as_str() == "header" {
+                // Invalid header is reported during the emission
+                if let Ok(text) = HtmlGenerator::get_text(component) {
+                    let id = assigned.generator.generate(&text);
+                    assigned.generated.push(id.clone());
+                    assigned.ids.insert(component_address(component), id);
+                }
+            }
+            for child in component_children(component) {
+                assign(assigned, child);
+            }
+        }
+
+        let components = components.into_iter().collect::<Vec<_>>();
+        let mut assigned = self.lock_ids();
+        for component in &components {
+            claim(&mut assigned, component);
+        }
+        for component in components {
+            assign(&mut assigned, component);
+        }
+    }
+
+    fn get_id(&self, component: &ir::Component<Span>) -> Option<String> {
+        self.lock_ids()
+            .ids
+            .get(&component_address(component))
+            .cloned()
+    }
+
+    fn lock_ids(&self) -> MutexGuard<'_, AssignedIds> {
+        self.ids.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    fn lock_head(&self) -> MutexGuard<'_, PageHead> {
+        self.head.lock().unwrap_or_else(PoisonError::into_inner)
     }
 
     fn emit_component(
@@ -54,10 +734,44 @@ impl HtmlGenerator {
         component: &ir::Component<Span>,
         ctx: Option<&ir::Component<Span>>,
     ) -> Result<HtmlChild, BackendError> {
-        if let Some(component) = self.try_emit_builtin_component(component, ctx)? {
-            Ok(component)
+        if let Some(mut child) = self.try_emit_builtin_component(component, ctx)? {
+            if let HtmlChild::Element(element) = &mut child {
+                for (name, value) in Self::custom_attributes(component)? {
+                    element.add_attribute(name, value);
+                }
+                if let Some(span) = self.source_span(component) {
+                    element.add_attribute(SPAN_ATTRIBUTE, span);
+                }
+                // Empty child prevents self-closing tags like `<div/>`,
+                // which aren't closed in HTML unless the element is void
+                if element.children.is_empty() && !is_void(&element.tag) {
+                    element.children.push(HtmlChild::Raw(String::new()));
+                }
+            }
+            Ok(child)
         } else {
-            Err(BackendError::Unimplemented)
+            Err(self.unknown_component(component).into())
+        }
+    }
+
+    fn unknown_component(&self, component: &ir::Component<Span>) -> UnknownComponentError {
+        let builtins = manifest::BUILTIN_COMPONENTS
+            .iter()
+            .map(|component| component.name);
+        let definitions = self.items.iter().filter_map(|item| match item {
+            ir::ModuleItem::ComponentDefinition(def) => Some(def.name.as_str()),
+            ir::ModuleItem::Constant(_) | ir::ModuleItem::Component(_) => None,
+        });
+        let suggestion =
+            closest_name(component.name.as_str(), builtins.chain(definitions)).map(str::to_owned);
+
+        UnknownComponentError {
+            name: component.name.as_str().to_owned(),
+            help: suggestion
+                .as_ref()
+                .map(|suggestion| format!("Did you mean `{suggestion}`?")),
+            suggestion,
+            span: component.name.span.clone(),
         }
     }
 
@@ -66,44 +780,34 @@ impl HtmlGenerator {
         component: &ir::Component<Span>,
         ctx: Option<&ir::Component<Span>>,
     ) -> Result<Option<HtmlChild>, BackendError> {
+        let properties = Self::effective_properties(component);
         Ok(Some(match component.name.as_str() {
             "box" => {
-                let is_vertical = match (
-                    Self::get_flag_property(component, "vertical"),
-                    Self::get_flag_property(component, "horizontal"),
-                ) {
-                    (true, true) => return Err(BackendError::Todo), // TODO
-                    (true, false) | (false, false) => true,
-                    (false, true) => false,
-                };
+                Self::check_exclusive_flags(&properties, "vertical", "horizontal")?;
+                let is_vertical = !properties.has_flag("horizontal");
                 let flex_direction = if is_vertical { "column" } else { "row" };
-                let x_align = Self::try_get_named_property(component, "x_align")
-                    .map(Self::cast_to_string)
-                    .transpose()?;
-                let y_align = Self::try_get_named_property(component, "y_align")
-                    .map(Self::cast_to_string)
-                    .transpose()?;
-                x_align
-                    .as_ref()
-                    .map(|value| Self::check_align_allowed(value))
-                    .transpose()?;
-                y_align
-                    .as_ref()
-                    .map(|value| Self::check_align_allowed(value))
-                    .transpose()?;
+                let x_align = Self::get_align(&properties, "x_align")?;
+                let y_align = Self::get_align(&properties, "y_align")?;
 
                 let justify_content = if is_vertical { &y_align } else { &x_align };
                 let align_items = if is_vertical { &x_align } else { &y_align };
 
-                let mut style = format!("display: flex; flex-direction: {flex_direction}");
+                let mut style = String::with_capacity(96);
+                let _ = write!(style, "display: flex; flex-direction: {flex_direction}");
                 if let Some(justify_content) = justify_content {
-                    style.push_str(&format!("; justify-content: {justify_content}"));
+                    let _ = write!(style, "; justify-content: {justify_content}");
                 }
                 if let Some(align_items) = align_items {
-                    style.push_str(&format!("; align-items: {align_items}"));
+                    let _ = write!(style, "; align-items: {align_items}");
+                }
+                if let Some(gap) = properties.get("gap") {
+                    let css = Self::style_value(&manifest::GAP_PROPERTY, gap)?;
+                    let _ = write!(style, "; gap: {css}");
                 }
+                Self::write_style_properties(component, &properties, &mut style)?;
 
-                let mut element = HtmlElement::new(HtmlTag::Div).with_attribute("style", style);
+                let (attribute, style) = self.style_attribute(style);
+                let mut element = HtmlElement::new(HtmlTag::Div).with_attribute(attribute, style);
 
                 element.children = component
                     .children
@@ -114,67 +818,293 @@ impl HtmlGenerator {
                 HtmlChild::Element(element)
             }
             "@" => {
-                let text = Self::get_text(component)?;
+                let text = self.emit_text(component, ctx)?;
+                let style = Self::create_style(component, &properties)?;
+                if let Some(flag) = component
+                    .properties
+                    .flag_properties
+                    .iter()
+                    .chain(&component.properties.negated_flags)
+                    .filter(|flag| {
+                        !TEXT_FLAG_TAGS
+                            .iter()
+                            .any(|(name, _)| flag.as_str() == *name)
+                    })
+                    .min_by_key(|flag| flag.span.start)
+                {
+                    return Err(UnknownFlagError {
+                        name: flag.as_str().to_owned(),
+                        span: flag.span.clone(),
+                    }
+                    .into());
+                }
+                let tags = TEXT_FLAG_TAGS
+                    .iter()
+                    .filter(|(flag, _)| properties.has_flag(flag))
+                    .map(|(_, tag)| *tag)
+                    .collect::<Vec<_>>();
+                // Outermost element carries the attributes, and the text is wrapped into the rest
+                let (outer, inner) = tags.split_first().unwrap_or((&"span", &[]));
+
+                let mut html = format!("<{outer}");
+                if !style.is_empty() {
+                    let (attribute, style) = self.style_attribute(style);
+                    let _ = write!(html, " {attribute}=\"{style}\"");
+                }
+                for (name, value) in Self::custom_attributes(component)? {
+                    let _ = write!(html, " {name}=\"{value}\"");
+                }
+                if let Some(span) = self.source_span(component) {
+                    let _ = write!(html, " {SPAN_ATTRIBUTE}=\"{span}\"");
+                }
+                html.push('>');
+                for tag in inner {
+                    let _ = write!(html, "<{tag}>");
+                }
+                html.push_str(&text);
+                for tag in inner.iter().rev() {
+                    let _ = write!(html, "</{tag}>");
+                }
+                let _ = write!(html, "</{outer}>");
 
-                HtmlChild::Raw(format!("<span>{text}</span>"))
+                HtmlChild::Raw(html)
             }
             "#" => {
-                let href =
-                    Self::cast_to_string(Self::get_default_or_named_property(component, "url")?)?;
-                let text = Self::get_text(component)?;
-
-                HtmlChild::Element(
-                    HtmlElement::new(HtmlTag::Link)
-                        .with_attribute("href", href)
-                        .with_child(text.into()),
-                )
+                let href = Self::cast_to_string(Self::get_required_property(
+                    component,
+                    &properties,
+                    "url",
+                )?)?;
+                // Links within the document point to the prefixed ids
+                let href = match href.strip_prefix('#') {
+                    Some(id) if !self.options.id_prefix.is_empty() => {
+                        Cow::Owned(format!("#{}{id}", self.options.id_prefix))
+                    }
+                    _ => href,
+                };
+                let text = self.emit_text(component, ctx)?;
+
+                let element = HtmlElement::new(HtmlTag::Link)
+                    .with_attribute("href", escape_html(&href))
+                    .with_child(text.into());
+
+                HtmlChild::Element(self.with_style(element, component, &properties)?)
             }
             "paragraph" => {
-                let text = Self::get_text(component)?;
+                // Raw text keeps its whitespace and is shown verbatim
+                let element = if component.text.as_ref().is_some_and(|text| text.raw) {
+                    HtmlElement::new(HtmlTag::PreformattedText)
+                        .with_child(escape_html(&Self::get_text(component)?).into())
+                } else {
+                    let paragraphs = match &component.text {
+                        Some(text) => self.text_paragraphs(text),
+                        None => Vec::new(),
+                    };
+                    if let [_, _, ..] = paragraphs.as_slice() {
+                        return self
+                            .emit_paragraphs(component, &properties, &paragraphs, ctx)
+                            .map(Some);
+                    }
+                    HtmlElement::new(HtmlTag::ParagraphText)
+                        .with_child(self.emit_text(component, ctx)?.into())
+                };
 
-                HtmlChild::Element(HtmlElement::new(HtmlTag::ParagraphText).with_child(text.into()))
+                HtmlChild::Element(self.with_style(element, component, &properties)?)
             }
             "header" => {
-                let text = Self::get_text(component)?;
-                let level = Self::try_get_default_or_named_property(component, "level")
-                    .map(Self::cast_to_int)
-                    .transpose()?
-                    .unwrap_or(1);
-
-                let tag = match level {
+                let text = self.emit_text(component, ctx)?;
+                let level = Self::get_required_property(component, &properties, "level")?;
+                let tag = match Self::cast_to_int(level)? {
                     1 => HtmlTag::Heading1,
                     2 => HtmlTag::Heading2,
                     3 => HtmlTag::Heading3,
                     4 => HtmlTag::Heading4,
                     5 => HtmlTag::Heading5,
                     6 => HtmlTag::Heading6,
-                    _ => return Err(BackendError::Todo), // TODO
+                    value => {
+                        return Err(InvalidHeaderLevelError {
+                            level: value,
+                            span: level.span.clone(),
+                        }
+                        .into())
+                    }
                 };
 
-                HtmlChild::Element(HtmlElement::new(tag).with_child(text.into()))
+                let mut element = HtmlElement::new(tag).with_child(text.into());
+                if let Some(id) = self.get_id(component) {
+                    element = element.with_attribute("id", escape_html(&id));
+                }
+
+                HtmlChild::Element(self.with_style(element, component, &properties)?)
             }
             "image" => {
-                let src =
-                    Self::cast_to_string(Self::get_default_or_named_property(component, "src")?)?;
+                let src = Self::cast_to_string(Self::get_required_property(
+                    component,
+                    &properties,
+                    "src",
+                )?)?;
+                let width = properties.get("width").map(Self::cast_to_int).transpose()?;
+                let height = properties
+                    .get("height")
+                    .map(Self::cast_to_int)
+                    .transpose()?;
 
-                HtmlChild::Element(HtmlElement::new(HtmlTag::Image).with_attribute("src", src))
+                let source = self.options.image_sources.get(src.as_ref());
+                let mut element = HtmlElement::new(HtmlTag::Image)
+                    .with_attribute("src", escape_html(source.map_or(&src, String::as_str)));
+                if let Some(alt) = properties.get("alt") {
+                    element =
+                        element.with_attribute("alt", escape_html(&Self::cast_to_string(alt)?));
+                }
+                let image = self.options.images.get(src.as_ref());
+                // Missing dimension is derived from the aspect ratio of the known image
+                let (width, height) = match (width, height, image) {
+                    (None, None, Some(image)) => {
+                        (Some(i64::from(image.width)), Some(i64::from(image.height)))
+                    }
+                    (Some(width), None, Some(image)) if image.width > 0 => (
+                        Some(width),
+                        Some(width * i64::from(image.height) / i64::from(image.width)),
+                    ),
+                    (None, Some(height), Some(image)) if image.height > 0 => (
+                        Some(height * i64::from(image.width) / i64::from(image.height)),
+                        Some(height),
+                    ),
+                    (width, height, _) => (width, height),
+                };
+                if let Some(width) = width {
+                    element = element.with_attribute("width", width);
+                }
+                if let Some(height) = height {
+                    element = element.with_attribute("height", height);
+                }
+                // Resized variants are only known for the original source
+                if let Some(image) =
+                    image.filter(|image| !image.variants.is_empty() && source.is_none())
+                {
+                    let mut srcset = String::new();
+                    for (variant, width) in &image.variants {
+                        let _ = write!(srcset, "{variant} {width}w, ");
+                    }
+                    let _ = write!(srcset, "{src} {}w", image.width);
+                    element = element.with_attribute("srcset", escape_html(&srcset));
+                }
+
+                HtmlChild::Element(self.with_style(element, component, &properties)?)
+            }
+            "code" => {
+                let text = escape_html(&Self::get_text(component)?);
+
+                let element = HtmlElement::new(HtmlTag::CodeText).with_child(text.into());
+
+                HtmlChild::Element(self.with_style(element, component, &properties)?)
+            }
+            "code_block" => {
+                let text = escape_html(&Self::get_text(component)?);
+                let language = properties
+                    .get("language")
+                    .map(|value| Ok::<_, BackendError>((Self::cast_to_string(value)?, value)))
+                    .transpose()?;
+
+                let mut code = HtmlElement::new(HtmlTag::CodeText).with_child(text.into());
+                if let Some((language, value)) = language {
+                    if !language
+                        .chars()
+                        .all(|char| char.is_ascii_alphanumeric() || "+-_".contains(char))
+                    {
+                        return Err(InvalidLanguageError {
+                            value: language.into_owned(),
+                            span: value.span.clone(),
+                        }
+                        .into());
+                    }
+                    code = code.with_attribute("class", format!("language-{language}"));
+                }
+                let element = HtmlElement::new(HtmlTag::PreformattedText).with_child(code.into());
+
+                HtmlChild::Element(self.with_style(element, component, &properties)?)
+            }
+            "quote" => {
+                let mut element = HtmlElement::new(HtmlTag::Blockquote);
+                if let Some(cite) = properties.get("cite") {
+                    element =
+                        element.with_attribute("cite", escape_html(&Self::cast_to_string(cite)?));
+                }
+                if let Some(text) = &component.text {
+                    let paragraphs = self.text_paragraphs(text);
+                    if let [_, _, ..] = paragraphs.as_slice() {
+                        for segments in paragraphs {
+                            element.add_child(
+                                HtmlElement::new(HtmlTag::ParagraphText)
+                                    .with_child(self.emit_segments(segments, ctx)?.into())
+                                    .into(),
+                            );
+                        }
+                    } else {
+                        element = element.with_child(self.emit_text(component, ctx)?.into());
+                    }
+                } else {
+                    element.children = component
+                        .children
+                        .iter()
+                        .map(|child| self.emit_component(child, ctx))
+                        .collect::<Result<_, _>>()?;
+                }
+
+                HtmlChild::Element(self.with_style(element, component, &properties)?)
+            }
+            "anchor" => {
+                Self::check_no_content(component)?;
+                let id = Self::cast_to_string(Self::get_required_property(
+                    component,
+                    &properties,
+                    "id",
+                )?)?;
+                let id = self.get_id(component).unwrap_or_else(|| id.into_owned());
+
+                let element =
+                    HtmlElement::new(HtmlTag::Link).with_attribute("id", escape_html(&id));
+
+                HtmlChild::Element(element)
+            }
+            "divider" => {
+                Self::check_no_content(component)?;
+
+                let element = HtmlElement::new(HtmlTag::HorizontalRule);
+
+                HtmlChild::Element(self.with_style(element, component, &properties)?)
+            }
+            "page_break" => {
+                Self::check_no_content(component)?;
+
+                let (attribute, style) = self.style_attribute("break-after: page".to_owned());
+                let element = HtmlElement::new(HtmlTag::Div).with_attribute(attribute, style);
+
+                HtmlChild::Element(element)
+            }
+            // Top-level head components are applied to the page head before the emission
+            "page" | "stylesheet" | "script" => {
+                return Err(NestedPageError {
+                    name: component.name.as_str().to_owned(),
+                    span: component.span.clone(),
+                }
+                .into())
+            }
+            "break" => {
+                Self::check_no_content(component)?;
+
+                HtmlChild::Element(HtmlElement::new(HtmlTag::LineBreak))
             }
             "list" => {
-                let is_unordered = match (
-                    Self::get_flag_property(component, "unordered"),
-                    Self::get_flag_property(component, "ordered"),
-                ) {
-                    (true, true) => return Err(BackendError::Todo), // TODO
-                    (true, false) | (false, false) => true,
-                    (false, true) => false,
-                };
+                Self::check_exclusive_flags(&properties, "unordered", "ordered")?;
+                let is_unordered = !properties.has_flag("ordered");
                 let tag = if is_unordered {
                     HtmlTag::UnorderedList
                 } else {
                     HtmlTag::OrderedList
                 };
 
-                let mut element = HtmlElement::new(tag);
+                let mut element = self.with_style(HtmlElement::new(tag), component, &properties)?;
                 element.children = component
                     .children
                     .iter()
@@ -193,11 +1123,198 @@ impl HtmlGenerator {
         }))
     }
 
-    fn cast_to_string(value: ir::Value<Span>) -> Result<String, BackendError> {
-        match value.kind {
+    /// Adds style properties of the component, if there are any
+    fn with_style(
+        &self,
+        element: HtmlElement,
+        component: &ir::Component<Span>,
+        properties: &ir::EffectiveProperties<Span>,
+    ) -> Result<HtmlElement, BackendError> {
+        let style = Self::create_style(component, properties)?;
+        if style.is_empty() {
+            return Ok(element);
+        }
+
+        let (attribute, style) = self.style_attribute(style);
+        Ok(element.with_attribute(attribute, style))
+    }
+
+    /// Returns attributes given with [`manifest::ATTRIBUTE_PREFIX`] in the order of the code,
+    /// with escaped values. Attributes with `true` value are emitted empty,
+    /// and the ones with `false` value are skipped
+    fn custom_attributes(
+        component: &ir::Component<Span>,
+    ) -> Result<Vec<(&str, String)>, BackendError> {
+        let mut properties = component
+            .properties
+            .named_properties
+            .iter()
+            .filter_map(|property| {
+                let name = property
+                    .key
+                    .as_str()
+                    .strip_prefix(manifest::ATTRIBUTE_PREFIX)?;
+                Some((name, property))
+            })
+            .collect::<Vec<_>>();
+        properties.sort_by_key(|(_, property)| property.span.start);
+
+        let mut attributes = Vec::with_capacity(properties.len());
+        for (name, property) in properties {
+            let value = match &property.value.kind {
+                ir::ValueKind::Bool(false) => continue,
+                ir::ValueKind::Bool(true) => String::new(),
+                ir::ValueKind::Integer(value) => value.to_string(),
+                _ => escape_html(&Self::cast_to_string(&property.value)?),
+            };
+            attributes.push((name, value));
+        }
+
+        Ok(attributes)
+    }
+
+    /// Returns attribute, which applies the style to the element:
+    /// either the style itself, or the class, collected into the stylesheet
+    fn style_attribute(&self, style: String) -> (&'static str, String) {
+        match self.options.css {
+            CssMode::Inline => ("style", style),
+            CssMode::Stylesheet => {
+                let class = format!("mml-{:08x}", style_hash(&style));
+                self.lock_classes().insert(class.clone(), style);
+                ("class", class)
+            }
+        }
+    }
+
+    /// Returns position of the component in the source, if the source is given
+    fn source_span(&self, component: &ir::Component<Span>) -> Option<String> {
+        let source = self.source.as_ref()?;
+        let (start, end) = source.span_location(&component.span);
+
+        Some(format!(
+            "{}:{}-{}:{}",
+            start.line, start.column, end.line, end.column
+        ))
+    }
+
+    /// Takes collected classes as CSS rules
+    fn take_classes(&self) -> String {
+        let classes = std::mem::take(&mut *self.lock_classes());
+        let mut css = String::new();
+        for (class, style) in classes {
+            let _ = write!(css, ".{class} {{ {style} }}");
+        }
+
+        css
+    }
+
+    fn lock_classes(&self) -> MutexGuard<'_, BTreeMap<String, String>> {
+        self.classes.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    fn create_style(
+        component: &ir::Component<Span>,
+        properties: &ir::EffectiveProperties<Span>,
+    ) -> Result<String, BackendError> {
+        let mut style = String::new();
+        Self::write_style_properties(component, properties, &mut style)?;
+
+        Ok(style)
+    }
+
+    /// Writes CSS declarations of the style properties, such as `color` or `font_size`.
+    /// Lengths might be given as integers, which are treated as pixels
+    fn write_style_properties(
+        component: &ir::Component<Span>,
+        properties: &ir::EffectiveProperties<Span>,
+        style: &mut String,
+    ) -> Result<(), BackendError> {
+        let own = manifest::ComponentManifest::find(component.name.as_str())
+            .map(|manifest| manifest.properties)
+            .unwrap_or_default();
+        for property in manifest::STYLE_PROPERTIES {
+            // Property of the component itself, such as width of the image, isn't a style
+            if own.iter().any(|own| own.name == property.name) {
+                continue;
+            }
+            let Some(value) = properties.get(property.name) else {
+                continue;
+            };
+            let css = Self::style_value(property, value)?;
+
+            if !style.is_empty() {
+                style.push_str("; ");
+            }
+            let name = match property.name {
+                "grow" => "flex-grow".to_owned(),
+                "shrink" => "flex-shrink".to_owned(),
+                name => name.replace('_', "-"),
+            };
+            let _ = write!(style, "{name}: {css}");
+        }
+        if component.name.as_str() == "image" && properties.has_flag("fit") {
+            if !style.is_empty() {
+                style.push_str("; ");
+            }
+            style.push_str("object-fit: contain");
+        }
+
+        Ok(())
+    }
+
+    /// Converts value of the style property into CSS, checking that it is valid.
+    /// Only margin can be negative
+    fn style_value<'v>(
+        property: &manifest::PropertyManifest,
+        value: &'v ir::Value<Span>,
+    ) -> Result<Cow<'v, str>, BackendError> {
+        let css = match (&value.kind, property.ty) {
+            (ir::ValueKind::Integer(length), manifest::PropertyType::Length) => {
+                Cow::Owned(format!("{length}px"))
+            }
+            (_, manifest::PropertyType::Integer) => {
+                Cow::Owned(Self::cast_to_int(value)?.to_string())
+            }
+            _ => Self::cast_to_string(value)?,
+        };
+        let is_allowed =
+            property.allowed_values.is_empty() || property.allowed_values.contains(&css.as_ref());
+        let is_numeric = matches!(
+            property.ty,
+            manifest::PropertyType::Integer | manifest::PropertyType::Length
+        );
+        let is_negative = is_numeric && property.name != "margin" && css.starts_with('-');
+        if is_allowed
+            && !is_negative
+            && !css.trim().is_empty()
+            && !css.contains(|c| INVALID_STYLE_CHARACTERS.contains(&c))
+        {
+            return Ok(css);
+        }
+
+        let help = if !property.allowed_values.is_empty() {
+            format!("Use one of: {}", property.allowed_values.join(", "))
+        } else if property.ty == manifest::PropertyType::Integer {
+            "Use non-negative integer".to_owned()
+        } else if is_negative {
+            "Use non-negative length".to_owned()
+        } else {
+            "Use CSS value without `;`, quotes, angle or curly brackets".to_owned()
+        };
+        Err(InvalidStyleValueError {
+            name: property.name.to_owned(),
+            value: css.into_owned(),
+            help,
+            span: value.span.clone(),
+        }
+        .into())
+    }
+
+    pub(crate) fn cast_to_string(value: &ir::Value<Span>) -> Result<Cow<'_, str>, BackendError> {
+        match &value.kind {
             ir::ValueKind::String(string_value) => Self::build_string(string_value),
             kind => Err(TypeMismatchError {
-                span: value.span,
+                span: value.span.clone(),
                 expected: "string",
                 got: Self::get_value_kind_name(kind),
             }
@@ -205,11 +1322,11 @@ impl HtmlGenerator {
         }
     }
 
-    fn cast_to_int(value: ir::Value<Span>) -> Result<i64, BackendError> {
-        match value.kind {
-            ir::ValueKind::Integer(value) => Ok(value),
+    fn cast_to_int(value: &ir::Value<Span>) -> Result<i64, BackendError> {
+        match &value.kind {
+            ir::ValueKind::Integer(value) => Ok(*value),
             kind => Err(TypeMismatchError {
-                span: value.span,
+                span: value.span.clone(),
                 expected: "int",
                 got: Self::get_value_kind_name(kind),
             }
@@ -217,32 +1334,207 @@ impl HtmlGenerator {
         }
     }
 
-    fn build_string(string: ir::StringValue<Span>) -> Result<String, BackendError> {
-        Self::interpolate_string(string.segments)
+    fn build_string(string: &ir::StringValue<Span>) -> Result<Cow<'_, str>, BackendError> {
+        Self::interpolate_string(&string.segments)
     }
 
-    fn build_text(text: ir::Text<Span>) -> Result<String, BackendError> {
-        Self::interpolate_string(text.segments)
+    fn build_text(text: &ir::Text<Span>) -> Result<Cow<'_, str>, BackendError> {
+        Self::interpolate_string(&text.segments)
     }
 
-    // TODO: Pass context
+    /// Joins literal segments of the string.
+    /// Strings with a single literal segment are borrowed without allocation
     fn interpolate_string(
-        segments: Vec<ir::InterpolationSegment<Span>>,
-    ) -> Result<String, BackendError> {
-        Ok(segments
-            .into_iter()
-            .flat_map(|segment| match segment.kind {
-                ir::InterpolationSegmentKind::Literal(string) => Some(string),
-                ir::InterpolationSegmentKind::Variable(_) => None,
-            })
-            .join(""))
+        segments: &[ir::InterpolationSegment<Span>],
+    ) -> Result<Cow<'_, str>, BackendError> {
+        if let [segment] = segments {
+            if let ir::InterpolationSegmentKind::Literal(string) = &segment.kind {
+                return Ok(Cow::Borrowed(string));
+            }
+        }
+
+        let mut buffer = String::new();
+        let _ = Self::write_interpolated(&mut buffer, segments);
+
+        Ok(Cow::Owned(buffer))
     }
 
-    fn get_default_or_named_property(
+    /// Writes literal segments of the string into the buffer. Variables are substituted
+    /// during the expansion, so the remaining ones are skipped. Unresolved messages are written
+    /// as their keys, inline components as their plain text and newlines as spaces
+    fn write_interpolated(
+        buffer: &mut impl fmt::Write,
+        segments: &[ir::InterpolationSegment<Span>],
+    ) -> fmt::Result {
+        for segment in segments {
+            match &segment.kind {
+                ir::InterpolationSegmentKind::Literal(string) => buffer.write_str(string)?,
+                ir::InterpolationSegmentKind::Variable(_)
+                | ir::InterpolationSegmentKind::Formatted(_) => {}
+                ir::InterpolationSegmentKind::Message(key) => buffer.write_str(key)?,
+                ir::InterpolationSegmentKind::Component(component) => {
+                    if let Some(text) = &component.text {
+                        Self::write_interpolated(buffer, &text.segments)?;
+                    }
+                }
+                ir::InterpolationSegmentKind::Newline => buffer.write_char(' ')?,
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Builds markup of the component text, emitting its inline components
+    fn emit_text<'c>(
+        &self,
+        component: &'c ir::Component<Span>,
+        ctx: Option<&ir::Component<Span>>,
+    ) -> Result<Cow<'c, str>, BackendError> {
+        let text = component.text.as_ref().ok_or_else(|| TextMissingError {
+            span: component.span.clone(),
+        })?;
+
+        self.emit_segments(self.text_segments(text), ctx)
+    }
+
+    /// Builds markup of the text segments, emitting its inline components.
+    /// Preserved newlines are emitted as line breaks, and blank lines as two of them
+    fn emit_segments<'c>(
+        &self,
+        segments: &'c [ir::InterpolationSegment<Span>],
+        ctx: Option<&ir::Component<Span>>,
+    ) -> Result<Cow<'c, str>, BackendError> {
+        let preserve_newlines = self.options.preserve_newlines;
+        let is_plain = segments.iter().all(|segment| match segment.kind {
+            ir::InterpolationSegmentKind::Component(_) => false,
+            ir::InterpolationSegmentKind::Newline => !preserve_newlines,
+            _ => true,
+        });
+        if is_plain {
+            return Self::interpolate_string(segments);
+        }
+
+        let mut html = String::new();
+        let mut segments = segments.iter().peekable();
+        while let Some(segment) = segments.next() {
+            match &segment.kind {
+                ir::InterpolationSegmentKind::Component(inline) => {
+                    if !manifest::INLINE_COMPONENTS.contains(&inline.name.as_str()) {
+                        return Err(NotInlineComponentError {
+                            name: inline.name.as_str().to_owned(),
+                            help: format!(
+                                "Use one of the inline components: {}",
+                                manifest::INLINE_COMPONENTS.join(", ")
+                            ),
+                            span: inline.span.clone(),
+                        }
+                        .into());
+                    }
+                    let _ = write!(html, "{}", self.emit_component(inline, ctx)?);
+                }
+                ir::InterpolationSegmentKind::Newline if preserve_newlines => {
+                    let line_break = HtmlChild::Element(HtmlElement::new(HtmlTag::LineBreak));
+                    let _ = write!(html, "{line_break}");
+                    if segments.next_if(|segment| is_newline(segment)).is_some() {
+                        let _ = write!(html, "{line_break}");
+                    }
+                    while segments.next_if(|segment| is_newline(segment)).is_some() {}
+                }
+                _ => {
+                    let _ = Self::write_interpolated(&mut html, std::slice::from_ref(segment));
+                }
+            }
+        }
+
+        Ok(Cow::Owned(html))
+    }
+
+    /// Emits paragraph, whose text is split at the blank lines, as a sequence of paragraphs
+    /// with the same style and attributes
+    fn emit_paragraphs(
+        &self,
         component: &ir::Component<Span>,
+        properties: &ir::EffectiveProperties<Span>,
+        paragraphs: &[&[ir::InterpolationSegment<Span>]],
+        ctx: Option<&ir::Component<Span>>,
+    ) -> Result<HtmlChild, BackendError> {
+        let mut element = self.with_style(
+            HtmlElement::new(HtmlTag::ParagraphText),
+            component,
+            properties,
+        )?;
+        for (name, value) in Self::custom_attributes(component)? {
+            element.add_attribute(name, value);
+        }
+        if let Some(span) = self.source_span(component) {
+            element.add_attribute(SPAN_ATTRIBUTE, span);
+        }
+
+        let mut html = String::new();
+        for segments in paragraphs {
+            let paragraph = element
+                .clone()
+                .with_child(self.emit_segments(segments, ctx)?.into());
+            let _ = write!(html, "{}", HtmlChild::Element(paragraph));
+        }
+
+        Ok(HtmlChild::Raw(html))
+    }
+
+    /// Returns segments of the text. Preserved newlines at its start and end are skipped,
+    /// so the text can be placed on separate lines from the brackets
+    fn text_segments<'c>(&self, text: &'c ir::Text<Span>) -> &'c [ir::InterpolationSegment<Span>] {
+        let segments = text.segments.as_slice();
+        if !self.options.preserve_newlines {
+            return segments;
+        }
+        let start = segments
+            .iter()
+            .position(|segment| !is_newline(segment))
+            .unwrap_or(segments.len());
+        let end = segments
+            .iter()
+            .rposition(|segment| !is_newline(segment))
+            .map_or(start, |end| end + 1);
+
+        &segments[start..end]
+    }
+
+    /// Splits the text into paragraphs at the blank lines, if newlines are preserved
+    fn text_paragraphs<'c>(
+        &self,
+        text: &'c ir::Text<Span>,
+    ) -> Vec<&'c [ir::InterpolationSegment<Span>]> {
+        let segments = self.text_segments(text);
+        if !self.options.preserve_newlines {
+            return vec![segments];
+        }
+
+        let mut paragraphs = Vec::new();
+        let mut start = 0;
+        let mut index = 0;
+        while index < segments.len() {
+            let newlines = segments[index..]
+                .iter()
+                .take_while(|segment| is_newline(segment))
+                .count();
+            if newlines > 1 {
+                paragraphs.push(&segments[start..index]);
+                start = index + newlines;
+            }
+            index += newlines.max(1);
+        }
+        paragraphs.push(&segments[start..]);
+
+        paragraphs
+    }
+
+    fn get_required_property<'c>(
+        component: &ir::Component<Span>,
+        properties: &ir::EffectiveProperties<'c, Span>,
         name: &str,
-    ) -> Result<ir::Value<Span>, BackendError> {
-        Self::try_get_default_or_named_property(component, name).ok_or_else(|| {
+    ) -> Result<&'c ir::Value<Span>, BackendError> {
+        properties.get(name).ok_or_else(|| {
             RequiredDefaultPropertyMissingError {
                 span: component.span.clone(),
                 name: name.to_owned(),
@@ -251,41 +1543,92 @@ impl HtmlGenerator {
         })
     }
 
-    fn try_get_default_or_named_property(
+    /// Resolves properties of the builtin component: default property is stored
+    /// under its name from the manifest along with the default values,
+    /// and aliases are stored under the names of their properties
+    pub(crate) fn effective_properties(
         component: &ir::Component<Span>,
-        name: &str,
-    ) -> Option<ir::Value<Span>> {
-        component
-            .properties
-            .default
-            .clone()
-            .or_else(|| Self::try_get_named_property(component, name))
-    }
+    ) -> ir::EffectiveProperties<'_, Span> {
+        let name = component.name.as_str();
+        let default_property = manifest::ComponentManifest::find(name).and_then(|manifest| {
+            manifest
+                .properties
+                .iter()
+                .find(|property| property.is_default)
+                .map(|property| property.name)
+        });
+        let defaults = DEFAULT_VALUES
+            .iter()
+            .filter(|(component, _, _)| *component == name)
+            .map(|(_, property, value)| (*property, value));
+        let mut properties =
+            ir::EffectiveProperties::resolve(&component.properties, default_property, defaults);
 
-    fn try_get_named_property(
-        component: &ir::Component<Span>,
-        name: &str,
-    ) -> Option<ir::Value<Span>> {
-        component
-            .properties
-            .named_properties
-            .get(name)
-            .map(|prop| prop.value.clone())
+        for (owner, alias, property) in manifest::PROPERTY_ALIASES {
+            if *owner != name || component.properties.named_properties.contains(*property) {
+                continue;
+            }
+            if let Some(value) = component.properties.named_properties.get(*alias) {
+                properties.values.insert(property, &value.value);
+            }
+        }
+
+        properties
     }
 
-    fn get_flag_property(component: &ir::Component<Span>, name: &str) -> bool {
-        component.properties.flag_properties.contains(name)
+    /// Checks that at most one of the mutually exclusive flags is set
+    fn check_exclusive_flags(
+        properties: &ir::EffectiveProperties<Span>,
+        first: &str,
+        second: &str,
+    ) -> Result<(), BackendError> {
+        let flags = &properties.flags;
+        match (flags.get(first), flags.get(second)) {
+            (Some(first), Some(second)) => {
+                let (first, second) = if first.span.start <= second.span.start {
+                    (first, second)
+                } else {
+                    (second, first)
+                };
+                Err(ConflictingFlagsError {
+                    first: first.as_str().to_owned(),
+                    second: second.as_str().to_owned(),
+                    first_span: first.span.clone(),
+                    second_span: second.span.clone(),
+                }
+                .into())
+            }
+            _ => Ok(()),
+        }
     }
 
-    fn get_text(component: &ir::Component<Span>) -> Result<String, BackendError> {
-        let text = component.text.clone().ok_or_else(|| TextMissingError {
+    fn get_text(component: &ir::Component<Span>) -> Result<Cow<'_, str>, BackendError> {
+        let text = component.text.as_ref().ok_or_else(|| TextMissingError {
             span: component.span.clone(),
         })?;
 
         Self::build_text(text)
     }
 
-    fn get_value_kind_name(kind: ir::ValueKind<Span>) -> &'static str {
+    /// Checks that the void component has neither children nor text
+    fn check_no_content(component: &ir::Component<Span>) -> Result<(), BackendError> {
+        let content = component
+            .text
+            .as_ref()
+            .map(|text| &text.span)
+            .or_else(|| component.children.first().map(|child| &child.span));
+        match content {
+            Some(span) => Err(UnexpectedContentError {
+                name: component.name.as_str().to_owned(),
+                component: component.name.span.clone(),
+                content: span.clone(),
+            }
+            .into()),
+            None => Ok(()),
+        }
+    }
+
+    fn get_value_kind_name(kind: &ir::ValueKind<Span>) -> &'static str {
         match kind {
             ir::ValueKind::String(_) => "string",
             ir::ValueKind::Integer(_) => "int",
@@ -294,10 +1637,87 @@ impl HtmlGenerator {
         }
     }
 
-    fn check_align_allowed(align: &str) -> Result<(), BackendError> {
-        match align {
-            "start" | "center" | "end" => Ok(()),
-            _ => Err(BackendError::Todo),
+    /// Returns alignment property, checking that it's one of [`manifest::ALIGN_VALUES`]
+    fn get_align<'c>(
+        properties: &ir::EffectiveProperties<'c, Span>,
+        name: &str,
+    ) -> Result<Option<Cow<'c, str>>, BackendError> {
+        let Some(value) = properties.get(name) else {
+            return Ok(None);
+        };
+        let align = Self::cast_to_string(value)?;
+        if !manifest::ALIGN_VALUES.contains(&align.as_ref()) {
+            return Err(InvalidAlignValueError {
+                name: name.to_owned(),
+                value: align.into_owned(),
+                span: value.span.clone(),
+            }
+            .into());
+        }
+
+        Ok(Some(align))
+    }
+}
+
+/// Writes the element on its own line. Elements with nested elements
+/// have them on separate lines with increased indentation,
+/// while the text and preformatted elements are kept inline, since whitespace in them is significant
+fn write_pretty(child: &HtmlChild, depth: usize, html: &mut String) {
+    html.push_str(&INDENT.repeat(depth));
+    match child {
+        HtmlChild::Element(element)
+            if element.tag != HtmlTag::PreformattedText
+                && element
+                    .children
+                    .iter()
+                    .any(|child| matches!(child, HtmlChild::Element(_))) =>
+        {
+            let _ = write!(html, "<{}", element.tag);
+            for (key, value) in &element.attributes {
+                let _ = write!(html, r#" {key}="{value}""#);
+            }
+            html.push_str(">\n");
+            for child in &element.children {
+                write_pretty(child, depth + 1, html);
+            }
+            html.push_str(&INDENT.repeat(depth));
+            let _ = write!(html, "</{}>", element.tag);
+        }
+        child => {
+            let _ = write!(html, "{child}");
         }
     }
+    html.push('\n');
+}
+
+/// Returns whether the element can't have children, so it's emitted as self-closing tag
+fn is_void(tag: &HtmlTag) -> bool {
+    matches!(
+        tag,
+        HtmlTag::HorizontalRule | HtmlTag::LineBreak | HtmlTag::Image
+    )
+}
+
+fn is_newline(segment: &ir::InterpolationSegment<Span>) -> bool {
+    matches!(segment.kind, ir::InterpolationSegmentKind::Newline)
+}
+
+/// FNV-1a hash of the style, which is stable across the runs and platforms
+fn style_hash(style: &str) -> u32 {
+    style.bytes().fold(0x811c9dc5, |hash, byte| {
+        (hash ^ u32::from(byte)).wrapping_mul(0x01000193)
+    })
+}
+
+/// Returns children of the component followed by its inline components
+fn component_children(
+    component: &ir::Component<Span>,
+) -> impl Iterator<Item = &ir::Component<Span>> {
+    let inline = component.text.iter().flat_map(ir::Text::components);
+    component.children.iter().chain(inline)
+}
+
+/// Address of the component, that identifies it while the IR is borrowed
+fn component_address(component: &ir::Component<Span>) -> usize {
+    component as *const ir::Component<Span> as usize
 }
@@ -0,0 +1,85 @@
+use std::collections::{HashMap, HashSet};
+
+/// Id used when nothing is left of the content after slugifying
+const FALLBACK_ID: &str = "section";
+
+/// Generator of the element ids, derived from the content.
+/// Ids are stable as long as the content and its order don't change.
+/// Repeated ids get numeric suffix, like `intro`, `intro-2`, `intro-3`
+#[derive(Debug, Default)]
+pub struct IdGenerator {
+    /// Prefix of every id, so that ids of multiple documents on one page don't collide
+    prefix: String,
+    /// Generated ids without the prefix
+    used: HashSet<String>,
+    /// Next numeric suffix to try for every slug
+    suffixes: HashMap<String, usize>,
+}
+
+impl IdGenerator {
+    /// Creates new instance with the given prefix of ids
+    pub fn new(prefix: impl Into<String>) -> Self {
+        IdGenerator {
+            prefix: prefix.into(),
+            used: HashSet::new(),
+            suffixes: HashMap::new(),
+        }
+    }
+
+    /// Generates unique id from the given content
+    pub fn generate(&mut self, content: &str) -> String {
+        let mut slug = slugify(content);
+        if slug.is_empty() {
+            slug.push_str(FALLBACK_ID);
+        }
+
+        let next = self.suffixes.entry(slug.clone()).or_insert(1);
+        let mut suffix = *next;
+        // Suffixed id might already be taken by the content, like `intro-2`
+        let id = loop {
+            let id = if suffix == 1 {
+                slug.clone()
+            } else {
+                format!("{slug}-{suffix}")
+            };
+            if !self.used.contains(&id) {
+                break id;
+            }
+            suffix += 1;
+        };
+        *next = suffix + 1;
+        self.used.insert(id.clone());
+
+        format!("{}{id}", self.prefix)
+    }
+
+    /// Marks the given id without the prefix as used, so that it isn't generated,
+    /// and returns it with the prefix
+    pub fn claim(&mut self, id: &str) -> String {
+        self.used.insert(id.to_owned());
+        format!("{}{id}", self.prefix)
+    }
+
+    /// Marks the previously generated id as used, so that it isn't generated again.
+    /// Allows to restore the state of the generator from the generated ids
+    pub fn reserve(&mut self, id: &str) {
+        let id = id.strip_prefix(self.prefix.as_str()).unwrap_or(id);
+        self.used.insert(id.to_owned());
+    }
+}
+
+/// Converts content into lowercase words of alphanumeric characters joined with dashes
+pub fn slugify(content: &str) -> String {
+    let mut slug = String::with_capacity(content.len());
+    for word in content
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|word| !word.is_empty())
+    {
+        if !slug.is_empty() {
+            slug.push('-');
+        }
+        slug.extend(word.chars().flat_map(char::to_lowercase));
+    }
+
+    slug
+}
@@ -6,12 +6,85 @@
 
 pub mod error;
 pub mod html_generator;
+pub mod ids;
+pub mod lint;
+pub mod manifest;
+pub mod theme;
 
-pub use error::BackendError;
+pub use error::{BackendError, BackendWarning};
+pub use html_generator::{CssMode, HtmlGeneratorOptions, ImageInfo, PrintOptions};
+pub use manifest::ComponentManifest;
+pub use theme::Theme;
 
+use error::ReservedComponentNameError;
+use markerml_middleend::component_expander::REPEAT;
 use markerml_middleend::Span;
 
 /// Generates HTML from the given IR
-pub fn generate_html(ir: markerml_middleend::ir::Module<Span>) -> Result<String, BackendError> {
+pub fn generate_html(ir: &markerml_middleend::ir::Module<Span>) -> Result<String, BackendError> {
     html_generator::HtmlGenerator::new(ir).generate()
 }
+
+/// Generates HTML from the given IR with the given options
+pub fn generate_html_with_options(
+    ir: &markerml_middleend::ir::Module<Span>,
+    options: &HtmlGeneratorOptions,
+) -> Result<String, BackendError> {
+    html_generator::HtmlGenerator::with_options(ir, options).generate()
+}
+
+/// Generates HTML from the given IR, writing it into the given writer
+/// component by component instead of building the whole page in memory
+pub fn generate_html_to_writer(
+    ir: &markerml_middleend::ir::Module<Span>,
+    writer: &mut impl std::io::Write,
+) -> Result<(), BackendError> {
+    html_generator::HtmlGenerator::new(ir).generate_to_writer(writer)
+}
+
+/// Generates HTML from the given IR with the given options, writing it into the given writer
+pub fn generate_html_to_writer_with_options(
+    ir: &markerml_middleend::ir::Module<Span>,
+    options: &HtmlGeneratorOptions,
+    writer: &mut impl std::io::Write,
+) -> Result<(), BackendError> {
+    html_generator::HtmlGenerator::with_options(ir, options).generate_to_writer(writer)
+}
+
+/// Generates only the component markup from the given IR, without the page
+/// and `<main>` wrappers, so it can be injected into existing templates
+pub fn generate_html_fragment(
+    ir: &markerml_middleend::ir::Module<Span>,
+) -> Result<String, BackendError> {
+    html_generator::HtmlGenerator::new(ir).generate_fragment()
+}
+
+/// Generates only the component markup from the given IR with the given options
+pub fn generate_html_fragment_with_options(
+    ir: &markerml_middleend::ir::Module<Span>,
+    options: &HtmlGeneratorOptions,
+) -> Result<String, BackendError> {
+    html_generator::HtmlGenerator::with_options(ir, options).generate_fragment()
+}
+
+/// Checks that the component definition doesn't have the name of the builtin component,
+/// which it would shadow
+pub fn check_definition(
+    def: &markerml_middleend::ir::ComponentDefinition<Span>,
+) -> Result<(), BackendError> {
+    let name = def.name.as_str();
+    if name != REPEAT && ComponentManifest::find(name).is_none() {
+        return Ok(());
+    }
+
+    Err(ReservedComponentNameError {
+        name: name.to_owned(),
+        span: def.name.span.clone(),
+    }
+    .into())
+}
+
+/// Returns description of the builtin components, their properties, types and defaults
+pub fn builtin_manifest() -> &'static [ComponentManifest] {
+    manifest::BUILTIN_COMPONENTS
+}
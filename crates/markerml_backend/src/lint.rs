@@ -0,0 +1,234 @@
+use crate::error::{
+    BackendWarning, DuplicateIdWarning, InvalidLinkWarning, MissingLinkTargetWarning,
+    UnknownPropertyWarning,
+};
+use crate::html_generator::HtmlGenerator;
+use crate::manifest::{self, ComponentManifest, PropertyType};
+use markerml_middleend::suggestions::closest_name;
+use markerml_middleend::{ir, Span};
+use std::collections::HashMap;
+use std::path::Path;
+
+/// Checks builtin components of the expanded module for properties they don't understand
+pub fn lint_module(module: &ir::Module<Span>) -> Vec<BackendWarning> {
+    let mut warnings = Vec::new();
+    for item in &module.items {
+        if let ir::ModuleItem::Component(component) = item {
+            lint_component(component, &mut warnings);
+        }
+    }
+
+    warnings
+}
+
+/// Checks the component, its children and inline components for properties they don't understand
+/// and links with invalid URLs.
+/// Custom components are expected to be expanded, unknown components are skipped
+pub fn lint_component(component: &ir::Component<Span>, warnings: &mut Vec<BackendWarning>) {
+    if let Some((url, value)) = link_url(component) {
+        if let Some(help) = check_url(&url) {
+            warnings.push(
+                InvalidLinkWarning {
+                    url,
+                    help: help.to_owned(),
+                    span: value.span.clone(),
+                }
+                .into(),
+            );
+        }
+    }
+
+    if let Some(manifest) = ComponentManifest::find(component.name.as_str()) {
+        let properties = &component.properties;
+        // Flags can also be given as properties with bool values
+        let mut unknown = properties
+            .named_properties
+            .iter()
+            .map(|property| {
+                let is_bool = matches!(property.value.kind, ir::ValueKind::Bool(_));
+                (
+                    &property.key,
+                    is_bool && is_flag(manifest, property.key.as_str()),
+                )
+            })
+            .chain(properties.flag_properties.iter().map(|flag| (flag, true)))
+            .chain(properties.negated_flags.iter().map(|flag| (flag, true)))
+            .filter(|(key, _)| !key.as_str().starts_with(manifest::ATTRIBUTE_PREFIX))
+            .filter(|(key, is_flag)| !is_known(manifest, key.as_str(), *is_flag))
+            .collect::<Vec<_>>();
+        // Properties are stored in sets, so they are reported in the order of the code
+        unknown.sort_by_key(|(key, _)| key.span.start);
+
+        for (key, is_flag) in unknown {
+            let candidates = manifest
+                .all_properties()
+                .filter(|property| (property.ty == PropertyType::Flag) == is_flag)
+                .map(|property| property.name);
+            let suggestion = closest_name(key.as_str(), candidates).map(str::to_owned);
+
+            warnings.push(
+                UnknownPropertyWarning {
+                    component: manifest.name.to_owned(),
+                    name: key.as_str().to_owned(),
+                    help: suggestion
+                        .as_ref()
+                        .map(|suggestion| format!("Did you mean `{suggestion}`?")),
+                    suggestion,
+                    span: key.span.clone(),
+                }
+                .into(),
+            );
+        }
+    }
+
+    let inline = component.text.iter().flat_map(ir::Text::components);
+    for child in component.children.iter().chain(inline) {
+        lint_component(child, warnings);
+    }
+}
+
+/// Checks that relative links of the component and its nested components point to existing files
+/// in the given directory. Links to `.html` files are also satisfied by `.mml` files,
+/// which they are converted from
+pub fn lint_link_targets(
+    component: &ir::Component<Span>,
+    base: &Path,
+    warnings: &mut Vec<BackendWarning>,
+) {
+    if let Some((url, value)) = link_url(component) {
+        let path = url.split(['#', '?']).next().unwrap_or_default();
+        if is_relative(&url) && !path.is_empty() {
+            let target = base.join(path);
+            let is_source = target
+                .extension()
+                .is_some_and(|ext| ext == "html" && target.with_extension("mml").exists());
+            if !target.exists() && !is_source {
+                warnings.push(
+                    MissingLinkTargetWarning {
+                        path: path.to_owned(),
+                        span: value.span.clone(),
+                    }
+                    .into(),
+                );
+            }
+        }
+    }
+
+    let inline = component.text.iter().flat_map(ir::Text::components);
+    for child in component.children.iter().chain(inline) {
+        lint_link_targets(child, base, warnings);
+    }
+}
+
+/// Collects explicit ids of the anchors in the component and its nested components
+/// along with spans of their values, in the document order
+pub fn anchor_ids(component: &ir::Component<Span>, ids: &mut Vec<(String, Span)>) {
+    if component.name.as_str() == "anchor" {
+        let properties = HtmlGenerator::effective_properties(component);
+        // Invalid anchor is reported during the emission
+        if let Some(value) = properties.get("id") {
+            if let Ok(id) = HtmlGenerator::cast_to_string(value) {
+                ids.push((id.into_owned(), value.span.clone()));
+            }
+        }
+    }
+
+    let inline = component.text.iter().flat_map(ir::Text::components);
+    for child in component.children.iter().chain(inline) {
+        anchor_ids(child, ids);
+    }
+}
+
+/// Checks that explicit ids of the anchors, collected from the whole document, don't repeat,
+/// since anchor links lead only to the first element with the id
+pub fn lint_duplicate_ids(ids: &[(String, Span)], warnings: &mut Vec<BackendWarning>) {
+    let mut seen = HashMap::<&str, &Span>::with_capacity(ids.len());
+    for (id, span) in ids {
+        if let Some(first) = seen.get(id.as_str()) {
+            warnings.push(
+                DuplicateIdWarning {
+                    id: id.clone(),
+                    span: span.clone(),
+                    first: (*first).clone(),
+                }
+                .into(),
+            );
+        } else {
+            seen.insert(id, span);
+        }
+    }
+}
+
+/// Returns URL of the link component along with its value
+fn link_url(component: &ir::Component<Span>) -> Option<(String, &ir::Value<Span>)> {
+    if component.name.as_str() != "#" {
+        return None;
+    }
+    let properties = HtmlGenerator::effective_properties(component);
+    let value = properties.get("url")?;
+    // Invalid value is reported during the emission
+    let url = HtmlGenerator::cast_to_string(value).ok()?.into_owned();
+
+    Some((url, value))
+}
+
+/// Returns description of the problem with the URL, if there is any
+fn check_url(url: &str) -> Option<&'static str> {
+    if url.trim().is_empty() {
+        return Some("URL is empty, use address like `https://example.com` or `#section`");
+    }
+    // Browsers ignore leading whitespace and tabs or newlines inside of the scheme
+    let normalized = url
+        .trim_start_matches(|c: char| c.is_ascii_whitespace() || c.is_ascii_control())
+        .replace(['\t', '\n', '\r'], "")
+        .to_ascii_lowercase();
+    if normalized.starts_with("javascript:") {
+        return Some("Links can't run scripts, `javascript:` URLs can be abused for injection");
+    }
+    // Colon after the path, query or fragment has started doesn't separate the scheme
+    let (scheme, rest) = url.split_once(':')?;
+    if scheme.contains(['/', '?', '#']) {
+        return None;
+    }
+    let is_valid = scheme.starts_with(|c: char| c.is_ascii_alphabetic())
+        && scheme
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || "+-.".contains(c));
+    if !is_valid {
+        return Some(
+            "Scheme must start with a letter and contain only letters, digits, `+`, `-` or `.`",
+        );
+    }
+    let is_web = ["http", "https"].contains(&scheme.to_ascii_lowercase().as_str());
+    if is_web && !rest.starts_with("//") {
+        return Some("Web address must have `//` after the scheme, like `https://example.com`");
+    }
+
+    None
+}
+
+/// Checks whether the URL is a path relative to the document
+fn is_relative(url: &str) -> bool {
+    let has_scheme = url
+        .split_once(':')
+        .is_some_and(|(scheme, _)| !scheme.contains(['/', '?', '#']));
+
+    !has_scheme && !url.starts_with(['/', '#', '?'])
+}
+
+fn is_flag(manifest: &ComponentManifest, name: &str) -> bool {
+    manifest
+        .all_properties()
+        .any(|property| property.name == name && property.ty == PropertyType::Flag)
+}
+
+fn is_known(manifest: &ComponentManifest, name: &str, is_flag: bool) -> bool {
+    let is_property = manifest
+        .all_properties()
+        .any(|property| property.name == name && (property.ty == PropertyType::Flag) == is_flag);
+    let is_alias = manifest::PROPERTY_ALIASES
+        .iter()
+        .any(|(owner, alias, _)| *owner == manifest.name && *alias == name);
+
+    is_property || (is_alias && !is_flag)
+}
@@ -0,0 +1,344 @@
+/// Description of the builtin component, that tools can use
+/// to stay in sync with the backend
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct ComponentManifest {
+    pub name: &'static str,
+    pub description: &'static str,
+    pub content: ContentKind,
+    pub properties: &'static [PropertyManifest],
+    /// Whether the component also accepts [`STYLE_PROPERTIES`]
+    pub is_styled: bool,
+}
+
+impl ComponentManifest {
+    /// Returns properties of the component, including style properties.
+    /// Properties of the component take precedence over style properties with the same name
+    pub fn all_properties(&self) -> impl Iterator<Item = &'static PropertyManifest> {
+        let style: &'static [PropertyManifest] = if self.is_styled {
+            STYLE_PROPERTIES
+        } else {
+            &[]
+        };
+        let properties = self.properties;
+
+        properties.iter().chain(style.iter().filter(move |style| {
+            properties
+                .iter()
+                .all(|property| property.name != style.name)
+        }))
+    }
+
+    /// Returns manifest of the builtin component with the given name
+    pub fn find(name: &str) -> Option<&'static ComponentManifest> {
+        BUILTIN_COMPONENTS
+            .iter()
+            .find(|component| component.name == name)
+    }
+}
+
+/// Content that the component accepts
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
+pub enum ContentKind {
+    /// Neither children nor text
+    None,
+    /// Child components
+    Children,
+    /// Text in parentheses
+    Text,
+    /// Either child components or text
+    TextOrChildren,
+}
+
+/// Description of the builtin component property
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct PropertyManifest {
+    pub name: &'static str,
+    pub ty: PropertyType,
+    /// Whether the property can be passed as the default property
+    pub is_default: bool,
+    /// Whether the property must be specified
+    pub is_required: bool,
+    /// Value used when the property isn't specified
+    pub default_value: Option<&'static str>,
+    /// Allowed values. Any value of the type is allowed if empty
+    pub allowed_values: &'static [&'static str],
+}
+
+/// Type of the builtin component property
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
+pub enum PropertyType {
+    String,
+    Integer,
+    /// Flag property, that is either present or not
+    Flag,
+    /// CSS length. Integers are treated as pixels
+    Length,
+}
+
+/// Allowed values of the alignment properties
+pub const ALIGN_VALUES: &[&str] = &["start", "center", "end"];
+/// Allowed values of the header level
+pub const HEADER_LEVELS: &[&str] = &["1", "2", "3", "4", "5", "6"];
+/// Allowed values of the page theme, see [`crate::Theme`]
+pub const THEME_VALUES: &[&str] = &["light", "dark", "plain"];
+
+impl PropertyManifest {
+    const fn flag(name: &'static str) -> Self {
+        PropertyManifest {
+            name,
+            ty: PropertyType::Flag,
+            is_default: false,
+            is_required: false,
+            default_value: None,
+            allowed_values: &[],
+        }
+    }
+
+    const fn named(name: &'static str, ty: PropertyType) -> Self {
+        PropertyManifest {
+            name,
+            ty,
+            is_default: false,
+            is_required: false,
+            default_value: None,
+            allowed_values: &[],
+        }
+    }
+
+    const fn default_property(name: &'static str, ty: PropertyType) -> Self {
+        PropertyManifest {
+            is_default: true,
+            is_required: true,
+            ..Self::named(name, ty)
+        }
+    }
+}
+
+/// Style properties, accepted by the visual builtin components.
+/// They are emitted as CSS properties with underscores replaced by dashes,
+/// except for `grow` and `shrink`, which become `flex-grow` and `flex-shrink`
+pub const STYLE_PROPERTIES: &[PropertyManifest] = &[
+    PropertyManifest::named("color", PropertyType::String),
+    PropertyManifest::named("background", PropertyType::String),
+    PropertyManifest::named("padding", PropertyType::Length),
+    PropertyManifest::named("margin", PropertyType::Length),
+    PropertyManifest::named("width", PropertyType::Length),
+    PropertyManifest::named("height", PropertyType::Length),
+    PropertyManifest::named("font_size", PropertyType::Length),
+    PropertyManifest::named("grow", PropertyType::Integer),
+    PropertyManifest::named("shrink", PropertyType::Integer),
+    PropertyManifest {
+        allowed_values: ALIGN_VALUES,
+        ..PropertyManifest::named("align_self", PropertyType::String)
+    },
+];
+
+/// Spacing between children of the box
+pub const GAP_PROPERTY: PropertyManifest = PropertyManifest::named("gap", PropertyType::Length);
+
+/// Prefix of the properties, that are passed through to the element as HTML attributes
+pub const ATTRIBUTE_PREFIX: &str = "attr:";
+
+/// Components, which are written into the page head instead of the content,
+/// so they must be at the top level
+pub const HEAD_COMPONENTS: &[&str] = &["page", "stylesheet", "script"];
+
+/// Components, which can be placed inside the text, like `$@[bold](word)`
+pub const INLINE_COMPONENTS: &[&str] = &["@", "#", "code", "break", "anchor"];
+
+/// Alternative names of the builtin component properties,
+/// given as the component name, alias and the property name
+pub const PROPERTY_ALIASES: &[(&str, &str, &str)] = &[("image", "url", "src")];
+
+/// Builtin components along with their properties
+pub const BUILTIN_COMPONENTS: &[ComponentManifest] = &[
+    ComponentManifest {
+        name: "box",
+        description: "Flex container, that lays out children vertically or horizontally",
+        content: ContentKind::Children,
+        properties: &[
+            PropertyManifest::flag("vertical"),
+            PropertyManifest::flag("horizontal"),
+            PropertyManifest {
+                allowed_values: ALIGN_VALUES,
+                ..PropertyManifest::named("x_align", PropertyType::String)
+            },
+            PropertyManifest {
+                allowed_values: ALIGN_VALUES,
+                ..PropertyManifest::named("y_align", PropertyType::String)
+            },
+            GAP_PROPERTY,
+        ],
+        is_styled: true,
+    },
+    ComponentManifest {
+        name: "@",
+        description:
+            "Inline text, optionally bold, italic, underlined, struck through or monospace",
+        content: ContentKind::Text,
+        properties: &[
+            PropertyManifest::flag("bold"),
+            PropertyManifest::flag("italic"),
+            PropertyManifest::flag("underline"),
+            PropertyManifest::flag("strike"),
+            PropertyManifest::flag("mono"),
+        ],
+        is_styled: true,
+    },
+    ComponentManifest {
+        name: "#",
+        description: "Link with text",
+        content: ContentKind::Text,
+        properties: &[PropertyManifest::default_property(
+            "url",
+            PropertyType::String,
+        )],
+        is_styled: true,
+    },
+    ComponentManifest {
+        name: "anchor",
+        description: "Target of the links within the document, like `#[\"#id\"](text)`",
+        content: ContentKind::None,
+        properties: &[PropertyManifest::default_property(
+            "id",
+            PropertyType::String,
+        )],
+        is_styled: false,
+    },
+    ComponentManifest {
+        name: "paragraph",
+        description: "Paragraph of text, or preformatted block of the raw text",
+        content: ContentKind::Text,
+        properties: &[],
+        is_styled: true,
+    },
+    ComponentManifest {
+        name: "header",
+        description: "Header of the given level, with id derived from its text",
+        content: ContentKind::Text,
+        properties: &[PropertyManifest {
+            is_required: false,
+            default_value: Some("1"),
+            allowed_values: HEADER_LEVELS,
+            ..PropertyManifest::default_property("level", PropertyType::Integer)
+        }],
+        is_styled: true,
+    },
+    ComponentManifest {
+        name: "image",
+        description: "Image from the given source",
+        content: ContentKind::None,
+        properties: &[
+            PropertyManifest::default_property("src", PropertyType::String),
+            PropertyManifest::named("alt", PropertyType::String),
+            PropertyManifest::named("width", PropertyType::Integer),
+            PropertyManifest::named("height", PropertyType::Integer),
+            PropertyManifest::flag("fit"),
+        ],
+        is_styled: true,
+    },
+    ComponentManifest {
+        name: "list",
+        description: "Unordered or ordered list, where every child is an item",
+        content: ContentKind::Children,
+        properties: &[
+            PropertyManifest::flag("unordered"),
+            PropertyManifest::flag("ordered"),
+        ],
+        is_styled: true,
+    },
+    ComponentManifest {
+        name: "code",
+        description: "Inline code",
+        content: ContentKind::Text,
+        properties: &[],
+        is_styled: true,
+    },
+    ComponentManifest {
+        name: "code_block",
+        description: "Block of preformatted code, that keeps newlines of the raw text",
+        content: ContentKind::Text,
+        properties: &[PropertyManifest::named("language", PropertyType::String)],
+        is_styled: true,
+    },
+    ComponentManifest {
+        name: "quote",
+        description: "Block quotation of the text or child components",
+        content: ContentKind::TextOrChildren,
+        properties: &[PropertyManifest::named("cite", PropertyType::String)],
+        is_styled: true,
+    },
+    ComponentManifest {
+        name: "divider",
+        description: "Horizontal rule between the blocks",
+        content: ContentKind::None,
+        properties: &[],
+        is_styled: true,
+    },
+    ComponentManifest {
+        name: "break",
+        description: "Line break",
+        content: ContentKind::None,
+        properties: &[],
+        is_styled: false,
+    },
+    ComponentManifest {
+        name: "page_break",
+        description: "Break between the pages of the printed document",
+        content: ContentKind::None,
+        properties: &[],
+        is_styled: false,
+    },
+    ComponentManifest {
+        name: "page",
+        description:
+            "Metadata of the page head, such as title and language. Must be at the top level",
+        content: ContentKind::None,
+        properties: &[
+            PropertyManifest {
+                is_default: true,
+                ..PropertyManifest::named("title", PropertyType::String)
+            },
+            PropertyManifest::named("description", PropertyType::String),
+            PropertyManifest::named("lang", PropertyType::String),
+            PropertyManifest::named("favicon", PropertyType::String),
+            PropertyManifest {
+                allowed_values: THEME_VALUES,
+                ..PropertyManifest::named("theme", PropertyType::String)
+            },
+            PropertyManifest {
+                default_value: Some("utf-8"),
+                ..PropertyManifest::named("charset", PropertyType::String)
+            },
+        ],
+        is_styled: false,
+    },
+    ComponentManifest {
+        name: "stylesheet",
+        description: "External stylesheet, linked from the page head. Must be at the top level",
+        content: ContentKind::None,
+        properties: &[PropertyManifest::default_property(
+            "href",
+            PropertyType::String,
+        )],
+        is_styled: false,
+    },
+    ComponentManifest {
+        name: "script",
+        description: "External script, loaded from the page head. Must be at the top level",
+        content: ContentKind::None,
+        properties: &[
+            PropertyManifest::default_property("src", PropertyType::String),
+            PropertyManifest::flag("defer"),
+            PropertyManifest::flag("async"),
+            PropertyManifest::flag("module"),
+        ],
+        is_styled: false,
+    },
+];
@@ -0,0 +1,54 @@
+use std::fmt;
+use std::str::FromStr;
+
+/// Built-in theme, that is included into the page as a stylesheet
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Theme {
+    Light,
+    Dark,
+    /// Minimal theme that only limits page width
+    Plain,
+}
+
+impl Theme {
+    /// All built-in themes
+    pub const ALL: [Theme; 3] = [Theme::Light, Theme::Dark, Theme::Plain];
+
+    /// Returns name of the theme
+    pub fn name(self) -> &'static str {
+        match self {
+            Theme::Light => "light",
+            Theme::Dark => "dark",
+            Theme::Plain => "plain",
+        }
+    }
+
+    /// Returns CSS of the theme
+    pub fn css(self) -> &'static str {
+        match self {
+            Theme::Light => include_str!("../themes/light.css"),
+            Theme::Dark => include_str!("../themes/dark.css"),
+            Theme::Plain => include_str!("../themes/plain.css"),
+        }
+    }
+}
+
+impl fmt::Display for Theme {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+impl FromStr for Theme {
+    type Err = String;
+
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        Theme::ALL
+            .into_iter()
+            .find(|theme| theme.name() == name)
+            .ok_or_else(|| {
+                let names = Theme::ALL.map(Theme::name).join(", ");
+                format!("Unknown theme '{name}', expected one of: {names}")
+            })
+    }
+}
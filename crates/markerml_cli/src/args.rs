@@ -1,4 +1,8 @@
-use clap::{Parser, Subcommand};
+use clap::builder::PossibleValuesParser;
+use clap::{ArgGroup, Args as ClapArgs, Parser, Subcommand, ValueEnum};
+use clap_complete::Shell;
+use markerml::{CssMode, Theme, Value};
+use std::net::IpAddr;
 
 /// Command line arguments that the program might receive
 #[derive(Parser)]
@@ -8,6 +12,16 @@ use clap::{Parser, Subcommand};
 pub struct Args {
     #[command(subcommand)]
     pub command: Command,
+    #[arg(short, long, global = true, help = "Print only errors")]
+    pub quiet: bool,
+    #[arg(
+        short,
+        long,
+        global = true,
+        conflicts_with = "quiet",
+        help = "Print debug messages"
+    )]
+    pub verbose: bool,
 }
 
 impl Args {
@@ -19,21 +33,180 @@ impl Args {
 /// Commands that program might perform
 #[derive(Subcommand)]
 pub enum Command {
-    /// Command for converting file with code to html file
-    #[clap(about = "Convert specified file")]
+    /// Command for converting files with code to html files
+    #[clap(about = "Convert specified files")]
+    #[command(group(ArgGroup::new("destination").args(["output", "out_dir"])))]
     Convert {
+        #[arg(
+            short,
+            long,
+            value_name = "Input files or glob patterns, or - for standard input",
+            num_args = 1..,
+            required_unless_present = "input_dir"
+        )]
+        input: Vec<String>,
+        #[arg(
+            long,
+            value_name = "Input directory",
+            conflicts_with = "input",
+            requires = "out_dir",
+            help = "Convert every .mml file of the directory recursively"
+        )]
+        input_dir: Option<String>,
+        #[arg(
+            short,
+            long,
+            value_name = "Output file, or - for standard output (default)"
+        )]
+        output: Option<String>,
+        #[arg(long, visible_alias = "output-dir", value_name = "Output directory")]
+        out_dir: Option<String>,
+        #[arg(
+            short,
+            long,
+            value_name = "N",
+            help = "Number of files converted in parallel. Defaults to the number of CPUs"
+        )]
+        jobs: Option<usize>,
+        #[arg(long, help = "Emit markup without the surrounding page")]
+        fragment: bool,
+        #[arg(
+            long,
+            conflicts_with = "fragment",
+            help = "Emit complete page (default)"
+        )]
+        standalone: bool,
+        #[arg(
+            long,
+            help = "Emit positions of the components in the code as data-mml-span attributes"
+        )]
+        source_spans: bool,
+        #[command(flatten)]
+        vars: VariableArgs,
+        #[command(flatten)]
+        style: StyleArgs,
+        #[command(flatten)]
+        images: ImageArgs,
+        #[command(flatten)]
+        lints: LintArgs,
+        #[arg(long, help = "Warn about relative links to missing files")]
+        check_links: bool,
+        #[arg(long, value_name = "Diagnostics format", default_value = "human")]
+        message_format: MessageFormat,
+    },
+    /// Command for checking files with code for errors without emitting html
+    #[clap(about = "Check specified files for errors")]
+    Check {
+        #[arg(short, long, value_name = "Input files or glob patterns", num_args = 1.., required = true)]
+        input: Vec<String>,
+        #[command(flatten)]
+        vars: VariableArgs,
+        #[command(flatten)]
+        lints: LintArgs,
+        #[arg(long, help = "Warn about relative links to missing files")]
+        check_links: bool,
+        #[arg(long, value_name = "Diagnostics format", default_value = "human")]
+        message_format: MessageFormat,
+    },
+    /// Command for formatting files with code
+    #[clap(about = "Format specified files")]
+    Fmt {
+        #[arg(
+            value_name = "Input files or glob patterns",
+            required_unless_present = "input"
+        )]
+        files: Vec<String>,
+        #[arg(short, long, value_name = "Input files or glob patterns", num_args = 1..)]
+        input: Vec<String>,
+        #[arg(long, help = "Check if files are formatted without modifying them")]
+        check: bool,
+    },
+    /// Command for printing parsed Abstract Syntax Tree
+    #[clap(about = "Print AST of specified file")]
+    DumpAst {
         #[arg(short, long, value_name = "Input file")]
         input: String,
-        #[arg(short, long, value_name = "Output file")]
-        output: String,
+        #[arg(short, long, value_name = "Output format", default_value = "debug")]
+        format: DumpFormat,
     },
-    /// Command to start web server and watch for changes in code file
-    #[clap(about = "Run webserver for specified file")]
-    Watch {
+    /// Command for printing generated Intermediate Representation
+    #[clap(about = "Print IR of specified file")]
+    DumpIr {
+        #[arg(short, long, value_name = "Input file")]
+        input: String,
+        #[arg(short, long, value_name = "Output format", default_value = "debug")]
+        format: DumpFormat,
+        #[arg(short, long, help = "Replace custom components with their definitions")]
+        expand: bool,
+    },
+    /// Command for printing token stream of the file
+    #[clap(about = "Print tokens of specified file")]
+    Tokens {
+        #[arg(short, long, value_name = "Input file")]
+        input: String,
+    },
+    /// Command for printing statistics of the document
+    #[clap(about = "Print statistics of specified file")]
+    Stats {
         #[arg(short, long, value_name = "Input file")]
         input: String,
+    },
+    /// Command for building static site from the directory with code.
+    /// Missing options are taken from the project configuration
+    #[clap(about = "Build static site from specified directory")]
+    Build {
+        #[arg(short, long, value_name = "Source directory")]
+        input: Option<String>,
+        #[arg(short, long, value_name = "Output directory")]
+        output: Option<String>,
+        #[arg(short, long, value_name = "Layout file")]
+        layout: Option<String>,
+        #[arg(short, long = "component", value_name = "Component definitions file")]
+        components: Vec<String>,
+        #[arg(
+            long,
+            help = "Probe images for dimensions and generate resized variants for srcset"
+        )]
+        responsive_images: bool,
+    },
+    /// Command for creating starter project
+    #[clap(about = "Create starter project")]
+    Init {
+        #[arg(value_name = "Project directory")]
+        name: Option<String>,
+    },
+    /// Command for converting snippets of code interactively
+    #[clap(about = "Start interactive session")]
+    Repl,
+    /// Command to start web server and watch for changes in code file or directory
+    #[clap(about = "Run webserver for specified file or directory")]
+    Watch {
+        #[arg(short, long, value_name = "Input file or directory")]
+        input: String,
         #[arg(short, long, value_name = "Port")]
         port: Option<u16>,
+        #[arg(long, value_name = "Bind address", default_value = "0.0.0.0")]
+        host: IpAddr,
+        #[arg(long, value_name = "TLS certificate file", requires = "tls_key")]
+        tls_cert: Option<String>,
+        #[arg(long, value_name = "TLS private key file", requires = "tls_cert")]
+        tls_key: Option<String>,
+        #[arg(long, help = "Open the page in the default browser")]
+        open: bool,
+        #[arg(short, long = "component", value_name = "Component definitions file")]
+        components: Vec<String>,
+        #[command(flatten)]
+        vars: VariableArgs,
+        #[command(flatten)]
+        style: StyleArgs,
+        #[command(flatten)]
+        lints: LintArgs,
+    },
+    /// Command to generate shell completion script
+    #[clap(about = "Generate completion script for specified shell")]
+    Completions {
+        #[arg(value_name = "Shell")]
+        shell: Shell,
     },
     /// Command to display credits
     #[clap(about = "Display credits information")]
@@ -42,3 +215,146 @@ pub enum Command {
     #[clap(about = "Display list of commands")]
     Help,
 }
+
+/// Format of the dumped compiler data structures
+#[derive(Clone, Copy, ValueEnum)]
+pub enum DumpFormat {
+    /// Pretty-printed debug representation
+    Debug,
+    /// Pretty-printed JSON
+    Json,
+}
+
+/// Format of the reported diagnostics
+#[derive(Clone, Copy, ValueEnum)]
+pub enum MessageFormat {
+    /// Graphical report with highlighted source code
+    Human,
+    /// Single JSON object per line, suitable for editors and CI
+    Json,
+}
+
+/// Variables available for interpolation in the whole module
+#[derive(ClapArgs)]
+pub struct VariableArgs {
+    #[arg(long = "var", value_name = "key=value", value_parser = parse_variable)]
+    pub vars: Vec<(String, Value)>,
+    #[arg(long, value_name = "JSON file with variables")]
+    pub vars_file: Option<String>,
+    #[arg(long, value_name = "TOML or JSON file with localized messages")]
+    pub messages: Option<String>,
+}
+
+/// Levels of the lints, that are reported as warnings by default
+#[derive(ClapArgs)]
+pub struct LintArgs {
+    #[arg(
+        short = 'A',
+        long = "allow",
+        value_name = "Lint",
+        value_parser = PossibleValuesParser::new(markerml::LINTS),
+        help = "Silence warnings of the lint"
+    )]
+    pub allow: Vec<String>,
+    #[arg(
+        short = 'D',
+        long = "deny",
+        value_name = "Lint",
+        value_parser = PossibleValuesParser::new(markerml::LINTS),
+        help = "Turn warnings of the lint into errors"
+    )]
+    pub deny: Vec<String>,
+}
+
+/// Parses variable in `key=value` format.
+/// Value is parsed as integer or boolean if possible, otherwise it's a string
+fn parse_variable(arg: &str) -> Result<(String, Value), String> {
+    let (key, value) = arg
+        .split_once('=')
+        .ok_or_else(|| format!("Expected key=value, got '{arg}'"))?;
+    let value = if let Ok(value) = value.parse() {
+        Value::Integer(value)
+    } else if let Ok(value) = value.parse() {
+        Value::Bool(value)
+    } else {
+        Value::String(value.to_owned())
+    };
+
+    Ok((key.to_owned(), value))
+}
+
+/// Styles of the generated page
+#[derive(ClapArgs)]
+pub struct StyleArgs {
+    #[arg(long = "style", value_name = "CSS file")]
+    pub styles: Vec<String>,
+    #[arg(long, help = "Embed content of the CSS files instead of linking them")]
+    pub inline_style: bool,
+    #[arg(long, value_name = "Built-in theme")]
+    pub theme: Option<Theme>,
+    #[arg(
+        long,
+        default_value = "",
+        help = "Prefix of the generated ids, so that multiple documents can share one page"
+    )]
+    pub id_prefix: String,
+    #[arg(
+        long,
+        value_name = "nonce",
+        help = "Nonce of the generated style blocks for Content-Security-Policy"
+    )]
+    pub nonce: Option<String>,
+    #[arg(
+        long,
+        default_value_t = CssMode::Inline,
+        help = "Emit styles of the components as inline attributes or as classes in a style block"
+    )]
+    pub css: CssMode,
+    #[arg(long, value_name = "title")]
+    pub title: Option<String>,
+    #[arg(long, help = "Put nested elements on separate indented lines")]
+    pub pretty: bool,
+    #[arg(
+        long,
+        help = "Emit newlines of the text as line breaks and blank lines as paragraph breaks"
+    )]
+    pub preserve_newlines: bool,
+    #[arg(
+        long,
+        help = "Emit print-oriented page, split into pages by page_break components"
+    )]
+    pub print: bool,
+    #[arg(long, value_name = "CSS page size", default_value = "A4", value_parser = parse_css_value)]
+    pub page_size: String,
+    #[arg(long, value_name = "CSS margin", default_value = "2cm", value_parser = parse_css_value)]
+    pub page_margin: String,
+}
+
+/// Handling of the local images, referenced by the page
+#[derive(ClapArgs, Clone, Default)]
+pub struct ImageArgs {
+    #[arg(long, help = "Embed small local images into the page as data URIs")]
+    pub inline_images: bool,
+    #[arg(
+        long,
+        value_name = "Assets directory",
+        help = "Copy local images into the directory, pointing the page to the copies"
+    )]
+    pub assets_dir: Option<String>,
+}
+
+impl ImageArgs {
+    /// Checks whether sources of the local images are rewritten
+    pub fn is_enabled(&self) -> bool {
+        self.inline_images || self.assets_dir.is_some()
+    }
+}
+
+/// Parses value, that is inserted into the generated CSS
+fn parse_css_value(arg: &str) -> Result<String, String> {
+    if arg.trim().is_empty() || arg.contains([';', '{', '}', '<', '>', '"', '\'']) {
+        Err(format!("Invalid CSS value '{arg}'"))
+    } else {
+        Ok(arg.to_owned())
+    }
+}
@@ -1,12 +1,80 @@
-use anyhow::{anyhow, Context, Result};
-use miette::{GraphicalReportHandler, NamedSource};
+use crate::args::{LintArgs, StyleArgs, VariableArgs};
+use crate::diagnostics;
+use anyhow::{anyhow, bail, Context, Result};
+use markerml::{
+    DiagnosticLevel, Diagnostics, HtmlGeneratorOptions, MarkermlError, Options, PrintOptions, Value,
+};
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::fmt;
 use std::fs::{self, File};
-use std::path::Path;
-use std::sync::LazyLock;
+use std::io;
+use std::path::{Path, PathBuf};
+use walkdir::WalkDir;
 
-/// Error reporter used for pretty-printing miette errors
-static ERROR_REPORTER: LazyLock<GraphicalReportHandler> =
-    LazyLock::new(GraphicalReportHandler::new);
+/// Kind of failure, that determines exit code of the program.
+/// Other errors, such as IO errors, result in exit code 1
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Failure {
+    /// Code contains errors
+    Compilation,
+    /// Code doesn't pass the checks, such as formatting
+    Lint,
+}
+
+impl Failure {
+    pub fn exit_code(self) -> u8 {
+        match self {
+            Failure::Compilation => 3,
+            Failure::Lint => 4,
+        }
+    }
+}
+
+impl fmt::Display for Failure {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Failure::Compilation => write!(f, "Compilation error"),
+            Failure::Lint => write!(f, "Lint failure"),
+        }
+    }
+}
+
+impl std::error::Error for Failure {}
+
+/// Returns exit code of the program for the given error
+pub fn exit_code(err: &anyhow::Error) -> u8 {
+    err.downcast_ref::<Failure>()
+        .map_or(1, |failure| failure.exit_code())
+}
+
+/// Combines errors of the processed files into a single error with the given message.
+/// Failure kind of the result is compilation, if any of the files had compilation errors
+pub fn combine_errors(errors: &[anyhow::Error], message: String) -> anyhow::Error {
+    let failure = errors
+        .iter()
+        .filter_map(|err| err.downcast_ref::<Failure>())
+        .min_by_key(|failure| failure.exit_code());
+
+    match failure {
+        Some(failure) => anyhow::Error::new(*failure).context(message),
+        None => anyhow!(message),
+    }
+}
+
+/// Extension of the files with MarkerML code
+pub const SOURCE_EXTENSION: &str = "mml";
+
+/// Path, that stands for the standard input or output
+pub const STDIO_PATH: &str = "-";
+
+/// Name of the standard input in the reports
+const STDIN_NAME: &str = "<stdin>";
+
+/// Checks whether the path stands for the standard input or output
+pub fn is_stdio(path: &Path) -> bool {
+    path == Path::new(STDIO_PATH)
+}
 
 /// Checks whether the file exists
 pub fn check_file_exists(filename: &Path) -> Result<()> {
@@ -15,22 +83,389 @@ pub fn check_file_exists(filename: &Path) -> Result<()> {
     Ok(())
 }
 
-/// Reads given code file, parses it and return string with html
-pub fn parse_file(filename: &Path) -> Result<String> {
+/// Collects variables from the command line and variables file.
+/// Command line variables take precedence
+pub fn read_variables(args: VariableArgs) -> Result<HashMap<String, Value>> {
+    let mut vars = HashMap::new();
+    if let Some(filename) = args.vars_file {
+        let content = fs::read_to_string(&filename)
+            .with_context(|| format!("Couldn't read variables file {filename}"))?;
+        let values: serde_json::Map<String, serde_json::Value> = serde_json::from_str(&content)
+            .with_context(|| format!("Variables file {filename} must contain JSON object"))?;
+        for (key, value) in values {
+            let value = match value {
+                serde_json::Value::String(value) => Value::String(value),
+                serde_json::Value::Bool(value) => Value::Bool(value),
+                serde_json::Value::Number(number) => {
+                    Value::Integer(number.as_i64().ok_or_else(|| {
+                        anyhow!("Variable '{key}' must be an integer, got {number}")
+                    })?)
+                }
+                value => bail!("Unsupported value of variable '{key}': {value}"),
+            };
+            vars.insert(key, value);
+        }
+    }
+    vars.extend(args.vars);
+
+    Ok(vars)
+}
+
+/// Reads catalog of localized messages from TOML or JSON file, depending on its extension.
+/// Nested tables are flattened, joining keys with dots
+pub fn read_messages(filename: Option<&str>) -> Result<Option<HashMap<String, String>>> {
+    let Some(filename) = filename else {
+        return Ok(None);
+    };
+
+    let content = fs::read_to_string(filename)
+        .with_context(|| format!("Couldn't read messages file {filename}"))?;
+    let catalog: serde_json::Value = if Path::new(filename)
+        .extension()
+        .is_some_and(|extension| extension == "json")
+    {
+        serde_json::from_str(&content)
+            .with_context(|| format!("Couldn't parse messages file {filename}"))?
+    } else {
+        toml::from_str(&content)
+            .with_context(|| format!("Couldn't parse messages file {filename}"))?
+    };
+
+    let mut messages = HashMap::new();
+    flatten_messages(String::new(), catalog, &mut messages)?;
+
+    Ok(Some(messages))
+}
+
+fn flatten_messages(
+    key: String,
+    value: serde_json::Value,
+    messages: &mut HashMap<String, String>,
+) -> Result<()> {
+    match value {
+        serde_json::Value::String(message) => {
+            messages.insert(key, message);
+        }
+        serde_json::Value::Object(values) => {
+            for (name, value) in values {
+                let key = if key.is_empty() {
+                    name
+                } else {
+                    format!("{key}.{name}")
+                };
+                flatten_messages(key, value, messages)?;
+            }
+        }
+        value => bail!("Message '{key}' must be a string, got {value}"),
+    }
+
+    Ok(())
+}
+
+/// Collects levels of the lints. Denied lints take precedence over allowed ones
+pub fn read_lints(args: LintArgs) -> HashMap<String, DiagnosticLevel> {
+    let allowed = args
+        .allow
+        .into_iter()
+        .map(|lint| (lint, DiagnosticLevel::Allow));
+    let denied = args
+        .deny
+        .into_iter()
+        .map(|lint| (lint, DiagnosticLevel::Deny));
+
+    allowed.chain(denied).collect()
+}
+
+/// Collects styles of the generated page.
+/// CSS files are either linked by their path or embedded into the page
+pub fn read_styles(args: StyleArgs) -> Result<HtmlGeneratorOptions> {
+    let mut options = HtmlGeneratorOptions {
+        theme: args.theme,
+        id_prefix: args.id_prefix,
+        nonce: args.nonce,
+        css: args.css,
+        title: args.title,
+        pretty: args.pretty,
+        preserve_newlines: args.preserve_newlines,
+        print: args.print.then_some(PrintOptions {
+            page_size: args.page_size,
+            margin: args.page_margin,
+        }),
+        ..HtmlGeneratorOptions::default()
+    };
+    for filename in args.styles {
+        if args.inline_style {
+            let content = fs::read_to_string(&filename)
+                .with_context(|| format!("Couldn't read style file {filename}"))?;
+            options.styles.push(content);
+        } else {
+            options.stylesheets.push(filename);
+        }
+    }
+
+    Ok(options)
+}
+
+/// Returns options, whose relative links are checked against the directory of the input file,
+/// if links are checked at all
+pub fn input_options<'a>(options: &'a Options, input: &Path) -> Cow<'a, Options> {
+    match &options.link_base {
+        Some(base) if !is_stdio(input) => Cow::Owned(Options {
+            link_base: Some(base.join(input.parent().unwrap_or(Path::new("")))),
+            ..options.clone()
+        }),
+        _ => Cow::Borrowed(options),
+    }
+}
+
+/// Reads given code file and converts it using the given function,
+/// printing the report in case of compilation error
+pub fn parse_file_with<T>(
+    filename: &Path,
+    convert: impl Fn(&str) -> Result<T, MarkermlError>,
+) -> Result<T> {
+    let mut report = String::new();
+    let result = render_file_with(filename, convert, &mut report);
+    eprint!("{report}");
+
+    result
+}
+
+/// Reads given code file and converts it using the given function, writing the report
+/// in case of compilation error into the buffer, so that the reports of the files,
+/// converted in parallel, can be printed one after another
+pub fn render_file_with<T>(
+    filename: &Path,
+    convert: impl Fn(&str) -> Result<T, MarkermlError>,
+    report: &mut String,
+) -> Result<T> {
     let content = fs::read_to_string(filename).context("Couldn't read file content")?;
 
-    let html = match markerml::parse(&content) {
+    let html = match convert(&content) {
         Ok(html) => html,
         Err(err) => {
-            let mut buffer = String::new();
-            let err = miette::Error::from(err)
-                .with_source_code(NamedSource::new(filename.display().to_string(), content));
-            ERROR_REPORTER.render_report(&mut buffer, err.as_ref())?;
-            println!("{}", buffer);
+            diagnostics::render_error(filename, content, err, report)?;
 
-            return Err(anyhow!("Compilation error"));
+            return Err(Failure::Compilation.into());
         }
     };
 
     Ok(html)
 }
+
+/// Reads given code file, or the standard input if the path is `-`, and converts it
+/// using the given function, writing the warnings and the report in case of compilation error
+/// into the buffer, so that the reports of the files, converted in parallel, don't interleave
+pub fn parse_file_with_diagnostics(
+    filename: &Path,
+    convert: impl Fn(&str) -> Result<Diagnostics, MarkermlError>,
+    report: &mut String,
+) -> Result<String> {
+    let (filename, content) = if is_stdio(filename) {
+        let content = io::read_to_string(io::stdin()).context("Couldn't read standard input")?;
+        (Path::new(STDIN_NAME), content)
+    } else {
+        let content = fs::read_to_string(filename).context("Couldn't read file content")?;
+        (filename, content)
+    };
+
+    match convert(&content) {
+        Ok(result) => {
+            diagnostics::render_warnings(filename, &content, &result.warnings, report)?;
+
+            Ok(result.html)
+        }
+        Err(err) => {
+            diagnostics::render_error(filename, content, err, report)?;
+
+            Err(Failure::Compilation.into())
+        }
+    }
+}
+
+/// Escapes special html characters in the text
+pub fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+/// Input file along with the directory its relative output path is computed from
+pub struct InputFile {
+    /// Path to the input file
+    pub path: PathBuf,
+    /// Directory which the structure of the output is preserved relative to
+    pub base: PathBuf,
+}
+
+impl InputFile {
+    /// Returns path of the html file inside of the output directory
+    pub fn output_path(&self, out_dir: &Path) -> PathBuf {
+        let relative = self.path.strip_prefix(&self.base).unwrap_or(&self.path);
+
+        out_dir.join(relative).with_extension("html")
+    }
+}
+
+/// Expands given list of file paths and glob patterns into the list of input files
+pub fn expand_inputs(patterns: &[String]) -> Result<Vec<InputFile>> {
+    let mut files = Vec::new();
+    for pattern in patterns {
+        if pattern == STDIO_PATH {
+            files.push(InputFile {
+                path: PathBuf::from(pattern),
+                base: PathBuf::new(),
+            });
+
+            continue;
+        }
+        if !is_glob_pattern(pattern) {
+            let path = PathBuf::from(pattern);
+            check_file_exists(&path)?;
+            let base = path.parent().map(Path::to_owned).unwrap_or_default();
+            files.push(InputFile { path, base });
+
+            continue;
+        }
+
+        let base = glob_base(pattern);
+        let count = files.len();
+        for entry in glob::glob(pattern).with_context(|| format!("Invalid pattern {pattern}"))? {
+            let path = entry.context("Couldn't read matched path")?;
+            if path.is_file() {
+                files.push(InputFile {
+                    path,
+                    base: base.clone(),
+                });
+            }
+        }
+        if files.len() == count {
+            bail!("Pattern {pattern} didn't match any files");
+        }
+    }
+
+    Ok(files)
+}
+
+/// Collects files with MarkerML code from the directory recursively.
+/// Files inside of the output directory are skipped, since it might be nested
+pub fn expand_input_dir(dir: &Path, out_dir: &Path) -> Result<Vec<InputFile>> {
+    let skipped = nested_dir(dir, out_dir)?;
+    let mut files = Vec::new();
+    let entries = WalkDir::new(dir).into_iter().filter_entry(|entry| {
+        skipped
+            .as_ref()
+            .is_none_or(|skipped| !entry.path().starts_with(skipped))
+    });
+    for entry in entries {
+        let entry = entry.with_context(|| format!("Couldn't read directory {}", dir.display()))?;
+        let is_source = entry
+            .path()
+            .extension()
+            .is_some_and(|ext| ext == SOURCE_EXTENSION);
+        if entry.file_type().is_file() && is_source {
+            files.push(InputFile {
+                path: entry.into_path(),
+                base: dir.to_owned(),
+            });
+        }
+    }
+    if files.is_empty() {
+        bail!(
+            "Directory {} doesn't contain .{SOURCE_EXTENSION} files",
+            dir.display()
+        );
+    }
+
+    Ok(files)
+}
+
+/// Returns the nested directory as a path inside of the given directory, so that
+/// it can be compared with the paths of the walked entries regardless of how both are written.
+/// Directory that doesn't exist yet or isn't nested is ignored
+fn nested_dir(dir: &Path, nested: &Path) -> Result<Option<PathBuf>> {
+    let Ok(nested) = fs::canonicalize(nested) else {
+        return Ok(None);
+    };
+    let canonical = fs::canonicalize(dir)
+        .with_context(|| format!("Couldn't resolve path {}", dir.display()))?;
+
+    Ok(nested
+        .strip_prefix(&canonical)
+        .ok()
+        .filter(|relative| !relative.as_os_str().is_empty())
+        .map(|relative| dir.join(relative)))
+}
+
+/// Checks whether the path contains glob special characters
+fn is_glob_pattern(pattern: &str) -> bool {
+    pattern.contains(['*', '?', '['])
+}
+
+/// Returns the longest leading directory of the pattern without special characters
+fn glob_base(pattern: &str) -> PathBuf {
+    Path::new(pattern)
+        .components()
+        .take_while(|component| !is_glob_pattern(&component.as_os_str().to_string_lossy()))
+        .collect()
+}
+
+#[cfg(test)]
+pub(crate) mod test {
+    use super::*;
+
+    /// Creates empty directory for the test inside of the temporary directory
+    pub(crate) fn test_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("markerml-{name}-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+
+        dir
+    }
+
+    fn input_names(files: &[InputFile]) -> Vec<String> {
+        let mut names = files
+            .iter()
+            .map(|file| {
+                file.path
+                    .strip_prefix(&file.base)
+                    .unwrap()
+                    .display()
+                    .to_string()
+            })
+            .collect::<Vec<_>>();
+        names.sort();
+
+        names
+    }
+
+    #[test]
+    fn input_dir_skips_output() -> Result<()> {
+        let dir = test_dir("input-dir");
+        fs::create_dir_all(dir.join("pages"))?;
+        fs::create_dir_all(dir.join("site"))?;
+        fs::write(dir.join("index.mml"), "")?;
+        fs::write(dir.join("pages/a.mml"), "")?;
+        fs::write(dir.join("site/old.mml"), "")?;
+
+        let out_dir = dir.join("pages/../site");
+        let files = expand_input_dir(&dir, &out_dir)?;
+        assert_eq!(input_names(&files), ["index.mml", "pages/a.mml"]);
+
+        // Output directory outside of the input one or the same as it doesn't hide inputs
+        let files = expand_input_dir(&dir.join("pages"), &dir)?;
+        assert_eq!(input_names(&files), ["a.mml"]);
+        let files = expand_input_dir(&dir, &dir)?;
+        assert_eq!(
+            input_names(&files),
+            ["index.mml", "pages/a.mml", "site/old.mml"]
+        );
+        let files = expand_input_dir(&dir, &dir.join("missing"))?;
+        assert_eq!(files.len(), 3);
+
+        fs::remove_dir_all(dir)?;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,213 @@
+use crate::args::MessageFormat;
+use anyhow::Result;
+use markerml::markerml_frontend::SourceMap;
+use markerml::{MarkermlError, MarkermlWarning};
+use miette::{Diagnostic, GraphicalReportHandler, NamedSource, Severity};
+use serde::Serialize;
+use std::path::Path;
+use std::sync::{LazyLock, OnceLock};
+
+/// Error reporter used for pretty-printing miette errors
+static ERROR_REPORTER: LazyLock<GraphicalReportHandler> =
+    LazyLock::new(GraphicalReportHandler::new);
+
+/// Format of the diagnostics, selected for the whole program run
+static MESSAGE_FORMAT: OnceLock<MessageFormat> = OnceLock::new();
+
+/// Diagnostic information, serialized as a single JSON line
+/// or sent to the live-reloading page of the watch server
+#[derive(Clone, PartialEq, Serialize)]
+pub struct DiagnosticReport {
+    severity: &'static str,
+    code: Option<String>,
+    message: String,
+    file: String,
+    span: Option<ReportSpan>,
+    labels: Vec<ReportLabel>,
+    help: Option<String>,
+}
+
+/// Span of the diagnostic in the source code
+#[derive(Clone, PartialEq, Serialize)]
+struct ReportSpan {
+    start: usize,
+    end: usize,
+    /// One-based line number of the span start
+    line: usize,
+    /// One-based column number of the span start
+    column: usize,
+}
+
+/// Labeled span of the diagnostic
+#[derive(Clone, PartialEq, Serialize)]
+struct ReportLabel {
+    label: Option<String>,
+    span: ReportSpan,
+}
+
+/// Sets format of the diagnostics. Human-readable format is used by default
+pub fn set_message_format(format: MessageFormat) {
+    let _ = MESSAGE_FORMAT.set(format);
+}
+
+fn message_format() -> MessageFormat {
+    MESSAGE_FORMAT
+        .get()
+        .copied()
+        .unwrap_or(MessageFormat::Human)
+}
+
+/// Prints compilation error in the selected format
+pub fn report_error(filename: &Path, content: String, err: MarkermlError) -> Result<()> {
+    let mut output = String::new();
+    render_error(filename, content, err, &mut output)?;
+    eprint!("{output}");
+
+    Ok(())
+}
+
+/// Prints compilation warnings in the selected format
+pub fn report_warnings(filename: &Path, content: &str, warnings: &[MarkermlWarning]) -> Result<()> {
+    let mut output = String::new();
+    render_warnings(filename, content, warnings, &mut output)?;
+    eprint!("{output}");
+
+    Ok(())
+}
+
+/// Writes compilation error in the selected format into the output
+pub fn render_error(
+    filename: &Path,
+    content: String,
+    err: MarkermlError,
+    output: &mut String,
+) -> Result<()> {
+    render(filename, content, err, output)
+}
+
+/// Writes compilation warnings in the selected format into the output
+pub fn render_warnings(
+    filename: &Path,
+    content: &str,
+    warnings: &[MarkermlWarning],
+    output: &mut String,
+) -> Result<()> {
+    for warning in warnings {
+        render(filename, content.to_owned(), warning.clone(), output)?;
+    }
+
+    Ok(())
+}
+
+fn render(
+    filename: &Path,
+    content: String,
+    diagnostic: impl Diagnostic + Send + Sync + 'static,
+    output: &mut String,
+) -> Result<()> {
+    match message_format() {
+        MessageFormat::Human => {
+            let report = miette::Error::from(diagnostic)
+                .with_source_code(NamedSource::new(filename.display().to_string(), content));
+            ERROR_REPORTER.render_report(output, report.as_ref())?;
+            output.push('\n');
+        }
+        MessageFormat::Json => {
+            let report = create_report(filename, &content, &diagnostic);
+            output.push_str(&serde_json::to_string(&report)?);
+            output.push('\n');
+        }
+    }
+
+    Ok(())
+}
+
+/// Creates structured diagnostic report of the compilation error or warning
+pub fn create_report(filename: &Path, content: &str, err: &dyn Diagnostic) -> DiagnosticReport {
+    let source_map = SourceMap::new(content);
+    let message = err.to_string();
+    let labels = err
+        .labels()
+        .into_iter()
+        .flatten()
+        .map(|label| ReportLabel {
+            label: label.label().map(str::to_owned),
+            span: create_span(&source_map, label.offset(), label.offset() + label.len()),
+        })
+        .collect::<Vec<_>>();
+    let severity = match err.severity() {
+        Some(Severity::Advice) => "advice",
+        Some(Severity::Warning) => "warning",
+        Some(Severity::Error) | None => "error",
+    };
+
+    DiagnosticReport {
+        severity,
+        code: err.code().map(|code| code.to_string()),
+        message,
+        file: filename.display().to_string(),
+        span: labels.first().map(|label| label.span.clone()),
+        labels,
+        help: err.help().map(|help| help.to_string()),
+    }
+}
+
+impl DiagnosticReport {
+    /// Creates error report without source location, such as failure to read the file
+    pub fn from_message(filename: &Path, message: String) -> Self {
+        DiagnosticReport {
+            severity: "error",
+            code: None,
+            message,
+            file: filename.display().to_string(),
+            span: None,
+            labels: Vec::new(),
+            help: None,
+        }
+    }
+}
+
+fn create_span(source_map: &SourceMap, start: usize, end: usize) -> ReportSpan {
+    let position = source_map.line_column(start.min(source_map.source().len()));
+
+    ReportSpan {
+        start,
+        end,
+        line: position.line,
+        column: position.column,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use markerml::Options;
+
+    #[test]
+    fn json_reports() -> Result<()> {
+        let code = "@(a)\nbox[foo = 1]";
+        let diagnostics = markerml::parse_with_diagnostics(code, &Options::default())?;
+        let report = create_report(Path::new("a.mml"), code, &diagnostics.warnings[0]);
+        assert_eq!(
+            serde_json::to_string(&report)?,
+            concat!(
+                r#"{"severity":"warning","code":"unknown_property","#,
+                r#""message":"Unknown property 'foo' of the component 'box'","file":"a.mml","#,
+                r#""span":{"start":9,"end":12,"line":2,"column":5},"#,
+                r#""labels":[{"label":"Property used here","#,
+                r#""span":{"start":9,"end":12,"line":2,"column":5}}],"help":null}"#
+            )
+        );
+
+        let report = DiagnosticReport::from_message(Path::new("b.mml"), "Not found".to_owned());
+        assert_eq!(
+            serde_json::to_string(&report)?,
+            concat!(
+                r#"{"severity":"error","code":null,"message":"Not found","file":"b.mml","#,
+                r#""span":null,"labels":[],"help":null}"#
+            )
+        );
+
+        Ok(())
+    }
+}
@@ -0,0 +1,364 @@
+use crate::args::ImageArgs;
+use anyhow::{Context, Result};
+use base64::Engine;
+use image::imageops::FilterType;
+use markerml::ImageInfo;
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{self, Component, Path, PathBuf};
+
+/// Widths of the resized variants. Only those smaller than the image are generated
+const VARIANT_WIDTHS: [u32; 3] = [480, 960, 1440];
+/// Extensions of the images, that are probed and resized
+const IMAGE_EXTENSIONS: [&str; 5] = ["png", "jpg", "jpeg", "gif", "webp"];
+/// Size of the largest image in bytes, that is embedded as data URI
+const INLINE_IMAGE_LIMIT: u64 = 16 * 1024;
+/// MIME types of the images, that can be embedded, by their extension
+const IMAGE_MIME_TYPES: [(&str, &str); 7] = [
+    ("png", "image/png"),
+    ("jpg", "image/jpeg"),
+    ("jpeg", "image/jpeg"),
+    ("gif", "image/gif"),
+    ("webp", "image/webp"),
+    ("avif", "image/avif"),
+    ("svg", "image/svg+xml"),
+];
+
+/// Checks whether the file is an image, that can be probed
+pub fn is_image(path: &Path) -> bool {
+    path.extension()
+        .and_then(|ext| ext.to_str())
+        .is_some_and(|ext| IMAGE_EXTENSIONS.contains(&ext.to_ascii_lowercase().as_str()))
+}
+
+/// Reads dimensions of the source image and writes its resized variants next to the target.
+/// Sources of the variants are their file names
+pub fn process_image(source: &Path, target: &Path) -> Result<ImageInfo> {
+    let image =
+        image::open(source).with_context(|| format!("Couldn't read image {}", source.display()))?;
+    let (width, height) = (image.width(), image.height());
+
+    let stem = target
+        .file_stem()
+        .map(|stem| stem.to_string_lossy())
+        .unwrap_or_default();
+    let extension = target
+        .extension()
+        .map(|ext| ext.to_string_lossy())
+        .unwrap_or_default();
+    let mut variants = Vec::new();
+    for variant_width in VARIANT_WIDTHS.into_iter().filter(|&w| w < width) {
+        let variant_height =
+            (u64::from(height) * u64::from(variant_width) / u64::from(width)).max(1) as u32;
+        let name = format!("{stem}-{variant_width}w.{extension}");
+        let path = target.with_file_name(&name);
+        image
+            .resize_exact(variant_width, variant_height, FilterType::Triangle)
+            .save(&path)
+            .with_context(|| format!("Couldn't write image {}", path.display()))?;
+        variants.push((name, variant_width));
+    }
+
+    Ok(ImageInfo {
+        width,
+        height,
+        variants,
+    })
+}
+
+/// Returns known images by every source the page can reference them with:
+/// relative to the directory of the page and relative to the site root, beginning with `/`.
+/// Images are given by their paths relative to the site root
+pub fn page_images(page: &Path, images: &[(String, ImageInfo)]) -> BTreeMap<String, ImageInfo> {
+    let page_dir = page.parent().map(path_segments).unwrap_or_default();
+
+    let mut result = BTreeMap::new();
+    for (path, image) in images {
+        let segments = path.split('/').collect::<Vec<_>>();
+        let (name, dir) = segments.split_last().unwrap_or((&"", &[]));
+        let common = page_dir.iter().zip(dir).take_while(|(a, b)| a == b).count();
+        let mut prefix = "../".repeat(page_dir.len() - common);
+        prefix.push_str(&dir[common..].join("/"));
+        if !prefix.is_empty() && !prefix.ends_with('/') {
+            prefix.push('/');
+        }
+        let root = match path.rsplit_once('/') {
+            Some((dir, _)) => format!("/{dir}/"),
+            None => "/".to_owned(),
+        };
+
+        for prefix in [prefix, root] {
+            let image = ImageInfo {
+                variants: image
+                    .variants
+                    .iter()
+                    .map(|(name, width)| (format!("{prefix}{name}"), *width))
+                    .collect(),
+                ..image.clone()
+            };
+            result.insert(format!("{prefix}{name}"), image);
+        }
+    }
+
+    result
+}
+
+/// Returns sources, that replace relative sources of the local images of the page:
+/// data URIs of the small images, when they are inlined,
+/// or paths of the copies in the assets directory, relative to the output.
+/// Images, that can't be read or copied, keep their sources
+pub fn resolve_assets(
+    sources: impl IntoIterator<Item = String>,
+    page: &Path,
+    output: &Path,
+    args: &ImageArgs,
+) -> BTreeMap<String, String> {
+    let page_dir = page.parent().unwrap_or(Path::new(""));
+    let output_dir = output
+        .parent()
+        .filter(|dir| !dir.as_os_str().is_empty())
+        .unwrap_or(Path::new("."));
+
+    let mut result = BTreeMap::new();
+    for src in sources.into_iter().filter(|src| is_local(src)) {
+        let path = page_dir.join(&src);
+        let resolved = match inline_image(&path, args) {
+            Some(data) => Ok(data),
+            None => match &args.assets_dir {
+                Some(dir) => copy_image(&path, Path::new(dir), output_dir),
+                None => continue,
+            },
+        };
+        match resolved {
+            Ok(resolved) => {
+                result.insert(src, resolved);
+            }
+            Err(err) => log::warn!("{err:#}"),
+        }
+    }
+
+    result
+}
+
+/// Checks whether the source is a path relative to the page, rather than URL or absolute path
+fn is_local(src: &str) -> bool {
+    let has_scheme = src.split_once(':').is_some_and(|(scheme, _)| {
+        scheme.starts_with(|c: char| c.is_ascii_alphabetic())
+            && scheme
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || "+-.".contains(c))
+    });
+
+    !src.is_empty() && !has_scheme && !src.starts_with(['/', '#', '?'])
+}
+
+/// Returns data URI of the image, if it is inlined and small enough
+fn inline_image(path: &Path, args: &ImageArgs) -> Option<String> {
+    if !args.inline_images {
+        return None;
+    }
+    let extension = path.extension()?.to_str()?.to_ascii_lowercase();
+    let (_, mime) = IMAGE_MIME_TYPES
+        .into_iter()
+        .find(|(ext, _)| *ext == extension)?;
+    if fs::metadata(path).ok()?.len() > INLINE_IMAGE_LIMIT {
+        return None;
+    }
+    let content = fs::read(path).ok()?;
+
+    Some(format!(
+        "data:{mime};base64,{}",
+        base64::engine::general_purpose::STANDARD.encode(content)
+    ))
+}
+
+/// Copies the image into the assets directory and returns path of the copy relative
+/// to the output directory. Copy is named after the image along with the hash of its content,
+/// so that different images with the same name, such as `img/a.png` and `../img/a.png`,
+/// don't overwrite each other, while the same image is copied once
+fn copy_image(path: &Path, assets_dir: &Path, output_dir: &Path) -> Result<String> {
+    let content =
+        fs::read(path).with_context(|| format!("Couldn't read image {}", path.display()))?;
+    let stem = path
+        .file_stem()
+        .map(|stem| stem.to_string_lossy())
+        .unwrap_or_default();
+    let name = match path.extension() {
+        Some(ext) => format!(
+            "{stem}-{:08x}.{}",
+            content_hash(&content),
+            ext.to_string_lossy()
+        ),
+        None => format!("{stem}-{:08x}", content_hash(&content)),
+    };
+    let target = assets_dir.join(name);
+    if !target.exists() {
+        fs::create_dir_all(assets_dir).with_context(|| {
+            format!("Couldn't create assets directory {}", assets_dir.display())
+        })?;
+        fs::write(&target, content)
+            .with_context(|| format!("Couldn't copy image {}", path.display()))?;
+    }
+
+    relative_path(output_dir, &target)
+}
+
+/// FNV-1a hash of the content, which stays the same between the builds
+fn content_hash(content: &[u8]) -> u32 {
+    content.iter().fold(0x811c_9dc5, |hash, &byte| {
+        (hash ^ u32::from(byte)).wrapping_mul(0x0100_0193)
+    })
+}
+
+/// Returns `/`-separated path of the target relative to the directory
+fn relative_path(dir: &Path, target: &Path) -> Result<String> {
+    let absolute = |path: &Path| -> Result<PathBuf> {
+        let path = path::absolute(path)
+            .with_context(|| format!("Couldn't resolve path {}", path.display()))?;
+        Ok(path.components().collect())
+    };
+    let (dir, target) = (absolute(dir)?, absolute(target)?);
+
+    let common = dir
+        .components()
+        .zip(target.components())
+        .take_while(|(a, b)| a == b)
+        .count();
+    let mut segments = vec!["..".to_owned(); dir.components().count() - common];
+    segments.extend(
+        target
+            .components()
+            .skip(common)
+            .map(|component| component.as_os_str().to_string_lossy().into_owned()),
+    );
+
+    Ok(segments.join("/"))
+}
+
+/// Returns path relative to the site root as `/`-separated string
+pub fn site_path(path: &Path) -> String {
+    path_segments(path).join("/")
+}
+
+fn path_segments(path: &Path) -> Vec<String> {
+    path.components()
+        .filter_map(|component| match component {
+            Component::Normal(name) => Some(name.to_string_lossy().into_owned()),
+            _ => None,
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::common::test::test_dir;
+
+    #[test]
+    fn page_image_sources() {
+        let image = ImageInfo {
+            width: 800,
+            height: 600,
+            variants: vec![("a-320w.png".to_owned(), 320)],
+        };
+        let images = page_images(
+            Path::new("docs/guide/page.mml"),
+            &[("docs/img/a.png".to_owned(), image)],
+        );
+
+        let sources = images.keys().map(String::as_str).collect::<Vec<_>>();
+        assert_eq!(sources, ["../img/a.png", "/docs/img/a.png"]);
+        assert_eq!(
+            images["../img/a.png"].variants,
+            [("../img/a-320w.png".to_owned(), 320)]
+        );
+        assert_eq!(
+            images["/docs/img/a.png"].variants,
+            [("/docs/img/a-320w.png".to_owned(), 320)]
+        );
+        assert_eq!(images["../img/a.png"].width, 800);
+
+        let image = ImageInfo {
+            width: 10,
+            height: 10,
+            variants: Vec::new(),
+        };
+        let images = page_images(Path::new("index.mml"), &[("b.png".to_owned(), image)]);
+        let sources = images.keys().map(String::as_str).collect::<Vec<_>>();
+        assert_eq!(sources, ["/b.png", "b.png"]);
+    }
+
+    #[test]
+    fn inlined_images() -> Result<()> {
+        let dir = test_dir("inline-images");
+        fs::write(dir.join("a.png"), "png")?;
+        fs::write(
+            dir.join("large.png"),
+            vec![0; INLINE_IMAGE_LIMIT as usize + 1],
+        )?;
+
+        let args = ImageArgs {
+            inline_images: true,
+            assets_dir: None,
+        };
+        let sources = [
+            "a.png",
+            "large.png",
+            "missing.png",
+            "/a.png",
+            "https://example.com/a.png",
+            "data:image/png;base64,",
+        ]
+        .map(str::to_owned);
+        let resolved = resolve_assets(
+            sources,
+            &dir.join("page.mml"),
+            &dir.join("page.html"),
+            &args,
+        );
+
+        // Large images without the assets directory and the ones, that aren't local, are kept
+        assert_eq!(
+            resolved.into_iter().collect::<Vec<_>>(),
+            [("a.png".to_owned(), "data:image/png;base64,cG5n".to_owned())]
+        );
+
+        fs::remove_dir_all(dir)?;
+
+        Ok(())
+    }
+
+    #[test]
+    fn copied_images_dont_collide() -> Result<()> {
+        let dir = test_dir("copy-images");
+        fs::create_dir_all(dir.join("doc/img"))?;
+        fs::create_dir_all(dir.join("img"))?;
+        fs::write(dir.join("doc/img/a.png"), "first")?;
+        fs::write(dir.join("img/a.png"), "second")?;
+        fs::write(dir.join("img/b.png"), "first")?;
+
+        let args = ImageArgs {
+            inline_images: false,
+            assets_dir: Some(dir.join("out/assets").display().to_string()),
+        };
+        let sources = ["img/a.png", "../img/a.png", "../img/b.png"].map(str::to_owned);
+        let resolved = resolve_assets(
+            sources,
+            &dir.join("doc/page.mml"),
+            &dir.join("out/page.html"),
+            &args,
+        );
+
+        let first = &resolved["img/a.png"];
+        let second = &resolved["../img/a.png"];
+        assert!(first.starts_with("assets/a-") && second.starts_with("assets/a-"));
+        assert_ne!(first, second);
+        assert_eq!(fs::read_to_string(dir.join("out").join(first))?, "first");
+        assert_eq!(fs::read_to_string(dir.join("out").join(second))?, "second");
+        assert!(resolved["../img/b.png"].starts_with("assets/b-"));
+
+        fs::remove_dir_all(dir)?;
+
+        Ok(())
+    }
+}
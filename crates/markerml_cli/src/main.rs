@@ -9,11 +9,190 @@
 //! ```sh
 //! markerml_cli convert --input file.txt --output file.html
 //! ```
+//! - Input `-` reads code from the standard input, and HTML is written to the standard output
+//!   if the output is `-` or isn't specified, so the command can be used in pipelines and editors
+//! - Diagnostics are always printed to the standard error, so they don't mix with the HTML
+//! ```sh
+//! cat file.mml | markerml_cli convert --input - --fragment > snippet.html
+//! ```
+//!
+//! - Command to convert multiple files or glob patterns in parallel
+//! ```sh
+//! markerml_cli convert --input 'docs/**/*.mml' --out-dir build/
+//! ```
+//! - Directory can be converted recursively with `--input-dir`, and `--jobs` limits
+//!   the number of files converted in parallel. Failed files are reported along with the summary
+//! ```sh
+//! markerml_cli convert --input-dir docs/ --output-dir site/ --jobs 4
+//! ```
+//!
+//! - `convert` command emits complete HTML page by default (`--standalone`),
+//!   while `--fragment` emits only the markup, that can be embedded into CMS or another page
+//! ```sh
+//! markerml_cli convert --input file.mml --output snippet.html --fragment
+//! ```
+//! - With `--source-spans` every element gets `data-mml-span="line:column-line:column"` attribute
+//!   with position of its component in the code, so that editors can map elements back to it
+//! - `convert` and `watch` commands warn about properties, that builtin components don't understand,
+//!   such as `box[vertcal]` (`unknown_property`), component definitions, that are never used (`unused_definition`),
+//!   and flags of the definitions, named as a variable, like `box[title]` instead of `box[${title}]` (`ambiguous_flag`). Warnings don't fail the conversion, unless the lint is denied with `-D/--deny`,
+//!   while `-A/--allow` silences them. `// markerml-allow(unknown_property)` comment silences the lint
+//!   on its line and the next one
+//! ```sh
+//! markerml_cli convert --input file.mml --output file.html --deny unknown_property
+//! ```
+//! - Links with empty URL, malformed or `javascript:` scheme, like `https:example.com`, are reported as `invalid_link`.
+//!   With `--check-links` both `convert` and `check` commands also warn about relative links
+//!   to missing files (`missing_link_target`). Links to `.html` files are satisfied by their `.mml` sources
+//! ```sh
+//! markerml_cli check --input 'docs/**/*.mml' --check-links
+//! ```
+//! - Anchors with the same explicit id, like two `anchor["intro"]`, are reported as `duplicate_id`,
+//!   since links lead only to the first of them
+//!
+//! - Command to check files for errors without writing HTML.
+//!   It runs all stages of the conversion, so errors of the HTML generation are reported too,
+//!   along with the warnings. Exits with non-zero code if any file contains errors.
+//!   Variables, messages and lint levels are accepted like in `convert` command
+//! ```sh
+//! markerml_cli check --input 'docs/**/*.mml' --var user=Name --deny unused_definition
+//! ```
+//!
+//! - Both `convert` and `check` commands can report diagnostics as JSON,
+//!   one object per line with severity, code, message, file and span
+//! ```sh
+//! markerml_cli check --input 'docs/**/*.mml' --message-format json
+//! ```
+//!
+//! - Command to format files into canonical form.
+//!   With `--check` files are not modified, but command fails if any of them isn't formatted.
+//!   Files can also be given with `--input`, like in other commands
+//! ```sh
+//! markerml_cli fmt [--check] 'docs/**/*.mml'
+//! markerml_cli fmt --input file.mml
+//! ```
+//!
+//! - Command to print parsed AST as pretty debug representation or JSON
+//! ```sh
+//! markerml_cli dump-ast --input file.mml --format json
+//! ```
+//!
+//! - Command to print generated IR, optionally after custom component expansion
+//! ```sh
+//! markerml_cli dump-ir --input file.mml --expand
+//! ```
+//!
+//! - Command to print token stream of the file along with spans
+//! ```sh
+//! markerml_cli tokens --input file.mml
+//! ```
+//!
+//! - Command to print statistics of the document: component counts,
+//!   definition usages, document depth, output size and time spent on each compilation stage
+//! ```sh
+//! markerml_cli stats --input file.mml
+//! ```
+//!
+//! - Command to convert snippets interactively. Output is switched between HTML, AST and expanded IR
+//!   with `:html`, `:ast` and `:ir` meta-commands, and components defined in the snippets
+//!   stay available in the following ones
+//! ```sh
+//! markerml_cli repl
+//! ```
+//!
+//! - Command to build static site from the directory, converting every `.mml` file,
+//!   copying static assets and wrapping pages into the shared layout.
+//!   With `--responsive-images` images get `width` and `height` attributes
+//!   and `srcset` with resized variants, which are written next to them
+//! ```sh
+//! markerml_cli build --input site/ --output build/ --layout layout.html [--responsive-images]
+//! ```
+//!
+//! - Command to create starter project with sample page, components file,
+//!   layout, assets directory and `markerml.toml` configuration.
+//!   Inside of the project `build` command takes missing options from the configuration
+//! ```sh
+//! markerml_cli init my_site && cd my_site && markerml_cli build
+//! ```
+//!
+//! - Both `convert` and `watch` commands accept variables,
+//!   available for interpolation in the whole module
+//! ```sh
+//! markerml_cli convert --input file.mml --output file.html --var user=Name --vars-file vars.json
+//! ```
+//!
+//! - Both `convert` and `watch` commands accept catalog of localized messages
+//!   in TOML or JSON format, which replace `${t:key}` interpolations,
+//!   so the same document can be compiled into multiple languages
+//! ```sh
+//! markerml_cli convert --input file.mml --output file.de.html --messages de.toml
+//! ```
+//!
+//! - Both `convert` and `watch` commands can style the page with the built-in theme
+//!   (`light`, `dark` or `plain`), overriding the one from the `page` component,
+//!   and user CSS files, which are linked by their path or embedded into the page with `--inline-style`.
+//!   Embedded styles and scripts can be marked with `--nonce` to satisfy strict Content-Security-Policy.
+//!   With `--css stylesheet` styles of the components are also collected into such a block
+//!   with generated class names instead of inline `style` attributes
+//! ```sh
+//! markerml_cli convert --input file.mml --output file.html --theme dark --style custom.css
+//! ```
+//! - Page gets the title with `--title`, overriding the one from the `page` component,
+//!   and its markup can be pretty-printed with `--pretty`
+//! ```sh
+//! markerml_cli convert --input file.mml --output file.html --title "My notes" --pretty
+//! ```
+//! - Newlines of the multiline text are collapsed into spaces, unless `--preserve-newlines` is given.
+//!   Then single newlines become line breaks, and blank lines split paragraphs and quotes
+//!   into separate paragraphs
+//! ```sh
+//! markerml_cli convert --input poem.mml --output poem.html --preserve-newlines
+//! ```
+//! - With `--print` the page is prepared for printing or saving as PDF: it's split into pages
+//!   by `page_break` components, and size and margins of the pages are set with
+//!   `--page-size` and `--page-margin`, which are `A4` and `2cm` by default
+//! ```sh
+//! markerml_cli convert --input file.mml --output file.html --print --page-size letter
+//! ```
+//! - Relative paths of the images don't break when the output is elsewhere:
+//!   with `--inline-images` images up to 16 KiB are embedded into the page as data URIs,
+//!   and with `--assets-dir` the rest are copied into the directory, which the page points to.
+//!   Copies are named after the images along with the hash of their content, so they don't collide
+//! ```sh
+//! markerml_cli convert --input doc/file.mml --output out/file.html --inline-images --assets-dir out/assets
+//! ```
 //!
 //! - Command to watch the given file with MarkerML code
-//!   and track changes on a live-reloading HTML page
+//!   and track changes on a live-reloading HTML page.
+//!   If directory is given, every `.mml` file is served at its own route
+//!   along with the index page listing all documents.
+//!   Component files, given with `--component` or taken from `markerml.toml`,
+//!   are watched as well and every document is rebuilt when they change.
+//!   Only the changed top-level components of the document are compiled again,
+//!   markup of the rest is reused, and only the changed part of the markup is sent to the page.
+//!   Errors and warnings are shown on the page along with the highlighted source code.
+//!   With `--open` the page is opened in the default browser
 //! ```sh
-//! markerml_cli watch --input file.txt
+//! markerml_cli watch --input file.txt [--open]
+//! ```
+//!
+//! - Watch server can be bound to the specific address and served over HTTPS,
+//!   so that the preview can be shared on LAN or behind HTTPS-only tunnels
+//! ```sh
+//! markerml_cli watch --input file.txt --host 192.168.0.10 --tls-cert cert.pem --tls-key key.pem
+//! ```
+//!
+//! - Command to generate completion script for bash, zsh, fish, powershell or elvish
+//! ```sh
+//! markerml_cli completions bash > /etc/bash_completion.d/markerml_cli
+//! ```
+//!
+//! - Global `-q/--quiet` flag suppresses everything except errors,
+//!   while `-v/--verbose` flag enables debug messages.
+//!   Exit code is `1` for IO and other errors, `2` for invalid arguments,
+//!   `3` for compilation errors and `4` for lint failures, such as unformatted files
+//! ```sh
+//! markerml_cli check --input file.mml --quiet || echo "Exit code $?"
 //! ```
 //!
 //! - Command to display credits information
@@ -29,17 +208,178 @@
 
 mod args;
 mod common;
+mod diagnostics;
+mod images;
+mod project;
+mod repl;
+mod site_builder;
+mod stats;
 mod web_server;
 
-use crate::args::{Args, Command};
-use anyhow::{Context, Result};
-use std::path::Path;
+use crate::args::{Args, Command, DumpFormat, ImageArgs};
+use crate::common::Failure;
+use crate::project::{BuildConfig, ProjectConfig};
+use crate::web_server::ServerOptions;
+use anyhow::{bail, Context, Result};
+use clap::CommandFactory;
+use clap_complete::Shell;
+use log::{Level, LevelFilter};
+use markerml::markerml_backend::html_generator::HtmlGenerator;
+use rayon::prelude::*;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
 
 #[tokio::main]
-async fn main() -> Result<()> {
-    match Args::read().command {
-        Command::Convert { input, output } => convert_file(input, output)?,
-        Command::Watch { input, port } => watch_file(input, port).await?,
+async fn main() -> ExitCode {
+    let args = Args::read();
+    init_logger(args.quiet, args.verbose);
+
+    match run(args.command).await {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(err) => {
+            log::error!("{err:#}");
+            ExitCode::from(common::exit_code(&err))
+        }
+    }
+}
+
+/// Initializes logger, that prints informational messages to stderr
+fn init_logger(quiet: bool, verbose: bool) {
+    let level = match (quiet, verbose) {
+        (true, _) => LevelFilter::Error,
+        (false, true) => LevelFilter::Debug,
+        (false, false) => LevelFilter::Info,
+    };
+
+    env_logger::Builder::new()
+        .filter_level(level)
+        .format(|buf, record| match record.level() {
+            Level::Info => writeln!(buf, "{}", record.args()),
+            level => writeln!(buf, "{}: {}", level.as_str().to_lowercase(), record.args()),
+        })
+        .init();
+}
+
+async fn run(command: Command) -> Result<()> {
+    match command {
+        Command::Convert {
+            input,
+            input_dir,
+            output,
+            out_dir,
+            jobs,
+            fragment,
+            standalone: _,
+            source_spans,
+            vars,
+            style,
+            images,
+            lints,
+            check_links,
+            message_format,
+        } => {
+            diagnostics::set_message_format(message_format);
+            let options = markerml::Options {
+                messages: common::read_messages(vars.messages.as_deref())?,
+                vars: common::read_variables(vars)?,
+                fragment,
+                source_spans,
+                html: common::read_styles(style)?,
+                lints: common::read_lints(lints),
+                link_base: check_links.then(PathBuf::new),
+                ..markerml::Options::default()
+            };
+            let inputs = match &input_dir {
+                Some(dir) => common::expand_input_dir(
+                    Path::new(dir),
+                    Path::new(out_dir.as_deref().unwrap_or_default()),
+                )?,
+                None => common::expand_inputs(&input)?,
+            };
+            match jobs {
+                Some(jobs) => rayon::ThreadPoolBuilder::new()
+                    .num_threads(jobs)
+                    .build()
+                    .context("Couldn't create thread pool")?
+                    .install(|| convert_files(inputs, output, out_dir, &options, &images))?,
+                None => convert_files(inputs, output, out_dir, &options, &images)?,
+            }
+        }
+        Command::Check {
+            input,
+            vars,
+            lints,
+            check_links,
+            message_format,
+        } => {
+            diagnostics::set_message_format(message_format);
+            let options = markerml::Options {
+                messages: common::read_messages(vars.messages.as_deref())?,
+                vars: common::read_variables(vars)?,
+                fragment: true,
+                lints: common::read_lints(lints),
+                link_base: check_links.then(PathBuf::new),
+                ..markerml::Options::default()
+            };
+            check_files(&input, &options)?
+        }
+        Command::Fmt {
+            mut files,
+            input,
+            check,
+        } => {
+            files.extend(input);
+            format_files(&files, check)?
+        }
+        Command::DumpAst { input, format } => dump_ast(Path::new(&input), format)?,
+        Command::DumpIr {
+            input,
+            format,
+            expand,
+        } => dump_ir(Path::new(&input), format, expand)?,
+        Command::Tokens { input } => dump_tokens(Path::new(&input))?,
+        Command::Stats { input } => stats::print_stats(Path::new(&input))?,
+        Command::Build {
+            input,
+            output,
+            layout,
+            components,
+            responsive_images,
+        } => build_site(input, output, layout, components, responsive_images)?,
+        Command::Init { name } => project::init_project(Path::new(name.as_deref().unwrap_or(".")))?,
+        Command::Repl => repl::run_repl()?,
+        Command::Watch {
+            input,
+            port,
+            host,
+            tls_cert,
+            tls_key,
+            open,
+            components,
+            vars,
+            style,
+            lints,
+        } => {
+            let options = ServerOptions {
+                host,
+                port: port.unwrap_or(3002),
+                tls: tls_cert
+                    .zip(tls_key)
+                    .map(|(cert, key)| (cert.into(), key.into())),
+                open,
+            };
+            let components = watched_components(components)?;
+            let convert_options = markerml::Options {
+                messages: common::read_messages(vars.messages.as_deref())?,
+                vars: common::read_variables(vars)?,
+                html: common::read_styles(style)?,
+                lints: common::read_lints(lints),
+                ..markerml::Options::default()
+            };
+            watch_file(input, options, &components, convert_options).await?
+        }
+        Command::Completions { shell } => generate_completions(shell),
         Command::Credits => display_credits(),
         Command::Help => display_help(),
     };
@@ -47,33 +387,357 @@ async fn main() -> Result<()> {
     Ok(())
 }
 
-fn convert_file(input: impl AsRef<Path>, output: impl AsRef<Path>) -> Result<()> {
-    println!("Converting file {}", input.as_ref().display());
-    common::check_file_exists(input.as_ref())?;
-    let file = common::parse_file(input.as_ref())?;
-    println!("Successfully converted");
+fn convert_files(
+    inputs: Vec<common::InputFile>,
+    output: Option<String>,
+    out_dir: Option<String>,
+    options: &markerml::Options,
+    images: &ImageArgs,
+) -> Result<()> {
+    if inputs.len() > 1 && inputs.iter().any(|input| common::is_stdio(&input.path)) {
+        bail!("Standard input can only be used as a single input");
+    }
+    let files: Vec<(PathBuf, PathBuf)> = match (output, out_dir) {
+        (None, Some(_)) if common::is_stdio(&inputs[0].path) => {
+            bail!(
+                "Standard input can't be converted into output directory, use output file instead"
+            )
+        }
+        (None, Some(out_dir)) => inputs
+            .iter()
+            .map(|input| (input.path.clone(), input.output_path(Path::new(&out_dir))))
+            .collect(),
+        (output, _) => match inputs.as_slice() {
+            [input] => vec![(
+                input.path.clone(),
+                PathBuf::from(output.as_deref().unwrap_or(common::STDIO_PATH)),
+            )],
+            _ if output.is_none() => {
+                bail!("Output directory must be specified for multiple inputs")
+            }
+            _ => bail!(
+                "Output file can only be used with a single input, use output directory instead"
+            ),
+        },
+    };
+
+    let results = files
+        .par_iter()
+        .map(|(input, output)| {
+            let mut report = String::new();
+            let result = convert_file(input, output, options, images, &mut report);
+
+            (input, output, report, result)
+        })
+        .collect::<Vec<_>>();
+    // Reports are printed in the order of the inputs, so that they don't interleave
+    let mut errors = Vec::new();
+    for (input, output, report, result) in results {
+        eprint!("{report}");
+        match result {
+            Ok(()) if !common::is_stdio(output) => {
+                log::info!("Successfully saved output to file {}", output.display());
+            }
+            Ok(()) => {}
+            Err(err) => {
+                log::error!("Failed to convert file {}: {err:#}", input.display());
+                errors.push(err);
+            }
+        }
+    }
+
+    if !errors.is_empty() {
+        let message = format!(
+            "{} of {} files failed to convert",
+            errors.len(),
+            files.len()
+        );
+        return Err(common::combine_errors(&errors, message));
+    }
+    if files.len() > 1 {
+        log::info!("Converted {} files", files.len());
+    }
+
+    Ok(())
+}
+
+fn convert_file(
+    input: impl AsRef<Path>,
+    output: impl AsRef<Path>,
+    options: &markerml::Options,
+    image_args: &ImageArgs,
+    report: &mut String,
+) -> Result<()> {
+    log::debug!("Converting file {}", input.as_ref().display());
+    if !common::is_stdio(input.as_ref()) {
+        common::check_file_exists(input.as_ref())?;
+    }
+    let options = &*common::input_options(options, input.as_ref());
+    let file = common::parse_file_with_diagnostics(
+        input.as_ref(),
+        |code| {
+            if !image_args.is_enabled() {
+                return markerml::parse_with_diagnostics(code, options);
+            }
 
+            // Images are only known after the expansion, so the code is lowered twice
+            let pipeline = markerml::Pipeline::new(options);
+            let ir = pipeline.lower_ir(pipeline.parse_ast(code)?)?;
+            let sources = HtmlGenerator::with_options(&ir, &options.html).image_sources();
+            // Standard input and output are relative to the current directory
+            let page = Some(input.as_ref()).filter(|path| !common::is_stdio(path));
+            let output = Some(output.as_ref()).filter(|path| !common::is_stdio(path));
+            let options = markerml::Options {
+                html: markerml::HtmlGeneratorOptions {
+                    image_sources: images::resolve_assets(
+                        sources,
+                        page.unwrap_or(Path::new("")),
+                        output.unwrap_or(Path::new("")),
+                        image_args,
+                    ),
+                    ..options.html.clone()
+                },
+                ..options.clone()
+            };
+            markerml::parse_with_diagnostics(code, &options)
+        },
+        report,
+    )?;
+    log::debug!("Successfully converted file {}", input.as_ref().display());
+
+    if common::is_stdio(output.as_ref()) {
+        // Only single input is written to the standard output, so its warnings are printed first.
+        // They go to the standard error, so that they don't mix with the HTML
+        eprint!("{}", std::mem::take(report));
+        let mut stdout = std::io::stdout().lock();
+        stdout
+            .write_all(file.as_bytes())
+            .and_then(|()| stdout.flush())
+            .context("Couldn't write output to standard output")?;
+
+        return Ok(());
+    }
+    if let Some(parent) = output.as_ref().parent() {
+        std::fs::create_dir_all(parent)
+            .with_context(|| format!("Couldn't create output directory {}", parent.display()))?;
+    }
     std::fs::write(&output, file).with_context(|| {
         format!(
             "Couldn't write output to file {}",
             output.as_ref().display()
         )
     })?;
-    println!(
-        "Successfully saved output to file {}",
-        output.as_ref().display()
-    );
 
     Ok(())
 }
 
-async fn watch_file(input: impl AsRef<Path>, port: Option<u16>) -> Result<()> {
-    let port = port.unwrap_or(3002);
+/// Checks files for errors, running all stages of the conversion and discarding the output
+fn check_files(inputs: &[String], options: &markerml::Options) -> Result<()> {
+    let inputs = common::expand_inputs(inputs)?;
+    let results = inputs
+        .par_iter()
+        .map(|input| {
+            let options = common::input_options(options, &input.path);
+            let mut report = String::new();
+            let result = common::parse_file_with_diagnostics(
+                &input.path,
+                |code| markerml::parse_with_diagnostics(code, &options),
+                &mut report,
+            );
+
+            (input, report, result.is_ok())
+        })
+        .collect::<Vec<_>>();
+    let mut failed = 0;
+    for (input, report, is_ok) in results {
+        eprint!("{report}");
+        if !is_ok {
+            log::error!("Errors found in file {}", input.path.display());
+            failed += 1;
+        }
+    }
+
+    if failed > 0 {
+        let message = format!("{failed} of {} files contain errors", inputs.len());
+        return Err(anyhow::Error::new(Failure::Compilation).context(message));
+    }
+    log::info!("No errors found in {} files", inputs.len());
+
+    Ok(())
+}
+
+fn format_files(inputs: &[String], check: bool) -> Result<()> {
+    let inputs = common::expand_inputs(inputs)?;
+    let results = inputs
+        .par_iter()
+        .map(|input| {
+            let mut report = String::new();
+            let result = format_file(&input.path, check, &mut report);
+
+            (input, report, result)
+        })
+        .collect::<Vec<_>>();
+    let mut errors = Vec::new();
+    for (input, report, result) in results {
+        eprint!("{report}");
+        match result {
+            Ok(true) => {}
+            Ok(false) => {
+                log::warn!("File {} is not formatted", input.path.display());
+                errors.push(Failure::Lint.into());
+            }
+            Err(err) => {
+                log::error!("Failed to format file {}: {err:#}", input.path.display());
+                errors.push(err);
+            }
+        }
+    }
+
+    if !errors.is_empty() {
+        let message = format!(
+            "{} of {} files are not formatted",
+            errors.len(),
+            inputs.len()
+        );
+        return Err(common::combine_errors(&errors, message));
+    }
+
+    Ok(())
+}
+
+/// Formats the file. Returns whether the file is formatted after the operation
+fn format_file(input: &Path, check: bool, report: &mut String) -> Result<bool> {
+    let content = std::fs::read_to_string(input).context("Couldn't read file content")?;
+    let formatted = common::render_file_with(input, markerml::format, report)?;
+    if formatted == content {
+        return Ok(true);
+    }
+    if check {
+        return Ok(false);
+    }
+
+    std::fs::write(input, formatted)
+        .with_context(|| format!("Couldn't write output to file {}", input.display()))?;
+    log::info!("Formatted file {}", input.display());
+
+    Ok(true)
+}
+
+fn dump_ast(input: &Path, format: DumpFormat) -> Result<()> {
+    common::check_file_exists(input)?;
+    let ast = common::parse_file_with(input, |code| {
+        markerml::markerml_frontend::parse(code)
+            .map_err(|err| markerml::MarkermlError::from_parser(&err, code))
+    })?;
+    let output = match format {
+        DumpFormat::Debug => format!("{ast:#?}"),
+        DumpFormat::Json => serde_json::to_string_pretty(&ast).context("Couldn't serialize AST")?,
+    };
+    println!("{output}");
+
+    Ok(())
+}
+
+fn dump_ir(input: &Path, format: DumpFormat, expand: bool) -> Result<()> {
+    common::check_file_exists(input)?;
+    let ir = common::parse_file_with(input, |code| markerml::generate_ir(code, expand))?;
+    let output = match format {
+        DumpFormat::Debug => format!("{ir:#?}"),
+        DumpFormat::Json => serde_json::to_string_pretty(&ir).context("Couldn't serialize IR")?,
+    };
+    println!("{output}");
 
-    println!("Watching file {}...", input.as_ref().display());
+    Ok(())
+}
+
+fn dump_tokens(input: &Path) -> Result<()> {
+    common::check_file_exists(input)?;
+    let tokens = common::parse_file_with(input, |code| {
+        markerml::markerml_frontend::tokens::tokenize(code)
+            .map_err(|err| markerml::MarkermlError::from_parser(&err, code))
+    })?;
+    for token in tokens {
+        let span = format!("{}..{}", token.span.start.offset(), token.span.end.offset());
+        println!("{span:<12} {:<24} {:?}", token.kind.to_string(), token.text);
+    }
+
+    Ok(())
+}
+
+fn build_site(
+    input: Option<String>,
+    output: Option<String>,
+    layout: Option<String>,
+    components: Vec<String>,
+    responsive_images: bool,
+) -> Result<()> {
+    let mut config = match (input, output) {
+        (Some(input), Some(output)) => BuildConfig {
+            input: input.into(),
+            output: output.into(),
+            layout: None,
+            components: Vec::new(),
+            responsive_images: false,
+        },
+        (input, output) => {
+            let filename = Path::new(project::CONFIG_FILENAME);
+            if !filename.exists() {
+                bail!(
+                    "Input and output directories must be specified, or {} must be present",
+                    project::CONFIG_FILENAME
+                );
+            }
+
+            let mut config = ProjectConfig::read(filename)?.build;
+            config.input = input.map_or(config.input, PathBuf::from);
+            config.output = output.map_or(config.output, PathBuf::from);
+            config
+        }
+    };
+    if let Some(layout) = layout {
+        config.layout = Some(layout.into());
+    }
+    config
+        .components
+        .extend(components.into_iter().map(PathBuf::from));
+    config.responsive_images |= responsive_images;
+
+    site_builder::build_site(&config)
+}
+
+/// Returns component files for the watch server.
+/// If none are specified, they are taken from the project configuration, if it's present
+fn watched_components(components: Vec<String>) -> Result<Vec<PathBuf>> {
+    let filename = Path::new(project::CONFIG_FILENAME);
+    if !components.is_empty() || !filename.exists() {
+        return Ok(components.into_iter().map(PathBuf::from).collect());
+    }
+
+    Ok(ProjectConfig::read(filename)?.build.components)
+}
+
+async fn watch_file(
+    input: impl AsRef<Path>,
+    options: ServerOptions,
+    components: &[PathBuf],
+    convert_options: markerml::Options,
+) -> Result<()> {
+    if input.as_ref().is_dir() {
+        log::info!("Watching directory {}...", input.as_ref().display());
+    } else {
+        log::info!("Watching file {}...", input.as_ref().display());
+    }
     common::check_file_exists(input.as_ref())?;
-    println!("Webserver listening at http://localhost:{port}");
-    web_server::run_web_server(input.as_ref(), port).await
+    web_server::run_web_server(input.as_ref(), options, components, convert_options).await
+}
+
+fn generate_completions(shell: Shell) {
+    clap_complete::generate(
+        shell,
+        &mut Args::command(),
+        "markerml_cli",
+        &mut std::io::stdout(),
+    );
 }
 
 fn display_credits() {
@@ -84,8 +748,57 @@ fn display_help() {
     println!("Usage: markerml_cli <command> <options>");
     println!("Commands:");
     println!("  convert --input <input_file> --output <output_file>    Convert specified file");
+    println!("  convert --input <input_files>... --out-dir <dir>       Convert specified files");
+    println!(
+        "  convert --input - [--output -]                         Convert standard input to standard output"
+    );
+    println!(
+        "  convert --input-dir <dir> --output-dir <dir> [-j <n>]  Convert directory recursively"
+    );
+    println!("  convert ... [--theme <theme>] [--style <css_file>]     Style generated page");
+    println!(
+        "  convert ... [--css stylesheet] [--nonce <nonce>]       Collect styles into style block"
+    );
+    println!("  convert ... --messages <catalog_file>                  Localize generated page");
+    println!(
+        "  convert ... --preserve-newlines                        Keep line breaks of the text"
+    );
+    println!("  convert ... --print [--page-size <size>]               Emit print-oriented page");
+    println!("  convert ... [--inline-images] [--assets-dir <dir>]     Embed or copy local images");
+    println!(
+        "  convert ... [--allow <lint>] [--deny <lint>]           Silence or deny lint warnings"
+    );
+    println!(
+        "  check --input <input_files>...                         Check specified files for errors"
+    );
+    println!(
+        "  check --input <input_files>... --message-format json   Report errors as JSON lines"
+    );
     println!(
-        "  watch --input <input_file>                             Run webserver for specified file"
+        "  check --input <input_files>... --check-links           Warn about links to missing files"
+    );
+    println!("  fmt [--check] <input_files>...                         Format specified files");
+    println!("  fmt [--check] --input <input_files>...                 Same as above");
+    println!(
+        "  dump-ast --input <input_file> [--format <format>]      Print AST of specified file"
+    );
+    println!("  dump-ir --input <input_file> [--format <format>]       Print IR of specified file");
+    println!(
+        "  tokens --input <input_file>                            Print tokens of specified file"
+    );
+    println!(
+        "  stats --input <input_file>                             Print statistics of specified file"
+    );
+    println!("  repl                                                   Start interactive session");
+    println!(
+        "  build --input <dir> --output <dir> [--layout <file>]   Build static site from specified directory"
+    );
+    println!("  init [<dir>]                                           Create starter project");
+    println!(
+        "  watch --input <input_file_or_dir> [--open] [--host <ip>] Run webserver for specified file or directory"
+    );
+    println!(
+        "  completions <shell>                                    Generate completion script for specified shell"
     );
     println!(
         "  credits                                                Display credits information"
@@ -93,4 +806,62 @@ fn display_help() {
     println!(
         "  help                                                   Display this list of commands"
     );
+    println!("Options:");
+    println!("  -q, --quiet                                            Print only errors");
+    println!("  -v, --verbose                                          Print debug messages");
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::common::test::test_dir;
+    use std::fs;
+
+    fn input(dir: &Path, name: &str) -> common::InputFile {
+        common::InputFile {
+            path: dir.join(name),
+            base: dir.to_owned(),
+        }
+    }
+
+    #[test]
+    fn converts_files_into_out_dir() -> Result<()> {
+        let dir = test_dir("convert-files");
+        fs::create_dir_all(dir.join("b"))?;
+        fs::write(dir.join("a.mml"), "@(a)")?;
+        fs::write(dir.join("b/c.mml"), "@(c)")?;
+        fs::write(dir.join("bad.mml"), "box {")?;
+        let options = markerml::Options {
+            fragment: true,
+            ..markerml::Options::default()
+        };
+        let images = ImageArgs {
+            inline_images: false,
+            assets_dir: None,
+        };
+        let out_dir = dir.join("out").display().to_string();
+
+        // Failure of one file doesn't stop conversion of the others
+        let inputs = ["a.mml", "bad.mml", "b/c.mml"].map(|name| input(&dir, name));
+        let err = convert_files(inputs.into(), None, Some(out_dir), &options, &images).unwrap_err();
+        assert_eq!(err.to_string(), "1 of 3 files failed to convert");
+        assert_eq!(
+            fs::read_to_string(dir.join("out/a.html"))?,
+            "<span>a</span>"
+        );
+        assert_eq!(
+            fs::read_to_string(dir.join("out/b/c.html"))?,
+            "<span>c</span>"
+        );
+        assert!(!dir.join("out/bad.html").exists());
+
+        let inputs = || ["a.mml", "b/c.mml"].map(|name| input(&dir, name)).into();
+        assert!(convert_files(inputs(), None, None, &options, &images).is_err());
+        let output = Some(dir.join("out.html").display().to_string());
+        assert!(convert_files(inputs(), output, None, &options, &images).is_err());
+
+        fs::remove_dir_all(dir)?;
+
+        Ok(())
+    }
 }
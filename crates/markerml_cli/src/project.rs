@@ -0,0 +1,97 @@
+use anyhow::{bail, Context, Result};
+use serde::Deserialize;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Name of the project configuration file
+pub const CONFIG_FILENAME: &str = "markerml.toml";
+
+/// Files of the starter project, created by the `init` command
+const TEMPLATE_FILES: [(&str, &str); 5] = [
+    (CONFIG_FILENAME, include_str!("../templates/markerml.toml")),
+    (
+        "components.mml",
+        include_str!("../templates/components.mml"),
+    ),
+    ("layout.html", include_str!("../templates/layout.html")),
+    ("src/index.mml", include_str!("../templates/index.mml")),
+    (
+        "src/assets/style.css",
+        include_str!("../templates/style.css"),
+    ),
+];
+
+/// Project configuration, read from the `markerml.toml` file
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ProjectConfig {
+    pub build: BuildConfig,
+}
+
+/// Configuration of the static site build
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct BuildConfig {
+    /// Source directory
+    pub input: PathBuf,
+    /// Output directory
+    pub output: PathBuf,
+    /// Layout file
+    pub layout: Option<PathBuf>,
+    /// Files with component definitions, available on every page
+    #[serde(default)]
+    pub components: Vec<PathBuf>,
+    /// Whether to probe images for dimensions and generate their resized variants
+    #[serde(default)]
+    pub responsive_images: bool,
+}
+
+impl ProjectConfig {
+    /// Reads configuration from the given file.
+    /// Paths are resolved relative to the directory of the file
+    pub fn read(filename: &Path) -> Result<Self> {
+        let content = fs::read_to_string(filename)
+            .with_context(|| format!("Couldn't read config file {}", filename.display()))?;
+        let mut config: ProjectConfig = toml::from_str(&content)
+            .with_context(|| format!("Invalid config file {}", filename.display()))?;
+
+        let root = filename.parent().unwrap_or(Path::new(""));
+        let build = &mut config.build;
+        build.input = root.join(&build.input);
+        build.output = root.join(&build.output);
+        build.layout = build.layout.as_ref().map(|layout| root.join(layout));
+        for component in &mut build.components {
+            *component = root.join(&component);
+        }
+
+        Ok(config)
+    }
+}
+
+/// Creates starter project in the given directory
+pub fn init_project(directory: &Path) -> Result<()> {
+    if directory.join(CONFIG_FILENAME).exists() {
+        bail!("Project already exists in {}", directory.display());
+    }
+
+    for (name, _) in TEMPLATE_FILES {
+        let path = directory.join(name);
+        if path.exists() {
+            bail!("File {} already exists", path.display());
+        }
+    }
+
+    for (name, content) in TEMPLATE_FILES {
+        let path = directory.join(name);
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)
+                .with_context(|| format!("Couldn't create directory {}", parent.display()))?;
+        }
+        fs::write(&path, content)
+            .with_context(|| format!("Couldn't write file {}", path.display()))?;
+        log::info!("Created {}", path.display());
+    }
+    log::info!("Project created. Run `markerml_cli build` inside of it to build the site");
+
+    Ok(())
+}
@@ -0,0 +1,229 @@
+use crate::diagnostics;
+use anyhow::{Context, Result};
+use markerml::{ast, ir, DiagnosticLevel, MarkermlError, MarkermlWarning, Pipeline};
+use std::io::{self, BufRead, Write};
+use std::path::Path;
+
+/// Name of the source, used in the diagnostics
+const REPL_NAME: &str = "<repl>";
+
+const REPL_HELP: &str = "\
+Enter MarkerML code to convert it. Input continues on the next line until brackets are closed.
+Components defined in the snippets stay available in the following ones.
+
+  :html [code]  Print generated HTML (default)
+  :ast [code]   Print AST
+  :ir [code]    Print expanded IR
+  :defs         List defined components
+  :clear        Forget defined components
+  :help         Print this message
+  :quit         Exit";
+
+/// What is printed for the snippet
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum OutputMode {
+    Html,
+    Ast,
+    Ir,
+}
+
+/// State of the interactive session
+struct Repl {
+    mode: OutputMode,
+    /// Source code of the component definitions by their names, in order of definition.
+    /// They are prepended to every snippet
+    definitions: Vec<(String, String)>,
+    options: markerml::Options,
+}
+
+/// Reads MarkerML snippets from the standard input and prints their HTML, AST or IR
+pub fn run_repl() -> Result<()> {
+    let mut repl = Repl {
+        mode: OutputMode::Html,
+        definitions: Vec::new(),
+        options: markerml::Options {
+            fragment: true,
+            // Definitions are usually used by the following snippets
+            lints: [("unused_definition".to_owned(), DiagnosticLevel::Allow)].into(),
+            ..markerml::Options::default()
+        },
+    };
+    println!("MarkerML REPL. Type :help for the list of commands");
+
+    let mut lines = io::stdin().lock().lines();
+    let mut snippet = String::new();
+    loop {
+        print!("{}", if snippet.is_empty() { "mml> " } else { "...> " });
+        io::stdout().flush()?;
+
+        let Some(line) = lines.next() else {
+            println!();
+            break;
+        };
+        let line = line.context("Couldn't read standard input")?;
+
+        if snippet.is_empty() && line.trim_start().starts_with(':') {
+            if !repl.run_command(line.trim())? {
+                break;
+            }
+            continue;
+        }
+
+        snippet.push_str(&line);
+        snippet.push('\n');
+        if line.trim().is_empty() || is_complete(&snippet) {
+            let code = std::mem::take(&mut snippet);
+            if !code.trim().is_empty() {
+                repl.eval(&code, repl.mode)?;
+            }
+        }
+    }
+
+    Ok(())
+}
+
+impl Repl {
+    /// Runs meta-command. Returns `false` if the session should end
+    fn run_command(&mut self, line: &str) -> Result<bool> {
+        let (command, code) = line.split_once(char::is_whitespace).unwrap_or((line, ""));
+        let mode = match command {
+            ":html" => OutputMode::Html,
+            ":ast" => OutputMode::Ast,
+            ":ir" => OutputMode::Ir,
+            ":defs" => {
+                for (name, _) in &self.definitions {
+                    println!("{name}");
+                }
+                return Ok(true);
+            }
+            ":clear" => {
+                self.definitions.clear();
+                return Ok(true);
+            }
+            ":help" => {
+                println!("{REPL_HELP}");
+                return Ok(true);
+            }
+            ":quit" | ":q" => return Ok(false),
+            _ => {
+                println!("Unknown command {command}. Type :help for the list of commands");
+                return Ok(true);
+            }
+        };
+
+        if code.trim().is_empty() {
+            self.mode = mode;
+        } else {
+            self.eval(code, mode)?;
+        }
+
+        Ok(true)
+    }
+
+    /// Converts the snippet, printing the output or the report in case of compilation error.
+    /// Definitions of the successfully converted snippet are kept for the following ones
+    fn eval(&mut self, code: &str, mode: OutputMode) -> Result<()> {
+        let filename = Path::new(REPL_NAME);
+        let snippet = match markerml::markerml_frontend::parse(code) {
+            Ok(snippet) => snippet,
+            Err(err) => {
+                let err = MarkermlError::from_parser(&err, code);
+                return diagnostics::report_error(filename, code.to_owned(), err);
+            }
+        };
+        let defined = snippet
+            .items
+            .iter()
+            .filter_map(|item| match item {
+                ast::ModuleItem::ComponentDefinition(def) => {
+                    let span = def.span.start.offset()..def.span.end.offset();
+                    Some((def.name.as_str().to_owned(), code[span].to_owned()))
+                }
+                _ => None,
+            })
+            .collect::<Vec<_>>();
+
+        // Previous definitions are prepended to the snippet, unless it redefines them,
+        // so that the diagnostics can point to them
+        let mut source = String::new();
+        for (name, definition) in &self.definitions {
+            if defined.iter().all(|(defined, _)| defined != name) {
+                source.push_str(definition);
+                source.push('\n');
+            }
+        }
+        let prelude_len = source.len();
+        source.push_str(code);
+
+        let output = match mode {
+            OutputMode::Html => Pipeline::new(&self.options)
+                .run(&source)
+                .map(|result| (result.html, result.warnings)),
+            OutputMode::Ast => Ok((format!("{:#?}", snippet.items), Vec::new())),
+            OutputMode::Ir => self.lower(&source, prelude_len),
+        };
+        match output {
+            Ok((output, warnings)) => {
+                diagnostics::report_warnings(filename, &source, &warnings)?;
+                if !output.trim().is_empty() {
+                    println!("{}", output.trim_end());
+                }
+
+                for (name, definition) in defined {
+                    self.definitions.retain(|(defined, _)| *defined != name);
+                    self.definitions.push((name, definition));
+                }
+
+                Ok(())
+            }
+            Err(err) => diagnostics::report_error(filename, source, err),
+        }
+    }
+
+    /// Lowers the code into expanded IR, omitting the previous definitions
+    fn lower(
+        &self,
+        source: &str,
+        prelude_len: usize,
+    ) -> Result<(String, Vec<MarkermlWarning>), MarkermlError> {
+        let pipeline = Pipeline::new(&self.options);
+        let ir = pipeline.lower_ir(pipeline.parse_ast(source)?)?;
+        let items = ir
+            .items
+            .iter()
+            .filter(|item| match item {
+                ir::ModuleItem::ComponentDefinition(def) => def.span.start.offset() >= prelude_len,
+                _ => true,
+            })
+            .collect::<Vec<_>>();
+
+        Ok((format!("{items:#?}"), Vec::new()))
+    }
+}
+
+/// Checks whether all brackets of the snippet are closed, skipping string literals
+fn is_complete(code: &str) -> bool {
+    let mut depth = 0i32;
+    let mut in_string = false;
+    let mut escaped = false;
+    for c in code.chars() {
+        if in_string {
+            match c {
+                _ if escaped => escaped = false,
+                '\\' => escaped = true,
+                '"' => in_string = false,
+                _ => {}
+            }
+            continue;
+        }
+
+        match c {
+            '"' => in_string = true,
+            '(' | '[' | '{' => depth += 1,
+            ')' | ']' | '}' => depth -= 1,
+            _ => {}
+        }
+    }
+
+    depth <= 0
+}
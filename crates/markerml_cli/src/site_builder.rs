@@ -0,0 +1,275 @@
+use crate::common::{self, SOURCE_EXTENSION};
+use crate::images;
+use crate::project::BuildConfig;
+use anyhow::{bail, Context, Result};
+use rayon::prelude::*;
+use std::fs;
+use std::path::{Path, PathBuf};
+use walkdir::WalkDir;
+
+/// Placeholder in the layout that is replaced with generated markup
+const CONTENT_PLACEHOLDER: &str = "{{ content }}";
+/// Placeholder in the layout that is replaced with page title
+const TITLE_PLACEHOLDER: &str = "{{ title }}";
+
+/// Builds static site from the source directory:
+/// - Converts every `.mml` file into `.html` file, preserving directory structure
+/// - Copies every other file as a static asset
+/// - Wraps generated markup into the shared layout, if it's specified
+/// - Makes definitions from the component files available on every page
+/// - Optionally probes images for dimensions and generates their resized variants
+pub fn build_site(config: &BuildConfig) -> Result<()> {
+    if !config.input.is_dir() {
+        bail!("Source {} is not a directory", config.input.display());
+    }
+    // Paths are canonicalized, so that the output inside of the source directory
+    // is recognized regardless of how both paths are written
+    fs::create_dir_all(&config.output).with_context(|| {
+        format!(
+            "Couldn't create output directory {}",
+            config.output.display()
+        )
+    })?;
+    let input = &canonicalize(&config.input)?;
+    let output = &canonicalize(&config.output)?;
+
+    let layout = config
+        .layout
+        .as_deref()
+        .map(|layout| {
+            let content = fs::read_to_string(layout)
+                .with_context(|| format!("Couldn't read layout {}", layout.display()))?;
+            if !content.contains(CONTENT_PLACEHOLDER) {
+                bail!(
+                    "Layout {} doesn't contain {CONTENT_PLACEHOLDER} placeholder",
+                    layout.display()
+                );
+            }
+
+            Ok(content)
+        })
+        .transpose()?;
+
+    let components = config
+        .components
+        .iter()
+        .map(|filename| {
+            common::check_file_exists(filename)?;
+            common::parse_file_with(filename, markerml::ComponentLibrary::parse)
+                .with_context(|| format!("Invalid component file {}", filename.display()))
+        })
+        .collect::<Result<Vec<_>>>()?;
+    let options = markerml::Options {
+        components,
+        fragment: layout.is_some(),
+        ..markerml::Options::default()
+    };
+
+    let mut sources = Vec::new();
+    let mut assets = Vec::new();
+    let entries = WalkDir::new(input)
+        .into_iter()
+        .filter_entry(|entry| !entry.path().starts_with(output));
+    for entry in entries {
+        let entry = entry.context("Couldn't read source directory")?;
+        if !entry.file_type().is_file() {
+            continue;
+        }
+
+        let path = entry.into_path();
+        if path.extension().is_some_and(|ext| ext == SOURCE_EXTENSION) {
+            sources.push(path);
+        } else {
+            assets.push(path);
+        }
+    }
+
+    for asset in &assets {
+        let target = output_path(input, output, asset);
+        create_parent_dir(&target)?;
+        fs::copy(asset, &target)
+            .with_context(|| format!("Couldn't copy asset {}", asset.display()))?;
+    }
+    log::info!("Copied {} assets", assets.len());
+
+    let images = if config.responsive_images {
+        let images = assets
+            .par_iter()
+            .filter(|asset| images::is_image(asset))
+            .map(|asset| {
+                let image = images::process_image(asset, &output_path(input, output, asset))?;
+                let path = images::site_path(asset.strip_prefix(input).unwrap_or(asset));
+
+                Ok((path, image))
+            })
+            .collect::<Result<Vec<_>>>()?;
+        log::info!("Processed {} images", images.len());
+
+        images
+    } else {
+        Vec::new()
+    };
+
+    let results = sources
+        .par_iter()
+        .map(|source| {
+            let target = output_path(input, output, source).with_extension("html");
+            let mut page_options = None;
+            if !images.is_empty() {
+                let mut options = options.clone();
+                options.html.images =
+                    images::page_images(source.strip_prefix(input).unwrap_or(source), &images);
+                page_options = Some(options);
+            }
+            let options = page_options.as_ref().unwrap_or(&options);
+            let mut report = String::new();
+            let result = build_page(source, &target, layout.as_deref(), options, &mut report);
+
+            (source, report, result)
+        })
+        .collect::<Vec<_>>();
+    // Reports are printed in the order of the pages, so that they don't interleave
+    let mut errors = Vec::new();
+    for (source, report, result) in results {
+        eprint!("{report}");
+        if let Err(err) = result {
+            log::error!("Failed to build page {}: {err:#}", source.display());
+            errors.push(err);
+        }
+    }
+
+    if !errors.is_empty() {
+        let message = format!(
+            "{} of {} pages failed to build",
+            errors.len(),
+            sources.len()
+        );
+        return Err(common::combine_errors(&errors, message));
+    }
+    log::info!(
+        "Successfully built {} pages into {}",
+        sources.len(),
+        config.output.display()
+    );
+
+    Ok(())
+}
+
+/// Converts single page and writes it to the output
+fn build_page(
+    source: &Path,
+    target: &Path,
+    layout: Option<&str>,
+    options: &markerml::Options,
+    report: &mut String,
+) -> Result<()> {
+    let html = common::parse_file_with_diagnostics(
+        source,
+        |code| markerml::parse_with_diagnostics(code, options),
+        report,
+    )?;
+    let html = match layout {
+        Some(layout) => {
+            let title = source
+                .file_stem()
+                .map(|stem| stem.to_string_lossy())
+                .unwrap_or_default();
+
+            layout
+                .replace(TITLE_PLACEHOLDER, &common::escape_html(&title))
+                .replace(CONTENT_PLACEHOLDER, &html)
+        }
+        None => html,
+    };
+
+    create_parent_dir(target)?;
+    fs::write(target, html)
+        .with_context(|| format!("Couldn't write output to file {}", target.display()))?;
+    log::debug!("Built page {}", target.display());
+
+    Ok(())
+}
+
+/// Maps path inside of the source directory to the same path inside of the output directory
+fn output_path(input: &Path, output: &Path, path: &Path) -> PathBuf {
+    output.join(path.strip_prefix(input).unwrap_or(path))
+}
+
+fn canonicalize(path: &Path) -> Result<PathBuf> {
+    fs::canonicalize(path).with_context(|| format!("Couldn't resolve path {}", path.display()))
+}
+
+fn create_parent_dir(path: &Path) -> Result<()> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)
+            .with_context(|| format!("Couldn't create output directory {}", parent.display()))?;
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::common::test::test_dir;
+
+    #[test]
+    fn builds_site_with_layout() -> Result<()> {
+        let dir = test_dir("site");
+        let input = dir.join("src");
+        fs::create_dir_all(input.join("docs"))?;
+        fs::write(input.join("index.mml"), "@(Home)")?;
+        fs::write(input.join("docs/a&b.mml"), "@(Docs)")?;
+        fs::write(input.join("docs/style.css"), "p {}")?;
+        fs::write(
+            dir.join("layout.html"),
+            "<title>{{ title }}</title>{{ content }}",
+        )?;
+        let config = BuildConfig {
+            input: input.clone(),
+            // Output inside of the source directory isn't treated as its content
+            output: input.join("site"),
+            layout: Some(dir.join("layout.html")),
+            components: Vec::new(),
+            responsive_images: false,
+        };
+
+        build_site(&config)?;
+        build_site(&config)?;
+        let output = input.join("site");
+        assert_eq!(
+            fs::read_to_string(output.join("index.html"))?,
+            "<title>index</title><span>Home</span>"
+        );
+        assert_eq!(
+            fs::read_to_string(output.join("docs/a&b.html"))?,
+            "<title>a&amp;b</title><span>Docs</span>"
+        );
+        assert_eq!(fs::read_to_string(output.join("docs/style.css"))?, "p {}");
+        assert!(!output.join("site").exists());
+
+        fs::remove_dir_all(dir)?;
+
+        Ok(())
+    }
+
+    #[test]
+    fn layout_requires_content_placeholder() -> Result<()> {
+        let dir = test_dir("site-layout");
+        fs::write(dir.join("layout.html"), "<title>{{ title }}</title>")?;
+        let config = BuildConfig {
+            input: dir.clone(),
+            output: dir.join("site"),
+            layout: Some(dir.join("layout.html")),
+            components: Vec::new(),
+            responsive_images: false,
+        };
+
+        let err = build_site(&config).unwrap_err();
+        assert!(err.to_string().contains(CONTENT_PLACEHOLDER));
+
+        fs::remove_dir_all(dir)?;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,129 @@
+use crate::common;
+use anyhow::Result;
+use markerml::markerml_middleend::{ir, Span};
+use markerml::{markerml_backend, markerml_frontend, markerml_middleend, MarkermlError};
+use std::collections::BTreeMap;
+use std::path::Path;
+use std::time::{Duration, Instant};
+
+/// Statistics of the single document
+struct DocumentStats {
+    /// Number of component instances by component name, including custom components
+    components: BTreeMap<String, usize>,
+    /// Number of instances of each custom component definition
+    definitions: BTreeMap<String, usize>,
+    /// Maximum nesting of components after custom component expansion
+    depth: usize,
+    /// Size of generated html in bytes
+    output_size: usize,
+    /// Time spent on each compilation stage
+    timings: Vec<(&'static str, Duration)>,
+}
+
+/// Compiles the file and prints its statistics
+pub fn print_stats(input: &Path) -> Result<()> {
+    common::check_file_exists(input)?;
+    let stats = common::parse_file_with(input, collect_stats)?;
+
+    println!("Components:");
+    for (name, count) in &stats.components {
+        println!("  {name:<24} {count}");
+    }
+    println!("Definition usages:");
+    for (name, count) in &stats.definitions {
+        println!("  {name:<24} {count}");
+    }
+    println!("Document depth:            {}", stats.depth);
+    println!("Output size:               {} bytes", stats.output_size);
+    println!("Timings:");
+    for (stage, duration) in &stats.timings {
+        println!("  {stage:<24} {duration:.2?}");
+    }
+
+    Ok(())
+}
+
+fn collect_stats(code: &str) -> Result<DocumentStats, MarkermlError> {
+    let mut timings = Vec::new();
+    let mut measure = |stage, start: Instant| timings.push((stage, start.elapsed()));
+
+    let start = Instant::now();
+    markerml_frontend::tokens::tokens(code)
+        .map_err(|err| MarkermlError::from_parser(&err, code))?
+        .for_each(drop);
+    measure("lex", start);
+
+    let start = Instant::now();
+    let ast =
+        markerml_frontend::parse(code).map_err(|err| MarkermlError::from_parser(&err, code))?;
+    measure("parse", start);
+
+    let start = Instant::now();
+    let ir = markerml_middleend::generate_ir(ast)?;
+    measure("ir", start);
+
+    let mut components = BTreeMap::new();
+    let mut definitions = BTreeMap::new();
+    for item in &ir.items {
+        match item {
+            ir::ModuleItem::Constant(_) => {}
+            ir::ModuleItem::Component(component) => count_components(component, &mut components),
+            ir::ModuleItem::ComponentDefinition(def) => {
+                definitions.insert(def.name.as_str().to_owned(), 0);
+                for child in &def.children {
+                    count_components(child, &mut components);
+                }
+            }
+        }
+    }
+    for (name, count) in definitions.iter_mut() {
+        *count = components.get(name).copied().unwrap_or_default();
+    }
+
+    let start = Instant::now();
+    let ir = markerml_middleend::expand_components(ir)?;
+    measure("expansion", start);
+
+    let depth = ir
+        .items
+        .iter()
+        .filter_map(|item| match item {
+            ir::ModuleItem::Component(component) => Some(component_depth(component)),
+            ir::ModuleItem::Constant(_) | ir::ModuleItem::ComponentDefinition(_) => None,
+        })
+        .max()
+        .unwrap_or_default();
+
+    let start = Instant::now();
+    let html = markerml_backend::generate_html(&ir)?;
+    measure("backend", start);
+
+    Ok(DocumentStats {
+        components,
+        definitions,
+        depth,
+        output_size: html.len(),
+        timings,
+    })
+}
+
+fn count_components(component: &ir::Component<Span>, counts: &mut BTreeMap<String, usize>) {
+    *counts
+        .entry(component.name.as_str().to_owned())
+        .or_default() += 1;
+    let inline = component.text.iter().flat_map(ir::Text::components);
+    for child in component.children.iter().chain(inline) {
+        count_components(child, counts);
+    }
+}
+
+fn component_depth(component: &ir::Component<Span>) -> usize {
+    let inline = component.text.iter().flat_map(ir::Text::components);
+    1 + component
+        .children
+        .iter()
+        .chain(inline)
+        .map(component_depth)
+        .max()
+        .unwrap_or_default()
+}
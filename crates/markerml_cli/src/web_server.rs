@@ -1,40 +1,139 @@
-use crate::common;
+use crate::common::{escape_html, SOURCE_EXTENSION};
+use crate::diagnostics::{self, DiagnosticReport};
 use anyhow::{Context, Result};
 use arc_swap::ArcSwap;
-use async_channel::{Receiver, Sender};
 use axum::body::Body;
 use axum::extract::ws::{Message, WebSocket, WebSocketUpgrade};
-use axum::extract::State;
-use axum::response::{Html, IntoResponse};
+use axum::extract::{Path as RoutePath, State};
+use axum::http::StatusCode;
+use axum::response::{Html, IntoResponse, Response};
 use axum::routing::get;
 use axum::Router;
+use axum_server::tls_rustls::RustlsConfig;
+use markerml::{ComponentLibrary, IncrementalConverter, MarkermlError};
 use notify::{RecursiveMode, Watcher};
 use serde::Serialize;
-use std::net::{Ipv4Addr, SocketAddrV4};
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::hash::{DefaultHasher, Hash, Hasher};
+use std::net::{IpAddr, SocketAddr};
 use std::path::{Path, PathBuf};
-use std::sync::Arc;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::sync::broadcast;
+use tokio::sync::broadcast::error::RecvError;
+use walkdir::WalkDir;
 
 /// Application state
 #[derive(Clone)]
 struct AppState {
+    /// Watched documents by their route.
+    /// Single watched file is available under the empty route
+    pub documents: Arc<ArcSwap<HashMap<String, Arc<Document>>>>,
+    /// Whether the directory is watched. Root page then lists all documents
+    pub is_directory: bool,
+}
+
+/// Watched document
+struct Document {
+    /// File with the code of the document
+    pub filename: PathBuf,
     /// Current code. Used for establishing new web socket connection
-    pub current: Arc<ArcSwap<CodeUpdateMessage>>,
-    /// Channel for receiving code updates
-    pub updates: Receiver<Arc<CodeUpdateMessage>>,
+    pub current: ArcSwap<CodeUpdateMessage>,
+    /// Channel for broadcasting code updates to every connected web socket
+    pub sender: broadcast::Sender<Arc<CodeUpdateMessage>>,
+}
+
+impl Document {
+    fn new(filename: PathBuf, message: Arc<CodeUpdateMessage>) -> Self {
+        let (sender, _) = broadcast::channel(UPDATES_CAPACITY);
+
+        Document {
+            filename,
+            current: ArcSwap::new(message),
+            sender,
+        }
+    }
 }
 
 /// Message with code
-#[derive(Serialize)]
+#[derive(Serialize, PartialEq)]
 #[serde(untagged)]
 enum CodeUpdateMessage {
-    /// Contains valid compiled code
-    Code { code: String },
-    /// Contains information about compilation error
-    Error { error: String },
-    /// Used for empty files
-    Empty,
+    /// Contains valid compiled code along with the warnings
+    /// and the source code they refer to
+    Code {
+        code: String,
+        #[serde(skip_serializing_if = "Vec::is_empty")]
+        warnings: Vec<DiagnosticReport>,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        source: Option<String>,
+    },
+    /// Contains compilation error along with the source code of the file it refers to
+    Error {
+        error: Box<DiagnosticReport>,
+        source: Option<String>,
+    },
 }
 
+/// Update of the compiled code, sent instead of [`CodeUpdateMessage::Code`]
+/// when the patch is smaller than the code
+#[derive(Serialize)]
+struct CodePatchMessage<'a> {
+    patch: CodePatch<'a>,
+    #[serde(skip_serializing_if = "<[_]>::is_empty")]
+    warnings: &'a [DiagnosticReport],
+    #[serde(skip_serializing_if = "Option::is_none")]
+    source: &'a Option<String>,
+}
+
+/// Replacement of the single range of the previous code.
+/// Offsets are in UTF-16 code units, as used by JavaScript strings
+#[derive(Serialize)]
+struct CodePatch<'a> {
+    start: usize,
+    deleted: usize,
+    text: &'a str,
+}
+
+impl<'a> CodePatch<'a> {
+    /// Creates patch replacing the range between common prefix and suffix of the code
+    fn new(previous: &str, code: &'a str) -> Self {
+        let mut prefix = previous
+            .bytes()
+            .zip(code.bytes())
+            .take_while(|(a, b)| a == b)
+            .count();
+        while !code.is_char_boundary(prefix) {
+            prefix -= 1;
+        }
+
+        let max_suffix = previous.len().min(code.len()) - prefix;
+        let mut suffix = previous
+            .bytes()
+            .rev()
+            .zip(code.bytes().rev())
+            .take(max_suffix)
+            .take_while(|(a, b)| a == b)
+            .count();
+        while !code.is_char_boundary(code.len() - suffix) {
+            suffix -= 1;
+        }
+
+        CodePatch {
+            start: previous[..prefix].encode_utf16().count(),
+            deleted: previous[prefix..previous.len() - suffix]
+                .encode_utf16()
+                .count(),
+            text: &code[prefix..code.len() - suffix],
+        }
+    }
+}
+
+/// Time without file changes after which the code is recompiled
+const DEBOUNCE_DURATION: Duration = Duration::from_millis(100);
+/// Number of updates kept for the slow sockets, which then skip to the current code
+const UPDATES_CAPACITY: usize = 16;
 /// Embedded live-reloading html page
 const INDEX_HTML: &str = include_str!("../web/index.html");
 /// Embedded styles for live-reloading html page
@@ -42,37 +141,143 @@ const STYLE: &str = include_str!("../web/style.css");
 /// Embedded script for live reloading
 const SCRIPT: &str = include_str!("../web/script.js");
 
-/// Start the web server watching specified file with code
-pub async fn run_web_server(filename: impl AsRef<Path>, port: u16) -> Result<()> {
-    let (tx, rx) = async_channel::unbounded();
-    let page = Arc::new(ArcSwap::from_pointee(CodeUpdateMessage::Empty));
+/// Options of the web server
+pub struct ServerOptions {
+    /// Address to bind the server to
+    pub host: IpAddr,
+    pub port: u16,
+    /// Certificate and private key files. Server uses HTTPS if they are specified
+    pub tls: Option<(PathBuf, PathBuf)>,
+    /// Whether to launch default browser once the server is listening
+    pub open: bool,
+}
+
+/// Start the web server watching specified file or directory with code.
+/// Definitions from the component files are available in every document
+pub async fn run_web_server(
+    path: impl AsRef<Path>,
+    options: ServerOptions,
+    components: &[PathBuf],
+    convert_options: markerml::Options,
+) -> Result<()> {
+    let path = canonicalize(path.as_ref())?;
+    let components = components
+        .iter()
+        .map(|component| canonicalize(component))
+        .collect::<Result<Vec<_>>>()?;
+    let state = AppState {
+        documents: Arc::new(ArcSwap::from_pointee(HashMap::new())),
+        is_directory: path.is_dir(),
+    };
     let app = Router::new()
-        .route("/listen", get(listen))
+        .route("/listen/", get(listen_index))
+        .route("/listen/*route", get(listen))
         .route("/", get(index_html))
+        .route("/*route", get(document_html))
         .route("/script.js", get(script))
         .route("/style.css", get(style))
-        .with_state(AppState {
-            current: page.clone(),
-            updates: rx,
-        });
+        .with_state(state.clone());
 
-    let filename = filename.as_ref().to_owned();
-    tokio::spawn(async move { watch_file(filename, page, tx).await });
+    let tls = match &options.tls {
+        Some((cert, key)) => {
+            let _ = rustls::crypto::ring::default_provider().install_default();
+            let config = RustlsConfig::from_pem_file(cert, key)
+                .await
+                .context("Couldn't load TLS certificate or key")?;
 
-    let addr = SocketAddrV4::new(Ipv4Addr::UNSPECIFIED, port);
-    let listener = tokio::net::TcpListener::bind(addr)
-        .await
-        .context("Couldn't start web server")?;
-    axum::serve(listener, app)
-        .await
+            Some(config)
+        }
+        None => None,
+    };
+
+    let context = WatchContext {
+        root: path,
+        components,
+        options: convert_options,
+        libraries: Mutex::default(),
+        converters: Mutex::default(),
+        state,
+    };
+    tokio::spawn(async move { watch_files(context).await });
+
+    let listener = std::net::TcpListener::bind(SocketAddr::new(options.host, options.port))
         .context("Couldn't start web server")?;
+    listener.set_nonblocking(true)?;
+
+    let url = server_url(listener.local_addr()?, tls.is_some());
+    log::info!("Webserver listening at {url}");
+    if options.open {
+        if let Err(err) = open::that_detached(&url) {
+            log::warn!("Couldn't open browser: {err}");
+        }
+    }
+
+    match tls {
+        Some(config) => axum_server::from_tcp_rustls(listener, config)
+            .serve(app.into_make_service())
+            .await
+            .context("Couldn't start web server")?,
+        None => axum::serve(tokio::net::TcpListener::from_std(listener)?, app)
+            .await
+            .context("Couldn't start web server")?,
+    }
 
     Ok(())
 }
 
-/// Endpoint for live-reloading html page
-async fn index_html() -> Html<&'static str> {
-    Html(INDEX_HTML)
+fn canonicalize(path: &Path) -> Result<PathBuf> {
+    path.canonicalize()
+        .with_context(|| format!("Couldn't resolve path {}", path.display()))
+}
+
+/// Returns URL under which the server is accessible.
+/// Unspecified address is replaced with `localhost`
+fn server_url(addr: SocketAddr, is_secure: bool) -> String {
+    let scheme = if is_secure { "https" } else { "http" };
+    if addr.ip().is_unspecified() {
+        log::info!("Webserver is bound to all interfaces at {addr}");
+        return format!("{scheme}://localhost:{}", addr.port());
+    }
+
+    format!("{scheme}://{addr}")
+}
+
+/// Endpoint for the root page. Serves live-reloading html page for the watched file,
+/// or index of all documents for the watched directory
+async fn index_html(State(state): State<AppState>) -> Html<String> {
+    if !state.is_directory {
+        return Html(INDEX_HTML.to_owned());
+    }
+
+    let documents = state.documents.load();
+    let mut routes = documents.keys().collect::<Vec<_>>();
+    routes.sort();
+
+    let items = routes
+        .into_iter()
+        .map(|route| {
+            let route = escape_html(route);
+            format!("<li><a href=\"/{route}\">{route}</a></li>")
+        })
+        .collect::<String>();
+
+    Html(format!(
+        "<!doctype html><html lang=\"en\"><head><meta charset=\"utf-8\"><title>MarkerML</title>\
+         <link rel=\"stylesheet\" href=\"/style.css\"></head>\
+         <body><main class=\"document-index\"><h1>Documents</h1><ul>{items}</ul></main></body></html>"
+    ))
+}
+
+/// Endpoint for live-reloading html page of the document inside of the watched directory
+async fn document_html(
+    RoutePath(route): RoutePath<String>,
+    State(state): State<AppState>,
+) -> Response {
+    if !state.documents.load().contains_key(&route) {
+        return StatusCode::NOT_FOUND.into_response();
+    }
+
+    Html(INDEX_HTML).into_response()
 }
 
 /// Endpoint for live-reloading script
@@ -85,74 +290,380 @@ async fn style() -> impl IntoResponse {
     Body::from(STYLE)
 }
 
-/// Endpoint for connecting to websocket that notifies when code changes
-async fn listen(ws: WebSocketUpgrade, State(state): State<AppState>) -> impl IntoResponse {
-    ws.on_upgrade(move |socket| handle_socket(socket, state))
+/// Endpoint for connecting to websocket that notifies when code of the single watched file changes
+async fn listen_index(ws: WebSocketUpgrade, state: State<AppState>) -> Response {
+    listen(ws, RoutePath(String::new()), state).await
+}
+
+/// Endpoint for connecting to websocket that notifies when code of the document changes
+async fn listen(
+    ws: WebSocketUpgrade,
+    RoutePath(route): RoutePath<String>,
+    State(state): State<AppState>,
+) -> Response {
+    let Some(document) = state.documents.load().get(&route).cloned() else {
+        return StatusCode::NOT_FOUND.into_response();
+    };
+
+    ws.on_upgrade(move |socket| handle_socket(socket, document))
+}
+
+/// Files that are compiled by the watch server
+struct WatchContext {
+    /// Watched file or directory
+    root: PathBuf,
+    /// Files with component definitions, that every document depends on
+    components: Vec<PathBuf>,
+    /// Conversion options. Component libraries are read on every compilation
+    options: markerml::Options,
+    /// Parsed component libraries along with the hash of their content,
+    /// so that unchanged libraries aren't parsed again on every rebuild
+    libraries: Mutex<HashMap<PathBuf, (u64, ComponentLibrary)>>,
+    /// Converters of the documents, which reuse markup of the unchanged components
+    converters: Mutex<HashMap<PathBuf, IncrementalConverter>>,
+    state: AppState,
 }
 
 /// Function that watches file changes and compiles code on demand.
+/// Only the changed file is recompiled, unless it's a dependency of other files.
 /// Updates are reflected in the application state.
-async fn watch_file(
-    filename: PathBuf,
-    page: Arc<ArcSwap<CodeUpdateMessage>>,
-    updates: Sender<Arc<CodeUpdateMessage>>,
-) -> Result<()> {
+async fn watch_files(context: WatchContext) -> Result<()> {
     let (tx, rx) = async_channel::unbounded();
 
     let mut watcher = notify::recommended_watcher(move |event: notify::Result<notify::Event>| {
         let Ok(event) = event else { return };
-        if let notify::EventKind::Modify(_) = event.kind {
-            tx.send_blocking(()).unwrap()
+        if let notify::EventKind::Create(_)
+        | notify::EventKind::Modify(_)
+        | notify::EventKind::Remove(_) = event.kind
+        {
+            for path in event.paths {
+                tx.send_blocking(path).unwrap()
+            }
         }
     })
     .context("Couldn't watch file changes")?;
 
+    let mode = if context.state.is_directory {
+        RecursiveMode::Recursive
+    } else {
+        RecursiveMode::NonRecursive
+    };
     watcher
-        .watch(&filename, RecursiveMode::NonRecursive)
+        .watch(&context.root, mode)
         .context("Couldn't watch file changes")?;
+    // Directories are watched, since editors might replace the file on save
+    for directory in context.components.iter().filter_map(|path| path.parent()) {
+        watcher
+            .watch(directory, RecursiveMode::NonRecursive)
+            .with_context(|| format!("Couldn't watch directory {}", directory.display()))?;
+    }
 
-    let update_code = || async {
-        let res = Arc::new(match common::parse_file(&filename) {
-            Ok(code) => {
-                println!("Code updated!");
-                CodeUpdateMessage::Code { code }
+    if context.state.is_directory {
+        for entry in WalkDir::new(&context.root) {
+            let entry = entry.context("Couldn't read watched directory")?;
+            if entry.file_type().is_file() {
+                context.update_document_or_log(entry.path()).await;
             }
-            Err(err) => {
-                println!("Compilation error: {err}");
-                CodeUpdateMessage::Error {
-                    error: err.to_string(),
-                }
+        }
+    } else {
+        context.update_document_or_log(&context.root).await;
+    }
+
+    loop {
+        let mut changed = HashSet::from([rx.recv().await?]);
+        // Editors emit several events per save, so wait until they stop coming
+        while let Ok(event) = tokio::time::timeout(DEBOUNCE_DURATION, rx.recv()).await {
+            changed.insert(event?);
+        }
+        changed.retain(|path| path.starts_with(&context.root) || context.components.contains(path));
+
+        if changed.iter().any(|path| context.components.contains(path)) {
+            log::info!("Dependencies changed, recompiling all documents");
+            let documents = context.state.documents.load();
+            changed.extend(documents.values().map(|document| document.filename.clone()));
+        }
+        for filename in changed {
+            context.update_document_or_log(&filename).await;
+        }
+    }
+}
+
+impl WatchContext {
+    /// Updates the document, logging the error instead of returning it,
+    /// so that the single failure doesn't stop watching
+    async fn update_document_or_log(&self, filename: &Path) {
+        if let Err(err) = self.update_document(filename).await {
+            log::error!("Couldn't update document {}: {err:#}", filename.display());
+        }
+    }
+
+    /// Compiles changed file and sends the update to its listeners.
+    /// Files without MarkerML code and component files are ignored
+    async fn update_document(&self, filename: &Path) -> Result<()> {
+        let state = &self.state;
+        let route = if state.is_directory {
+            if filename
+                .extension()
+                .is_none_or(|ext| ext != SOURCE_EXTENSION)
+                || self
+                    .components
+                    .iter()
+                    .any(|component| component == filename)
+            {
+                return Ok(());
             }
-        });
 
-        updates.send(res.clone()).await?;
-        page.store(res);
+            document_route(&self.root, filename)
+        } else {
+            String::new()
+        };
 
-        Ok::<(), anyhow::Error>(())
-    };
+        if state.is_directory && !filename.exists() {
+            log::info!("Removed {}", filename.display());
+            self.converters.lock().unwrap().remove(filename);
+            state.documents.rcu(|documents| {
+                let mut documents = HashMap::clone(documents);
+                documents.remove(&route);
+                documents
+            });
 
-    update_code().await?;
-    loop {
-        rx.recv().await?;
+            return Ok(());
+        }
+
+        let res = Arc::new(self.compile_document(filename)?);
+
+        let document = state.documents.load().get(&route).cloned();
+        match document {
+            Some(document) if **document.current.load() == *res => {}
+            Some(document) => {
+                // Current code is stored first, so that sockets connecting in between
+                // either receive the update or load the new code.
+                // Sending fails only if nobody listens
+                document.current.store(res.clone());
+                let _ = document.sender.send(res);
+            }
+            None => {
+                let document = Arc::new(Document::new(filename.to_owned(), res));
+                state.documents.rcu(|documents| {
+                    let mut documents = HashMap::clone(documents);
+                    documents.insert(route.clone(), document.clone());
+                    documents
+                });
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Compiles document along with its dependencies, reusing markup of the unchanged
+    /// top-level components, and renders the report in case of compilation error
+    fn compile_document(&self, filename: &Path) -> Result<CodeUpdateMessage> {
+        let mut components = Vec::new();
+        for component in &self.components {
+            let content = match read_source(component) {
+                Ok(content) => content,
+                Err(message) => return Ok(message),
+            };
+            match self.parse_library(component, &content) {
+                Ok(library) => components.push(library),
+                Err(err) => return report_error(component, content, err),
+            }
+        }
+        let content = match read_source(filename) {
+            Ok(content) => content,
+            Err(message) => return Ok(message),
+        };
+        let mut converters = self.converters.lock().unwrap();
+        let converter = converters
+            .entry(filename.to_owned())
+            .or_insert_with(|| IncrementalConverter::new(self.options.clone()));
+        converter.set_components(components);
+        match converter.convert_with_diagnostics(&content) {
+            Ok(result) => {
+                log::info!("Code updated: {}", filename.display());
+                diagnostics::report_warnings(filename, &content, &result.warnings)?;
+                let warnings = result
+                    .warnings
+                    .iter()
+                    .map(|warning| diagnostics::create_report(filename, &content, warning))
+                    .collect::<Vec<_>>();
+                let source = (!warnings.is_empty()).then_some(content);
+
+                Ok(CodeUpdateMessage::Code {
+                    code: result.html,
+                    warnings,
+                    source,
+                })
+            }
+            Err(err) => report_error(filename, content, err),
+        }
+    }
+
+    /// Returns parsed component library, reusing cached one if the content hasn't changed
+    fn parse_library(
+        &self,
+        filename: &Path,
+        content: &str,
+    ) -> Result<ComponentLibrary, MarkermlError> {
+        let mut hasher = DefaultHasher::new();
+        content.hash(&mut hasher);
+        let hash = hasher.finish();
+
+        let mut libraries = self.libraries.lock().unwrap();
+        if let Some((cached_hash, library)) = libraries.get(filename) {
+            if *cached_hash == hash {
+                return Ok(library.clone());
+            }
+        }
 
-        update_code().await?;
+        log::debug!("Parsing component library {}", filename.display());
+        let library = ComponentLibrary::parse(content)?;
+        libraries.insert(filename.to_owned(), (hash, library.clone()));
+
+        Ok(library)
     }
 }
 
+/// Reads source file, returning error message in case of failure
+fn read_source(filename: &Path) -> Result<String, CodeUpdateMessage> {
+    fs::read_to_string(filename).map_err(|err| {
+        let error = format!("Couldn't read file {}: {err}", filename.display());
+        log::error!("{error}");
+
+        CodeUpdateMessage::Error {
+            error: Box::new(DiagnosticReport::from_message(filename, error)),
+            source: None,
+        }
+    })
+}
+
+/// Reports compilation error to the terminal and creates its report for the page
+fn report_error(filename: &Path, content: String, err: MarkermlError) -> Result<CodeUpdateMessage> {
+    let message = CodeUpdateMessage::Error {
+        error: Box::new(diagnostics::create_report(filename, &content, &err)),
+        source: Some(content.clone()),
+    };
+    diagnostics::report_error(filename, content, err)?;
+
+    Ok(message)
+}
+
+/// Returns route of the document inside of the watched directory
+fn document_route(root: &Path, filename: &Path) -> String {
+    let relative = filename.strip_prefix(root).unwrap_or(filename);
+
+    relative
+        .with_extension("html")
+        .components()
+        .map(|component| component.as_os_str().to_string_lossy())
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
 /// Handles websocket connection:
 /// - Sends initial code on connection
-/// - Sends any update when the code is changed
-async fn handle_socket(mut socket: WebSocket, state: AppState) {
-    {
-        let result = state.current.load().clone();
+/// - Sends any update when the code is changed,
+///   as a patch of the code previously sent to the client if it's smaller
+async fn handle_socket(mut socket: WebSocket, document: Arc<Document>) {
+    // Subscribed before loading the current code, so that no update is missed
+    let mut updates = document.sender.subscribe();
+    let mut last_code = {
+        let result = document.current.load().clone();
         let message = serde_json::to_string(result.as_ref()).unwrap_or_default();
 
-        socket.send(Message::Text(message)).await.unwrap();
+        if socket.send(Message::Text(message)).await.is_err() {
+            return;
+        }
+        match result.as_ref() {
+            CodeUpdateMessage::Code { code, .. } => Some(code.clone()),
+            CodeUpdateMessage::Error { .. } => None,
+        }
+    };
+
+    loop {
+        let result = match updates.recv().await {
+            Ok(result) => result,
+            // Skipped updates are superseded by the current code
+            Err(RecvError::Lagged(_)) => document.current.load().clone(),
+            Err(RecvError::Closed) => break,
+        };
+        let message = match (result.as_ref(), &last_code) {
+            (
+                CodeUpdateMessage::Code {
+                    code,
+                    warnings,
+                    source,
+                },
+                Some(previous),
+            ) => {
+                let patch = CodePatch::new(previous, code);
+                let message = if patch.text.len() < code.len() {
+                    serde_json::to_string(&CodePatchMessage {
+                        patch,
+                        warnings,
+                        source,
+                    })
+                } else {
+                    serde_json::to_string(result.as_ref())
+                };
+                last_code = Some(code.clone());
+
+                message
+            }
+            (CodeUpdateMessage::Code { code, .. }, None) => {
+                last_code = Some(code.clone());
+                serde_json::to_string(result.as_ref())
+            }
+            (CodeUpdateMessage::Error { .. }, _) => serde_json::to_string(result.as_ref()),
+        };
+
+        // Client has disconnected
+        if socket
+            .send(Message::Text(message.unwrap_or_default()))
+            .await
+            .is_err()
+        {
+            break;
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn code_message(code: &str) -> Arc<CodeUpdateMessage> {
+        Arc::new(CodeUpdateMessage::Code {
+            code: code.to_owned(),
+            warnings: Vec::new(),
+            source: None,
+        })
     }
 
-    while let Ok(result) = state.updates.recv().await {
-        let message = serde_json::to_string(result.as_ref()).unwrap_or_default();
+    #[tokio::test]
+    async fn updates_reach_every_socket() {
+        let document = Document::new(PathBuf::from("a.mml"), code_message("a"));
+        let mut first = document.sender.subscribe();
+        let mut second = document.sender.subscribe();
+        assert!(document.sender.send(code_message("b")).is_ok());
+
+        assert!(*first.recv().await.unwrap() == *code_message("b"));
+        assert!(*second.recv().await.unwrap() == *code_message("b"));
+    }
+
+    fn patch(previous: &str, code: &str) -> (usize, usize, String) {
+        let patch = CodePatch::new(previous, code);
+        (patch.start, patch.deleted, patch.text.to_owned())
+    }
 
-        socket.send(Message::Text(message)).await.unwrap();
+    #[test]
+    fn code_patches() {
+        assert_eq!(patch("box { a }", "box { b }"), (6, 1, "b".to_owned()));
+        assert_eq!(patch("abc", "abc"), (3, 0, String::new()));
+        // Common prefix and suffix don't overlap, when the repeated text is inserted
+        assert_eq!(patch("aa", "aaa"), (2, 0, "a".to_owned()));
+        assert_eq!(patch("aaa", "a"), (1, 2, String::new()));
+        // Offsets are in UTF-16 code units and don't split characters
+        assert_eq!(patch("😀é", "😀è"), (2, 1, "è".to_owned()));
     }
 }
@@ -0,0 +1,70 @@
+#[cfg(test)]
+mod test {
+    use anyhow::Result;
+    use std::io::Write;
+    use std::process::{Command, Output, Stdio};
+
+    fn run(args: &[&str], input: &str) -> Result<Output> {
+        let mut child = Command::new(env!("CARGO_BIN_EXE_markerml_cli"))
+            .args(args)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()?;
+        child
+            .stdin
+            .take()
+            .expect("stdin is piped")
+            .write_all(input.as_bytes())?;
+
+        Ok(child.wait_with_output()?)
+    }
+
+    #[test]
+    fn convert_stdio_with_warning() -> Result<()> {
+        let output = run(
+            &["convert", "--input", "-", "--fragment"],
+            "box[foo = 1] { @(a) }",
+        )?;
+        assert!(output.status.success());
+        assert_eq!(
+            String::from_utf8(output.stdout)?,
+            r#"<div style="display: flex; flex-direction: column"><span>a</span></div>"#
+        );
+        assert!(String::from_utf8(output.stderr)?.contains("foo"));
+
+        Ok(())
+    }
+
+    #[test]
+    fn parallel_reports_keep_input_order() -> Result<()> {
+        let dir = std::env::temp_dir().join(format!("markerml-cli-order-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir)?;
+        let names = (0..8).map(|i| format!("page{i}.mml")).collect::<Vec<_>>();
+        for name in &names {
+            std::fs::write(dir.join(name), "box {")?;
+        }
+
+        let inputs = names
+            .iter()
+            .map(|name| dir.join(name).display().to_string())
+            .collect::<Vec<_>>();
+        let out_dir = dir.join("out").display().to_string();
+        let mut args = vec!["convert", "--jobs", "4", "--out-dir", &out_dir, "--input"];
+        args.extend(inputs.iter().map(String::as_str));
+        let output = run(&args, "")?;
+        assert!(!output.status.success());
+
+        let stderr = String::from_utf8(output.stderr)?;
+        let positions = names
+            .iter()
+            .map(|name| stderr.find(name.as_str()).expect("every file is reported"))
+            .collect::<Vec<_>>();
+        assert!(positions.is_sorted(), "Reports are out of order:\n{stderr}");
+
+        std::fs::remove_dir_all(dir)?;
+
+        Ok(())
+    }
+}
@@ -1,21 +1,203 @@
-const webSocket = new WebSocket("ws://localhost:3002/listen");
+const protocol = location.protocol === "https:" ? "wss:" : "ws:";
+const webSocket = new WebSocket(`${protocol}//${location.host}/listen${location.pathname}`);
 const main = document.querySelector("#main");
+const overlay = document.querySelector("#error-overlay");
+const warnings = document.querySelector("#warnings");
 
 const emptyContent = "<h1 class='full-screen empty-content'>No Code</h1>";
 const errorContent = "<h1 class='full-screen error-content'>Compilation Error</h1>";
 
+const scrollKey = `markerml-scroll:${location.pathname}`;
+
 main.innerHTML = emptyContent;
+let hasContent = false;
+// Last received code, which patches are applied to
+let currentCode = "";
+
+// Scroll position is stored to be restored after the page is reloaded
+window.addEventListener("scroll", () => {
+    sessionStorage.setItem(scrollKey, JSON.stringify([window.scrollX, window.scrollY]));
+}, { passive: true });
 
 webSocket.onmessage = (event) => {
     const data = JSON.parse(event.data);
     console.log(data);
 
-    const { code, error } = data;
+    const { error, source } = data;
+    const codePatch = data.patch;
+    const code = codePatch !== undefined ? applyPatch(currentCode, codePatch) : data.code;
+    renderReports(warnings, data.warnings ?? [], source);
+    warnings.hidden = data.warnings === undefined;
     if (error !== undefined) {
-        main.innerHTML = errorContent;
+        // Keep last successfully compiled content under the overlay
+        if (!hasContent) {
+            patch(main, errorContent);
+        }
+        renderReports(overlay, [error], source);
+        overlay.hidden = false;
     } else if (code !== undefined) {
-        main.innerHTML = code;
+        overlay.hidden = true;
+        currentCode = code;
+        updateContent(code);
     } else {
-        main.innerHTML = emptyContent;
+        overlay.hidden = true;
+        patch(main, emptyContent);
     }
 };
+
+// Replaces the range of the code. Offsets are in UTF-16 code units
+function applyPatch(code, { start, deleted, text }) {
+    return code.slice(0, start) + text + code.slice(start + deleted);
+}
+
+// Renders diagnostic reports into the container, highlighting labeled spans in the source code
+function renderReports(container, reports, source) {
+    const bytes = new TextEncoder().encode(source ?? "");
+    container.replaceChildren(...reports.map((report) => renderReport(report, bytes)));
+}
+
+function renderReport(report, bytes) {
+    const element = createElement("div", "error-report");
+    element.append(createElement("h2", "error-message", `${report.severity}: ${report.message}`));
+
+    const { span } = report;
+    const location = span ? `${report.file}:${span.line}:${span.column}` : report.file;
+    element.append(createElement("div", "error-location", location));
+
+    if (report.labels.length > 0 && bytes.length > 0) {
+        element.append(renderSource(bytes, report.labels.map((label) => label.span)));
+    }
+    for (const { label, span } of report.labels.filter((label) => label.label)) {
+        element.append(createElement("div", "error-label", `${span.line}:${span.column}: ${label}`));
+    }
+    if (report.help) {
+        element.append(createElement("div", "error-help", `help: ${report.help}`));
+    }
+
+    return element;
+}
+
+// Renders source code lines containing spans along with a line of context around them.
+// Spans are byte offsets, so the source is sliced as UTF-8
+function renderSource(bytes, spans) {
+    const decoder = new TextDecoder();
+    const text = (start, end) => decoder.decode(bytes.subarray(start, end));
+    const marks = [...spans].sort((a, b) => a.start - b.start);
+
+    const lineStarts = [0];
+    bytes.forEach((byte, index) => {
+        if (byte === 10) {
+            lineStarts.push(index + 1);
+        }
+    });
+    const lineOf = (offset) => lineStarts.findLastIndex((start) => start <= offset);
+
+    const first = Math.max(Math.min(...marks.map((span) => lineOf(span.start))) - 1, 0);
+    const last = Math.min(Math.max(...marks.map((span) => lineOf(span.end))) + 1, lineStarts.length - 1);
+
+    const element = createElement("pre", "error-source");
+    for (let line = first; line <= last; line++) {
+        const lineStart = lineStarts[line];
+        const lineEnd = line + 1 < lineStarts.length ? lineStarts[line + 1] - 1 : bytes.length;
+        element.append(createElement("span", "line-number", `${String(line + 1).padStart(4)} | `));
+
+        let position = lineStart;
+        for (const span of marks) {
+            const start = Math.max(span.start, lineStart);
+            const end = Math.min(span.end, lineEnd);
+            if (start < position || start > end || span.end < lineStart || span.start > lineEnd) {
+                continue;
+            }
+
+            element.append(text(position, start));
+            const className = start === end ? "error-point" : "error-span";
+            element.append(createElement("mark", className, text(start, end)));
+            position = end;
+        }
+        element.append(text(position, lineEnd), "\n");
+    }
+
+    return element;
+}
+
+function createElement(tag, className, text) {
+    const element = document.createElement(tag);
+    element.className = className;
+    if (text !== undefined) {
+        element.textContent = text;
+    }
+
+    return element;
+}
+
+// Updates content of the page, keeping the scroll position
+function updateContent(html) {
+    const [x, y] = hasContent
+        ? [window.scrollX, window.scrollY]
+        : JSON.parse(sessionStorage.getItem(scrollKey) ?? "[0, 0]");
+
+    hasContent = true;
+    patch(main, html);
+    window.scrollTo(x, y);
+}
+
+// Updates content of the element in place, touching only changed nodes,
+// so that large pages don't flash on every update
+function patch(element, html) {
+    const template = document.createElement("template");
+    template.innerHTML = html;
+
+    patchChildren(element, template.content);
+}
+
+function patchChildren(target, source) {
+    const targetNodes = [...target.childNodes];
+    const sourceNodes = [...source.childNodes];
+
+    sourceNodes.forEach((node, index) => {
+        const existing = targetNodes[index];
+        if (existing === undefined) {
+            target.appendChild(node.cloneNode(true));
+        } else {
+            patchNode(existing, node);
+        }
+    });
+    targetNodes.slice(sourceNodes.length).forEach((node) => node.remove());
+}
+
+function patchNode(target, source) {
+    if (target.nodeType !== source.nodeType || target.nodeName !== source.nodeName) {
+        target.replaceWith(source.cloneNode(true));
+        return;
+    }
+
+    if (target.nodeType !== Node.ELEMENT_NODE) {
+        if (target.nodeValue !== source.nodeValue) {
+            target.nodeValue = source.nodeValue;
+        }
+        return;
+    }
+
+    patchAttributes(target, source);
+    patchChildren(target, source);
+}
+
+function patchAttributes(target, source) {
+    for (const { name } of [...target.attributes]) {
+        if (!source.hasAttribute(name) && !isUserState(target, name)) {
+            target.removeAttribute(name);
+        }
+    }
+    for (const { name, value } of source.attributes) {
+        if (target.getAttribute(name) !== value) {
+            target.setAttribute(name, value);
+        }
+    }
+}
+
+// Checks whether the attribute reflects state changed by the user,
+// such as expanded details, which shouldn't be reset on update.
+// Values of form controls are kept, since only their attributes are patched
+function isUserState(element, attribute) {
+    return element.nodeName === "DETAILS" && attribute === "open";
+}
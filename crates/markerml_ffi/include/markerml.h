@@ -0,0 +1,39 @@
+#ifndef MARKERML_H
+#define MARKERML_H
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+/*
+ * Converts given null-terminated MarkerML code into HTML page.
+ * Returns string, that must be freed with markerml_free_string,
+ * or NULL in case of error.
+ */
+char *markerml_parse(const char *code);
+
+/*
+ * Converts given null-terminated MarkerML code into HTML markup
+ * without the surrounding page.
+ * Returns string, that must be freed with markerml_free_string,
+ * or NULL in case of error.
+ */
+char *markerml_parse_fragment(const char *code);
+
+/*
+ * Returns message of the last error, that happened on the current thread,
+ * or NULL if there was no error.
+ * The string is owned by the library and stays valid until the next conversion.
+ */
+const char *markerml_last_error(void);
+
+/*
+ * Frees string returned by the conversion functions.
+ */
+void markerml_free_string(char *string);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif /* MARKERML_H */
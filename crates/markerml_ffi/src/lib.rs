@@ -0,0 +1,99 @@
+//! This is a crate that provides C ABI of the MarkerML converter,
+//! so that it can be embedded into non-Rust applications.
+//! Declarations of the functions are located in `include/markerml.h`.
+//!
+//! Converted HTML is returned as a string, that is owned by the caller
+//! and must be freed with [`markerml_free_string`].
+//! In case of failure null is returned and the error message
+//! can be retrieved with [`markerml_last_error`].
+//!
+//! For the full grammar overview,
+//! refer to the [`markerml`](https://crates.io/crates/markerml) crate.
+
+use std::cell::RefCell;
+use std::ffi::{c_char, CStr, CString};
+use std::panic::{self, UnwindSafe};
+use std::ptr;
+
+thread_local! {
+    /// Message of the last error, that happened on the current thread
+    static LAST_ERROR: RefCell<Option<CString>> = const { RefCell::new(None) };
+}
+
+/// Converts given null-terminated MarkerML code into HTML page.
+/// Returns null in case of error
+///
+/// # Safety
+/// `code` must be null or point to a valid null-terminated string
+#[no_mangle]
+pub unsafe extern "C" fn markerml_parse(code: *const c_char) -> *mut c_char {
+    convert(code, markerml::parse)
+}
+
+/// Converts given null-terminated MarkerML code into HTML markup
+/// without the surrounding page. Returns null in case of error
+///
+/// # Safety
+/// `code` must be null or point to a valid null-terminated string
+#[no_mangle]
+pub unsafe extern "C" fn markerml_parse_fragment(code: *const c_char) -> *mut c_char {
+    convert(code, markerml::parse_fragment)
+}
+
+/// Returns message of the last error, that happened on the current thread,
+/// or null if there was no error.
+/// The string is owned by the library and stays valid until the next conversion
+#[no_mangle]
+pub extern "C" fn markerml_last_error() -> *const c_char {
+    LAST_ERROR.with_borrow(|error| error.as_ref().map_or(ptr::null(), |error| error.as_ptr()))
+}
+
+/// Frees string returned by the conversion functions
+///
+/// # Safety
+/// `string` must be null or returned by the conversion functions and not freed before
+#[no_mangle]
+pub unsafe extern "C" fn markerml_free_string(string: *mut c_char) {
+    if !string.is_null() {
+        drop(CString::from_raw(string));
+    }
+}
+
+/// Converts the code, storing the error message in case of failure
+unsafe fn convert(
+    code: *const c_char,
+    convert: impl FnOnce(&str) -> Result<String, markerml::MarkermlError> + UnwindSafe,
+) -> *mut c_char {
+    let result = if code.is_null() {
+        Err("Code is null".to_owned())
+    } else {
+        CStr::from_ptr(code)
+            .to_str()
+            .map_err(|err| format!("Code is not valid UTF-8: {err}"))
+            .and_then(|code| {
+                panic::catch_unwind(|| convert(code))
+                    .map_err(|_| "Converter panicked".to_owned())?
+                    .map_err(|err| err.to_string())
+            })
+            .and_then(|html| {
+                CString::new(html).map_err(|_| "Generated HTML contains null byte".to_owned())
+            })
+    };
+
+    match result {
+        Ok(html) => {
+            set_last_error(None);
+            html.into_raw()
+        }
+        Err(message) => {
+            set_last_error(Some(message));
+            ptr::null_mut()
+        }
+    }
+}
+
+fn set_last_error(message: Option<String>) {
+    let message =
+        message.map(|message| CString::new(message.replace('\0', " ")).unwrap_or_default());
+    LAST_ERROR.with_borrow_mut(|error| *error = message);
+}
@@ -1,24 +1,44 @@
+pub mod builder;
+pub mod visit;
+
+pub use visit::{Visitor, VisitorMut};
+
+use std::fmt;
+
 /// Represents top level module
 #[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Module<SpanT> {
     pub span: SpanT,
     pub items: Vec<ModuleItem<SpanT>>,
 }
 
-/// Represents module item: component or component definition
+/// Represents module item: constant, component or component definition
 #[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum ModuleItem<SpanT> {
+    Constant(Constant<SpanT>),
     Component(Component<SpanT>),
     ComponentDefinition(ComponentDefinition<SpanT>),
 }
 
+/// Represents module-level constant, consisting of name and value
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Constant<SpanT> {
+    pub span: SpanT,
+    pub name: Identifier<SpanT>,
+    pub value: Value<SpanT>,
+}
+
 /// Represents component. It has name
 /// and also might contain properties, children and text
 #[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Component<SpanT> {
     pub span: SpanT,
     pub name: Identifier<SpanT>,
-    pub properties: Option<Properties<SpanT>>,
+    pub properties: Option<Box<Properties<SpanT>>>,
     pub children: Option<ComponentChildren<SpanT>>,
     pub text: Option<Text<SpanT>>,
 }
@@ -27,6 +47,7 @@ pub struct Component<SpanT> {
 /// Might contain single default property and list
 /// of named or flag properties
 #[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Properties<SpanT> {
     pub span: SpanT,
     pub default: Option<Value<SpanT>>,
@@ -35,6 +56,7 @@ pub struct Properties<SpanT> {
 
 /// Represents key-value or flag property along with a span
 #[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Property<SpanT> {
     pub span: SpanT,
     pub kind: PropertyKind<SpanT>,
@@ -42,6 +64,7 @@ pub struct Property<SpanT> {
 
 /// Represents key-value or flag property
 #[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum PropertyKind<SpanT> {
     KeyValue {
         key: Identifier<SpanT>,
@@ -50,10 +73,15 @@ pub enum PropertyKind<SpanT> {
     Flag {
         key: Identifier<SpanT>,
     },
+    /// Flag that is explicitly disabled with `!`
+    NegatedFlag {
+        key: Identifier<SpanT>,
+    },
 }
 
 /// Represents list of component children
 #[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct ComponentChildren<SpanT> {
     pub span: SpanT,
     pub children: Vec<Component<SpanT>>,
@@ -62,15 +90,17 @@ pub struct ComponentChildren<SpanT> {
 /// Represents component definition.
 /// Consists of name, optional properties and children
 #[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct ComponentDefinition<SpanT> {
     pub span: SpanT,
     pub name: Identifier<SpanT>,
-    pub properties: Option<PropertiesDefinition<SpanT>>,
+    pub properties: Option<Box<PropertiesDefinition<SpanT>>>,
     pub children: Option<ComponentChildren<SpanT>>,
 }
 
 /// Represents list of property definitions
 #[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct PropertiesDefinition<SpanT> {
     pub span: SpanT,
     pub properties: Vec<PropertyDefinition<SpanT>>,
@@ -78,6 +108,7 @@ pub struct PropertiesDefinition<SpanT> {
 
 /// Represents property definition along with a span
 #[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct PropertyDefinition<SpanT> {
     pub span: SpanT,
     pub kind: PropertyDefinitionKind<SpanT>,
@@ -85,6 +116,7 @@ pub struct PropertyDefinition<SpanT> {
 
 /// Represents property definition, which can be text, default, or named
 #[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum PropertyDefinitionKind<SpanT> {
     Text(TextPropertyDefinition<SpanT>),
     Default(NamedPropertyDefinition<SpanT>),
@@ -93,6 +125,7 @@ pub enum PropertyDefinitionKind<SpanT> {
 
 /// Represents text property definition (which always has string type)
 #[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct TextPropertyDefinition<SpanT> {
     pub name: Identifier<SpanT>,
 }
@@ -100,6 +133,7 @@ pub struct TextPropertyDefinition<SpanT> {
 /// Represents named property definition, consisting of name, type
 /// and optional default value
 #[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct NamedPropertyDefinition<SpanT> {
     pub name: Identifier<SpanT>,
     pub ty: Type<SpanT>,
@@ -108,6 +142,7 @@ pub struct NamedPropertyDefinition<SpanT> {
 
 /// Represents value along with a span
 #[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Value<SpanT> {
     pub span: SpanT,
     pub kind: ValueKind<SpanT>,
@@ -115,6 +150,7 @@ pub struct Value<SpanT> {
 
 /// Represents value
 #[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum ValueKind<SpanT> {
     String(StringValue<SpanT>),
     Integer(i64),
@@ -124,6 +160,7 @@ pub enum ValueKind<SpanT> {
 
 /// Represents string value, consisting of multiple interpolation segments
 #[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct StringValue<SpanT> {
     pub span: SpanT,
     pub segments: Vec<InterpolationSegment<SpanT>>,
@@ -131,13 +168,18 @@ pub struct StringValue<SpanT> {
 
 /// Represents text value, consisting of multiple interpolation segments
 #[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Text<SpanT> {
     pub span: SpanT,
     pub segments: Vec<InterpolationSegment<SpanT>>,
+    /// Whether the text is raw: enclosed in backticks
+    /// and consisting of a single verbatim literal
+    pub raw: bool,
 }
 
 /// Represents interpolation segment along with a span
 #[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct InterpolationSegment<SpanT> {
     pub span: SpanT,
     pub kind: InterpolationSegmentKind<SpanT>,
@@ -145,13 +187,54 @@ pub struct InterpolationSegment<SpanT> {
 
 /// Represents interpolation segment: literal string or variable interpolation
 #[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum InterpolationSegmentKind<SpanT> {
     Literal(String),
     Variable(Identifier<SpanT>),
+    /// Variable with the format specifier, like `${price:.2}`
+    Formatted(Box<FormattedVariable<SpanT>>),
+    /// Key of the localized message
+    Message(String),
+    /// Component inside the text, like `$@[bold](word)`. Only text can contain it
+    Component(Box<Component<SpanT>>),
+    /// Line break of the multiline text, which is rendered as a space unless newlines are preserved
+    Newline,
+}
+
+/// Represents interpolated variable along with its format specifier
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct FormattedVariable<SpanT> {
+    pub name: Identifier<SpanT>,
+    pub spec: FormatSpec,
+}
+
+/// Represents format specifier of the interpolated variable: `[align][0][width][.precision]`
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct FormatSpec {
+    /// Alignment of the value, when it's shorter than the width
+    pub align: Option<FormatAlign>,
+    /// Whether numbers are padded with zeros after the sign
+    pub zero_pad: bool,
+    /// Minimal number of characters
+    pub width: Option<usize>,
+    /// Number of digits after the decimal point
+    pub precision: Option<usize>,
+}
+
+/// Represents alignment of the formatted value
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum FormatAlign {
+    Left,
+    Center,
+    Right,
 }
 
 /// Represents identifier
 #[derive(Debug, Clone, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Identifier<SpanT> {
     pub span: SpanT,
     pub name: String,
@@ -159,6 +242,7 @@ pub struct Identifier<SpanT> {
 
 /// Represents type along with a span
 #[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Type<SpanT> {
     pub span: SpanT,
     pub kind: TypeKind,
@@ -166,6 +250,7 @@ pub struct Type<SpanT> {
 
 /// Represents type
 #[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum TypeKind {
     String,
     Integer,
@@ -198,6 +283,7 @@ impl<SpanT: Default> Text<SpanT> {
             segments: vec![
                 InterpolationSegmentKind::Literal(string.to_owned()).spanned(Default::default())
             ],
+            raw: false,
         }
     }
 }
@@ -220,6 +306,12 @@ impl<SpanT> From<Identifier<SpanT>> for String {
     }
 }
 
+impl<SpanT> From<Constant<SpanT>> for ModuleItem<SpanT> {
+    fn from(constant: Constant<SpanT>) -> Self {
+        ModuleItem::Constant(constant)
+    }
+}
+
 impl<SpanT> From<Component<SpanT>> for ModuleItem<SpanT> {
     fn from(component: Component<SpanT>) -> Self {
         ModuleItem::Component(component)
@@ -302,3 +394,25 @@ impl TypeKind {
         Type { span, kind: self }
     }
 }
+
+impl fmt::Display for FormatSpec {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.align {
+            Some(FormatAlign::Left) => f.write_str("<")?,
+            Some(FormatAlign::Center) => f.write_str("^")?,
+            Some(FormatAlign::Right) => f.write_str(">")?,
+            None => {}
+        }
+        if self.zero_pad {
+            f.write_str("0")?;
+        }
+        if let Some(width) = self.width {
+            write!(f, "{width}")?;
+        }
+        if let Some(precision) = self.precision {
+            write!(f, ".{precision}")?;
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,308 @@
+//! Fluent constructors of the AST, so that programs can generate documents from data
+//! without building the code from strings. Spans of the constructed nodes are default.
+//!
+//! ```
+//! use markerml_frontend::ast::builder::{component, definition, module};
+//! use markerml_frontend::ast::TypeKind;
+//! use markerml_frontend::Span;
+//!
+//! let module = module::<Span>()
+//!     .definition(
+//!         definition("card")
+//!             .property("title", TypeKind::String)
+//!             .child(component("header").default(1).text("Card")),
+//!     )
+//!     .component(
+//!         component("box")
+//!             .flag("horizontal")
+//!             .property("x_align", "center")
+//!             .child(component("card").property("title", "Hello")),
+//!     )
+//!     .build();
+//! ```
+
+use super::*;
+
+/// Creates builder of the module
+pub fn module<SpanT: Default>() -> ModuleBuilder<SpanT> {
+    ModuleBuilder { items: Vec::new() }
+}
+
+/// Creates builder of the component with the given name
+pub fn component<SpanT: Default>(name: &str) -> ComponentBuilder<SpanT> {
+    ComponentBuilder {
+        component: Component {
+            span: Default::default(),
+            name: Identifier::from_literal(name),
+            properties: None,
+            children: None,
+            text: None,
+        },
+    }
+}
+
+/// Creates builder of the component definition with the given name
+pub fn definition<SpanT: Default>(name: &str) -> DefinitionBuilder<SpanT> {
+    DefinitionBuilder {
+        definition: ComponentDefinition {
+            span: Default::default(),
+            name: Identifier::from_literal(name),
+            properties: None,
+            children: None,
+        },
+    }
+}
+
+/// Creates value referencing the variable with the given name
+pub fn variable<SpanT: Default>(name: &str) -> Value<SpanT> {
+    ValueKind::Variable(Identifier::from_literal(name)).into()
+}
+
+/// Builder of the module
+#[derive(Debug, Clone)]
+pub struct ModuleBuilder<SpanT> {
+    items: Vec<ModuleItem<SpanT>>,
+}
+
+impl<SpanT: Default> ModuleBuilder<SpanT> {
+    /// Adds constant with the given name and value
+    pub fn constant(mut self, name: &str, value: impl Into<Value<SpanT>>) -> Self {
+        self.items.push(ModuleItem::Constant(Constant {
+            span: Default::default(),
+            name: Identifier::from_literal(name),
+            value: value.into(),
+        }));
+        self
+    }
+
+    /// Adds top-level component
+    pub fn component(mut self, component: impl Into<Component<SpanT>>) -> Self {
+        self.items.push(ModuleItem::Component(component.into()));
+        self
+    }
+
+    /// Adds component definition
+    pub fn definition(mut self, definition: impl Into<ComponentDefinition<SpanT>>) -> Self {
+        self.items
+            .push(ModuleItem::ComponentDefinition(definition.into()));
+        self
+    }
+
+    pub fn build(self) -> Module<SpanT> {
+        Module {
+            span: Default::default(),
+            items: self.items,
+        }
+    }
+}
+
+/// Builder of the component
+#[derive(Debug, Clone)]
+pub struct ComponentBuilder<SpanT> {
+    component: Component<SpanT>,
+}
+
+impl<SpanT: Default> ComponentBuilder<SpanT> {
+    /// Sets default property
+    pub fn default(mut self, value: impl Into<Value<SpanT>>) -> Self {
+        self.properties().default = Some(value.into());
+        self
+    }
+
+    /// Adds key-value property
+    pub fn property(mut self, key: &str, value: impl Into<Value<SpanT>>) -> Self {
+        let property = PropertyKind::KeyValue {
+            key: Identifier::from_literal(key),
+            value: value.into(),
+        };
+        self.properties().properties.push(property.into());
+        self
+    }
+
+    /// Adds flag property
+    pub fn flag(mut self, key: &str) -> Self {
+        let property = PropertyKind::Flag {
+            key: Identifier::from_literal(key),
+        };
+        self.properties().properties.push(property.into());
+        self
+    }
+
+    /// Adds explicitly disabled flag property
+    pub fn negated_flag(mut self, key: &str) -> Self {
+        let property = PropertyKind::NegatedFlag {
+            key: Identifier::from_literal(key),
+        };
+        self.properties().properties.push(property.into());
+        self
+    }
+
+    /// Adds child component
+    pub fn child(mut self, child: impl Into<Component<SpanT>>) -> Self {
+        push_child(&mut self.component.children, child.into());
+        self
+    }
+
+    /// Adds child components
+    pub fn children<C: Into<Component<SpanT>>>(
+        mut self,
+        children: impl IntoIterator<Item = C>,
+    ) -> Self {
+        for child in children {
+            push_child(&mut self.component.children, child.into());
+        }
+        self
+    }
+
+    /// Sets text, which is taken literally
+    pub fn text(mut self, text: &str) -> Self {
+        self.component.text = Some(Text::from_literal(text));
+        self
+    }
+
+    pub fn build(self) -> Component<SpanT> {
+        self.component
+    }
+
+    fn properties(&mut self) -> &mut Properties<SpanT> {
+        self.component.properties.get_or_insert_with(|| {
+            Box::new(Properties {
+                span: Default::default(),
+                default: None,
+                properties: Vec::new(),
+            })
+        })
+    }
+}
+
+/// Builder of the component definition
+#[derive(Debug, Clone)]
+pub struct DefinitionBuilder<SpanT> {
+    definition: ComponentDefinition<SpanT>,
+}
+
+impl<SpanT: Default> DefinitionBuilder<SpanT> {
+    /// Adds text property definition
+    pub fn text_property(self, name: &str) -> Self {
+        self.push_property(PropertyDefinitionKind::Text(TextPropertyDefinition {
+            name: Identifier::from_literal(name),
+        }))
+    }
+
+    /// Adds default property definition
+    pub fn default_property(self, name: &str, ty: TypeKind) -> Self {
+        self.push_property(PropertyDefinitionKind::Default(named(name, ty, None)))
+    }
+
+    /// Adds named property definition
+    pub fn property(self, name: &str, ty: TypeKind) -> Self {
+        self.push_property(PropertyDefinitionKind::Named(named(name, ty, None)))
+    }
+
+    /// Adds named property definition with the default value
+    pub fn property_with_default(
+        self,
+        name: &str,
+        ty: TypeKind,
+        value: impl Into<Value<SpanT>>,
+    ) -> Self {
+        let def = named(name, ty, Some(value.into()));
+        self.push_property(PropertyDefinitionKind::Named(def))
+    }
+
+    /// Adds child component
+    pub fn child(mut self, child: impl Into<Component<SpanT>>) -> Self {
+        push_child(&mut self.definition.children, child.into());
+        self
+    }
+
+    /// Adds child components
+    pub fn children<C: Into<Component<SpanT>>>(
+        mut self,
+        children: impl IntoIterator<Item = C>,
+    ) -> Self {
+        for child in children {
+            push_child(&mut self.definition.children, child.into());
+        }
+        self
+    }
+
+    pub fn build(self) -> ComponentDefinition<SpanT> {
+        self.definition
+    }
+
+    fn push_property(mut self, kind: PropertyDefinitionKind<SpanT>) -> Self {
+        self.definition
+            .properties
+            .get_or_insert_with(|| {
+                Box::new(PropertiesDefinition {
+                    span: Default::default(),
+                    properties: Vec::new(),
+                })
+            })
+            .properties
+            .push(kind.into());
+        self
+    }
+}
+
+fn named<SpanT: Default>(
+    name: &str,
+    ty: TypeKind,
+    default_value: Option<Value<SpanT>>,
+) -> NamedPropertyDefinition<SpanT> {
+    NamedPropertyDefinition {
+        name: Identifier::from_literal(name),
+        ty: ty.into(),
+        default_value,
+    }
+}
+
+fn push_child<SpanT: Default>(
+    children: &mut Option<ComponentChildren<SpanT>>,
+    child: Component<SpanT>,
+) {
+    children
+        .get_or_insert_with(|| ComponentChildren {
+            span: Default::default(),
+            children: Vec::new(),
+        })
+        .children
+        .push(child);
+}
+
+impl<SpanT: Default> From<ComponentBuilder<SpanT>> for Component<SpanT> {
+    fn from(builder: ComponentBuilder<SpanT>) -> Self {
+        builder.build()
+    }
+}
+
+impl<SpanT: Default> From<DefinitionBuilder<SpanT>> for ComponentDefinition<SpanT> {
+    fn from(builder: DefinitionBuilder<SpanT>) -> Self {
+        builder.build()
+    }
+}
+
+impl<SpanT: Default> From<&str> for Value<SpanT> {
+    fn from(value: &str) -> Self {
+        StringValue::from_literal(value).into()
+    }
+}
+
+impl<SpanT: Default> From<i32> for Value<SpanT> {
+    fn from(value: i32) -> Self {
+        ValueKind::Integer(value.into()).into()
+    }
+}
+
+impl<SpanT: Default> From<i64> for Value<SpanT> {
+    fn from(value: i64) -> Self {
+        ValueKind::Integer(value).into()
+    }
+}
+
+impl<SpanT: Default> From<bool> for Value<SpanT> {
+    fn from(value: bool) -> Self {
+        ValueKind::Bool(value).into()
+    }
+}
@@ -0,0 +1,392 @@
+use crate::ast::*;
+
+/// Visitor of the AST. Every method walks into the children of the node by default,
+/// so implementations override only the nodes they are interested in
+/// and call the corresponding `walk_*` function to keep descending
+pub trait Visitor<SpanT> {
+    fn visit_module(&mut self, module: &Module<SpanT>) {
+        walk_module(self, module)
+    }
+
+    fn visit_module_item(&mut self, item: &ModuleItem<SpanT>) {
+        walk_module_item(self, item)
+    }
+
+    fn visit_constant(&mut self, constant: &Constant<SpanT>) {
+        walk_constant(self, constant)
+    }
+
+    fn visit_component(&mut self, component: &Component<SpanT>) {
+        walk_component(self, component)
+    }
+
+    fn visit_properties(&mut self, properties: &Properties<SpanT>) {
+        walk_properties(self, properties)
+    }
+
+    fn visit_property(&mut self, property: &Property<SpanT>) {
+        walk_property(self, property)
+    }
+
+    fn visit_component_definition(&mut self, definition: &ComponentDefinition<SpanT>) {
+        walk_component_definition(self, definition)
+    }
+
+    fn visit_property_definition(&mut self, definition: &PropertyDefinition<SpanT>) {
+        walk_property_definition(self, definition)
+    }
+
+    fn visit_value(&mut self, value: &Value<SpanT>) {
+        walk_value(self, value)
+    }
+
+    fn visit_text(&mut self, text: &Text<SpanT>) {
+        walk_text(self, text)
+    }
+
+    fn visit_interpolation_segment(&mut self, segment: &InterpolationSegment<SpanT>) {
+        walk_interpolation_segment(self, segment)
+    }
+
+    fn visit_identifier(&mut self, _identifier: &Identifier<SpanT>) {}
+
+    fn visit_type(&mut self, _ty: &Type<SpanT>) {}
+}
+
+pub fn walk_module<SpanT, V: Visitor<SpanT> + ?Sized>(visitor: &mut V, module: &Module<SpanT>) {
+    for item in &module.items {
+        visitor.visit_module_item(item);
+    }
+}
+
+pub fn walk_module_item<SpanT, V: Visitor<SpanT> + ?Sized>(
+    visitor: &mut V,
+    item: &ModuleItem<SpanT>,
+) {
+    match item {
+        ModuleItem::Constant(constant) => visitor.visit_constant(constant),
+        ModuleItem::Component(component) => visitor.visit_component(component),
+        ModuleItem::ComponentDefinition(definition) => {
+            visitor.visit_component_definition(definition)
+        }
+    }
+}
+
+pub fn walk_constant<SpanT, V: Visitor<SpanT> + ?Sized>(
+    visitor: &mut V,
+    constant: &Constant<SpanT>,
+) {
+    visitor.visit_identifier(&constant.name);
+    visitor.visit_value(&constant.value);
+}
+
+pub fn walk_component<SpanT, V: Visitor<SpanT> + ?Sized>(
+    visitor: &mut V,
+    component: &Component<SpanT>,
+) {
+    visitor.visit_identifier(&component.name);
+    if let Some(properties) = &component.properties {
+        visitor.visit_properties(properties);
+    }
+    if let Some(children) = &component.children {
+        for child in &children.children {
+            visitor.visit_component(child);
+        }
+    }
+    if let Some(text) = &component.text {
+        visitor.visit_text(text);
+    }
+}
+
+pub fn walk_properties<SpanT, V: Visitor<SpanT> + ?Sized>(
+    visitor: &mut V,
+    properties: &Properties<SpanT>,
+) {
+    if let Some(value) = &properties.default {
+        visitor.visit_value(value);
+    }
+    for property in &properties.properties {
+        visitor.visit_property(property);
+    }
+}
+
+pub fn walk_property<SpanT, V: Visitor<SpanT> + ?Sized>(
+    visitor: &mut V,
+    property: &Property<SpanT>,
+) {
+    match &property.kind {
+        PropertyKind::KeyValue { key, value } => {
+            visitor.visit_identifier(key);
+            visitor.visit_value(value);
+        }
+        PropertyKind::Flag { key } | PropertyKind::NegatedFlag { key } => {
+            visitor.visit_identifier(key)
+        }
+    }
+}
+
+pub fn walk_component_definition<SpanT, V: Visitor<SpanT> + ?Sized>(
+    visitor: &mut V,
+    definition: &ComponentDefinition<SpanT>,
+) {
+    visitor.visit_identifier(&definition.name);
+    if let Some(properties) = &definition.properties {
+        for property in &properties.properties {
+            visitor.visit_property_definition(property);
+        }
+    }
+    if let Some(children) = &definition.children {
+        for child in &children.children {
+            visitor.visit_component(child);
+        }
+    }
+}
+
+pub fn walk_property_definition<SpanT, V: Visitor<SpanT> + ?Sized>(
+    visitor: &mut V,
+    definition: &PropertyDefinition<SpanT>,
+) {
+    match &definition.kind {
+        PropertyDefinitionKind::Text(definition) => visitor.visit_identifier(&definition.name),
+        PropertyDefinitionKind::Default(definition) | PropertyDefinitionKind::Named(definition) => {
+            visitor.visit_identifier(&definition.name);
+            visitor.visit_type(&definition.ty);
+            if let Some(value) = &definition.default_value {
+                visitor.visit_value(value);
+            }
+        }
+    }
+}
+
+pub fn walk_value<SpanT, V: Visitor<SpanT> + ?Sized>(visitor: &mut V, value: &Value<SpanT>) {
+    match &value.kind {
+        ValueKind::String(string) => {
+            for segment in &string.segments {
+                visitor.visit_interpolation_segment(segment);
+            }
+        }
+        ValueKind::Variable(name) => visitor.visit_identifier(name),
+        ValueKind::Integer(_) | ValueKind::Bool(_) => {}
+    }
+}
+
+pub fn walk_text<SpanT, V: Visitor<SpanT> + ?Sized>(visitor: &mut V, text: &Text<SpanT>) {
+    for segment in &text.segments {
+        visitor.visit_interpolation_segment(segment);
+    }
+}
+
+pub fn walk_interpolation_segment<SpanT, V: Visitor<SpanT> + ?Sized>(
+    visitor: &mut V,
+    segment: &InterpolationSegment<SpanT>,
+) {
+    match &segment.kind {
+        InterpolationSegmentKind::Variable(name) => visitor.visit_identifier(name),
+        InterpolationSegmentKind::Formatted(variable) => visitor.visit_identifier(&variable.name),
+        InterpolationSegmentKind::Component(component) => visitor.visit_component(component),
+        InterpolationSegmentKind::Literal(_)
+        | InterpolationSegmentKind::Message(_)
+        | InterpolationSegmentKind::Newline => {}
+    }
+}
+
+/// Visitor of the AST, that can modify the visited nodes. Used by rewriting tools
+pub trait VisitorMut<SpanT> {
+    fn visit_module_mut(&mut self, module: &mut Module<SpanT>) {
+        walk_module_mut(self, module)
+    }
+
+    fn visit_module_item_mut(&mut self, item: &mut ModuleItem<SpanT>) {
+        walk_module_item_mut(self, item)
+    }
+
+    fn visit_constant_mut(&mut self, constant: &mut Constant<SpanT>) {
+        walk_constant_mut(self, constant)
+    }
+
+    fn visit_component_mut(&mut self, component: &mut Component<SpanT>) {
+        walk_component_mut(self, component)
+    }
+
+    fn visit_properties_mut(&mut self, properties: &mut Properties<SpanT>) {
+        walk_properties_mut(self, properties)
+    }
+
+    fn visit_property_mut(&mut self, property: &mut Property<SpanT>) {
+        walk_property_mut(self, property)
+    }
+
+    fn visit_component_definition_mut(&mut self, definition: &mut ComponentDefinition<SpanT>) {
+        walk_component_definition_mut(self, definition)
+    }
+
+    fn visit_property_definition_mut(&mut self, definition: &mut PropertyDefinition<SpanT>) {
+        walk_property_definition_mut(self, definition)
+    }
+
+    fn visit_value_mut(&mut self, value: &mut Value<SpanT>) {
+        walk_value_mut(self, value)
+    }
+
+    fn visit_text_mut(&mut self, text: &mut Text<SpanT>) {
+        walk_text_mut(self, text)
+    }
+
+    fn visit_interpolation_segment_mut(&mut self, segment: &mut InterpolationSegment<SpanT>) {
+        walk_interpolation_segment_mut(self, segment)
+    }
+
+    fn visit_identifier_mut(&mut self, _identifier: &mut Identifier<SpanT>) {}
+
+    fn visit_type_mut(&mut self, _ty: &mut Type<SpanT>) {}
+}
+
+pub fn walk_module_mut<SpanT, V: VisitorMut<SpanT> + ?Sized>(
+    visitor: &mut V,
+    module: &mut Module<SpanT>,
+) {
+    for item in &mut module.items {
+        visitor.visit_module_item_mut(item);
+    }
+}
+
+pub fn walk_module_item_mut<SpanT, V: VisitorMut<SpanT> + ?Sized>(
+    visitor: &mut V,
+    item: &mut ModuleItem<SpanT>,
+) {
+    match item {
+        ModuleItem::Constant(constant) => visitor.visit_constant_mut(constant),
+        ModuleItem::Component(component) => visitor.visit_component_mut(component),
+        ModuleItem::ComponentDefinition(definition) => {
+            visitor.visit_component_definition_mut(definition)
+        }
+    }
+}
+
+pub fn walk_constant_mut<SpanT, V: VisitorMut<SpanT> + ?Sized>(
+    visitor: &mut V,
+    constant: &mut Constant<SpanT>,
+) {
+    visitor.visit_identifier_mut(&mut constant.name);
+    visitor.visit_value_mut(&mut constant.value);
+}
+
+pub fn walk_component_mut<SpanT, V: VisitorMut<SpanT> + ?Sized>(
+    visitor: &mut V,
+    component: &mut Component<SpanT>,
+) {
+    visitor.visit_identifier_mut(&mut component.name);
+    if let Some(properties) = &mut component.properties {
+        visitor.visit_properties_mut(properties);
+    }
+    if let Some(children) = &mut component.children {
+        for child in &mut children.children {
+            visitor.visit_component_mut(child);
+        }
+    }
+    if let Some(text) = &mut component.text {
+        visitor.visit_text_mut(text);
+    }
+}
+
+pub fn walk_properties_mut<SpanT, V: VisitorMut<SpanT> + ?Sized>(
+    visitor: &mut V,
+    properties: &mut Properties<SpanT>,
+) {
+    if let Some(value) = &mut properties.default {
+        visitor.visit_value_mut(value);
+    }
+    for property in &mut properties.properties {
+        visitor.visit_property_mut(property);
+    }
+}
+
+pub fn walk_property_mut<SpanT, V: VisitorMut<SpanT> + ?Sized>(
+    visitor: &mut V,
+    property: &mut Property<SpanT>,
+) {
+    match &mut property.kind {
+        PropertyKind::KeyValue { key, value } => {
+            visitor.visit_identifier_mut(key);
+            visitor.visit_value_mut(value);
+        }
+        PropertyKind::Flag { key } | PropertyKind::NegatedFlag { key } => {
+            visitor.visit_identifier_mut(key)
+        }
+    }
+}
+
+pub fn walk_component_definition_mut<SpanT, V: VisitorMut<SpanT> + ?Sized>(
+    visitor: &mut V,
+    definition: &mut ComponentDefinition<SpanT>,
+) {
+    visitor.visit_identifier_mut(&mut definition.name);
+    if let Some(properties) = &mut definition.properties {
+        for property in &mut properties.properties {
+            visitor.visit_property_definition_mut(property);
+        }
+    }
+    if let Some(children) = &mut definition.children {
+        for child in &mut children.children {
+            visitor.visit_component_mut(child);
+        }
+    }
+}
+
+pub fn walk_property_definition_mut<SpanT, V: VisitorMut<SpanT> + ?Sized>(
+    visitor: &mut V,
+    definition: &mut PropertyDefinition<SpanT>,
+) {
+    match &mut definition.kind {
+        PropertyDefinitionKind::Text(definition) => {
+            visitor.visit_identifier_mut(&mut definition.name)
+        }
+        PropertyDefinitionKind::Default(definition) | PropertyDefinitionKind::Named(definition) => {
+            visitor.visit_identifier_mut(&mut definition.name);
+            visitor.visit_type_mut(&mut definition.ty);
+            if let Some(value) = &mut definition.default_value {
+                visitor.visit_value_mut(value);
+            }
+        }
+    }
+}
+
+pub fn walk_value_mut<SpanT, V: VisitorMut<SpanT> + ?Sized>(
+    visitor: &mut V,
+    value: &mut Value<SpanT>,
+) {
+    match &mut value.kind {
+        ValueKind::String(string) => {
+            for segment in &mut string.segments {
+                visitor.visit_interpolation_segment_mut(segment);
+            }
+        }
+        ValueKind::Variable(name) => visitor.visit_identifier_mut(name),
+        ValueKind::Integer(_) | ValueKind::Bool(_) => {}
+    }
+}
+
+pub fn walk_text_mut<SpanT, V: VisitorMut<SpanT> + ?Sized>(
+    visitor: &mut V,
+    text: &mut Text<SpanT>,
+) {
+    for segment in &mut text.segments {
+        visitor.visit_interpolation_segment_mut(segment);
+    }
+}
+
+pub fn walk_interpolation_segment_mut<SpanT, V: VisitorMut<SpanT> + ?Sized>(
+    visitor: &mut V,
+    segment: &mut InterpolationSegment<SpanT>,
+) {
+    match &mut segment.kind {
+        InterpolationSegmentKind::Variable(name) => visitor.visit_identifier_mut(name),
+        InterpolationSegmentKind::Formatted(variable) => {
+            visitor.visit_identifier_mut(&mut variable.name)
+        }
+        InterpolationSegmentKind::Component(component) => visitor.visit_component_mut(component),
+        InterpolationSegmentKind::Literal(_)
+        | InterpolationSegmentKind::Message(_)
+        | InterpolationSegmentKind::Newline => {}
+    }
+}
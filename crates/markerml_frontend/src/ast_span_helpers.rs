@@ -38,6 +38,7 @@ impl<SpanT> MapSpan<SpanT> for ModuleItem<SpanT> {
         F: FnMut(SpanT) -> NewSpanT,
     {
         match self {
+            ModuleItem::Constant(constant) => ModuleItem::Constant(constant.map_span(f)),
             ModuleItem::Component(component) => ModuleItem::Component(component.map_span(f)),
             ModuleItem::ComponentDefinition(definition) => {
                 ModuleItem::ComponentDefinition(definition.map_span(f))
@@ -46,6 +47,20 @@ impl<SpanT> MapSpan<SpanT> for ModuleItem<SpanT> {
     }
 }
 
+impl<SpanT> MapSpan<SpanT> for Constant<SpanT> {
+    type Item<T> = Constant<T>;
+    fn map_span<F, NewSpanT>(self, f: &mut F) -> Constant<NewSpanT>
+    where
+        F: FnMut(SpanT) -> NewSpanT,
+    {
+        Constant {
+            span: f(self.span),
+            name: self.name.map_span(f),
+            value: self.value.map_span(f),
+        }
+    }
+}
+
 impl<SpanT> MapSpan<SpanT> for Component<SpanT> {
     type Item<T> = Component<T>;
     fn map_span<F, NewSpanT>(self, f: &mut F) -> Component<NewSpanT>
@@ -55,7 +70,7 @@ impl<SpanT> MapSpan<SpanT> for Component<SpanT> {
         Component {
             span: f(self.span),
             name: self.name.map_span(f),
-            properties: self.properties.map(|props| props.map_span(f)),
+            properties: self.properties.map(|props| Box::new(props.map_span(f))),
             children: self.children.map(|children| children.map_span(f)),
             text: self.text.map(|text| text.map_span(f)),
         }
@@ -107,6 +122,9 @@ impl<SpanT> MapSpan<SpanT> for PropertyKind<SpanT> {
             PropertyKind::Flag { key } => PropertyKind::Flag {
                 key: key.map_span(f),
             },
+            PropertyKind::NegatedFlag { key } => PropertyKind::NegatedFlag {
+                key: key.map_span(f),
+            },
         }
     }
 }
@@ -148,7 +166,7 @@ impl<SpanT> MapSpan<SpanT> for ComponentDefinition<SpanT> {
         ComponentDefinition {
             span: f(self.span),
             name: self.name.map_span(f),
-            properties: self.properties.map(|props| props.map_span(f)),
+            properties: self.properties.map(|props| Box::new(props.map_span(f))),
             children: self.children.map(|children| children.map_span(f)),
         }
     }
@@ -245,6 +263,7 @@ impl<SpanT> MapSpan<SpanT> for Text<SpanT> {
                 .into_iter()
                 .map(|seg| seg.map_span(f))
                 .collect(),
+            raw: self.raw,
         }
     }
 }
@@ -316,6 +335,17 @@ impl<SpanT> MapSpan<SpanT> for InterpolationSegmentKind<SpanT> {
             InterpolationSegmentKind::Variable(identifier) => {
                 InterpolationSegmentKind::Variable(identifier.map_span(f))
             }
+            InterpolationSegmentKind::Formatted(variable) => {
+                InterpolationSegmentKind::Formatted(Box::new(FormattedVariable {
+                    name: variable.name.map_span(f),
+                    spec: variable.spec,
+                }))
+            }
+            InterpolationSegmentKind::Message(key) => InterpolationSegmentKind::Message(key),
+            InterpolationSegmentKind::Component(component) => {
+                InterpolationSegmentKind::Component(Box::new(component.map_span(f)))
+            }
+            InterpolationSegmentKind::Newline => InterpolationSegmentKind::Newline,
         }
     }
 }
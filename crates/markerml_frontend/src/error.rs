@@ -0,0 +1,139 @@
+use crate::parser::{ParserError, Rule};
+use crate::span::{Position, Span};
+use miette::Diagnostic;
+use pest::error::{ErrorVariant, InputLocation};
+use thiserror::Error;
+
+/// Syntax error of the source code, created from the parser error
+#[derive(Debug, Clone, Error, Diagnostic)]
+#[error("{message}")]
+pub struct SyntaxError {
+    /// Description of the error
+    pub message: String,
+    /// Human readable names of the expected tokens
+    pub expected: Vec<String>,
+    /// Label of the span, describing what was found instead
+    pub label: String,
+    /// Span of the unexpected input
+    #[label("{label}")]
+    pub span: Span,
+}
+
+impl SyntaxError {
+    /// Creates syntax error from the parser error and the parsed code
+    pub fn new(err: &ParserError, code: &str) -> Self {
+        let (start, end) = match err.location {
+            InputLocation::Pos(pos) => {
+                let len = code[pos..].chars().next().map_or(0, char::len_utf8);
+                (pos, pos + len)
+            }
+            InputLocation::Span(span) => span,
+        };
+        let found = match code[start..].chars().next() {
+            _ if start != end && matches!(err.variant, ErrorVariant::CustomError { .. }) => {
+                "Here".to_owned()
+            }
+            Some('\n') | Some('\r') => "Found end of line".to_owned(),
+            Some(c) => format!("Found `{c}`"),
+            None => "Found end of input".to_owned(),
+        };
+
+        let (message, expected) = match &err.variant {
+            ErrorVariant::ParsingError {
+                positives,
+                negatives,
+            } => {
+                let expected = human_rules(positives);
+                let unexpected = human_rules(negatives);
+                let message = match (expected.is_empty(), unexpected.is_empty()) {
+                    (false, _) => format!("Expected {}", join_alternatives(&expected)),
+                    (true, false) => format!("Unexpected {}", join_alternatives(&unexpected)),
+                    (true, true) => "Unexpected input".to_owned(),
+                };
+                (message, expected)
+            }
+            ErrorVariant::CustomError { message } => (message.clone(), Vec::new()),
+        };
+
+        SyntaxError {
+            message,
+            expected,
+            label: found,
+            span: Span {
+                start: Position::new(start),
+                end: Position::new(end),
+            },
+        }
+    }
+}
+
+/// Returns human readable name of the grammar rule
+pub fn rule_human_display(rule: Rule) -> &'static str {
+    match rule {
+        Rule::EOI => "end of input",
+        Rule::WHITESPACE => "whitespace",
+        Rule::COMMENT => "comment",
+        Rule::line_comment => "line comment",
+        Rule::block_comment => "block comment",
+        Rule::integer => "integer",
+        Rule::bool => "boolean",
+        Rule::identifier => "identifier",
+        Rule::literal_newline => "newline",
+        Rule::escape_sequence => "escape sequence",
+        Rule::string_literal_segment | Rule::string_segment => "string content",
+        Rule::text_literal_segment | Rule::text_segment => "text content",
+        Rule::format_spec => "format specifier",
+        Rule::format_size => "format width or precision",
+        Rule::variable_interpolation => "variable interpolation",
+        Rule::message_key => "message key",
+        Rule::message_interpolation => "message interpolation",
+        Rule::string => "string",
+        Rule::text => "text",
+        Rule::raw_text_content => "raw text content",
+        Rule::raw_text => "raw text",
+        Rule::value => "value",
+        Rule::component_name => "component name",
+        Rule::default_property => "default property",
+        Rule::attribute_name => "attribute name",
+        Rule::named_property => "named property",
+        Rule::flag_property => "flag",
+        Rule::negated_flag_property => "negated flag",
+        Rule::property => "property",
+        Rule::properties_list => "properties",
+        Rule::properties => "properties",
+        Rule::children => "children",
+        Rule::component => "component",
+        Rule::inline_component_name => "inline component name",
+        Rule::inline_properties => "properties",
+        Rule::inline_component => "inline component",
+        Rule::ty => "type",
+        Rule::default_property_definition => "default property definition",
+        Rule::text_property_definition => "text property definition",
+        Rule::named_property_definition => "named property definition",
+        Rule::property_definition => "property definition",
+        Rule::properties_definition_list | Rule::properties_definition => "properties definition",
+        Rule::component_definition => "component definition",
+        Rule::constant => "constant",
+        Rule::module_item => "component or definition",
+        Rule::module => "module",
+    }
+}
+
+fn human_rules(rules: &[Rule]) -> Vec<String> {
+    let mut names = Vec::<String>::new();
+    for name in rules.iter().copied().map(rule_human_display) {
+        if !names.iter().any(|existing| existing == name) {
+            names.push(name.to_owned());
+        }
+    }
+
+    names
+}
+
+fn join_alternatives(names: &[String]) -> String {
+    match names {
+        [] => String::new(),
+        [name] => name.clone(),
+        [rest @ .., last] => format!("{} or {last}", rest.join(", ")),
+    }
+}
@@ -0,0 +1,558 @@
+use crate::ast::*;
+use crate::span::{Position, Span};
+
+/// Maximum line width, after which properties are split into multiple lines
+const MAX_WIDTH: usize = 100;
+/// String used for a single level of indentation
+const INDENT: &str = "    ";
+
+/// Represents comment in the source code
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Comment {
+    pub span: Span,
+    /// Comment text including leading `"//"`, but without newline,
+    /// or block comment including `"/*"` and `"*/"`
+    pub text: String,
+}
+
+impl Comment {
+    /// Whether the comment is a block comment, that isn't closed before the end of the code
+    pub fn is_unterminated(&self) -> bool {
+        self.text.starts_with("/*") && block_comment_length(&self.text) > self.text.len()
+    }
+}
+
+/// Collects all comments from the given code.
+///
+/// Comment markers inside strings, text and raw text are ignored.
+pub fn collect_comments(code: &str) -> Vec<Comment> {
+    let mut comments = Vec::new();
+    let mut chars = code.char_indices().peekable();
+
+    while let Some((start, char)) = chars.next() {
+        let closing = match char {
+            '"' => '"',
+            '(' => ')',
+            '/' if chars.next_if(|(_, char)| *char == '/').is_some() => {
+                let mut end = start + 2;
+                while let Some((index, char)) = chars.next_if(|(_, char)| *char != '\n') {
+                    end = index + char.len_utf8();
+                }
+                let text = code[start..end].trim_end().to_owned();
+
+                comments.push(Comment {
+                    span: Span {
+                        start: Position::new(start),
+                        end: Position::new(start + text.len()),
+                    },
+                    text,
+                });
+                continue;
+            }
+            '/' if chars.next_if(|(_, char)| *char == '*').is_some() => {
+                let length = block_comment_length(&code[start..]).min(code.len() - start);
+                while chars
+                    .next_if(|(index, _)| *index < start + length)
+                    .is_some()
+                {}
+
+                comments.push(Comment {
+                    span: Span {
+                        start: Position::new(start),
+                        end: Position::new(start + length),
+                    },
+                    text: code[start..start + length].to_owned(),
+                });
+                continue;
+            }
+            '`' => {
+                let mut fence = 1;
+                while chars.next_if(|(_, char)| *char == '`').is_some() {
+                    fence += 1;
+                }
+                let mut run = 0;
+                for (_, char) in chars.by_ref() {
+                    run = if char == '`' { run + 1 } else { 0 };
+                    if run == fence {
+                        break;
+                    }
+                }
+                continue;
+            }
+            _ => continue,
+        };
+
+        // Text can contain inline components, which have their own text
+        let mut depth = 0;
+        let mut is_inline_pending = false;
+        while let Some((_, char)) = chars.next() {
+            if char == '\\' {
+                chars.next();
+            } else if closing == ')' && char == '$' {
+                is_inline_pending = chars.next_if(|(_, char)| *char == '{').is_none();
+            } else if is_inline_pending && char == '"' {
+                while let Some((_, char)) = chars.next() {
+                    if char == '\\' {
+                        chars.next();
+                    } else if char == '"' {
+                        break;
+                    }
+                }
+            } else if is_inline_pending && char == '(' {
+                is_inline_pending = false;
+                depth += 1;
+            } else if char == closing && depth > 0 {
+                depth -= 1;
+            } else if char == closing {
+                break;
+            }
+        }
+    }
+
+    comments
+}
+
+/// Returns length of the block comment at the start of the code, including nested comments.
+/// Length of unterminated comment is past the end of the code
+pub(crate) fn block_comment_length(code: &str) -> usize {
+    let bytes = code.as_bytes();
+    let mut depth = 0;
+    let mut index = 0;
+    while index < bytes.len() {
+        match &bytes[index..] {
+            [b'/', b'*', ..] => {
+                depth += 1;
+                index += 2;
+            }
+            [b'*', b'/', ..] => {
+                depth -= 1;
+                index += 2;
+                if depth == 0 {
+                    return index;
+                }
+            }
+            _ => index += 1,
+        }
+    }
+
+    bytes.len() + 1
+}
+
+/// Formatter that turns AST back into canonical MarkerML source code.
+///
+/// Original code is used to preserve comments and blank lines between items.
+pub struct Formatter<'a> {
+    code: &'a str,
+    comments: Vec<Comment>,
+    next_comment: usize,
+    output: String,
+    indent: usize,
+}
+
+impl<'a> Formatter<'a> {
+    /// Creates new instance for the given code
+    pub fn new(code: &'a str) -> Self {
+        Formatter {
+            code,
+            comments: collect_comments(code),
+            next_comment: 0,
+            output: String::new(),
+            indent: 0,
+        }
+    }
+
+    /// Formats module that was parsed from the stored code
+    pub fn format(mut self, module: &Module<Span>) -> String {
+        self.write_block(
+            &module.items,
+            None,
+            self.code.len(),
+            Self::write_module_item,
+        );
+        if !self.output.is_empty() {
+            self.output.push('\n');
+        }
+
+        self.output
+    }
+
+    /// Writes items of a module or children list, one per line,
+    /// along with all comments located before the `end` position
+    fn write_block<T: Spanned>(
+        &mut self,
+        items: &[T],
+        start: Option<usize>,
+        end: usize,
+        write: fn(&mut Self, &T),
+    ) {
+        let mut previous_end = start;
+        for item in items {
+            let span = item.span();
+            self.write_comments(&mut previous_end, span.start.offset());
+            self.start_line(previous_end, span.start.offset());
+            write(self, item);
+            previous_end = Some(self.content_end(span));
+        }
+        self.write_comments(&mut previous_end, end);
+    }
+
+    /// Returns end position of the item, excluding trailing
+    /// whitespaces and comments, that are included in its span
+    fn content_end(&self, span: &Span) -> usize {
+        let mut end = span.end.offset();
+        loop {
+            end = span.start.offset() + self.code[span.start.offset()..end].trim_end().len();
+            match self
+                .comments
+                .iter()
+                .find(|comment| comment.span.end.offset() == end)
+            {
+                Some(comment) if comment.span.start.offset() >= span.start.offset() => {
+                    end = comment.span.start.offset()
+                }
+                _ => return end,
+            }
+        }
+    }
+
+    /// Writes all not yet written comments located before the `end` position.
+    /// Comment that is on the same line as previous item is kept there.
+    fn write_comments(&mut self, previous_end: &mut Option<usize>, end: usize) {
+        while let Some(comment) = self.comments.get(self.next_comment) {
+            let start = comment.span.start.offset();
+            if start >= end {
+                break;
+            }
+
+            let is_trailing = previous_end
+                .and_then(|previous_end| self.code.get(previous_end..start))
+                .is_some_and(|gap| !gap.contains('\n'));
+            if is_trailing {
+                self.output.push(' ');
+            } else {
+                self.start_line(*previous_end, start);
+            }
+
+            let comment = &self.comments[self.next_comment];
+            self.output.push_str(&comment.text);
+            *previous_end = Some(comment.span.end.offset());
+            self.next_comment += 1;
+        }
+    }
+
+    /// Begins new indented line, keeping single blank line
+    /// if it was present in the original code
+    fn start_line(&mut self, previous_end: Option<usize>, start: usize) {
+        let is_block_start = self.output.is_empty() || self.output.ends_with('{');
+        if !self.output.is_empty() && !self.output.ends_with('\n') {
+            self.output.push('\n');
+        }
+
+        let has_blank_line = !is_block_start
+            && previous_end
+                .and_then(|previous_end| self.code.get(previous_end..start))
+                .is_some_and(|gap| gap.matches('\n').count() > 1);
+        if has_blank_line {
+            self.output.push('\n');
+        }
+
+        self.output.push_str(&INDENT.repeat(self.indent));
+    }
+
+    fn write_module_item(&mut self, item: &ModuleItem<Span>) {
+        match item {
+            ModuleItem::Constant(constant) => self.write_constant(constant),
+            ModuleItem::Component(component) => self.write_component(component),
+            ModuleItem::ComponentDefinition(def) => self.write_component_definition(def),
+        }
+    }
+
+    fn write_constant(&mut self, constant: &Constant<Span>) {
+        self.output.push_str("let ");
+        self.output.push_str(constant.name.as_str());
+        self.output.push_str(" = ");
+        self.output.push_str(&format_value(&constant.value));
+    }
+
+    fn write_component(&mut self, component: &Component<Span>) {
+        self.output.push_str(component.name.as_str());
+        if let Some(properties) = &component.properties {
+            let mut items = Vec::new();
+            items.extend(properties.default.as_ref().map(format_value));
+            items.extend(properties.properties.iter().map(format_property));
+            self.write_list(&items);
+        }
+        if let Some(children) = &component.children {
+            self.write_children(children);
+        }
+        if let Some(text) = &component.text {
+            if text.raw {
+                self.write_raw_text(&format_raw_segments(&text.segments));
+            } else {
+                self.output.push('(');
+                self.write_text(&format_segments(&text.segments, ')'));
+                self.output.push(')');
+            }
+        }
+    }
+
+    /// Writes text, indenting its lines after the first one deeper than the component.
+    /// Line that closes the text is indented as the component
+    fn write_text(&mut self, content: &str) {
+        let mut lines = content.split('\n');
+        self.output.push_str(lines.next().unwrap_or_default());
+        let mut lines = lines.peekable();
+        while let Some(line) = lines.next() {
+            self.output.push('\n');
+            if !line.is_empty() {
+                self.output.push_str(&INDENT.repeat(self.indent + 1));
+                self.output.push_str(line);
+            } else if lines.peek().is_none() {
+                self.output.push_str(&INDENT.repeat(self.indent));
+            }
+        }
+    }
+
+    /// Writes raw text, enclosing it in more backticks than there are in a row inside of it.
+    /// Multiline text is placed on separate lines, indented deeper than the component
+    fn write_raw_text(&mut self, content: &str) {
+        let longest_run = content
+            .split(|char| char != '`')
+            .map(str::len)
+            .max()
+            .unwrap_or(0);
+        let is_multiline =
+            content.contains('\n') || content.starts_with('`') || content.ends_with('`');
+        let count = if is_multiline {
+            (longest_run + 1).max(3)
+        } else {
+            longest_run + 1
+        };
+        let fence = "`".repeat(count);
+
+        self.output.push_str(&fence);
+        if is_multiline {
+            let indent = INDENT.repeat(self.indent + 1);
+            for line in content.split('\n') {
+                self.output.push('\n');
+                if !line.is_empty() {
+                    self.output.push_str(&indent);
+                    self.output.push_str(line);
+                }
+            }
+            self.output.push('\n');
+            self.output.push_str(&INDENT.repeat(self.indent));
+        } else {
+            self.output.push_str(content);
+        }
+        self.output.push_str(&fence);
+    }
+
+    fn write_component_definition(&mut self, def: &ComponentDefinition<Span>) {
+        self.output.push_str("component ");
+        self.output.push_str(def.name.as_str());
+        if let Some(properties) = &def.properties {
+            let items = properties
+                .properties
+                .iter()
+                .map(format_property_definition)
+                .collect::<Vec<_>>();
+            self.write_list(&items);
+        }
+        if let Some(children) = &def.children {
+            self.write_children(children);
+        }
+    }
+
+    /// Writes comma-separated list in square brackets.
+    /// List is split into multiple lines, if it doesn't fit into a single line.
+    fn write_list(&mut self, items: &[String]) {
+        let line = items.join(", ");
+        let line_start = self.output.rfind('\n').map_or(0, |index| index + 1);
+        if self.output.len() - line_start + line.len() + 2 <= MAX_WIDTH {
+            self.output.push('[');
+            self.output.push_str(&line);
+            self.output.push(']');
+
+            return;
+        }
+
+        let indent = INDENT.repeat(self.indent);
+        self.output.push('[');
+        for (index, item) in items.iter().enumerate() {
+            self.output.push('\n');
+            self.output.push_str(&indent);
+            self.output.push_str(INDENT);
+            self.output.push_str(item);
+            if index + 1 < items.len() {
+                self.output.push(',');
+            }
+        }
+        self.output.push('\n');
+        self.output.push_str(&indent);
+        self.output.push(']');
+    }
+
+    fn write_children(&mut self, children: &ComponentChildren<Span>) {
+        self.output.push_str(" {");
+        let length = self.output.len();
+
+        self.indent += 1;
+        self.write_block(
+            &children.children,
+            Some(children.span.start.offset() + 1),
+            children.span.end.offset().saturating_sub(1),
+            Self::write_component,
+        );
+        self.indent -= 1;
+
+        if self.output.len() != length {
+            self.output.push('\n');
+            self.output.push_str(&INDENT.repeat(self.indent));
+        }
+        self.output.push('}');
+    }
+}
+
+/// Helper trait to get span of block items
+trait Spanned {
+    fn span(&self) -> &Span;
+}
+
+impl Spanned for ModuleItem<Span> {
+    fn span(&self) -> &Span {
+        match self {
+            ModuleItem::Constant(constant) => &constant.span,
+            ModuleItem::Component(component) => &component.span,
+            ModuleItem::ComponentDefinition(def) => &def.span,
+        }
+    }
+}
+
+impl Spanned for Component<Span> {
+    fn span(&self) -> &Span {
+        &self.span
+    }
+}
+
+fn format_property<SpanT>(property: &Property<SpanT>) -> String {
+    match &property.kind {
+        PropertyKind::KeyValue { key, value } => {
+            format!("{} = {}", key.as_str(), format_value(value))
+        }
+        PropertyKind::Flag { key } => key.as_str().to_owned(),
+        PropertyKind::NegatedFlag { key } => format!("!{}", key.as_str()),
+    }
+}
+
+fn format_property_definition<SpanT>(property: &PropertyDefinition<SpanT>) -> String {
+    match &property.kind {
+        PropertyDefinitionKind::Text(def) => format!("text {}", def.name.as_str()),
+        PropertyDefinitionKind::Default(def) => {
+            format!("default {}", format_named_property_definition(def))
+        }
+        PropertyDefinitionKind::Named(def) => format_named_property_definition(def),
+    }
+}
+
+fn format_named_property_definition<SpanT>(def: &NamedPropertyDefinition<SpanT>) -> String {
+    let mut result = format!("{}: {}", def.name.as_str(), format_type(&def.ty.kind));
+    if let Some(value) = &def.default_value {
+        result.push_str(" = ");
+        result.push_str(&format_value(value));
+    }
+
+    result
+}
+
+fn format_value<SpanT>(value: &Value<SpanT>) -> String {
+    match &value.kind {
+        ValueKind::String(string) => format!("\"{}\"", format_segments(&string.segments, '"')),
+        ValueKind::Integer(value) => value.to_string(),
+        ValueKind::Bool(value) => value.to_string(),
+        ValueKind::Variable(identifier) => format!("${{{}}}", identifier.as_str()),
+    }
+}
+
+/// Formats segments of raw text, which are kept verbatim
+fn format_raw_segments<SpanT>(segments: &[InterpolationSegment<SpanT>]) -> String {
+    segments
+        .iter()
+        .filter_map(|segment| match &segment.kind {
+            InterpolationSegmentKind::Literal(literal) => Some(literal.as_str()),
+            _ => None,
+        })
+        .collect()
+}
+
+/// Formats segments of string or text, escaping the closing delimiter in literals
+fn format_segments<SpanT>(segments: &[InterpolationSegment<SpanT>], delimiter: char) -> String {
+    segments
+        .iter()
+        .map(|segment| match &segment.kind {
+            InterpolationSegmentKind::Literal(literal) => escape_literal(literal, delimiter),
+            InterpolationSegmentKind::Variable(identifier) => {
+                format!("${{{}}}", identifier.as_str())
+            }
+            InterpolationSegmentKind::Formatted(variable) => {
+                format!("${{{}:{}}}", variable.name.as_str(), variable.spec)
+            }
+            InterpolationSegmentKind::Message(key) => format!("${{t:{key}}}"),
+            InterpolationSegmentKind::Component(component) => format_inline_component(component),
+            InterpolationSegmentKind::Newline => "\n".to_owned(),
+        })
+        .collect()
+}
+
+/// Formats component inside the text, keeping it on the same line
+fn format_inline_component<SpanT>(component: &Component<SpanT>) -> String {
+    let mut result = format!("${}", component.name.as_str());
+    if let Some(properties) = &component.properties {
+        let items = properties
+            .default
+            .iter()
+            .map(format_value)
+            .chain(properties.properties.iter().map(format_property))
+            .collect::<Vec<_>>();
+        result.push('[');
+        result.push_str(&items.join(", "));
+        result.push(']');
+    }
+    if let Some(text) = &component.text {
+        result.push('(');
+        result.push_str(&format_segments(&text.segments, ')'));
+        result.push(')');
+    }
+
+    result
+}
+
+fn escape_literal(literal: &str, delimiter: char) -> String {
+    let mut result = String::with_capacity(literal.len());
+    for char in literal.chars() {
+        match char {
+            '\n' => result.push_str("\\n"),
+            '\\' | '$' => {
+                result.push('\\');
+                result.push(char);
+            }
+            _ if char == delimiter => {
+                result.push('\\');
+                result.push(char);
+            }
+            _ => result.push(char),
+        }
+    }
+
+    result
+}
+
+fn format_type(ty: &TypeKind) -> &'static str {
+    match ty {
+        TypeKind::String => "string",
+        TypeKind::Integer => "int",
+        TypeKind::Bool => "bool",
+        TypeKind::Slot => "slot",
+        TypeKind::SlotList => "slot[]",
+    }
+}
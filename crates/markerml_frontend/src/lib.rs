@@ -6,15 +6,27 @@
 
 pub mod ast;
 pub mod ast_span_helpers;
+pub mod error;
+pub mod format;
 pub mod parser;
 pub mod span;
+pub mod tokens;
 
+/// Syntax error with labeled span
+pub use error::SyntaxError;
 /// Parser error
 pub use parser::ParserError;
 /// Source code span. Used for error reporting
-pub use span::Span;
+pub use span::{SourceMap, Span};
 
 /// Parses given code into AST
 pub fn parse(code: &str) -> Result<ast::Module<Span>, Box<ParserError>> {
     parser::parse(code)
 }
+
+/// Formats given code into canonical form, preserving comments
+pub fn format(code: &str) -> Result<String, Box<ParserError>> {
+    let module = parser::parse(code)?;
+
+    Ok(format::Formatter::new(code).format(&module))
+}
@@ -1,4 +1,5 @@
 use crate::ast::*;
+use crate::format::collect_comments;
 use crate::span;
 use pest::error::{Error, ErrorVariant};
 use pest::{iterators::Pair, Parser, Position};
@@ -15,9 +16,27 @@ pub struct MarkermlParser;
 
 type Result<T> = std::result::Result<T, Box<ParserError>>;
 
+/// Largest width and precision of the format specifier, so that the padding stays reasonable
+pub const MAX_FORMAT_SIZE: usize = 1024;
+
 /// Parses given code into an AST
 pub fn parse(code: &str) -> Result<Module<Span>> {
-    let pairs = MarkermlParser::parse(Rule::module, code)?;
+    check_source_len(code)?;
+    let pairs = MarkermlParser::parse(Rule::module, code).map_err(|err| {
+        // Unterminated block comment makes the rest of the code look like garbage,
+        // so it's reported instead of whatever the parser stumbled upon
+        let unterminated = collect_comments(code)
+            .into_iter()
+            .find(|comment| comment.is_unterminated())
+            .and_then(|comment| {
+                let start = comment.span.start.offset();
+                pest::Span::new(code, start, start + 2)
+            });
+        match unterminated {
+            Some(span) => create_error("Unterminated block comment".to_owned(), span),
+            None => Box::new(err),
+        }
+    })?;
     let position = Position::from_start(pairs.as_str());
     let pair = pairs.into_iter().next().ok_or_else(|| {
         ParserError::new_from_pos(
@@ -37,6 +56,7 @@ fn parse_module(pair: Pair<Rule>) -> Result<Module<Span>> {
         .into_inner()
         .map(|pair| {
             Ok(match pair.as_rule() {
+                Rule::constant => Some(ModuleItem::Constant(parse_constant(pair)?)),
                 Rule::component => Some(ModuleItem::Component(parse_component(pair)?)),
                 Rule::component_definition => Some(ModuleItem::ComponentDefinition(
                     parse_component_definition(pair)?,
@@ -54,6 +74,30 @@ fn parse_module(pair: Pair<Rule>) -> Result<Module<Span>> {
     })
 }
 
+fn parse_constant(pair: Pair<Rule>) -> Result<Constant<Span>> {
+    let span = pair.as_span();
+    let mut name = None;
+    let mut value = None;
+
+    for pair in pair.into_inner() {
+        match pair.as_rule() {
+            Rule::identifier => {
+                name = Some(parse_identifier(pair)?);
+            }
+            Rule::value => {
+                value = Some(parse_value(pair)?);
+            }
+            _ => {}
+        }
+    }
+
+    Ok(Constant {
+        span: span.into(),
+        name: name.ok_or_else(|| create_error("Missing name in constant".to_owned(), span))?,
+        value: value.ok_or_else(|| create_error("Missing value in constant".to_owned(), span))?,
+    })
+}
+
 fn parse_component(pair: Pair<Rule>) -> Result<Component<Span>> {
     let span = pair.as_span();
     let mut name = None;
@@ -66,8 +110,25 @@ fn parse_component(pair: Pair<Rule>) -> Result<Component<Span>> {
             Rule::component_name => {
                 name = Some(parse_component_name(pair)?);
             }
+            Rule::inline_component_name => {
+                let span = pair.as_span();
+                let pair = pair.into_inner().next().ok_or_else(|| {
+                    create_error("Missing name of the inline component".to_owned(), span)
+                })?;
+                name = Some(parse_component_name(pair)?);
+            }
             Rule::properties => {
-                properties = Some(parse_properties(pair)?);
+                properties = Some(Box::new(parse_properties(pair)?));
+            }
+            Rule::inline_properties => {
+                let span = pair.as_span();
+                let pair = pair.into_inner().next().ok_or_else(|| {
+                    create_error(
+                        "Missing properties of the inline component".to_owned(),
+                        span,
+                    )
+                })?;
+                properties = Some(Box::new(parse_properties(pair)?));
             }
             Rule::children => {
                 children = Some(parse_children(pair)?);
@@ -75,6 +136,9 @@ fn parse_component(pair: Pair<Rule>) -> Result<Component<Span>> {
             Rule::text => {
                 text = Some(parse_text(pair)?);
             }
+            Rule::raw_text => {
+                text = Some(parse_raw_text(pair)?);
+            }
             _ => {}
         }
     }
@@ -137,6 +201,10 @@ fn parse_property(pair: Pair<Rule>) -> Result<Property<Span>> {
     let kind = match pair.as_rule() {
         Rule::named_property => parse_named_property(pair)?,
         Rule::flag_property => parse_flag_property(pair)?,
+        Rule::negated_flag_property => match parse_flag_property(pair)? {
+            PropertyKind::Flag { key } => PropertyKind::NegatedFlag { key },
+            kind => kind,
+        },
         rule => {
             return Err(create_error(
                 format!("Unexpected {rule:?} in property"),
@@ -155,7 +223,7 @@ fn parse_named_property(pair: Pair<Rule>) -> Result<PropertyKind<Span>> {
 
     for pair in pair.into_inner() {
         match pair.as_rule() {
-            Rule::identifier => {
+            Rule::identifier | Rule::attribute_name => {
                 key = Some(parse_identifier(pair)?);
             }
             Rule::value => {
@@ -238,7 +306,7 @@ fn parse_component_definition(pair: Pair<Rule>) -> Result<ComponentDefinition<Sp
                 name = Some(parse_identifier(pair)?);
             }
             Rule::properties_definition => {
-                properties = Some(parse_properties_definition(pair)?);
+                properties = Some(Box::new(parse_properties_definition(pair)?));
             }
             Rule::children => {
                 children = Some(parse_children(pair)?);
@@ -363,17 +431,26 @@ fn parse_value(pair: Pair<Rule>) -> Result<Value<Span>> {
             let string_value = parse_string(pair)?;
             ValueKind::String(string_value)
         }
-        Rule::integer => {
-            let int_value: i64 = pair.as_str().parse().unwrap();
-            ValueKind::Integer(int_value)
-        }
+        Rule::integer => ValueKind::Integer(parse_integer(pair)?),
         Rule::bool => {
             let bool_value: bool = pair.as_str().parse().unwrap();
             ValueKind::Bool(bool_value)
         }
-        Rule::identifier => {
-            let identifier = parse_identifier(pair)?;
-            ValueKind::Variable(identifier)
+        Rule::variable_interpolation => {
+            let mut inner = pair.into_inner();
+            let ident = inner.next().ok_or_else(|| {
+                create_error(
+                    "Missing identifier in variable interpolation".to_owned(),
+                    span,
+                )
+            })?;
+            if let Some(spec) = inner.next() {
+                return Err(create_error(
+                    "Format specifier can only be used inside strings and text".to_owned(),
+                    spec.as_span(),
+                ));
+            }
+            ValueKind::Variable(parse_identifier(ident)?)
         }
         rule => return Err(create_error(format!("Unexpected {rule:?} in value"), span)),
     };
@@ -404,9 +481,56 @@ fn parse_text(pair: Pair<Rule>) -> Result<Text<Span>> {
     Ok(Text {
         span: span.into(),
         segments,
+        raw: false,
+    })
+}
+
+fn parse_raw_text(pair: Pair<Rule>) -> Result<Text<Span>> {
+    let span = pair.as_span();
+    let content = pair
+        .into_inner()
+        .next()
+        .ok_or_else(|| create_error("Missing content in raw text".to_owned(), span))?;
+    let literal = InterpolationSegmentKind::Literal(dedent(content.as_str()));
+
+    Ok(Text {
+        span: span.into(),
+        segments: vec![literal.spanned(content.as_span().into())],
+        raw: true,
     })
 }
 
+/// Removes indentation, that is common for all lines of the multiline raw text,
+/// along with the blank first and last lines, so the text can be indented with the code
+fn dedent(content: &str) -> String {
+    if !content.contains('\n') {
+        return content.to_owned();
+    }
+
+    let mut lines = content
+        .split('\n')
+        .map(|line| line.strip_suffix('\r').unwrap_or(line))
+        .collect::<Vec<_>>();
+    if lines.first().is_some_and(|line| line.trim().is_empty()) {
+        lines.remove(0);
+    }
+    if lines.last().is_some_and(|line| line.trim().is_empty()) {
+        lines.pop();
+    }
+    let indent = lines
+        .iter()
+        .filter(|line| !line.trim().is_empty())
+        .map(|line| line.len() - line.trim_start_matches([' ', '\t']).len())
+        .min()
+        .unwrap_or(0);
+
+    lines
+        .iter()
+        .map(|line| line.get(indent..).unwrap_or_default())
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
 fn parse_string_interpolation_segment(pair: Pair<Rule>) -> Result<InterpolationSegment<Span>> {
     let span = pair.as_span();
     let pair = pair
@@ -416,15 +540,9 @@ fn parse_string_interpolation_segment(pair: Pair<Rule>) -> Result<InterpolationS
 
     let kind = match pair.as_rule() {
         Rule::string_literal_segment => InterpolationSegmentKind::Literal(pair.as_str().to_owned()),
-        Rule::variable_interpolation => {
-            let ident = pair.into_inner().next().ok_or_else(|| {
-                create_error(
-                    "Missing identifier in string interpolation".to_owned(),
-                    span,
-                )
-            })?;
-            InterpolationSegmentKind::Variable(parse_identifier(ident)?)
-        }
+        Rule::escape_sequence => InterpolationSegmentKind::Literal(parse_escape_sequence(pair)?),
+        Rule::variable_interpolation => parse_variable_interpolation(pair)?,
+        Rule::message_interpolation => InterpolationSegmentKind::Message(parse_message_key(pair)?),
         Rule::literal_newline => InterpolationSegmentKind::Literal(" ".to_owned()),
         rule => {
             return Err(create_error(
@@ -449,14 +567,13 @@ fn parse_text_interpolation_segment(pair: Pair<Rule>) -> Result<InterpolationSeg
 
     let kind = match pair.as_rule() {
         Rule::text_literal_segment => InterpolationSegmentKind::Literal(pair.as_str().to_owned()),
-        Rule::variable_interpolation => {
-            let ident = pair.into_inner().next().ok_or_else(|| {
-                create_error("Missing identifier in text interpolation".to_owned(), span)
-            })?;
-
-            InterpolationSegmentKind::Variable(parse_identifier(ident)?)
+        Rule::escape_sequence => InterpolationSegmentKind::Literal(parse_escape_sequence(pair)?),
+        Rule::variable_interpolation => parse_variable_interpolation(pair)?,
+        Rule::message_interpolation => InterpolationSegmentKind::Message(parse_message_key(pair)?),
+        Rule::literal_newline => InterpolationSegmentKind::Newline,
+        Rule::inline_component => {
+            InterpolationSegmentKind::Component(Box::new(parse_component(pair)?))
         }
-        Rule::literal_newline => InterpolationSegmentKind::Literal(" ".to_owned()),
         rule => {
             return Err(create_error(
                 format!("Unexpected {rule:?} in text interpolation segment"),
@@ -471,6 +588,118 @@ fn parse_text_interpolation_segment(pair: Pair<Rule>) -> Result<InterpolationSeg
     })
 }
 
+fn parse_variable_interpolation(pair: Pair<Rule>) -> Result<InterpolationSegmentKind<Span>> {
+    let span = pair.as_span();
+    let mut inner = pair.into_inner();
+    let ident = inner
+        .next()
+        .ok_or_else(|| create_error("Missing identifier in interpolation".to_owned(), span))?;
+    let name = parse_identifier(ident)?;
+
+    Ok(match inner.next() {
+        Some(spec) => InterpolationSegmentKind::Formatted(Box::new(FormattedVariable {
+            name,
+            spec: parse_format_spec(spec)?,
+        })),
+        None => InterpolationSegmentKind::Variable(name),
+    })
+}
+
+/// Parses `[align][0][width][.precision]`, which the grammar already checked
+fn parse_format_spec(pair: Pair<Rule>) -> Result<FormatSpec> {
+    let span = pair.as_span();
+    let mut spec = pair.as_str();
+    let align = match spec.chars().next() {
+        Some('<') => Some(FormatAlign::Left),
+        Some('^') => Some(FormatAlign::Center),
+        Some('>') => Some(FormatAlign::Right),
+        _ => None,
+    };
+    if align.is_some() {
+        spec = &spec[1..];
+    }
+    let (width, precision) = match spec.split_once('.') {
+        Some((width, precision)) => (width, Some(precision)),
+        None => (spec, None),
+    };
+    let parse_size = |digits: &str| {
+        digits
+            .parse::<usize>()
+            .ok()
+            .filter(|size| *size <= MAX_FORMAT_SIZE)
+            .ok_or_else(|| {
+                create_error(
+                    format!("Width and precision can't be larger than {MAX_FORMAT_SIZE}"),
+                    span,
+                )
+            })
+    };
+
+    Ok(FormatSpec {
+        align,
+        zero_pad: width.len() > 1 && width.starts_with('0'),
+        width: Some(width)
+            .filter(|width| !width.is_empty())
+            .map(parse_size)
+            .transpose()?,
+        precision: precision.map(parse_size).transpose()?,
+    })
+}
+
+fn parse_integer(pair: Pair<Rule>) -> Result<i64> {
+    let literal = pair.as_str().replace('_', "");
+    let (sign, digits) = match literal.strip_prefix('-') {
+        Some(digits) => ("-", digits),
+        None => ("", literal.as_str()),
+    };
+    let (radix, digits) = if let Some(digits) = digits.strip_prefix("0x") {
+        (16, digits)
+    } else if let Some(digits) = digits.strip_prefix("0b") {
+        (2, digits)
+    } else {
+        (10, digits)
+    };
+
+    i64::from_str_radix(&format!("{sign}{digits}"), radix).map_err(|_| {
+        create_error(
+            format!(
+                "Integer {} doesn't fit into the range from {} to {}",
+                pair.as_str(),
+                i64::MIN,
+                i64::MAX
+            ),
+            pair.as_span(),
+        )
+    })
+}
+
+fn parse_escape_sequence(pair: Pair<Rule>) -> Result<String> {
+    let escaped = &pair.as_str()[1..];
+    let char = match escaped {
+        "n" => '\n',
+        "t" => '\t',
+        _ => match escaped.strip_prefix("u{") {
+            Some(code) => u32::from_str_radix(code.trim_end_matches('}'), 16)
+                .ok()
+                .and_then(char::from_u32)
+                .ok_or_else(|| {
+                    create_error(
+                        format!(
+                            "Invalid unicode code point in escape sequence {}",
+                            pair.as_str()
+                        ),
+                        pair.as_span(),
+                    )
+                })?,
+            None => escaped.chars().next().ok_or_else(|| {
+                create_error("Missing escaped character".to_owned(), pair.as_span())
+            })?,
+        },
+    };
+
+    Ok(char.to_string())
+}
+
 fn parse_type(pair: Pair<Rule>) -> Result<Type<Span>> {
     let span = pair.as_span();
     let kind = match pair.as_str() {
@@ -491,7 +720,7 @@ fn parse_type(pair: Pair<Rule>) -> Result<Type<Span>> {
 fn parse_identifier(pair: Pair<Rule>) -> Result<Identifier<Span>> {
     let span = pair.as_span();
     match pair.as_rule() {
-        Rule::identifier => Ok(Identifier {
+        Rule::identifier | Rule::attribute_name => Ok(Identifier {
             span: span.into(),
             name: pair.as_str().to_owned(),
         }),
@@ -502,6 +731,34 @@ fn parse_identifier(pair: Pair<Rule>) -> Result<Identifier<Span>> {
     }
 }
 
+fn parse_message_key(pair: Pair<Rule>) -> Result<String> {
+    let span = pair.as_span();
+    let key = pair
+        .into_inner()
+        .next()
+        .ok_or_else(|| create_error("Missing key in message interpolation".to_owned(), span))?;
+
+    Ok(key.as_str().to_owned())
+}
+
+/// Rejects the code, whose positions don't fit into the spans
+pub(crate) fn check_source_len(code: &str) -> Result<()> {
+    if code.len() <= span::MAX_SOURCE_LEN {
+        return Ok(());
+    }
+
+    Err(Box::new(ParserError::new_from_pos(
+        ErrorVariant::CustomError {
+            message: format!(
+                "Source code is too large: {} bytes, while the maximum is {} bytes",
+                code.len(),
+                span::MAX_SOURCE_LEN
+            ),
+        },
+        Position::from_start(code),
+    )))
+}
+
 fn create_error(message: String, span: pest::Span) -> Box<ParserError> {
     Box::new(ParserError::new_from_span(
         ErrorVariant::CustomError { message },
@@ -1,21 +1,45 @@
 use std::ops::Range;
 
 /// Represents span in the source code
-#[derive(Debug, Clone, Hash, Eq, PartialEq)]
+#[derive(Debug, Clone, Default, Hash, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Span {
     pub start: Position,
     pub end: Position,
 }
 
-/// Represents byte position in the source code
-#[derive(Debug, Clone, Hash, Eq, PartialEq, Ord, PartialOrd)]
-pub struct Position(pub usize);
+/// Maximum length of the source code in bytes, since positions are stored as `u32`
+pub const MAX_SOURCE_LEN: usize = u32::MAX as usize;
+
+/// Represents byte position in the source code.
+/// Stored as `u32` to keep syntax tree nodes compact, so sources are limited to 4 GiB
+#[derive(Debug, Clone, Copy, Default, Hash, Eq, PartialEq, Ord, PartialOrd)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Position(pub u32);
+
+impl Position {
+    /// Creates position from the byte offset.
+    /// Panics if the offset exceeds [`MAX_SOURCE_LEN`], since the parser rejects such sources
+    pub fn new(offset: usize) -> Self {
+        Self::try_new(offset).expect("offset exceeds maximum length of the source code")
+    }
+
+    /// Creates position from the byte offset, unless it exceeds [`MAX_SOURCE_LEN`]
+    pub fn try_new(offset: usize) -> Option<Self> {
+        u32::try_from(offset).ok().map(Position)
+    }
+
+    /// Returns byte offset in the source code
+    pub fn offset(self) -> usize {
+        self.0 as usize
+    }
+}
 
 impl From<Span> for miette::SourceSpan {
     fn from(span: Span) -> Self {
         miette::SourceSpan::from(Range {
-            start: span.start.0,
-            end: span.end.0,
+            start: span.start.offset(),
+            end: span.end.offset(),
         })
     }
 }
@@ -23,8 +47,79 @@ impl From<Span> for miette::SourceSpan {
 impl From<pest::Span<'_>> for Span {
     fn from(span: pest::Span<'_>) -> Self {
         Span {
-            start: Position(span.start()),
-            end: Position(span.end()),
+            start: Position::new(span.start()),
+            end: Position::new(span.end()),
         }
     }
 }
+
+/// One-based line and column in the source code. Column is counted in characters
+#[derive(Debug, Clone, Copy, Default, Hash, Eq, PartialEq, Ord, PartialOrd)]
+pub struct LineColumn {
+    pub line: usize,
+    pub column: usize,
+}
+
+/// Source code with byte offsets of its lines,
+/// which converts byte offsets of the spans into lines and columns
+#[derive(Debug, Clone)]
+pub struct SourceMap<'a> {
+    source: &'a str,
+    starts: Vec<usize>,
+}
+
+impl<'a> SourceMap<'a> {
+    pub fn new(source: &'a str) -> Self {
+        let starts = std::iter::once(0)
+            .chain(source.match_indices('\n').map(|(offset, _)| offset + 1))
+            .collect();
+
+        SourceMap { source, starts }
+    }
+
+    pub fn source(&self) -> &'a str {
+        self.source
+    }
+
+    /// Returns zero-based index of the line, containing the byte offset
+    pub fn line_index(&self, offset: usize) -> usize {
+        self.starts.partition_point(|&start| start <= offset) - 1
+    }
+
+    /// Returns byte range of the line with the given zero-based index, excluding the newline.
+    /// Range is empty past the end of the source
+    pub fn line_range(&self, index: usize) -> Range<usize> {
+        let Some(&start) = self.starts.get(index) else {
+            return self.source.len()..self.source.len();
+        };
+        let end = self
+            .starts
+            .get(index + 1)
+            .map_or(self.source.len(), |next| next - 1);
+
+        start..end
+    }
+
+    /// Returns line and column of the byte offset
+    pub fn line_column(&self, offset: usize) -> LineColumn {
+        let line = self.line_index(offset);
+        let start = self.starts[line];
+        let column = self
+            .source
+            .get(start..offset)
+            .map_or(offset - start, |text| text.chars().count());
+
+        LineColumn {
+            line: line + 1,
+            column: column + 1,
+        }
+    }
+
+    /// Returns lines and columns of the start and the end of the span
+    pub fn span_location(&self, span: &Span) -> (LineColumn, LineColumn) {
+        (
+            self.line_column(span.start.offset()),
+            self.line_column(span.end.offset()),
+        )
+    }
+}
@@ -0,0 +1,152 @@
+use crate::format::block_comment_length;
+use crate::parser::{check_source_len, MarkermlParser, ParserError, Rule};
+use crate::span::{Position, Span};
+use pest::iterators::{FlatPairs, Pair};
+use pest::Parser;
+use std::collections::VecDeque;
+use std::fmt;
+
+/// Represents single token of the source code
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Token {
+    pub span: Span,
+    pub kind: TokenKind,
+    pub text: String,
+}
+
+/// Represents kind of the token
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TokenKind {
+    /// Terminal grammar rule, such as identifier, integer or string segment
+    Rule(Rule),
+    /// Keyword, such as `component`, `default` or `text`
+    Keyword,
+    /// Punctuation, such as brackets, quotes or equals sign
+    Punctuation,
+    /// Line or block comment
+    Comment,
+}
+
+impl fmt::Display for TokenKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TokenKind::Rule(rule) => write!(f, "{rule:?}"),
+            TokenKind::Keyword => write!(f, "keyword"),
+            TokenKind::Punctuation => write!(f, "punctuation"),
+            TokenKind::Comment => write!(f, "comment"),
+        }
+    }
+}
+
+/// Splits given code into the stream of tokens.
+///
+/// Terminal grammar rules are reported as is,
+/// while the rest of the code is split into keywords, punctuation and comments.
+/// Whitespaces are skipped.
+pub fn tokenize(code: &str) -> Result<Vec<Token>, Box<ParserError>> {
+    Ok(tokens(code)?.collect())
+}
+
+/// Returns lazy iterator over the tokens of the given code, same as [`tokenize`].
+///
+/// Tokens are created on demand, so only the text between two terminal rules is buffered.
+/// Note that the code is still parsed as a whole before the first token is returned
+pub fn tokens(code: &str) -> Result<Tokens<'_>, Box<ParserError>> {
+    check_source_len(code)?;
+    let pairs = MarkermlParser::parse(Rule::module, code)?;
+
+    Ok(Tokens {
+        code,
+        pairs: pairs.flatten(),
+        position: 0,
+        buffer: VecDeque::new(),
+        is_finished: false,
+    })
+}
+
+/// Lazy iterator over the tokens of the code
+pub struct Tokens<'a> {
+    code: &'a str,
+    pairs: FlatPairs<'a, Rule>,
+    /// End of the last tokenized terminal rule
+    position: usize,
+    /// Tokens of the gap before the next terminal rule, followed by the rule itself
+    buffer: VecDeque<Token>,
+    is_finished: bool,
+}
+
+impl Iterator for Tokens<'_> {
+    type Item = Token;
+
+    fn next(&mut self) -> Option<Token> {
+        while self.buffer.is_empty() && !self.is_finished {
+            let Some(pair) = self.pairs.next() else {
+                tokenize_gap(self.code, self.position, self.code.len(), &mut self.buffer);
+                self.is_finished = true;
+                break;
+            };
+            if pair.as_rule() == Rule::EOI || !is_terminal(&pair) {
+                continue;
+            }
+
+            let span = pair.as_span();
+            tokenize_gap(self.code, self.position, span.start(), &mut self.buffer);
+            self.buffer.push_back(Token {
+                span: span.into(),
+                kind: TokenKind::Rule(pair.as_rule()),
+                text: pair.as_str().to_owned(),
+            });
+            self.position = span.end();
+        }
+
+        self.buffer.pop_front()
+    }
+}
+
+fn is_terminal(pair: &Pair<Rule>) -> bool {
+    pair.clone().into_inner().next().is_none()
+}
+
+/// Splits code between terminal rules into keywords, punctuation and comments
+fn tokenize_gap(code: &str, start: usize, end: usize, tokens: &mut VecDeque<Token>) {
+    let gap = &code[start..end];
+    let mut chars = gap.char_indices().peekable();
+
+    while let Some((index, char)) = chars.next() {
+        let mut length = char.len_utf8();
+        let kind = match char {
+            _ if char.is_whitespace() => continue,
+            '/' if chars.next_if(|(_, char)| *char == '/').is_some() => {
+                length = gap[index..].find(['\r', '\n']).unwrap_or(gap.len() - index);
+                while chars.next_if(|(next, _)| *next < index + length).is_some() {}
+                TokenKind::Comment
+            }
+            '/' if chars.next_if(|(_, char)| *char == '*').is_some() => {
+                length = block_comment_length(&gap[index..]).min(gap.len() - index);
+                while chars.next_if(|(next, _)| *next < index + length).is_some() {}
+                TokenKind::Comment
+            }
+            '$' if chars.next_if(|(_, char)| *char == '{').is_some() => {
+                length += 1;
+                TokenKind::Punctuation
+            }
+            _ if char.is_ascii_alphabetic() => {
+                while let Some((_, char)) = chars.next_if(|(_, char)| char.is_ascii_alphabetic()) {
+                    length += char.len_utf8();
+                }
+                TokenKind::Keyword
+            }
+            _ => TokenKind::Punctuation,
+        };
+
+        let start = start + index;
+        tokens.push_back(Token {
+            span: Span {
+                start: Position::new(start),
+                end: Position::new(start + length),
+            },
+            kind,
+            text: code[start..start + length].to_owned(),
+        });
+    }
+}
@@ -0,0 +1,247 @@
+#[cfg(test)]
+mod test {
+    use anyhow::Result;
+    use markerml_frontend::ast_span_helpers::MapSpan;
+
+    fn format(code: &str) -> Result<String> {
+        let formatted = markerml_frontend::format(code)?;
+
+        Ok(formatted)
+    }
+
+    fn assert_same_ast(a: &str, b: &str) -> Result<()> {
+        let a = markerml_frontend::parse(a)?.map_span(&mut |_| ());
+        let b = markerml_frontend::parse(b)?.map_span(&mut |_| ());
+        assert_eq!(a, b);
+
+        Ok(())
+    }
+
+    #[test]
+    fn canonical_spacing() -> Result<()> {
+        let code =
+            "box [ vertical ,x_align=\"center\" , ] {header [ 1 ] (Hello)   paragraph(Text)}";
+        let res = r#"box[vertical, x_align = "center"] {
+    header[1](Hello)
+    paragraph(Text)
+}
+"#;
+
+        assert_eq!(format(code)?, res);
+        assert_same_ast(code, res)?;
+
+        Ok(())
+    }
+
+    #[test]
+    fn empty_children() -> Result<()> {
+        let code = "box {\n\n}\nbox[]";
+        let res = "box {}\nbox[]\n";
+
+        assert_eq!(format(code)?, res);
+
+        Ok(())
+    }
+
+    #[test]
+    fn component_definition() -> Result<()> {
+        let code = r#"
+            component custom[default a: string, text b,
+                c: int = 5] { @(${b}) }
+        "#;
+        let res = r#"component custom[default a: string, text b, c: int = 5] {
+    @(${b})
+}
+"#;
+
+        assert_eq!(format(code)?, res);
+        assert_same_ast(code, res)?;
+
+        Ok(())
+    }
+
+    #[test]
+    fn message_interpolation() -> Result<()> {
+        let code = "#[ \"${t:links.home}\" ](${t:menu.home})";
+        let res = "#[\"${t:links.home}\"](${t:menu.home})\n";
+
+        assert_eq!(format(code)?, res);
+        assert_same_ast(code, res)?;
+
+        Ok(())
+    }
+
+    #[test]
+    fn formatted_interpolation() -> Result<()> {
+        let code = "#[ \"${url:>8}\" ](${price:.2} of ${n:04})";
+        let res = "#[\"${url:>8}\"](${price:.2} of ${n:04})\n";
+
+        assert_eq!(format(code)?, res);
+        assert_same_ast(code, res)?;
+
+        Ok(())
+    }
+
+    #[test]
+    fn escapes() -> Result<()> {
+        let code = r#"paragraph[ "a \"b\" (c) \$ \u{41}" ](f\(x\) "y" \\ \n)"#;
+        let res = "paragraph[\"a \\\"b\\\" (c) \\$ A\"](f(x\\) \"y\" \\\\ \\n)\n";
+
+        assert_eq!(format(code)?, res);
+        assert_eq!(format(res)?, res);
+
+        Ok(())
+    }
+
+    #[test]
+    fn constants() -> Result<()> {
+        let code =
+            "let   accent=\"red\" // Main color\nlet size = ${accent}\n\n@[color = ${accent}]";
+        let res = r#"let accent = "red" // Main color
+let size = ${accent}
+
+@[color = ${accent}]
+"#;
+
+        assert_eq!(format(code)?, res);
+        assert_same_ast(code, res)?;
+
+        Ok(())
+    }
+
+    #[test]
+    fn raw_text() -> Result<()> {
+        let code = "box { code ` a // b `  code_block[language = \"rust\"] ````\nfn f() {\n    g(```);\n}\n```` }";
+        let res = r#"box {
+    code` a // b `
+    code_block[language = "rust"]````
+        fn f() {
+            g(```);
+        }
+    ````
+}
+"#;
+
+        assert_eq!(format(code)?, res);
+        assert_same_ast(code, res)?;
+
+        Ok(())
+    }
+
+    #[test]
+    fn long_properties() -> Result<()> {
+        let code = r#"box[first_property = "some long value", second_property = "another long value", third_property = "value"]"#;
+        let res = r#"box[
+    first_property = "some long value",
+    second_property = "another long value",
+    third_property = "value"
+]
+"#;
+
+        assert_eq!(format(code)?, res);
+        assert_same_ast(code, res)?;
+
+        Ok(())
+    }
+
+    #[test]
+    fn comments() -> Result<()> {
+        let code = r#"
+// Header comment
+box { // Trailing comment
+  @(Text // not a comment)
+
+    // Inner comment
+   #["//link"](Link)
+   // Last comment
+}
+"#;
+        let res = r#"// Header comment
+box { // Trailing comment
+    @(Text // not a comment)
+
+    // Inner comment
+    #["//link"](Link)
+    // Last comment
+}
+"#;
+
+        assert_eq!(format(code)?, res);
+
+        Ok(())
+    }
+
+    #[test]
+    fn block_comments() -> Result<()> {
+        let code = "/* Header\n   comment */\nbox { /* trailing */\n  @(\\) /* not a comment)\n}\n";
+        let res =
+            "/* Header\n   comment */\nbox { /* trailing */\n    @(\\) /* not a comment)\n}\n";
+
+        assert_eq!(format(code)?, res);
+        assert_eq!(format(res)?, res);
+
+        Ok(())
+    }
+
+    #[test]
+    fn inline_components() -> Result<()> {
+        let code = "paragraph(See $#[ \"http://a\" ](the $@[ bold ](docs))$break or http://b)";
+        let res = "paragraph(See $#[\"http://a\"](the $@[bold](docs))$break or http://b)\n";
+
+        assert_eq!(format(code)?, res);
+        assert_same_ast(code, res)?;
+
+        Ok(())
+    }
+
+    #[test]
+    fn multiline_text() -> Result<()> {
+        let code =
+            "box {\nparagraph(\n  Roses are red,\n   violets are blue\n\n  Second\n)\n@(a\nb)\n}";
+        let res = "box {\n    paragraph(\n        Roses are red,\n        violets are blue\n\n        Second\n    )\n    @(a\n        b)\n}\n";
+
+        assert_eq!(format(code)?, res);
+        assert_same_ast(code, res)?;
+
+        Ok(())
+    }
+
+    #[test]
+    fn negated_flags() -> Result<()> {
+        let code = "box[ ! vertical,horizontal ]";
+        let res = "box[!vertical, horizontal]\n";
+
+        assert_eq!(format(code)?, res);
+        assert_same_ast(code, res)?;
+
+        Ok(())
+    }
+
+    #[test]
+    fn blank_lines() -> Result<()> {
+        let code = "box\n\n\n\nbox\nbox\n";
+        let res = "box\n\nbox\nbox\n";
+
+        assert_eq!(format(code)?, res);
+
+        Ok(())
+    }
+
+    #[test]
+    fn idempotent() -> Result<()> {
+        let code = r#"
+            // Comment
+            component card[default title: string, text body] {
+                box[vertical] { header[2](${title}) paragraph(${body}) }
+            }
+
+            card["Title"](Body text)   // Trailing
+        "#;
+        let formatted = format(code)?;
+
+        assert_eq!(format(&formatted)?, formatted);
+        assert_same_ast(code, &formatted)?;
+
+        Ok(())
+    }
+}
@@ -6,13 +6,13 @@ mod test {
     use markerml_frontend::{self, ast::*};
 
     fn parse(code: &str) -> Result<Module<Span>> {
-        let module = markerml_frontend::parser::parse(&code)?;
+        let module = markerml_frontend::parser::parse(code)?;
 
         Ok(module)
     }
 
     fn parse_no_spans(code: &str) -> Result<Module<()>> {
-        parse(&code).map(|module| module.map_span(&mut |_| ()))
+        parse(code).map(|module| module.map_span(&mut |_| ()))
     }
 
     #[test]
@@ -21,7 +21,7 @@ mod test {
         let res = Module {
             items: vec![Component {
                 name: Identifier::from_literal("box"),
-                properties: Some(Properties {
+                properties: Some(Box::new(Properties {
                     default: None,
                     properties: vec![
                         PropertyKind::Flag {
@@ -38,7 +38,7 @@ mod test {
                         .into(),
                     ],
                     span: (),
-                }),
+                })),
                 children: Some(ComponentChildren {
                     children: vec![],
                     span: (),
@@ -61,7 +61,7 @@ mod test {
         let res = Module {
             items: vec![Component {
                 name: Identifier::from_literal("box"),
-                properties: Some(Properties {
+                properties: Some(Box::new(Properties {
                     default: None,
                     properties: vec![
                         PropertyKind::KeyValue {
@@ -81,7 +81,7 @@ mod test {
                         .into(),
                     ],
                     span: (),
-                }),
+                })),
                 children: None,
                 text: None,
                 span: (),
@@ -101,7 +101,7 @@ mod test {
         let res = Module {
             items: vec![Component {
                 name: Identifier::from_literal("box"),
-                properties: Some(Properties {
+                properties: Some(Box::new(Properties {
                     default: None,
                     properties: vec![
                         PropertyKind::KeyValue {
@@ -116,7 +116,7 @@ mod test {
                         .into(),
                     ],
                     span: (),
-                }),
+                })),
                 children: None,
                 text: None,
                 span: (),
@@ -205,7 +205,7 @@ mod test {
         let res = Module {
             items: vec![ComponentDefinition {
                 name: Identifier::from_literal("custom"),
-                properties: Some(PropertiesDefinition {
+                properties: Some(Box::new(PropertiesDefinition {
                     properties: vec![
                         PropertyDefinitionKind::Named(NamedPropertyDefinition {
                             name: Identifier::from_literal("a"),
@@ -239,7 +239,7 @@ mod test {
                         .into(),
                     ],
                     span: (),
-                }),
+                })),
                 children: None,
                 span: (),
             }
@@ -282,11 +282,11 @@ mod test {
         let res = Module {
             items: vec![Component {
                 name: Identifier::from_literal("box"),
-                properties: Some(Properties {
+                properties: Some(Box::new(Properties {
                     default: None,
                     properties: vec![],
                     span: (),
-                }),
+                })),
                 children: None,
                 text: None,
                 span: (),
@@ -308,14 +308,14 @@ mod test {
         let res = Module {
             items: vec![Component {
                 name: Identifier::from_literal("box"),
-                properties: Some(Properties {
+                properties: Some(Box::new(Properties {
                     default: None,
                     properties: vec![PropertyKind::Flag {
                         key: Identifier::from_literal("vertical"),
                     }
                     .into()],
                     span: (),
-                }),
+                })),
                 children: None,
                 text: None,
                 span: (),
@@ -397,7 +397,7 @@ mod test {
         let res = Module {
             items: vec![Component {
                 name: Identifier::from_literal("box"),
-                properties: Some(Properties {
+                properties: Some(Box::new(Properties {
                     default: None,
                     properties: vec![
                         PropertyKind::KeyValue {
@@ -417,7 +417,7 @@ mod test {
                         .into(),
                     ],
                     span: (),
-                }),
+                })),
                 children: Some(ComponentChildren {
                     children: Vec::new(),
                     span: (),
@@ -461,14 +461,14 @@ mod test {
                             children: Some(ComponentChildren {
                                 children: vec![Component {
                                     name: Identifier::from_literal("box"),
-                                    properties: Some(Properties {
+                                    properties: Some(Box::new(Properties {
                                         default: None,
                                         properties: vec![PropertyKind::Flag {
                                             key: Identifier::from_literal("horizontal"),
                                         }
                                         .into()],
                                         span: (),
-                                    }),
+                                    })),
                                     children: Some(ComponentChildren {
                                         children: Vec::new(),
                                         span: (),
@@ -514,13 +514,13 @@ mod test {
         let res = Module {
             items: vec![Component {
                 name: Identifier::from_literal("#"),
-                properties: Some(Properties {
+                properties: Some(Box::new(Properties {
                     default: Some(
                         ValueKind::String(StringValue::from_literal("google.com")).into(),
                     ),
                     properties: Vec::new(),
                     span: (),
-                }),
+                })),
                 children: None,
                 text: Some(Text::from_literal("google")),
                 span: (),
@@ -544,14 +544,14 @@ mod test {
             items: vec![
                 Component {
                     name: Identifier::from_literal("@"),
-                    properties: Some(Properties {
+                    properties: Some(Box::new(Properties {
                         default: None,
                         properties: vec![PropertyKind::Flag {
                             key: Identifier::from_literal("bold"),
                         }
                         .into()],
                         span: (),
-                    }),
+                    })),
                     children: None,
                     text: Some(Text::from_literal("Hello, world!")),
                     span: (),
@@ -614,6 +614,119 @@ mod test {
         Ok(())
     }
 
+    #[test]
+    fn negated_flag() -> Result<()> {
+        let code = "box[horizontal, !vertical]";
+        let res = Module {
+            items: vec![Component {
+                name: Identifier::from_literal("box"),
+                properties: Some(Box::new(Properties {
+                    default: None,
+                    properties: vec![
+                        PropertyKind::Flag {
+                            key: Identifier::from_literal("horizontal"),
+                        }
+                        .into(),
+                        PropertyKind::NegatedFlag {
+                            key: Identifier::from_literal("vertical"),
+                        }
+                        .into(),
+                    ],
+                    span: (),
+                })),
+                children: None,
+                text: None,
+                span: (),
+            }
+            .into()],
+            span: (),
+        };
+
+        assert_eq!(parse_no_spans(code)?, res);
+
+        Ok(())
+    }
+
+    #[test]
+    fn attribute_property() -> Result<()> {
+        let code = r#"box[attr:data-test = "hero", attr:v-on:click.prevent = "go"]"#;
+        let module = parse_no_spans(code)?;
+        let Some(ModuleItem::Component(component)) = module.items.first() else {
+            panic!("Expected component");
+        };
+        let keys = component
+            .properties
+            .as_ref()
+            .unwrap()
+            .properties
+            .iter()
+            .map(|property| match &property.kind {
+                PropertyKind::KeyValue { key, .. } => key.as_str(),
+                _ => panic!("Expected named property"),
+            })
+            .collect::<Vec<_>>();
+
+        assert_eq!(keys, vec!["attr:data-test", "attr:v-on:click.prevent"]);
+        assert!(parse_no_spans("box[attr:-x = 1]").is_err());
+        assert!(parse_no_spans("box[attr:hidden]").is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn integer_literals() -> Result<()> {
+        let code = "box[a = 0xff, b = -0b1010, c = 1_000_000, d = 0x7FFF_FFFF_FFFF_FFFF, e = -9_223_372_036_854_775_808]";
+        let expected =
+            "box[a = 255, b = -10, c = 1000000, d = 9223372036854775807, e = -9223372036854775808]";
+
+        assert_eq!(parse_no_spans(code)?, parse_no_spans(expected)?);
+
+        Ok(())
+    }
+
+    #[test]
+    fn integer_overflow() {
+        use markerml_frontend::SyntaxError;
+
+        let code = "box[a = 0x1_0000_0000_0000_0000]";
+        let err = markerml_frontend::parse(code).unwrap_err();
+        let err = SyntaxError::new(&err, code);
+
+        assert!(err
+            .message
+            .starts_with("Integer 0x1_0000_0000_0000_0000 doesn't fit"));
+        assert_eq!(err.span.start.offset()..err.span.end.offset(), 8..31);
+    }
+
+    #[test]
+    fn block_comment() -> Result<()> {
+        let code = r#"
+            box /* first */ {
+                /* multiline
+                   box { /* nested */ }
+                */
+                box[a /* , b */]
+            }
+        "#;
+        let expected = "box { box[a] }";
+
+        assert_eq!(parse_no_spans(code)?, parse_no_spans(expected)?);
+
+        Ok(())
+    }
+
+    #[test]
+    fn unterminated_block_comment() {
+        use markerml_frontend::SyntaxError;
+
+        let code = "box {\n    /* outer /* inner */\n    paragraph(Text)\n}";
+        let err = markerml_frontend::parse(code).unwrap_err();
+        let err = SyntaxError::new(&err, code);
+
+        assert_eq!(err.message, "Unterminated block comment");
+        assert_eq!(err.span.start.offset()..err.span.end.offset(), 10..12);
+    }
+
     #[should_panic]
     #[test]
     fn invalid_token() {
@@ -701,16 +814,17 @@ mod test {
                 children: None,
                 text: Some(Text {
                     segments: vec![
-                        InterpolationSegmentKind::Literal(" ".to_owned()).spanned(()),
+                        InterpolationSegmentKind::Newline.spanned(()),
                         InterpolationSegmentKind::Literal("text".to_owned()).spanned(()),
-                        InterpolationSegmentKind::Literal(" ".to_owned()).spanned(()),
+                        InterpolationSegmentKind::Newline.spanned(()),
                         InterpolationSegmentKind::Literal("a".to_owned()).spanned(()),
-                        InterpolationSegmentKind::Literal(" ".to_owned()).spanned(()),
+                        InterpolationSegmentKind::Newline.spanned(()),
                         InterpolationSegmentKind::Literal("b".to_owned()).spanned(()),
-                        InterpolationSegmentKind::Literal(" ".to_owned()).spanned(()),
+                        InterpolationSegmentKind::Newline.spanned(()),
                         InterpolationSegmentKind::Literal("c".to_owned()).spanned(()),
-                        InterpolationSegmentKind::Literal(" ".to_owned()).spanned(()),
+                        InterpolationSegmentKind::Newline.spanned(()),
                     ],
+                    raw: false,
                     span: (),
                 }),
                 span: (),
@@ -730,7 +844,7 @@ mod test {
         let res = Module {
             items: vec![Component {
                 name: Identifier::from_literal("box"),
-                properties: Some(Properties {
+                properties: Some(Box::new(Properties {
                     default: None,
                     properties: vec![PropertyKind::KeyValue {
                         key: Identifier::from_literal("a"),
@@ -748,7 +862,35 @@ mod test {
                     }
                     .into()],
                     span: (),
-                }),
+                })),
+                children: None,
+                text: None,
+                span: (),
+            }
+            .into()],
+            span: (),
+        };
+
+        assert_eq!(parse_no_spans(code)?, res);
+
+        Ok(())
+    }
+
+    #[test]
+    fn variable_value() -> Result<()> {
+        let code = r#"box[${default}, a = ${variable}]"#;
+        let res = Module {
+            items: vec![Component {
+                name: Identifier::from_literal("box"),
+                properties: Some(Box::new(Properties {
+                    default: Some(ValueKind::Variable(Identifier::from_literal("default")).into()),
+                    properties: vec![PropertyKind::KeyValue {
+                        key: Identifier::from_literal("a"),
+                        value: ValueKind::Variable(Identifier::from_literal("variable")).into(),
+                    }
+                    .into()],
+                    span: (),
+                })),
                 children: None,
                 text: None,
                 span: (),
@@ -777,6 +919,205 @@ mod test {
                             .spanned(()),
                         InterpolationSegmentKind::Literal("!".to_owned()).spanned(()),
                     ],
+                    raw: false,
+                    span: (),
+                }),
+                span: (),
+            }
+            .into()],
+            span: (),
+        };
+
+        assert_eq!(parse_no_spans(code)?, res);
+
+        Ok(())
+    }
+
+    #[test]
+    fn formatted_interpolation() -> Result<()> {
+        let code = r#"paragraph(${price:.2} ${n:04} ${name:^10})"#;
+        let formatted = |name: &str, spec: FormatSpec| {
+            InterpolationSegmentKind::Formatted(Box::new(FormattedVariable {
+                name: Identifier::from_literal(name),
+                spec,
+            }))
+            .spanned(())
+        };
+        let space = || InterpolationSegmentKind::Literal(" ".to_owned()).spanned(());
+        let res = Module {
+            items: vec![Component {
+                name: Identifier::from_literal("paragraph"),
+                properties: None,
+                children: None,
+                text: Some(Text {
+                    segments: vec![
+                        formatted(
+                            "price",
+                            FormatSpec {
+                                precision: Some(2),
+                                ..Default::default()
+                            },
+                        ),
+                        space(),
+                        formatted(
+                            "n",
+                            FormatSpec {
+                                zero_pad: true,
+                                width: Some(4),
+                                ..Default::default()
+                            },
+                        ),
+                        space(),
+                        formatted(
+                            "name",
+                            FormatSpec {
+                                align: Some(FormatAlign::Center),
+                                width: Some(10),
+                                ..Default::default()
+                            },
+                        ),
+                    ],
+                    raw: false,
+                    span: (),
+                }),
+                span: (),
+            }
+            .into()],
+            span: (),
+        };
+
+        assert_eq!(parse_no_spans(code)?, res);
+        parse_no_spans(r#"box[width = ${size:04}]"#).unwrap_err();
+        parse_no_spans(r#"@(${size:})"#).unwrap_err();
+        parse_no_spans(r#"@(${size:99999})"#).unwrap_err();
+
+        Ok(())
+    }
+
+    #[test]
+    fn text_escapes() -> Result<()> {
+        let code = r#"paragraph(f\(x\) = \$1 \\ \u{1F600}\n\t\")"#;
+        let literal =
+            |literal: &str| InterpolationSegmentKind::Literal(literal.to_owned()).spanned(());
+        let res = Module {
+            items: vec![Component {
+                name: Identifier::from_literal("paragraph"),
+                properties: None,
+                children: None,
+                text: Some(Text {
+                    segments: vec![
+                        literal("f"),
+                        literal("("),
+                        literal("x"),
+                        literal(")"),
+                        literal(" = "),
+                        literal("$"),
+                        literal("1 "),
+                        literal("\\"),
+                        literal(" "),
+                        literal("\u{1F600}"),
+                        literal("\n"),
+                        literal("\t"),
+                        literal("\""),
+                    ],
+                    raw: false,
+                    span: (),
+                }),
+                span: (),
+            }
+            .into()],
+            span: (),
+        };
+
+        assert_eq!(parse_no_spans(code)?, res);
+
+        Ok(())
+    }
+
+    #[test]
+    fn string_escapes() -> Result<()> {
+        let code = r#"box[a = "say \"\${x}\" \u{e9})"]"#;
+        let literal =
+            |literal: &str| InterpolationSegmentKind::Literal(literal.to_owned()).spanned(());
+        let res = Module {
+            items: vec![Component {
+                name: Identifier::from_literal("box"),
+                properties: Some(Box::new(Properties {
+                    default: None,
+                    properties: vec![PropertyKind::KeyValue {
+                        key: Identifier::from_literal("a"),
+                        value: StringValue {
+                            segments: vec![
+                                literal("say "),
+                                literal("\""),
+                                literal("$"),
+                                literal("{x}"),
+                                literal("\""),
+                                literal(" "),
+                                literal("\u{e9}"),
+                                literal(")"),
+                            ],
+                            span: (),
+                        }
+                        .into(),
+                    }
+                    .into()],
+                    span: (),
+                })),
+                children: None,
+                text: None,
+                span: (),
+            }
+            .into()],
+            span: (),
+        };
+
+        assert_eq!(parse_no_spans(code)?, res);
+
+        Ok(())
+    }
+
+    #[should_panic]
+    #[test]
+    fn unknown_escape() {
+        parse_no_spans(r#"paragraph(C:\dir)"#).unwrap();
+    }
+
+    #[should_panic]
+    #[test]
+    fn invalid_unicode_escape() {
+        parse_no_spans(r#"paragraph(\u{D800})"#).unwrap();
+    }
+
+    #[test]
+    fn message_interpolation() -> Result<()> {
+        let code = r#"#["${t:links.home}"](${t:welcome}, ${user})"#;
+        let res = Module {
+            items: vec![Component {
+                name: Identifier::from_literal("#"),
+                properties: Some(Box::new(Properties {
+                    default: Some(
+                        ValueKind::String(StringValue {
+                            segments: vec![InterpolationSegmentKind::Message(
+                                "links.home".to_owned(),
+                            )
+                            .spanned(())],
+                            span: (),
+                        })
+                        .into(),
+                    ),
+                    properties: vec![],
+                    span: (),
+                })),
+                children: None,
+                text: Some(Text {
+                    segments: vec![
+                        InterpolationSegmentKind::Message("welcome".to_owned()).spanned(()),
+                        InterpolationSegmentKind::Literal(", ".to_owned()).spanned(()),
+                        InterpolationSegmentKind::Variable(Identifier::from_literal("user"))
+                            .spanned(()),
+                    ],
+                    raw: false,
                     span: (),
                 }),
                 span: (),
@@ -790,6 +1131,138 @@ mod test {
         Ok(())
     }
 
+    #[test]
+    fn inline_component() -> Result<()> {
+        let code = r#"paragraph(See $#["url"](the $@[bold](docs)) now)"#;
+        let link = Component {
+            name: Identifier::from_literal("#"),
+            properties: Some(Box::new(Properties {
+                default: Some(
+                    ValueKind::String(StringValue {
+                        segments: vec![
+                            InterpolationSegmentKind::Literal("url".to_owned()).spanned(())
+                        ],
+                        span: (),
+                    })
+                    .into(),
+                ),
+                properties: vec![],
+                span: (),
+            })),
+            children: None,
+            text: Some(Text {
+                segments: vec![
+                    InterpolationSegmentKind::Literal("the ".to_owned()).spanned(()),
+                    InterpolationSegmentKind::Component(Box::new(Component {
+                        name: Identifier::from_literal("@"),
+                        properties: Some(Box::new(Properties {
+                            default: None,
+                            properties: vec![PropertyKind::Flag {
+                                key: Identifier::from_literal("bold"),
+                            }
+                            .into()],
+                            span: (),
+                        })),
+                        children: None,
+                        text: Some(Text {
+                            segments: vec![
+                                InterpolationSegmentKind::Literal("docs".to_owned()).spanned(())
+                            ],
+                            raw: false,
+                            span: (),
+                        }),
+                        span: (),
+                    }))
+                    .spanned(()),
+                ],
+                raw: false,
+                span: (),
+            }),
+            span: (),
+        };
+        let res = Module {
+            items: vec![Component {
+                name: Identifier::from_literal("paragraph"),
+                properties: None,
+                children: None,
+                text: Some(Text {
+                    segments: vec![
+                        InterpolationSegmentKind::Literal("See ".to_owned()).spanned(()),
+                        InterpolationSegmentKind::Component(Box::new(link)).spanned(()),
+                        InterpolationSegmentKind::Literal(" now".to_owned()).spanned(()),
+                    ],
+                    raw: false,
+                    span: (),
+                }),
+                span: (),
+            }
+            .into()],
+            span: (),
+        };
+
+        assert_eq!(parse_no_spans(code)?, res);
+
+        Ok(())
+    }
+
+    #[test]
+    fn inline_component_without_name() {
+        parse_no_spans(r#"paragraph(See $ now)"#).unwrap_err();
+    }
+
+    #[test]
+    fn raw_text() -> Result<()> {
+        let code = "code_block ``\n    let x = f(`${y}`);\n\n      z\n    ``";
+        let res = Module {
+            items: vec![Component {
+                name: Identifier::from_literal("code_block"),
+                properties: None,
+                children: None,
+                text: Some(Text {
+                    segments: vec![InterpolationSegmentKind::Literal(
+                        "let x = f(`${y}`);\n\n  z".to_owned(),
+                    )
+                    .spanned(())],
+                    raw: true,
+                    span: (),
+                }),
+                span: (),
+            }
+            .into()],
+            span: (),
+        };
+
+        assert_eq!(parse_no_spans(code)?, res);
+
+        Ok(())
+    }
+
+    #[test]
+    fn constant() -> Result<()> {
+        let code = r#"let accent = "red" let size = 2"#;
+        let res = Module {
+            items: vec![
+                Constant {
+                    name: Identifier::from_literal("accent"),
+                    value: ValueKind::String(StringValue::from_literal("red")).into(),
+                    span: (),
+                }
+                .into(),
+                Constant {
+                    name: Identifier::from_literal("size"),
+                    value: ValueKind::Integer(2).into(),
+                    span: (),
+                }
+                .into(),
+            ],
+            span: (),
+        };
+
+        assert_eq!(parse_no_spans(code)?, res);
+
+        Ok(())
+    }
+
     #[test]
     fn component_definition() -> Result<()> {
         let code = r#"component custom {}"#;
@@ -820,7 +1293,7 @@ mod test {
         let res = Module {
             items: vec![ComponentDefinition {
                 name: Identifier::from_literal("custom"),
-                properties: Some(PropertiesDefinition {
+                properties: Some(Box::new(PropertiesDefinition {
                     properties: vec![PropertyDefinitionKind::Default(NamedPropertyDefinition {
                         name: Identifier::from_literal("prop"),
                         ty: TypeKind::String.into(),
@@ -828,7 +1301,7 @@ mod test {
                     })
                     .into()],
                     span: (),
-                }),
+                })),
                 children: None,
                 span: (),
             }
@@ -849,13 +1322,13 @@ mod test {
         let res = Module {
             items: vec![ComponentDefinition {
                 name: Identifier::from_literal("custom"),
-                properties: Some(PropertiesDefinition {
+                properties: Some(Box::new(PropertiesDefinition {
                     properties: vec![PropertyDefinitionKind::Text(TextPropertyDefinition {
                         name: Identifier::from_literal("prop"),
                     })
                     .into()],
                     span: (),
-                }),
+                })),
                 children: None,
                 span: (),
             }
@@ -877,7 +1350,7 @@ mod test {
         let res = Module {
             items: vec![ComponentDefinition {
                 name: Identifier::from_literal("custom_component"),
-                properties: Some(PropertiesDefinition {
+                properties: Some(Box::new(PropertiesDefinition {
                     properties: vec![
                         PropertyDefinitionKind::Named(NamedPropertyDefinition {
                             name: Identifier::from_literal("something"),
@@ -893,7 +1366,7 @@ mod test {
                         .into(),
                     ],
                     span: (),
-                }),
+                })),
                 children: None,
                 span: (),
             }
@@ -905,4 +1378,146 @@ mod test {
 
         Ok(())
     }
+
+    #[test]
+    fn syntax_error() {
+        use markerml_frontend::SyntaxError;
+
+        let code = "text[size=](\"a\")";
+        let err = markerml_frontend::parse(code).unwrap_err();
+        let err = SyntaxError::new(&err, code);
+
+        assert_eq!(err.message, "Expected value");
+        assert_eq!(err.expected, vec!["value".to_owned()]);
+        assert_eq!(err.label, "Found `]`");
+        assert_eq!(err.span.start.offset()..err.span.end.offset(), 10..11);
+    }
+
+    #[test]
+    fn visitor() -> Result<()> {
+        use markerml_frontend::ast::visit::{self, Visitor, VisitorMut};
+
+        #[derive(Default)]
+        struct Names(Vec<String>);
+
+        impl Visitor<Span> for Names {
+            fn visit_component(&mut self, component: &Component<Span>) {
+                self.0.push(component.name.name.clone());
+                visit::walk_component(self, component);
+            }
+
+            fn visit_interpolation_segment(&mut self, segment: &InterpolationSegment<Span>) {
+                if let InterpolationSegmentKind::Variable(name) = &segment.kind {
+                    self.0.push(format!("${{{}}}", name.as_str()));
+                }
+            }
+        }
+
+        struct Rename;
+
+        impl VisitorMut<Span> for Rename {
+            fn visit_identifier_mut(&mut self, identifier: &mut Identifier<Span>) {
+                if identifier.name == "title" {
+                    identifier.name = "heading".to_owned();
+                }
+            }
+        }
+
+        let code = r#"
+            component card[default title: string] { header(${title}) }
+            box { card["A"] @(${title}) }
+        "#;
+        let mut module = parse(code)?;
+        let mut names = Names::default();
+        names.visit_module(&module);
+        assert_eq!(
+            names.0,
+            ["header", "${title}", "box", "card", "@", "${title}"]
+        );
+
+        Rename.visit_module_mut(&mut module);
+        let mut names = Names::default();
+        names.visit_module(&module);
+        assert_eq!(
+            names.0,
+            ["header", "${heading}", "box", "card", "@", "${heading}"]
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn builder() -> Result<()> {
+        use markerml_frontend::ast::builder::{component, definition, module, variable};
+
+        let built = module::<()>()
+            .constant("name", "World")
+            .definition(
+                definition("card")
+                    .default_property("title", TypeKind::String)
+                    .property_with_default("level", TypeKind::Integer, 2)
+                    .child(component("header").default(variable("level"))),
+            )
+            .component(
+                component("box")
+                    .flag("horizontal")
+                    .property("x_align", "center")
+                    .children([
+                        component("card").default("Hello"),
+                        component("paragraph").text("Text"),
+                    ]),
+            )
+            .build();
+
+        let code = r#"
+            let name = "World"
+            component card[default title: string, level: int = 2] { header[${level}] }
+            box[horizontal, x_align = "center"] { card["Hello"] paragraph(Text) }
+        "#;
+        let mut parsed = parse(code)?.map_span(&mut |_| ());
+        parsed.span = ();
+        assert_eq!(built, parsed);
+
+        Ok(())
+    }
+
+    #[test]
+    fn source_map() {
+        use markerml_frontend::span::{LineColumn, SourceMap};
+
+        let source = "box {\n    @(Привіт)\n}";
+        let map = SourceMap::new(source);
+
+        assert_eq!(map.line_column(0), LineColumn { line: 1, column: 1 });
+        let offset = source.find(')').unwrap();
+        assert_eq!(
+            map.line_column(offset),
+            LineColumn {
+                line: 2,
+                column: 13
+            }
+        );
+        assert_eq!(map.line_index(source.len()), 2);
+        assert_eq!(&source[map.line_range(1)], "    @(Привіт)");
+        assert_eq!(map.line_range(3), source.len()..source.len());
+    }
+
+    #[test]
+    fn node_sizes() {
+        use std::mem::size_of;
+
+        assert_eq!(size_of::<Span>(), 8);
+        assert!(size_of::<Component<Span>>() <= 120);
+        assert!(size_of::<ModuleItem<Span>>() <= 120);
+        assert!(size_of::<Value<Span>>() <= 48);
+        assert!(size_of::<InterpolationSegment<Span>>() <= 40);
+    }
+
+    #[test]
+    fn position_limit() {
+        use markerml_frontend::span::{Position, MAX_SOURCE_LEN};
+
+        assert_eq!(Position::try_new(MAX_SOURCE_LEN), Some(Position(u32::MAX)));
+        assert_eq!(Position::try_new(MAX_SOURCE_LEN + 1), None);
+    }
 }
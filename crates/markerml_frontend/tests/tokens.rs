@@ -0,0 +1,95 @@
+#[cfg(test)]
+mod test {
+    use anyhow::Result;
+    use markerml_frontend::parser::Rule;
+    use markerml_frontend::tokens::{tokenize, TokenKind};
+
+    fn tokens(code: &str) -> Result<Vec<(TokenKind, String)>> {
+        let tokens = tokenize(code)?
+            .into_iter()
+            .map(|token| (token.kind, token.text))
+            .collect();
+
+        Ok(tokens)
+    }
+
+    #[test]
+    fn component() -> Result<()> {
+        let code = r#"box[a = "x ${y}"] // Comment
+            @(Text)"#;
+        let res = vec![
+            (TokenKind::Rule(Rule::identifier), "box"),
+            (TokenKind::Punctuation, "["),
+            (TokenKind::Rule(Rule::identifier), "a"),
+            (TokenKind::Punctuation, "="),
+            (TokenKind::Punctuation, "\""),
+            (TokenKind::Rule(Rule::string_literal_segment), "x "),
+            (TokenKind::Punctuation, "${"),
+            (TokenKind::Rule(Rule::identifier), "y"),
+            (TokenKind::Punctuation, "}"),
+            (TokenKind::Punctuation, "\""),
+            (TokenKind::Punctuation, "]"),
+            (TokenKind::Comment, "// Comment"),
+            (TokenKind::Rule(Rule::component_name), "@"),
+            (TokenKind::Punctuation, "("),
+            (TokenKind::Rule(Rule::text_literal_segment), "Text"),
+            (TokenKind::Punctuation, ")"),
+        ];
+        let res = res
+            .into_iter()
+            .map(|(kind, text)| (kind, text.to_owned()))
+            .collect::<Vec<_>>();
+
+        assert_eq!(tokens(code)?, res);
+
+        Ok(())
+    }
+
+    #[test]
+    fn block_comment() -> Result<()> {
+        let code = "box /* a /* b */ c */ @(Text /* not a comment */)";
+        let comments = tokens(code)?
+            .into_iter()
+            .filter(|(kind, _)| *kind == TokenKind::Comment)
+            .map(|(_, text)| text)
+            .collect::<Vec<_>>();
+
+        assert_eq!(comments, vec!["/* a /* b */ c */"]);
+
+        Ok(())
+    }
+
+    #[test]
+    fn keywords() -> Result<()> {
+        let code = r#"component custom[default a: int, text b]"#;
+        let kinds = tokens(code)?
+            .into_iter()
+            .filter(|(kind, _)| *kind == TokenKind::Keyword)
+            .map(|(_, text)| text)
+            .collect::<Vec<_>>();
+
+        assert_eq!(kinds, vec!["component", "default", "text"]);
+
+        Ok(())
+    }
+
+    #[test]
+    fn lazy_tokens() -> Result<()> {
+        let code = "box {\n    @(Text)\n}\n// Trailing comment\n";
+        let lazy = markerml_frontend::tokens::tokens(code)?.collect::<Vec<_>>();
+
+        assert_eq!(lazy, tokenize(code)?);
+        assert_eq!(
+            lazy.last().map(|token| token.kind),
+            Some(TokenKind::Comment)
+        );
+
+        Ok(())
+    }
+
+    #[should_panic]
+    #[test]
+    fn unclosed_string() {
+        tokens(r#"box[a = "x]"#).unwrap();
+    }
+}
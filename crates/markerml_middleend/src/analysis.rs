@@ -0,0 +1,144 @@
+use crate::error::{AmbiguousFlagWarning, IrWarning, UnusedDefinitionWarning};
+use crate::ir;
+use crate::Span;
+use std::collections::{HashMap, HashSet};
+
+/// Reports component definitions of the module, that are never instantiated
+/// by its top-level components, directly or through other definitions,
+/// and flags of the definitions, that have the same name as a variable
+pub fn lint_module(module: &ir::Module<Span>) -> Vec<IrWarning> {
+    let mut used = UsedComponents::default();
+    let mut definitions = Vec::new();
+    let mut constants = HashSet::new();
+    for item in &module.items {
+        match item {
+            ir::ModuleItem::Component(component) => used.visit_component(component),
+            ir::ModuleItem::ComponentDefinition(def) => definitions.push(def),
+            ir::ModuleItem::Constant(constant) => {
+                constants.insert(constant.name.as_str());
+            }
+        }
+    }
+
+    let mut warnings = ambiguous_flags(definitions.iter().copied(), &constants);
+    warnings.splice(0..0, unused_definitions(definitions, used.0));
+    warnings
+}
+
+/// Reports definitions, that aren't reachable from the given names of the used components.
+/// Warnings are ordered by the position of the definitions
+pub fn unused_definitions<'a>(
+    definitions: impl IntoIterator<Item = &'a ir::ComponentDefinition<Span>>,
+    used: HashSet<&'a str>,
+) -> Vec<IrWarning> {
+    let definitions = definitions
+        .into_iter()
+        .map(|def| (def.name.as_str(), def))
+        .collect::<HashMap<_, _>>();
+
+    let mut reachable = HashSet::new();
+    let mut queue = used.into_iter().collect::<Vec<_>>();
+    while let Some(name) = queue.pop() {
+        if !reachable.insert(name) {
+            continue;
+        }
+        if let Some(def) = definitions.get(name) {
+            let mut used = UsedComponents::default();
+            for child in &def.children {
+                used.visit_component(child);
+            }
+            queue.extend(used.0);
+        }
+    }
+
+    let mut unused = definitions
+        .into_values()
+        .filter(|def| !reachable.contains(def.name.as_str()))
+        .collect::<Vec<_>>();
+    unused.sort_by_key(|def| def.name.span.start.offset());
+
+    unused
+        .into_iter()
+        .map(|def| {
+            UnusedDefinitionWarning {
+                name: def.name.as_str().to_owned(),
+                span: def.name.span.clone(),
+            }
+            .into()
+        })
+        .collect()
+}
+
+/// Reports flags of the definitions, that have the same name as a property
+/// of the definition or as one of the given constants. Flag is always set to `true`
+/// and never refers to the variable, so such flag is most likely a mistake.
+/// Warnings are ordered by the position of the flags
+pub fn ambiguous_flags<'a>(
+    definitions: impl IntoIterator<Item = &'a ir::ComponentDefinition<Span>>,
+    constants: &HashSet<&str>,
+) -> Vec<IrWarning> {
+    let mut warnings = Vec::new();
+    for def in definitions {
+        let properties = &def.properties;
+        let mut variables = constants.clone();
+        variables.extend(properties.text_property.iter().map(|name| name.as_str()));
+        variables.extend(
+            properties
+                .default_property
+                .iter()
+                .chain(&properties.properties)
+                .map(|property| property.name.as_str()),
+        );
+
+        let mut flags = FlagsNamedAs {
+            variables: &variables,
+            flags: Vec::new(),
+        };
+        for child in &def.children {
+            flags.visit_component(child);
+        }
+        warnings.extend(flags.flags.into_iter().map(|flag| AmbiguousFlagWarning {
+            name: flag.as_str().to_owned(),
+            definition: def.name.as_str().to_owned(),
+            span: flag.span.clone(),
+        }));
+    }
+    warnings.sort_by_key(|warning| warning.span.start.offset());
+
+    warnings.into_iter().map(IrWarning::from).collect()
+}
+
+/// Collects flags of the visited components, that are named as one of the variables
+struct FlagsNamedAs<'a, 'v> {
+    variables: &'v HashSet<&'v str>,
+    flags: Vec<&'a ir::Identifier<Span>>,
+}
+
+impl<'a> FlagsNamedAs<'a, '_> {
+    fn visit_component(&mut self, component: &'a ir::Component<Span>) {
+        let flags = &component.properties.flag_properties;
+        self.flags.extend(
+            flags
+                .iter()
+                .filter(|flag| self.variables.contains(flag.as_str())),
+        );
+        let inline = component.text.iter().flat_map(|text| text.components());
+        for child in component.children.iter().chain(inline) {
+            self.visit_component(child);
+        }
+    }
+}
+
+/// Collects names of the visited components
+#[derive(Default)]
+struct UsedComponents<'a>(HashSet<&'a str>);
+
+impl<'a> UsedComponents<'a> {
+    fn visit_component(&mut self, component: &'a ir::Component<Span>) {
+        self.0.insert(component.name.as_str());
+        let inline = component.text.iter().flat_map(|text| text.components());
+        for child in component.children.iter().chain(inline) {
+            self.visit_component(child);
+        }
+    }
+}
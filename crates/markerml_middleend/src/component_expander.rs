@@ -0,0 +1,977 @@
+use crate::error::*;
+use crate::format;
+use crate::ir::visit::{self, VisitorMut};
+use crate::{ir, IrGeneratorError};
+use markerml_frontend::parser::Span;
+use std::collections::{HashMap, HashSet};
+use std::rc::Rc;
+
+/// Values of component properties, available for interpolation
+type Scope = HashMap<String, ir::Value<Span>>;
+
+/// Variables, that are substituted into the interpolated values
+trait Variables {
+    fn get(&self, name: &str) -> Option<&ir::Value<Span>>;
+}
+
+impl Variables for Scope {
+    fn get(&self, name: &str) -> Option<&ir::Value<Span>> {
+        HashMap::get(self, name)
+    }
+}
+
+/// Property values of the component instance,
+/// which shadow the top-level variables without copying them
+struct InstanceScope<'v> {
+    properties: &'v Scope,
+    variables: &'v Scope,
+}
+
+impl Variables for InstanceScope<'_> {
+    fn get(&self, name: &str) -> Option<&ir::Value<Span>> {
+        self.properties
+            .get(name)
+            .or_else(|| self.variables.get(name))
+    }
+}
+
+/// Name of the slot along with the components that fill it
+type Slot = (String, Vec<ir::Component<Span>>);
+
+/// Identifies result of the component expansion:
+/// name of the definition along with the resolved property values, sorted by name
+type ExpansionKey = (String, Vec<(String, ValueKey)>);
+
+/// Expanded children of the definition, shared by the instances with equal property values
+struct Expansion {
+    /// Property values of the instance, that was expanded. Spans of the children refer to them
+    properties: Scope,
+    children: Vec<ir::Component<Span>>,
+}
+
+/// Property value without spans, so that equal values from different instances match
+#[derive(PartialEq, Eq, Hash)]
+enum ValueKey {
+    String(Vec<SegmentKey>),
+    Integer(i64),
+    Bool(bool),
+    Variable(String),
+}
+
+#[derive(PartialEq, Eq, Hash)]
+enum SegmentKey {
+    Literal(String),
+    Variable(String),
+    Formatted(String, ir::FormatSpec),
+    Message(String),
+    Newline,
+}
+
+impl ValueKey {
+    /// Creates key of the value. Values with inline components aren't memoized,
+    /// since comparing the components costs as much as expanding them
+    fn new(value: &ir::Value<Span>) -> Option<Self> {
+        Some(match &value.kind {
+            ir::ValueKind::String(string) => ValueKey::String(
+                string
+                    .segments
+                    .iter()
+                    .map(|segment| match &segment.kind {
+                        ir::InterpolationSegmentKind::Literal(literal) => {
+                            Some(SegmentKey::Literal(literal.clone()))
+                        }
+                        ir::InterpolationSegmentKind::Variable(name) => {
+                            Some(SegmentKey::Variable(name.as_str().to_owned()))
+                        }
+                        ir::InterpolationSegmentKind::Formatted(variable) => Some(
+                            SegmentKey::Formatted(variable.name.as_str().to_owned(), variable.spec),
+                        ),
+                        ir::InterpolationSegmentKind::Message(key) => {
+                            Some(SegmentKey::Message(key.clone()))
+                        }
+                        ir::InterpolationSegmentKind::Component(_) => None,
+                        ir::InterpolationSegmentKind::Newline => Some(SegmentKey::Newline),
+                    })
+                    .collect::<Option<_>>()?,
+            ),
+            ir::ValueKind::Integer(value) => ValueKey::Integer(*value),
+            ir::ValueKind::Bool(value) => ValueKey::Bool(*value),
+            ir::ValueKind::Variable(name) => ValueKey::Variable(name.as_str().to_owned()),
+        })
+    }
+}
+
+/// Replaces spans of the property values of one instance with the spans
+/// of the equal values of another one, so that memoized expansion points to the latter
+#[derive(Default)]
+struct Respan {
+    spans: HashMap<Span, Span>,
+}
+
+impl Respan {
+    fn new(from: &Scope, to: &Scope) -> Self {
+        let mut respan = Respan::default();
+        for (name, to) in to {
+            if let Some(from) = from.get(name) {
+                respan.add_value(from, to);
+            }
+        }
+
+        respan
+    }
+
+    /// Values are equal except for their spans, so their segments match
+    fn add_value(&mut self, from: &ir::Value<Span>, to: &ir::Value<Span>) {
+        self.add(&from.span, &to.span);
+        if let (ir::ValueKind::String(from), ir::ValueKind::String(to)) = (&from.kind, &to.kind) {
+            self.add(&from.span, &to.span);
+            for (from, to) in from.segments.iter().zip(&to.segments) {
+                self.add(&from.span, &to.span);
+            }
+        }
+    }
+
+    fn add(&mut self, from: &Span, to: &Span) {
+        if from != to {
+            self.spans.insert(from.clone(), to.clone());
+        }
+    }
+
+    fn respan(&self, span: &mut Span) {
+        if let Some(to) = self.spans.get(span) {
+            *span = to.clone();
+        }
+    }
+}
+
+impl VisitorMut<Span> for Respan {
+    fn visit_component_mut(&mut self, component: &mut ir::Component<Span>) {
+        self.respan(&mut component.span);
+        visit::walk_component_mut(self, component);
+    }
+
+    fn visit_property_mut(&mut self, property: &mut ir::Property<Span>) {
+        self.respan(&mut property.span);
+        visit::walk_property_mut(self, property);
+    }
+
+    fn visit_value_mut(&mut self, value: &mut ir::Value<Span>) {
+        self.respan(&mut value.span);
+        if let ir::ValueKind::String(string) = &mut value.kind {
+            self.respan(&mut string.span);
+        }
+        visit::walk_value_mut(self, value);
+    }
+
+    fn visit_text_mut(&mut self, text: &mut ir::Text<Span>) {
+        self.respan(&mut text.span);
+        visit::walk_text_mut(self, text);
+    }
+
+    fn visit_interpolation_segment_mut(&mut self, segment: &mut ir::InterpolationSegment<Span>) {
+        self.respan(&mut segment.span);
+        visit::walk_interpolation_segment_mut(self, segment);
+    }
+}
+
+/// Name of the component, that repeats its children
+pub const REPEAT: &str = "repeat";
+/// Name of the variable with the index of the repetition, unless it's specified
+const DEFAULT_REPEAT_INDEX: &str = "index";
+/// Maximum count of the single repetition
+pub const MAX_REPEAT_COUNT: i64 = 10_000;
+/// Maximum number of the components, produced by all repetitions of the module,
+/// so that nested repetitions can't exhaust the memory
+pub const MAX_REPEATED_COMPONENTS: usize = 1_000_000;
+
+/// Replaces instances of custom components with children of their definitions,
+/// substituting property values for interpolated variables.
+/// Children of the instance fill the `slot` or `slot[]` property of the definition:
+/// they are rendered in place of the components named after the property.
+/// Children of the `repeat` component are repeated given number of times
+/// with the index of the repetition available for interpolation
+pub struct ComponentExpander {
+    ir: Option<ir::Module<Span>>,
+    /// Definitions are shared between the instances,
+    /// so their children are cloned only when the expansion isn't memoized
+    definitions: HashMap<String, Rc<ir::ComponentDefinition<Span>>>,
+    /// Top-level variables, available for interpolation in the whole module
+    variables: Scope,
+    /// Names of the components that are currently being expanded
+    stack: Vec<String>,
+    /// Expanded instances of the custom components.
+    /// Instances with the same property values share the result,
+    /// with spans of the substituted values moved to the instance
+    expanded: HashMap<ExpansionKey, Expansion>,
+    /// Catalog of localized messages. Messages are left unresolved if it's missing
+    messages: Option<HashMap<String, String>>,
+    /// Number of the components, produced by the repetitions so far
+    repeated: usize,
+}
+
+impl ComponentExpander {
+    /// Creates new instance from the given IR
+    pub fn new(ir: ir::Module<Span>) -> Self {
+        Self::with_variables(ir, HashMap::new())
+    }
+
+    /// Creates new instance from the given IR and top-level variables
+    pub fn with_variables(
+        ir: ir::Module<Span>,
+        variables: HashMap<String, ir::Value<Span>>,
+    ) -> Self {
+        ComponentExpander {
+            ir: Some(ir),
+            definitions: HashMap::new(),
+            variables,
+            stack: Vec::new(),
+            expanded: HashMap::new(),
+            messages: None,
+            repeated: 0,
+        }
+    }
+
+    /// Sets catalog of localized messages, that are resolved after the expansion
+    pub fn with_messages(mut self, messages: HashMap<String, String>) -> Self {
+        self.messages = Some(messages);
+        self
+    }
+
+    /// Creates new instance from the given definitions and top-level variables,
+    /// which expands components one by one with [`ComponentExpander::expand_top_level`]
+    pub fn with_definitions(
+        definitions: impl IntoIterator<Item = ir::ComponentDefinition<Span>>,
+        variables: HashMap<String, ir::Value<Span>>,
+    ) -> Self {
+        let mut expander = Self::with_variables(
+            ir::Module {
+                span: Span::default(),
+                items: Vec::new(),
+            },
+            variables,
+        );
+        expander.definitions = definitions
+            .into_iter()
+            .map(|def| (def.name.as_str().to_owned(), Rc::new(def)))
+            .collect();
+
+        expander
+    }
+
+    /// Expands all custom components in the stored IR.
+    /// Constants are resolved and removed from the module
+    pub fn expand(mut self) -> Result<ir::Module<Span>, IrGeneratorError> {
+        let module = self.ir.take().unwrap();
+        self.definitions = module
+            .items
+            .iter()
+            .filter_map(|item| match item {
+                ir::ModuleItem::ComponentDefinition(def) => {
+                    Some((def.name.as_str().to_owned(), Rc::new(def.clone())))
+                }
+                ir::ModuleItem::Constant(_) | ir::ModuleItem::Component(_) => None,
+            })
+            .collect();
+        for item in &module.items {
+            if let ir::ModuleItem::Constant(constant) = item {
+                self.define_constant(constant.clone())?;
+            }
+        }
+
+        let mut items = Vec::new();
+        for item in module.items {
+            match item {
+                ir::ModuleItem::Constant(_) => {}
+                ir::ModuleItem::Component(component) => items.extend(
+                    self.expand_top_level(component)?
+                        .into_iter()
+                        .map(ir::ModuleItem::Component),
+                ),
+                def @ ir::ModuleItem::ComponentDefinition(_) => items.push(def),
+            }
+        }
+
+        Ok(ir::Module {
+            span: module.span,
+            items,
+        })
+    }
+
+    /// Adds module-level constant to the top-level variables, replacing the variable
+    /// with the same name. Value of the constant can refer to the previously defined ones
+    pub fn define_constant(
+        &mut self,
+        constant: ir::Constant<Span>,
+    ) -> Result<(), IrGeneratorError> {
+        let value = Self::substitute_value(constant.value, &self.variables)?;
+        self.variables
+            .insert(constant.name.as_str().to_owned(), value);
+        // Expansions aren't keyed by the top-level variables, so they might be outdated
+        self.expanded.clear();
+
+        Ok(())
+    }
+
+    /// Expands top-level component, substituting top-level variables
+    pub fn expand_top_level(
+        &mut self,
+        component: ir::Component<Span>,
+    ) -> Result<Vec<ir::Component<Span>>, IrGeneratorError> {
+        let component = Self::substitute_component(component, &self.variables)?;
+        let components = self.expand_component(component)?;
+        for component in &components {
+            self.check_variables(component)?;
+        }
+
+        match &self.messages {
+            Some(messages) => components
+                .into_iter()
+                .map(|component| Self::localize_component(component, messages))
+                .collect(),
+            None => Ok(components),
+        }
+    }
+
+    fn expand_component(
+        &mut self,
+        mut component: ir::Component<Span>,
+    ) -> Result<Vec<ir::Component<Span>>, IrGeneratorError> {
+        // Slot is filled after the expansion of the component, that defines it
+        if self.is_slot_placeholder(&component) {
+            return Ok(vec![component]);
+        }
+
+        let Some(def) = self.definitions.get(component.name.as_str()).cloned() else {
+            if component.name.as_str() == REPEAT {
+                return self.expand_repeat(component);
+            }
+            let children = self.expand_children(component.children)?;
+            let text = self.expand_text(component.text)?;
+
+            return Ok(vec![ir::Component {
+                children,
+                text,
+                ..component
+            }]);
+        };
+
+        if self.stack.iter().any(|name| name == def.name.as_str()) {
+            return Err(RecursiveExpansionError {
+                name: def.name.clone().into(),
+                definition: def.name.span.clone(),
+                component: component.span,
+            }
+            .into());
+        }
+
+        // Inline components of the text belong to the outer scope, like the slot content
+        component.text = self.expand_text(component.text.take())?;
+        let children = std::mem::take(&mut component.children);
+        let slot = self.create_slot(&def, &component, children)?;
+        let children = self.expand_definition(&def, component)?;
+
+        Ok(match slot {
+            Some((name, content)) => Self::fill_slot(children, &name, &content),
+            None => children,
+        })
+    }
+
+    fn expand_definition(
+        &mut self,
+        def: &ir::ComponentDefinition<Span>,
+        component: ir::Component<Span>,
+    ) -> Result<Vec<ir::Component<Span>>, IrGeneratorError> {
+        let properties = Self::create_scope(def, component);
+        let key = Self::create_key(def.name.as_str(), &properties);
+        if let Some(expansion) = key.as_ref().and_then(|key| self.expanded.get(key)) {
+            let mut children = expansion.children.clone();
+            let mut respan = Respan::new(&expansion.properties, &properties);
+            if !respan.spans.is_empty() {
+                for child in &mut children {
+                    respan.visit_component_mut(child);
+                }
+            }
+
+            return Ok(children);
+        }
+
+        let scope = InstanceScope {
+            properties: &properties,
+            variables: &self.variables,
+        };
+        let children = def
+            .children
+            .iter()
+            .map(|child| Self::substitute_component(child.clone(), &scope))
+            .collect::<Result<_, _>>()?;
+
+        self.stack.push(def.name.as_str().to_owned());
+        let children = self.expand_children(children);
+        self.stack.pop();
+
+        let children = children?;
+        if let Some(key) = key {
+            let expansion = Expansion {
+                properties,
+                children: children.clone(),
+            };
+            self.expanded.insert(key, expansion);
+        }
+
+        Ok(children)
+    }
+
+    /// Expands children of the `repeat` component for every index from zero up to the count
+    fn expand_repeat(
+        &mut self,
+        component: ir::Component<Span>,
+    ) -> Result<Vec<ir::Component<Span>>, IrGeneratorError> {
+        let properties = Self::repeat_properties(&component);
+        let count = match properties.get("count").map(|value| (&value.kind, value)) {
+            Some((ir::ValueKind::Integer(count), _)) if *count >= 0 => *count,
+            Some((_, value)) => {
+                return Err(InvalidRepeatCountError {
+                    span: value.span.clone(),
+                }
+                .into())
+            }
+            None => {
+                return Err(InvalidRepeatCountError {
+                    span: component.span,
+                }
+                .into())
+            }
+        };
+        if count > MAX_REPEAT_COUNT {
+            return Err(RepeatLimitError {
+                help: format!("Count of the repetition can't exceed {MAX_REPEAT_COUNT}"),
+                span: component.span,
+            }
+            .into());
+        }
+        let index = Self::repeat_index(&component)?;
+
+        let mut result = Vec::new();
+        for i in 0..count {
+            let value = ir::ValueKind::Integer(i).spanned(component.span.clone());
+            let scope = Scope::from([(index.to_owned(), value)]);
+            let children = component
+                .children
+                .iter()
+                .map(|child| Self::substitute_index(child.clone(), &scope, index))
+                .collect::<Result<_, _>>()?;
+            let children = self.expand_children(children)?;
+            self.repeated += children.iter().map(component_count).sum::<usize>();
+            if self.repeated > MAX_REPEATED_COMPONENTS {
+                return Err(RepeatLimitError {
+                    help: format!(
+                        "Repetitions can't produce more than {MAX_REPEATED_COMPONENTS} components"
+                    ),
+                    span: component.span,
+                }
+                .into());
+            }
+            result.extend(children);
+        }
+
+        Ok(result)
+    }
+
+    /// Resolves properties of the `repeat` component, where count is the default property
+    fn repeat_properties(component: &ir::Component<Span>) -> ir::EffectiveProperties<'_, Span> {
+        ir::EffectiveProperties::resolve(&component.properties, Some("count"), [])
+    }
+
+    /// Returns name of the index variable of the `repeat` component
+    fn repeat_index(component: &ir::Component<Span>) -> Result<&str, IrGeneratorError> {
+        let Some(value) = Self::repeat_properties(component).get("index") else {
+            return Ok(DEFAULT_REPEAT_INDEX);
+        };
+
+        match &value.kind {
+            ir::ValueKind::String(string) => match string.segments.as_slice() {
+                [ir::InterpolationSegment {
+                    kind: ir::InterpolationSegmentKind::Literal(name),
+                    ..
+                }] if is_identifier(name) => Ok(name),
+                _ => Err(InvalidRepeatIndexError {
+                    span: value.span.clone(),
+                }
+                .into()),
+            },
+            _ => Err(InvalidRepeatIndexError {
+                span: value.span.clone(),
+            }
+            .into()),
+        }
+    }
+
+    /// Substitutes index of the repetition. Nested repetitions
+    /// with the same index shadow it, so their children are left intact
+    fn substitute_index(
+        mut component: ir::Component<Span>,
+        scope: &Scope,
+        index: &str,
+    ) -> Result<ir::Component<Span>, IrGeneratorError> {
+        let is_shadowed = component.name.as_str() == REPEAT
+            && Self::repeat_index(&component).is_ok_and(|name| name == index);
+        let children = std::mem::take(&mut component.children);
+        let children = if is_shadowed {
+            children
+        } else {
+            children
+                .into_iter()
+                .map(|child| Self::substitute_index(child, scope, index))
+                .collect::<Result<_, _>>()?
+        };
+
+        Ok(ir::Component {
+            children,
+            ..Self::substitute_component(component, scope)?
+        })
+    }
+
+    /// Checks whether the component is the slot of the definition that is being expanded
+    fn is_slot_placeholder(&self, component: &ir::Component<Span>) -> bool {
+        self.stack
+            .last()
+            .and_then(|name| self.definitions.get(name))
+            .and_then(|def| Self::find_slot(def))
+            .is_some_and(|slot| slot.name.as_str() == component.name.as_str())
+    }
+
+    fn find_slot(def: &ir::ComponentDefinition<Span>) -> Option<&ir::PropertyDefinition<Span>> {
+        def.properties.properties.iter().find(|property| {
+            matches!(
+                property.ty.kind,
+                ir::TypeKind::Slot | ir::TypeKind::SlotList
+            )
+        })
+    }
+
+    /// Expands children of the component instance, which fill the slot of the definition
+    fn create_slot(
+        &mut self,
+        def: &ir::ComponentDefinition<Span>,
+        component: &ir::Component<Span>,
+        children: Vec<ir::Component<Span>>,
+    ) -> Result<Option<Slot>, IrGeneratorError> {
+        let Some(slot) = Self::find_slot(def) else {
+            if children.is_empty() {
+                return Ok(None);
+            }
+
+            return Err(UnexpectedChildrenError {
+                name: def.name.clone().into(),
+                definition: def.name.span.clone(),
+                component: component.span.clone(),
+            }
+            .into());
+        };
+        if matches!(slot.ty.kind, ir::TypeKind::Slot) && children.len() > 1 {
+            return Err(MultipleSlotChildrenError {
+                name: slot.name.clone().into(),
+                property: slot.span.clone(),
+                component: component.span.clone(),
+            }
+            .into());
+        }
+
+        let content = self.expand_children(children)?;
+
+        Ok(Some((slot.name.as_str().to_owned(), content)))
+    }
+
+    /// Replaces components named after the slot with its content.
+    /// Content itself isn't searched, since it belongs to the outer scope
+    fn fill_slot(
+        components: Vec<ir::Component<Span>>,
+        name: &str,
+        content: &[ir::Component<Span>],
+    ) -> Vec<ir::Component<Span>> {
+        let mut result = Vec::with_capacity(components.len());
+        for component in components {
+            if component.name.as_str() == name {
+                result.extend(content.iter().cloned());
+            } else {
+                result.push(ir::Component {
+                    children: Self::fill_slot(component.children, name, content),
+                    ..component
+                });
+            }
+        }
+
+        result
+    }
+
+    /// Creates key of the expansion from the property values of the instance.
+    /// Top-level variables don't change during the expansion, so they aren't included
+    fn create_key(name: &str, properties: &Scope) -> Option<ExpansionKey> {
+        let mut values = properties
+            .iter()
+            .map(|(name, value)| Some((name.clone(), ValueKey::new(value)?)))
+            .collect::<Option<Vec<_>>>()?;
+        values.sort_unstable_by(|(a, _), (b, _)| a.cmp(b));
+
+        Some((name.to_owned(), values))
+    }
+
+    fn expand_children(
+        &mut self,
+        children: Vec<ir::Component<Span>>,
+    ) -> Result<Vec<ir::Component<Span>>, IrGeneratorError> {
+        let mut result = Vec::new();
+        for child in children {
+            result.extend(self.expand_component(child)?);
+        }
+
+        Ok(result)
+    }
+
+    /// Expands inline components of the text, each of which can turn into several ones
+    fn expand_text(
+        &mut self,
+        text: Option<ir::Text<Span>>,
+    ) -> Result<Option<ir::Text<Span>>, IrGeneratorError> {
+        let Some(mut text) = text else {
+            return Ok(None);
+        };
+
+        let mut segments = Vec::with_capacity(text.segments.len());
+        for segment in text.segments {
+            let ir::InterpolationSegmentKind::Component(component) = segment.kind else {
+                segments.push(segment);
+                continue;
+            };
+
+            for component in self.expand_component(*component)? {
+                segments.push(
+                    ir::InterpolationSegmentKind::Component(Box::new(component))
+                        .spanned(segment.span.clone()),
+                );
+            }
+        }
+        text.segments = segments;
+
+        Ok(Some(text))
+    }
+
+    /// Collects property values of the component instance,
+    /// falling back to default values from the definition
+    fn create_scope(def: &ir::ComponentDefinition<Span>, component: ir::Component<Span>) -> Scope {
+        let mut scope = Scope::new();
+        let properties =
+            ir::EffectiveProperties::from_definition(&def.properties, &component.properties);
+        for (name, value) in properties.values {
+            scope.insert(name.to_owned(), value.clone());
+        }
+        for (name, flag) in properties.flags {
+            let value = ir::ValueKind::Bool(true).spanned(flag.span.clone());
+            scope.insert(name.to_owned(), value);
+        }
+        for (name, flag) in properties.negated_flags {
+            let value = ir::ValueKind::Bool(false).spanned(flag.span.clone());
+            scope.insert(name.to_owned(), value);
+        }
+        if let (Some(name), Some(text)) = (&def.properties.text_property, component.text) {
+            let value = ir::ValueKind::String(ir::StringValue {
+                span: text.span.clone(),
+                segments: text.segments,
+            })
+            .spanned(text.span);
+            scope.insert(name.as_str().to_owned(), value);
+        }
+
+        scope
+    }
+
+    /// Checks that all variables of the expanded component are resolved,
+    /// reporting the first of the undefined ones along with the available top-level variables
+    fn check_variables(&self, component: &ir::Component<Span>) -> Result<(), IrGeneratorError> {
+        let mut undefined = Vec::new();
+        collect_variables(component, &mut undefined);
+        let Some(name) = undefined
+            .into_iter()
+            .min_by_key(|name| name.span.start.offset())
+        else {
+            return Ok(());
+        };
+
+        let mut available = self.variables.keys().cloned().collect::<Vec<_>>();
+        available.sort();
+        Err(UndefinedVariableError {
+            name: name.as_str().to_owned(),
+            available,
+            span: name.span.clone(),
+        }
+        .into())
+    }
+
+    fn substitute_component(
+        component: ir::Component<Span>,
+        scope: &impl Variables,
+    ) -> Result<ir::Component<Span>, IrGeneratorError> {
+        let properties = component.properties;
+        Ok(ir::Component {
+            span: component.span,
+            name: component.name,
+            properties: ir::Properties {
+                default: properties
+                    .default
+                    .map(|value| Self::substitute_value(value, scope))
+                    .transpose()?,
+                flag_properties: properties.flag_properties,
+                negated_flags: properties.negated_flags,
+                named_properties: properties
+                    .named_properties
+                    .into_iter()
+                    .map(|property| {
+                        Ok(ir::Property {
+                            value: Self::substitute_value(property.value, scope)?,
+                            ..property
+                        })
+                    })
+                    .collect::<Result<HashSet<_>, IrGeneratorError>>()?,
+            },
+            children: component
+                .children
+                .into_iter()
+                .map(|child| Self::substitute_component(child, scope))
+                .collect::<Result<_, _>>()?,
+            text: component
+                .text
+                .map(|text| {
+                    Ok::<_, IrGeneratorError>(ir::Text {
+                        span: text.span,
+                        segments: Self::substitute_segments(text.segments, scope)?,
+                        raw: text.raw,
+                    })
+                })
+                .transpose()?,
+        })
+    }
+
+    fn substitute_value(
+        value: ir::Value<Span>,
+        scope: &impl Variables,
+    ) -> Result<ir::Value<Span>, IrGeneratorError> {
+        Ok(match value.kind {
+            ir::ValueKind::Variable(name) => match scope.get(name.as_str()) {
+                Some(resolved) => resolved.clone(),
+                None => ir::ValueKind::Variable(name).spanned(value.span),
+            },
+            ir::ValueKind::String(string) => ir::ValueKind::String(ir::StringValue {
+                span: string.span,
+                segments: Self::substitute_segments(string.segments, scope)?,
+            })
+            .spanned(value.span),
+            kind => kind.spanned(value.span),
+        })
+    }
+
+    fn substitute_segments(
+        segments: Vec<ir::InterpolationSegment<Span>>,
+        scope: &impl Variables,
+    ) -> Result<Vec<ir::InterpolationSegment<Span>>, IrGeneratorError> {
+        let mut result = Vec::new();
+        for segment in segments {
+            let name = match segment.kind {
+                ir::InterpolationSegmentKind::Variable(ref name) => name,
+                ir::InterpolationSegmentKind::Formatted(ref variable) => {
+                    match scope.get(variable.name.as_str()) {
+                        Some(value) if !matches!(value.kind, ir::ValueKind::Variable(_)) => {
+                            let literal = format::format_value(&value.kind, &variable.spec)
+                                .map_err(|help| InvalidFormatError {
+                                    name: variable.name.as_str().to_owned(),
+                                    spec: variable.spec.to_string(),
+                                    help: help.to_owned(),
+                                    span: segment.span.clone(),
+                                })?;
+                            result.push(
+                                ir::InterpolationSegmentKind::Literal(literal)
+                                    .spanned(segment.span),
+                            );
+                        }
+                        _ => result.push(segment),
+                    }
+                    continue;
+                }
+                ir::InterpolationSegmentKind::Component(component) => {
+                    result.push(
+                        ir::InterpolationSegmentKind::Component(Box::new(
+                            Self::substitute_component(*component, scope)?,
+                        ))
+                        .spanned(segment.span),
+                    );
+                    continue;
+                }
+                _ => {
+                    result.push(segment);
+                    continue;
+                }
+            };
+
+            match scope.get(name.as_str()).map(|value| &value.kind) {
+                Some(ir::ValueKind::String(string)) => result.extend(string.segments.clone()),
+                Some(ir::ValueKind::Integer(value)) => result.push(
+                    ir::InterpolationSegmentKind::Literal(value.to_string()).spanned(segment.span),
+                ),
+                Some(ir::ValueKind::Bool(value)) => result.push(
+                    ir::InterpolationSegmentKind::Literal(value.to_string()).spanned(segment.span),
+                ),
+                Some(ir::ValueKind::Variable(_)) | None => result.push(segment),
+            }
+        }
+
+        Ok(result)
+    }
+
+    fn localize_component(
+        component: ir::Component<Span>,
+        messages: &HashMap<String, String>,
+    ) -> Result<ir::Component<Span>, IrGeneratorError> {
+        let properties = component.properties;
+        Ok(ir::Component {
+            span: component.span,
+            name: component.name,
+            properties: ir::Properties {
+                default: properties
+                    .default
+                    .map(|value| Self::localize_value(value, messages))
+                    .transpose()?,
+                flag_properties: properties.flag_properties,
+                negated_flags: properties.negated_flags,
+                named_properties: properties
+                    .named_properties
+                    .into_iter()
+                    .map(|property| {
+                        Ok(ir::Property {
+                            value: Self::localize_value(property.value, messages)?,
+                            ..property
+                        })
+                    })
+                    .collect::<Result<HashSet<_>, IrGeneratorError>>()?,
+            },
+            children: component
+                .children
+                .into_iter()
+                .map(|child| Self::localize_component(child, messages))
+                .collect::<Result<_, _>>()?,
+            text: component
+                .text
+                .map(|text| {
+                    Ok::<_, IrGeneratorError>(ir::Text {
+                        span: text.span,
+                        segments: Self::localize_segments(text.segments, messages)?,
+                        raw: text.raw,
+                    })
+                })
+                .transpose()?,
+        })
+    }
+
+    fn localize_value(
+        value: ir::Value<Span>,
+        messages: &HashMap<String, String>,
+    ) -> Result<ir::Value<Span>, IrGeneratorError> {
+        Ok(match value.kind {
+            ir::ValueKind::String(string) => ir::ValueKind::String(ir::StringValue {
+                span: string.span,
+                segments: Self::localize_segments(string.segments, messages)?,
+            })
+            .spanned(value.span),
+            kind => kind.spanned(value.span),
+        })
+    }
+
+    fn localize_segments(
+        segments: Vec<ir::InterpolationSegment<Span>>,
+        messages: &HashMap<String, String>,
+    ) -> Result<Vec<ir::InterpolationSegment<Span>>, IrGeneratorError> {
+        segments
+            .into_iter()
+            .map(|segment| {
+                let key = match segment.kind {
+                    ir::InterpolationSegmentKind::Message(key) => key,
+                    ir::InterpolationSegmentKind::Component(component) => {
+                        return Ok(ir::InterpolationSegmentKind::Component(Box::new(
+                            Self::localize_component(*component, messages)?,
+                        ))
+                        .spanned(segment.span));
+                    }
+                    kind => return Ok(kind.spanned(segment.span)),
+                };
+
+                match messages.get(&key) {
+                    Some(message) => Ok(ir::InterpolationSegmentKind::Literal(message.clone())
+                        .spanned(segment.span)),
+                    None => Err(MissingMessageError {
+                        key,
+                        span: segment.span,
+                    }
+                    .into()),
+                }
+            })
+            .collect()
+    }
+}
+
+/// Collects names of the variables that are left in the component
+fn collect_variables<'c>(
+    component: &'c ir::Component<Span>,
+    variables: &mut Vec<&'c ir::Identifier<Span>>,
+) {
+    fn collect_segments<'c>(
+        segments: &'c [ir::InterpolationSegment<Span>],
+        variables: &mut Vec<&'c ir::Identifier<Span>>,
+    ) {
+        for segment in segments {
+            match &segment.kind {
+                ir::InterpolationSegmentKind::Variable(name) => variables.push(name),
+                ir::InterpolationSegmentKind::Formatted(variable) => variables.push(&variable.name),
+                ir::InterpolationSegmentKind::Component(component) => {
+                    collect_variables(component, variables)
+                }
+                _ => {}
+            }
+        }
+    }
+
+    let properties = &component.properties;
+    let values = properties.default.iter().chain(
+        properties
+            .named_properties
+            .iter()
+            .map(|property| &property.value),
+    );
+    for value in values {
+        match &value.kind {
+            ir::ValueKind::Variable(name) => variables.push(name),
+            ir::ValueKind::String(string) => collect_segments(&string.segments, variables),
+            _ => {}
+        }
+    }
+    if let Some(text) = &component.text {
+        collect_segments(&text.segments, variables);
+    }
+    for child in &component.children {
+        collect_variables(child, variables);
+    }
+}
+
+fn is_identifier(name: &str) -> bool {
+    let mut chars = name.chars();
+    chars
+        .next()
+        .is_some_and(|char| char.is_ascii_alphabetic() || char == '_')
+        && chars.all(|char| char.is_ascii_alphanumeric() || char == '_')
+}
+
+/// Counts the component along with its descendants
+fn component_count(component: &ir::Component<Span>) -> usize {
+    1 + component
+        .children
+        .iter()
+        .map(component_count)
+        .sum::<usize>()
+}
@@ -10,6 +10,14 @@ pub enum IrGeneratorError {
     #[error(transparent)]
     #[diagnostic(transparent)]
     DuplicatedProperty(#[from] DuplicatedPropertyError),
+    /// Constant name is declared multiple times
+    #[error(transparent)]
+    #[diagnostic(transparent)]
+    DuplicatedConstant(#[from] DuplicatedConstantError),
+    /// Component is defined multiple times
+    #[error(transparent)]
+    #[diagnostic(transparent)]
+    DuplicatedComponentDefinition(#[from] DuplicatedComponentDefinitionError),
     /// Component has children and text at the same time
     #[error(transparent)]
     #[diagnostic(transparent)]
@@ -26,6 +34,62 @@ pub enum IrGeneratorError {
     #[error(transparent)]
     #[diagnostic(transparent)]
     DefaultPropertyWithValue(#[from] DefaultPropertyWithValueError),
+    /// Component is used inside of its own expansion
+    #[error(transparent)]
+    #[diagnostic(transparent)]
+    RecursiveExpansion(#[from] RecursiveExpansionError),
+    /// Component definition has multiple `slot` or `slot[]` properties
+    #[error(transparent)]
+    #[diagnostic(transparent)]
+    MultipleSlotProperties(#[from] MultipleSlotPropertiesError),
+    /// Children are passed to the component without slot property
+    #[error(transparent)]
+    #[diagnostic(transparent)]
+    UnexpectedChildren(#[from] UnexpectedChildrenError),
+    /// Multiple children are passed to the `slot` property
+    #[error(transparent)]
+    #[diagnostic(transparent)]
+    MultipleSlotChildren(#[from] MultipleSlotChildrenError),
+    /// Value doesn't match declared type of the property
+    #[error(transparent)]
+    #[diagnostic(transparent)]
+    TypeMismatch(#[from] TypeMismatchError),
+    /// Required property of the component isn't specified
+    #[error(transparent)]
+    #[diagnostic(transparent)]
+    MissingProperty(#[from] MissingPropertyError),
+    /// Property isn't declared in the component definition
+    #[error(transparent)]
+    #[diagnostic(transparent)]
+    UnknownProperty(#[from] UnknownPropertyError),
+    /// Default property is specified for the component without one
+    #[error(transparent)]
+    #[diagnostic(transparent)]
+    UnexpectedDefaultProperty(#[from] UnexpectedDefaultPropertyError),
+    /// Variable is neither a property, nor a constant or externally supplied variable
+    #[error(transparent)]
+    #[diagnostic(transparent)]
+    UndefinedVariable(#[from] UndefinedVariableError),
+    /// Value of the variable doesn't support its format specifier
+    #[error(transparent)]
+    #[diagnostic(transparent)]
+    InvalidFormat(#[from] InvalidFormatError),
+    /// Message is missing from the catalog
+    #[error(transparent)]
+    #[diagnostic(transparent)]
+    MissingMessage(#[from] MissingMessageError),
+    /// Count of the `repeat` component isn't a non-negative integer
+    #[error(transparent)]
+    #[diagnostic(transparent)]
+    InvalidRepeatCount(#[from] InvalidRepeatCountError),
+    /// Index of the `repeat` component isn't an identifier
+    #[error(transparent)]
+    #[diagnostic(transparent)]
+    InvalidRepeatIndex(#[from] InvalidRepeatIndexError),
+    /// Repetition exceeds the maximum count or number of the produced components
+    #[error(transparent)]
+    #[diagnostic(transparent)]
+    RepeatLimit(#[from] RepeatLimitError),
 }
 
 #[derive(Debug, Error, Diagnostic)]
@@ -42,6 +106,34 @@ pub struct DuplicatedPropertyError {
     pub second: Span,
 }
 
+#[derive(Debug, Error, Diagnostic)]
+#[error("Constant named '{name}' is duplicated")]
+#[diagnostic(help("Rename one of the constants"))]
+pub struct DuplicatedConstantError {
+    /// Name of the constant
+    pub name: String,
+    /// Place where the constant was first declared
+    #[label("First declared here")]
+    pub first: Span,
+    /// Place where the constant was declared again
+    #[label("Then declared here")]
+    pub second: Span,
+}
+
+#[derive(Debug, Error, Diagnostic)]
+#[error("Component '{name}' is defined multiple times")]
+#[diagnostic(help("Rename or remove one of the definitions"))]
+pub struct DuplicatedComponentDefinitionError {
+    /// Name of the component
+    pub name: String,
+    /// Place where the component was first defined
+    #[label("First defined here")]
+    pub first: Span,
+    /// Place where the component was defined again
+    #[label("Then defined here")]
+    pub second: Span,
+}
+
 #[derive(Debug, Error, Diagnostic)]
 #[error("Text component can't have children")]
 #[diagnostic(help("Either remove text or children from the component"))]
@@ -112,3 +204,244 @@ pub struct DefaultPropertyWithValueError {
     #[label("Default value")]
     pub default_value: Span,
 }
+
+#[derive(Debug, Error, Diagnostic)]
+#[error("Component '{name}' is used inside of its own expansion")]
+#[diagnostic(help("Remove indirect reference to the component from its definition"))]
+pub struct RecursiveExpansionError {
+    /// Name of the component
+    pub name: String,
+    /// Span with name of the component definition
+    #[label("Component defined here")]
+    pub definition: Span,
+    /// Place where the component was used recursively
+    #[label("Recursively used here")]
+    pub component: Span,
+}
+
+#[derive(Debug, Error, Diagnostic)]
+#[error("Message '{key}' is missing from the catalog")]
+#[diagnostic(help("Add the message to the catalog of the current locale"))]
+pub struct MissingMessageError {
+    /// Key of the message
+    pub key: String,
+    /// Place where the message was used
+    #[label("Used here")]
+    pub span: Span,
+}
+
+#[derive(Debug, Error, Diagnostic)]
+#[error("Component defines multiple slot properties")]
+#[diagnostic(help("Children of the component fill single `slot` or `slot[]` property"))]
+pub struct MultipleSlotPropertiesError {
+    /// Span with properties of the component
+    #[label("Properties")]
+    pub properties: Span,
+    /// Place where the property was first defined
+    #[label("First defined here")]
+    pub first: Span,
+    /// Place where the property was defined again
+    #[label("Then defined here")]
+    pub second: Span,
+}
+
+#[derive(Debug, Error, Diagnostic)]
+#[error("Component '{name}' doesn't accept children")]
+#[diagnostic(help("Add `slot` or `slot[]` property to the component definition"))]
+pub struct UnexpectedChildrenError {
+    /// Name of the component
+    pub name: String,
+    /// Span with name of the component definition
+    #[label("Component defined here")]
+    pub definition: Span,
+    /// Place where the component was used with children
+    #[label("Used with children here")]
+    pub component: Span,
+}
+
+#[derive(Debug, Error, Diagnostic)]
+#[error("Slot '{name}' accepts single component")]
+#[diagnostic(help("Change type of the property to `slot[]` to accept multiple components"))]
+pub struct MultipleSlotChildrenError {
+    /// Name of the slot property
+    pub name: String,
+    /// Place where the slot property was defined
+    #[label("Slot defined here")]
+    pub property: Span,
+    /// Place where the component was used with multiple children
+    #[label("Used with multiple children here")]
+    pub component: Span,
+}
+
+#[derive(Debug, Error, Diagnostic)]
+#[error("Expected value of type '{expected}', found '{found}'")]
+#[diagnostic(help("Change the value to match type of the property"))]
+pub struct TypeMismatchError {
+    /// Declared type of the property
+    pub expected: &'static str,
+    /// Type of the value
+    pub found: &'static str,
+    /// Place where the value was specified
+    #[label("Value")]
+    pub value: Span,
+    /// Place where the property was defined
+    #[label("Property defined here")]
+    pub property: Span,
+}
+
+#[derive(Debug, Error, Diagnostic)]
+#[error("Required property '{name}' is missing")]
+#[diagnostic(help("Specify the property or add default value to its definition"))]
+pub struct MissingPropertyError {
+    /// Name of the property
+    pub name: String,
+    /// Component without the property
+    #[label("Component")]
+    pub component: Span,
+    /// Place where the property was defined
+    #[label("Property defined here")]
+    pub property: Span,
+}
+
+#[derive(Debug, Error, Diagnostic)]
+#[error("Component '{component}' doesn't have property '{name}'")]
+#[diagnostic(help("Remove the property or declare it in the component definition"))]
+pub struct UnknownPropertyError {
+    /// Name of the property
+    pub name: String,
+    /// Name of the component
+    pub component: String,
+    /// Place where the property was specified
+    #[label("Unknown property")]
+    pub property: Span,
+    /// Span with name of the component definition
+    #[label("Component defined here")]
+    pub definition: Span,
+}
+
+#[derive(Debug, Error, Diagnostic)]
+#[error("Component '{name}' doesn't have default property")]
+#[diagnostic(help("Specify the property by name or declare default property in the definition"))]
+pub struct UnexpectedDefaultPropertyError {
+    /// Name of the component
+    pub name: String,
+    /// Span with name of the component definition
+    #[label("Component defined here")]
+    pub definition: Span,
+    /// Place where the value was specified
+    #[label("Value")]
+    pub value: Span,
+}
+
+#[derive(Debug, Error, Diagnostic)]
+#[error("Count of the repetition must be a non-negative integer")]
+#[diagnostic(help("Specify count like `repeat[3]` or `repeat[count = 3]`"))]
+pub struct InvalidRepeatCountError {
+    /// Place where the count was specified, or the component, if it's missing
+    #[label("Count given here")]
+    pub span: Span,
+}
+
+#[derive(Debug, Error, Diagnostic)]
+#[error("Repetition is too large")]
+#[diagnostic(help("{help}"))]
+pub struct RepeatLimitError {
+    /// Description of the exceeded limit
+    pub help: String,
+    /// Place of the repetition
+    #[label("Repeated here")]
+    pub span: Span,
+}
+
+#[derive(Debug, Error, Diagnostic)]
+#[error("Index of the repetition must be a name of the variable")]
+#[diagnostic(help("Specify index like `repeat[3, index = \"i\"]` and use it as `${{i}}`"))]
+pub struct InvalidRepeatIndexError {
+    /// Place where the index was specified
+    #[label("Index given here")]
+    pub span: Span,
+}
+
+#[derive(Debug, Error, Diagnostic)]
+#[error("Variable '{name}' can't be formatted with '{spec}'")]
+#[diagnostic(help("{help}"))]
+pub struct InvalidFormatError {
+    /// Name of the variable
+    pub name: String,
+    /// Format specifier
+    pub spec: String,
+    /// Reason why the value can't be formatted
+    pub help: String,
+    /// Place where the variable is formatted
+    #[label("Formatted here")]
+    pub span: Span,
+}
+
+#[derive(Debug, Error, Diagnostic)]
+#[error("Variable '{name}' is undefined")]
+#[diagnostic(help("{}", available_help(available)))]
+pub struct UndefinedVariableError {
+    /// Name of the variable
+    pub name: String,
+    /// Names of the top-level variables, which are available in the whole module
+    pub available: Vec<String>,
+    /// Place where the variable is used
+    #[label("Used here")]
+    pub span: Span,
+}
+
+/// Represents warning of the IR analysis, that doesn't prevent the conversion
+#[derive(Debug, Clone, Error, Diagnostic)]
+pub enum IrWarning {
+    /// Component definition is never instantiated
+    #[error(transparent)]
+    #[diagnostic(transparent)]
+    UnusedDefinition(#[from] UnusedDefinitionWarning),
+    /// Flag has the same name as a variable in scope
+    #[error(transparent)]
+    #[diagnostic(transparent)]
+    AmbiguousFlag(#[from] AmbiguousFlagWarning),
+}
+
+#[derive(Debug, Clone, Error, Diagnostic)]
+#[error("Component '{name}' is never used")]
+#[diagnostic(
+    severity(Warning),
+    code(unused_definition),
+    help("Remove the definition or use the component")
+)]
+pub struct UnusedDefinitionWarning {
+    /// Name of the component
+    pub name: String,
+    /// Span with name of the component definition
+    #[label("Defined here")]
+    pub span: Span,
+}
+
+#[derive(Debug, Clone, Error, Diagnostic)]
+#[error("Flag '{name}' has the same name as a variable")]
+#[diagnostic(
+    severity(Warning),
+    code(ambiguous_flag),
+    help(
+        "Bare names in properties are always flags. Write `${{{name}}}` to pass the variable \
+        as the default property, or `{name} = ${{{name}}}` to pass it as the named property"
+    )
+)]
+pub struct AmbiguousFlagWarning {
+    /// Name of the flag
+    pub name: String,
+    /// Name of the component definition, that uses the flag
+    pub definition: String,
+    /// Place where the flag is set
+    #[label("Set as a flag here")]
+    pub span: Span,
+}
+
+fn available_help(available: &[String]) -> String {
+    if available.is_empty() {
+        "No variables are available. Declare constant with `let` or supply the variable".to_owned()
+    } else {
+        format!("Available variables: {}", available.join(", "))
+    }
+}
@@ -0,0 +1,117 @@
+//! Formatting of the interpolated values according to their format specifiers
+
+use crate::ir::{self, FormatAlign, FormatSpec};
+use std::iter;
+
+/// Formats value of the variable. Integers and strings with decimal numbers are numbers:
+/// they are aligned to the right by default and support zero padding and precision.
+/// Returns the reason, why the value can't be formatted
+pub fn format_value<SpanT: Eq>(
+    value: &ir::ValueKind<SpanT>,
+    spec: &FormatSpec,
+) -> Result<String, &'static str> {
+    let (text, is_number) = match value {
+        ir::ValueKind::Integer(value) => match spec.precision {
+            Some(precision) if precision > 0 => (format!("{value}.{:0<precision$}", ""), true),
+            _ => (value.to_string(), true),
+        },
+        ir::ValueKind::Bool(value) => (value.to_string(), false),
+        ir::ValueKind::String(string) => {
+            let mut text = String::new();
+            for segment in &string.segments {
+                match &segment.kind {
+                    ir::InterpolationSegmentKind::Literal(literal) => text.push_str(literal),
+                    _ => return Err("Only values without interpolation can be formatted"),
+                }
+            }
+            match spec.precision {
+                Some(precision) if is_decimal(&text) => (round_decimal(&text, precision), true),
+                _ => {
+                    let is_number = is_decimal(&text);
+                    (text, is_number)
+                }
+            }
+        }
+        ir::ValueKind::Variable(_) => return Err("Value of the variable isn't resolved"),
+    };
+    if !is_number && spec.precision.is_some() {
+        return Err("Precision can only be used with numbers");
+    }
+    if !is_number && spec.zero_pad {
+        return Err("Zero padding can only be used with numbers");
+    }
+
+    let length = text.chars().count();
+    let Some(padding) = spec.width.and_then(|width| width.checked_sub(length)) else {
+        return Ok(text);
+    };
+    if spec.zero_pad {
+        let (sign, digits) = match text.strip_prefix('-') {
+            Some(digits) => ("-", digits),
+            None => ("", text.as_str()),
+        };
+        return Ok(format!("{sign}{}{digits}", "0".repeat(padding)));
+    }
+
+    let default = if is_number {
+        FormatAlign::Right
+    } else {
+        FormatAlign::Left
+    };
+    let (before, after) = match spec.align.unwrap_or(default) {
+        FormatAlign::Left => (0, padding),
+        FormatAlign::Center => (padding / 2, padding - padding / 2),
+        FormatAlign::Right => (padding, 0),
+    };
+
+    Ok(format!("{}{text}{}", " ".repeat(before), " ".repeat(after)))
+}
+
+/// Checks whether the text is a decimal number, like `-12` or `3.25`
+fn is_decimal(text: &str) -> bool {
+    let digits = text.strip_prefix('-').unwrap_or(text);
+    let (integer, fraction) = digits.split_once('.').unwrap_or((digits, ""));
+
+    !integer.is_empty()
+        && integer.bytes().all(|digit| digit.is_ascii_digit())
+        && fraction.bytes().all(|digit| digit.is_ascii_digit())
+        && !digits.ends_with('.')
+}
+
+/// Rounds the decimal number half away from zero, keeping given number of fractional digits
+fn round_decimal(number: &str, precision: usize) -> String {
+    let (sign, digits) = match number.strip_prefix('-') {
+        Some(digits) => ("-", digits),
+        None => ("", number),
+    };
+    let (integer, fraction) = digits.split_once('.').unwrap_or((digits, ""));
+    let mut result = integer
+        .bytes()
+        .chain(fraction.bytes().chain(iter::repeat(b'0')).take(precision))
+        .collect::<Vec<_>>();
+    let mut integer_length = integer.len();
+
+    if fraction
+        .as_bytes()
+        .get(precision)
+        .is_some_and(|digit| *digit >= b'5')
+    {
+        let carry = result.iter_mut().rev().all(|digit| {
+            let is_overflow = *digit == b'9';
+            *digit = if is_overflow { b'0' } else { *digit + 1 };
+            is_overflow
+        });
+        if carry {
+            result.insert(0, b'1');
+            integer_length += 1;
+        }
+    }
+
+    let (integer, fraction) = result.split_at(integer_length);
+    let integer = String::from_utf8_lossy(integer);
+    if fraction.is_empty() {
+        format!("{sign}{integer}")
+    } else {
+        format!("{sign}{integer}.{}", String::from_utf8_lossy(fraction))
+    }
+}
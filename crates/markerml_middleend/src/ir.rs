@@ -1,20 +1,39 @@
 use std::borrow::Borrow;
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet};
 use std::hash::{Hash, Hasher};
 
+pub mod symbol;
+pub mod visit;
+
+pub use markerml_frontend::ast::{FormatAlign, FormatSpec};
+pub use symbol::{Interner, Symbol};
+pub use visit::{Transform, Visitor, VisitorMut};
+
 #[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct Module<SpanT: Eq> {
     pub span: SpanT,
     pub items: Vec<ModuleItem<SpanT>>,
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub enum ModuleItem<SpanT: Eq> {
+    Constant(Constant<SpanT>),
     Component(Component<SpanT>),
     ComponentDefinition(ComponentDefinition<SpanT>),
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct Constant<SpanT: Eq> {
+    pub span: SpanT,
+    pub name: Identifier<SpanT>,
+    pub value: Value<SpanT>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct Component<SpanT: Eq> {
     pub span: SpanT,
     pub name: Identifier<SpanT>,
@@ -24,13 +43,27 @@ pub struct Component<SpanT: Eq> {
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct Properties<SpanT: Eq> {
     pub default: Option<Value<SpanT>>,
     pub flag_properties: HashSet<Identifier<SpanT>>,
+    /// Flags that are explicitly disabled with `!`
+    pub negated_flags: HashSet<Identifier<SpanT>>,
     pub named_properties: HashSet<Property<SpanT>>,
 }
 
+/// Resolved properties of the component instance: declared default values
+/// overlaid with the values given by the caller.
+/// Value of the default property is stored under its declared name
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EffectiveProperties<'a, SpanT: Eq> {
+    pub values: HashMap<&'a str, &'a Value<SpanT>>,
+    pub flags: HashMap<&'a str, &'a Identifier<SpanT>>,
+    pub negated_flags: HashMap<&'a str, &'a Identifier<SpanT>>,
+}
+
 #[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct Property<SpanT: Eq> {
     pub span: SpanT,
     pub key: Identifier<SpanT>,
@@ -38,6 +71,7 @@ pub struct Property<SpanT: Eq> {
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct ComponentDefinition<SpanT: Eq> {
     pub span: SpanT,
     pub name: Identifier<SpanT>,
@@ -46,6 +80,7 @@ pub struct ComponentDefinition<SpanT: Eq> {
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct PropertiesDefinition<SpanT: Eq> {
     pub span: SpanT,
     pub text_property: Option<Identifier<SpanT>>,
@@ -54,6 +89,7 @@ pub struct PropertiesDefinition<SpanT: Eq> {
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct PropertyDefinition<SpanT: Eq> {
     pub span: SpanT,
     pub name: Identifier<SpanT>,
@@ -62,12 +98,14 @@ pub struct PropertyDefinition<SpanT: Eq> {
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct Value<SpanT: Eq> {
     pub span: SpanT,
     pub kind: ValueKind<SpanT>,
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub enum ValueKind<SpanT: Eq> {
     String(StringValue<SpanT>),
     Integer(i64),
@@ -76,42 +114,66 @@ pub enum ValueKind<SpanT: Eq> {
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct StringValue<SpanT: Eq> {
     pub span: SpanT,
     pub segments: Vec<InterpolationSegment<SpanT>>,
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct Text<SpanT: Eq> {
     pub span: SpanT,
     pub segments: Vec<InterpolationSegment<SpanT>>,
+    /// Whether the text is raw, so its whitespace is significant
+    pub raw: bool,
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct InterpolationSegment<SpanT: Eq> {
     pub span: SpanT,
     pub kind: InterpolationSegmentKind<SpanT>,
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub enum InterpolationSegmentKind<SpanT: Eq> {
     Literal(String),
     Variable(Identifier<SpanT>),
+    /// Variable with the format specifier, like `${price:.2}`
+    Formatted(Box<FormattedVariable<SpanT>>),
+    /// Key of the localized message, that isn't resolved from the catalog yet
+    Message(String),
+    /// Component inside the text, like `$@[bold](word)`
+    Component(Box<Component<SpanT>>),
+    /// Line break of the multiline text
+    Newline,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct FormattedVariable<SpanT: Eq> {
+    pub name: Identifier<SpanT>,
+    pub spec: FormatSpec,
 }
 
 #[derive(Debug, Clone, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct Identifier<SpanT: Eq> {
     pub span: SpanT,
-    pub name: String,
+    pub name: Symbol,
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct Type<SpanT: Eq> {
     pub span: SpanT,
     pub kind: TypeKind,
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub enum TypeKind {
     String,
     Integer,
@@ -120,6 +182,84 @@ pub enum TypeKind {
     SlotList,
 }
 
+impl<'a, SpanT: Eq> EffectiveProperties<'a, SpanT> {
+    /// Resolves properties of the instance, given name of the default property
+    /// and declared default values. Named properties take precedence over the default one
+    pub fn resolve(
+        properties: &'a Properties<SpanT>,
+        default_property: Option<&'a str>,
+        defaults: impl IntoIterator<Item = (&'a str, &'a Value<SpanT>)>,
+    ) -> Self {
+        let mut values = defaults.into_iter().collect::<HashMap<_, _>>();
+        if let (Some(name), Some(value)) = (default_property, &properties.default) {
+            values.insert(name, value);
+        }
+        for property in &properties.named_properties {
+            values.insert(property.key.as_str(), &property.value);
+        }
+        let flags = properties
+            .flag_properties
+            .iter()
+            .map(|flag| (flag.as_str(), flag))
+            .collect();
+        let negated_flags = properties
+            .negated_flags
+            .iter()
+            .map(|flag| (flag.as_str(), flag))
+            .collect();
+
+        EffectiveProperties {
+            values,
+            flags,
+            negated_flags,
+        }
+    }
+
+    /// Resolves properties of the custom component instance from its definition
+    pub fn from_definition(
+        definition: &'a PropertiesDefinition<SpanT>,
+        properties: &'a Properties<SpanT>,
+    ) -> Self {
+        let defaults = definition.properties.iter().filter_map(|property| {
+            Some((property.name.as_str(), property.default_value.as_ref()?))
+        });
+
+        Self::resolve(
+            properties,
+            definition
+                .default_property
+                .as_ref()
+                .map(|property| property.name.as_str()),
+            defaults,
+        )
+    }
+
+    /// Returns value of the property
+    pub fn get(&self, name: &str) -> Option<&'a Value<SpanT>> {
+        self.values.get(name).copied()
+    }
+
+    /// Returns whether the flag is set, either as a flag or as a property with `true` value
+    pub fn has_flag(&self, name: &str) -> bool {
+        self.flag(name).unwrap_or(false)
+    }
+
+    /// Returns whether the flag is enabled or disabled, either explicitly with `!`
+    /// or as a property with bool value, or `None` if it isn't specified
+    pub fn flag(&self, name: &str) -> Option<bool> {
+        if self.flags.contains_key(name) {
+            Some(true)
+        } else if self.negated_flags.contains_key(name) {
+            Some(false)
+        } else {
+            match self.values.get(name).map(|value| &value.kind) {
+                Some(ValueKind::Bool(value)) => Some(*value),
+                _ => None,
+            }
+        }
+    }
+}
+
 impl<SpanT: Eq> Identifier<SpanT> {
     pub fn as_str(&self) -> &str {
         &self.name
@@ -130,7 +270,7 @@ impl<SpanT: Default + Eq> Identifier<SpanT> {
     pub fn from_literal(name: &str) -> Self {
         Identifier {
             span: Default::default(),
-            name: name.to_owned(),
+            name: name.into(),
         }
     }
 }
@@ -142,10 +282,23 @@ impl<SpanT: Default + Eq> Text<SpanT> {
             segments: vec![
                 InterpolationSegmentKind::Literal(string.to_owned()).spanned(Default::default())
             ],
+            raw: false,
         }
     }
 }
 
+impl<SpanT: Eq> Text<SpanT> {
+    /// Returns inline components of the text, without the nested ones
+    pub fn components(&self) -> impl Iterator<Item = &Component<SpanT>> {
+        self.segments
+            .iter()
+            .filter_map(|segment| match &segment.kind {
+                InterpolationSegmentKind::Component(component) => Some(component.as_ref()),
+                _ => None,
+            })
+    }
+}
+
 impl<SpanT: Default + Eq> StringValue<SpanT> {
     pub fn from_literal(string: &str) -> Self {
         StringValue {
@@ -159,7 +312,13 @@ impl<SpanT: Default + Eq> StringValue<SpanT> {
 
 impl<SpanT: Eq> From<Identifier<SpanT>> for String {
     fn from(identifier: Identifier<SpanT>) -> String {
-        identifier.name
+        identifier.name.into()
+    }
+}
+
+impl<SpanT: Eq> From<Constant<SpanT>> for ModuleItem<SpanT> {
+    fn from(constant: Constant<SpanT>) -> Self {
+        ModuleItem::Constant(constant)
     }
 }
 
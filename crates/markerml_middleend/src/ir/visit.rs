@@ -0,0 +1,506 @@
+use crate::ir::*;
+use std::collections::HashSet;
+use std::hash::Hash;
+
+/// Visitor of the IR. Every method walks into the children of the node by default,
+/// so implementations override only the nodes they are interested in
+/// and call the corresponding `walk_*` function to keep descending.
+/// Flags and named properties are visited in no particular order
+pub trait Visitor<SpanT: Eq> {
+    fn visit_module(&mut self, module: &Module<SpanT>) {
+        walk_module(self, module)
+    }
+
+    fn visit_module_item(&mut self, item: &ModuleItem<SpanT>) {
+        walk_module_item(self, item)
+    }
+
+    fn visit_constant(&mut self, constant: &Constant<SpanT>) {
+        walk_constant(self, constant)
+    }
+
+    fn visit_component(&mut self, component: &Component<SpanT>) {
+        walk_component(self, component)
+    }
+
+    fn visit_properties(&mut self, properties: &Properties<SpanT>) {
+        walk_properties(self, properties)
+    }
+
+    fn visit_property(&mut self, property: &Property<SpanT>) {
+        walk_property(self, property)
+    }
+
+    fn visit_component_definition(&mut self, definition: &ComponentDefinition<SpanT>) {
+        walk_component_definition(self, definition)
+    }
+
+    fn visit_property_definition(&mut self, definition: &PropertyDefinition<SpanT>) {
+        walk_property_definition(self, definition)
+    }
+
+    fn visit_value(&mut self, value: &Value<SpanT>) {
+        walk_value(self, value)
+    }
+
+    fn visit_text(&mut self, text: &Text<SpanT>) {
+        walk_text(self, text)
+    }
+
+    fn visit_interpolation_segment(&mut self, segment: &InterpolationSegment<SpanT>) {
+        walk_interpolation_segment(self, segment)
+    }
+
+    fn visit_identifier(&mut self, _identifier: &Identifier<SpanT>) {}
+
+    fn visit_type(&mut self, _ty: &Type<SpanT>) {}
+}
+
+pub fn walk_module<SpanT: Eq, V: Visitor<SpanT> + ?Sized>(visitor: &mut V, module: &Module<SpanT>) {
+    for item in &module.items {
+        visitor.visit_module_item(item);
+    }
+}
+
+pub fn walk_module_item<SpanT: Eq, V: Visitor<SpanT> + ?Sized>(
+    visitor: &mut V,
+    item: &ModuleItem<SpanT>,
+) {
+    match item {
+        ModuleItem::Constant(constant) => visitor.visit_constant(constant),
+        ModuleItem::Component(component) => visitor.visit_component(component),
+        ModuleItem::ComponentDefinition(definition) => {
+            visitor.visit_component_definition(definition)
+        }
+    }
+}
+
+pub fn walk_constant<SpanT: Eq, V: Visitor<SpanT> + ?Sized>(
+    visitor: &mut V,
+    constant: &Constant<SpanT>,
+) {
+    visitor.visit_identifier(&constant.name);
+    visitor.visit_value(&constant.value);
+}
+
+pub fn walk_component<SpanT: Eq, V: Visitor<SpanT> + ?Sized>(
+    visitor: &mut V,
+    component: &Component<SpanT>,
+) {
+    visitor.visit_identifier(&component.name);
+    visitor.visit_properties(&component.properties);
+    for child in &component.children {
+        visitor.visit_component(child);
+    }
+    if let Some(text) = &component.text {
+        visitor.visit_text(text);
+    }
+}
+
+pub fn walk_properties<SpanT: Eq, V: Visitor<SpanT> + ?Sized>(
+    visitor: &mut V,
+    properties: &Properties<SpanT>,
+) {
+    if let Some(value) = &properties.default {
+        visitor.visit_value(value);
+    }
+    for flag in &properties.flag_properties {
+        visitor.visit_identifier(flag);
+    }
+    for flag in &properties.negated_flags {
+        visitor.visit_identifier(flag);
+    }
+    for property in &properties.named_properties {
+        visitor.visit_property(property);
+    }
+}
+
+pub fn walk_property<SpanT: Eq, V: Visitor<SpanT> + ?Sized>(
+    visitor: &mut V,
+    property: &Property<SpanT>,
+) {
+    visitor.visit_identifier(&property.key);
+    visitor.visit_value(&property.value);
+}
+
+pub fn walk_component_definition<SpanT: Eq, V: Visitor<SpanT> + ?Sized>(
+    visitor: &mut V,
+    definition: &ComponentDefinition<SpanT>,
+) {
+    visitor.visit_identifier(&definition.name);
+    let properties = &definition.properties;
+    if let Some(text) = &properties.text_property {
+        visitor.visit_identifier(text);
+    }
+    if let Some(property) = &properties.default_property {
+        visitor.visit_property_definition(property);
+    }
+    for property in &properties.properties {
+        visitor.visit_property_definition(property);
+    }
+    for child in &definition.children {
+        visitor.visit_component(child);
+    }
+}
+
+pub fn walk_property_definition<SpanT: Eq, V: Visitor<SpanT> + ?Sized>(
+    visitor: &mut V,
+    definition: &PropertyDefinition<SpanT>,
+) {
+    visitor.visit_identifier(&definition.name);
+    visitor.visit_type(&definition.ty);
+    if let Some(value) = &definition.default_value {
+        visitor.visit_value(value);
+    }
+}
+
+pub fn walk_value<SpanT: Eq, V: Visitor<SpanT> + ?Sized>(visitor: &mut V, value: &Value<SpanT>) {
+    match &value.kind {
+        ValueKind::String(string) => {
+            for segment in &string.segments {
+                visitor.visit_interpolation_segment(segment);
+            }
+        }
+        ValueKind::Variable(name) => visitor.visit_identifier(name),
+        ValueKind::Integer(_) | ValueKind::Bool(_) => {}
+    }
+}
+
+pub fn walk_text<SpanT: Eq, V: Visitor<SpanT> + ?Sized>(visitor: &mut V, text: &Text<SpanT>) {
+    for segment in &text.segments {
+        visitor.visit_interpolation_segment(segment);
+    }
+}
+
+pub fn walk_interpolation_segment<SpanT: Eq, V: Visitor<SpanT> + ?Sized>(
+    visitor: &mut V,
+    segment: &InterpolationSegment<SpanT>,
+) {
+    match &segment.kind {
+        InterpolationSegmentKind::Variable(name) => visitor.visit_identifier(name),
+        InterpolationSegmentKind::Formatted(variable) => visitor.visit_identifier(&variable.name),
+        InterpolationSegmentKind::Component(component) => visitor.visit_component(component),
+        InterpolationSegmentKind::Literal(_)
+        | InterpolationSegmentKind::Message(_)
+        | InterpolationSegmentKind::Newline => {}
+    }
+}
+
+/// Visitor of the IR, that can modify the visited nodes
+pub trait VisitorMut<SpanT: Eq> {
+    fn visit_module_mut(&mut self, module: &mut Module<SpanT>) {
+        walk_module_mut(self, module)
+    }
+
+    fn visit_module_item_mut(&mut self, item: &mut ModuleItem<SpanT>) {
+        walk_module_item_mut(self, item)
+    }
+
+    fn visit_constant_mut(&mut self, constant: &mut Constant<SpanT>) {
+        walk_constant_mut(self, constant)
+    }
+
+    fn visit_component_mut(&mut self, component: &mut Component<SpanT>) {
+        walk_component_mut(self, component)
+    }
+
+    fn visit_properties_mut(&mut self, properties: &mut Properties<SpanT>) {
+        walk_properties_mut(self, properties)
+    }
+
+    fn visit_property_mut(&mut self, property: &mut Property<SpanT>) {
+        walk_property_mut(self, property)
+    }
+
+    fn visit_component_definition_mut(&mut self, definition: &mut ComponentDefinition<SpanT>) {
+        walk_component_definition_mut(self, definition)
+    }
+
+    fn visit_property_definition_mut(&mut self, definition: &mut PropertyDefinition<SpanT>) {
+        walk_property_definition_mut(self, definition)
+    }
+
+    fn visit_value_mut(&mut self, value: &mut Value<SpanT>) {
+        walk_value_mut(self, value)
+    }
+
+    fn visit_text_mut(&mut self, text: &mut Text<SpanT>) {
+        walk_text_mut(self, text)
+    }
+
+    fn visit_interpolation_segment_mut(&mut self, segment: &mut InterpolationSegment<SpanT>) {
+        walk_interpolation_segment_mut(self, segment)
+    }
+
+    fn visit_identifier_mut(&mut self, _identifier: &mut Identifier<SpanT>) {}
+
+    fn visit_type_mut(&mut self, _ty: &mut Type<SpanT>) {}
+}
+
+pub fn walk_module_mut<SpanT: Eq, V: VisitorMut<SpanT> + ?Sized>(
+    visitor: &mut V,
+    module: &mut Module<SpanT>,
+) {
+    for item in &mut module.items {
+        visitor.visit_module_item_mut(item);
+    }
+}
+
+pub fn walk_module_item_mut<SpanT: Eq, V: VisitorMut<SpanT> + ?Sized>(
+    visitor: &mut V,
+    item: &mut ModuleItem<SpanT>,
+) {
+    match item {
+        ModuleItem::Constant(constant) => visitor.visit_constant_mut(constant),
+        ModuleItem::Component(component) => visitor.visit_component_mut(component),
+        ModuleItem::ComponentDefinition(definition) => {
+            visitor.visit_component_definition_mut(definition)
+        }
+    }
+}
+
+pub fn walk_constant_mut<SpanT: Eq, V: VisitorMut<SpanT> + ?Sized>(
+    visitor: &mut V,
+    constant: &mut Constant<SpanT>,
+) {
+    visitor.visit_identifier_mut(&mut constant.name);
+    visitor.visit_value_mut(&mut constant.value);
+}
+
+pub fn walk_component_mut<SpanT: Eq, V: VisitorMut<SpanT> + ?Sized>(
+    visitor: &mut V,
+    component: &mut Component<SpanT>,
+) {
+    visitor.visit_identifier_mut(&mut component.name);
+    visitor.visit_properties_mut(&mut component.properties);
+    for child in &mut component.children {
+        visitor.visit_component_mut(child);
+    }
+    if let Some(text) = &mut component.text {
+        visitor.visit_text_mut(text);
+    }
+}
+
+pub fn walk_properties_mut<SpanT: Eq, V: VisitorMut<SpanT> + ?Sized>(
+    visitor: &mut V,
+    properties: &mut Properties<SpanT>,
+) {
+    if let Some(value) = &mut properties.default {
+        visitor.visit_value_mut(value);
+    }
+    properties.flag_properties = rebuild(&mut properties.flag_properties, |flag| {
+        visitor.visit_identifier_mut(flag)
+    });
+    properties.negated_flags = rebuild(&mut properties.negated_flags, |flag| {
+        visitor.visit_identifier_mut(flag)
+    });
+    properties.named_properties = rebuild(&mut properties.named_properties, |property| {
+        visitor.visit_property_mut(property)
+    });
+}
+
+pub fn walk_property_mut<SpanT: Eq, V: VisitorMut<SpanT> + ?Sized>(
+    visitor: &mut V,
+    property: &mut Property<SpanT>,
+) {
+    visitor.visit_identifier_mut(&mut property.key);
+    visitor.visit_value_mut(&mut property.value);
+}
+
+pub fn walk_component_definition_mut<SpanT: Eq, V: VisitorMut<SpanT> + ?Sized>(
+    visitor: &mut V,
+    definition: &mut ComponentDefinition<SpanT>,
+) {
+    visitor.visit_identifier_mut(&mut definition.name);
+    let properties = &mut definition.properties;
+    if let Some(text) = &mut properties.text_property {
+        visitor.visit_identifier_mut(text);
+    }
+    if let Some(property) = &mut properties.default_property {
+        visitor.visit_property_definition_mut(property);
+    }
+    properties.properties = rebuild(&mut properties.properties, |property| {
+        visitor.visit_property_definition_mut(property)
+    });
+    for child in &mut definition.children {
+        visitor.visit_component_mut(child);
+    }
+}
+
+/// Visits elements of the set, which are keyed by their names, inserting them back
+/// afterwards, since names might change
+fn rebuild<T: Hash + Eq>(set: &mut HashSet<T>, mut visit: impl FnMut(&mut T)) -> HashSet<T> {
+    std::mem::take(set)
+        .into_iter()
+        .map(|mut element| {
+            visit(&mut element);
+            element
+        })
+        .collect()
+}
+
+pub fn walk_property_definition_mut<SpanT: Eq, V: VisitorMut<SpanT> + ?Sized>(
+    visitor: &mut V,
+    definition: &mut PropertyDefinition<SpanT>,
+) {
+    visitor.visit_identifier_mut(&mut definition.name);
+    visitor.visit_type_mut(&mut definition.ty);
+    if let Some(value) = &mut definition.default_value {
+        visitor.visit_value_mut(value);
+    }
+}
+
+pub fn walk_value_mut<SpanT: Eq, V: VisitorMut<SpanT> + ?Sized>(
+    visitor: &mut V,
+    value: &mut Value<SpanT>,
+) {
+    match &mut value.kind {
+        ValueKind::String(string) => {
+            for segment in &mut string.segments {
+                visitor.visit_interpolation_segment_mut(segment);
+            }
+        }
+        ValueKind::Variable(name) => visitor.visit_identifier_mut(name),
+        ValueKind::Integer(_) | ValueKind::Bool(_) => {}
+    }
+}
+
+pub fn walk_text_mut<SpanT: Eq, V: VisitorMut<SpanT> + ?Sized>(
+    visitor: &mut V,
+    text: &mut Text<SpanT>,
+) {
+    for segment in &mut text.segments {
+        visitor.visit_interpolation_segment_mut(segment);
+    }
+}
+
+pub fn walk_interpolation_segment_mut<SpanT: Eq, V: VisitorMut<SpanT> + ?Sized>(
+    visitor: &mut V,
+    segment: &mut InterpolationSegment<SpanT>,
+) {
+    match &mut segment.kind {
+        InterpolationSegmentKind::Variable(name) => visitor.visit_identifier_mut(name),
+        InterpolationSegmentKind::Formatted(variable) => {
+            visitor.visit_identifier_mut(&mut variable.name)
+        }
+        InterpolationSegmentKind::Component(component) => visitor.visit_component_mut(component),
+        InterpolationSegmentKind::Literal(_)
+        | InterpolationSegmentKind::Message(_)
+        | InterpolationSegmentKind::Newline => {}
+    }
+}
+
+/// IR-to-IR rewrite, such as inlining or constant folding.
+/// Unlike the visitors, it can replace or remove components and module items.
+/// Every method folds the children of the node by default with the corresponding `fold_*` function
+pub trait Transform<SpanT: Eq + Clone> {
+    type Error;
+
+    fn transform_module(&mut self, module: &mut Module<SpanT>) -> Result<(), Self::Error> {
+        fold_module(self, module)
+    }
+
+    /// Returns items that replace the given one
+    fn transform_module_item(
+        &mut self,
+        item: ModuleItem<SpanT>,
+    ) -> Result<Vec<ModuleItem<SpanT>>, Self::Error> {
+        Ok(vec![fold_module_item(self, item)?])
+    }
+
+    /// Returns components that replace the given one
+    fn transform_component(
+        &mut self,
+        component: Component<SpanT>,
+    ) -> Result<Vec<Component<SpanT>>, Self::Error> {
+        Ok(vec![fold_component(self, component)?])
+    }
+
+    fn transform_value(&mut self, value: Value<SpanT>) -> Result<Value<SpanT>, Self::Error> {
+        Ok(value)
+    }
+}
+
+pub fn fold_module<SpanT: Eq + Clone, T: Transform<SpanT> + ?Sized>(
+    transform: &mut T,
+    module: &mut Module<SpanT>,
+) -> Result<(), T::Error> {
+    let mut items = Vec::with_capacity(module.items.len());
+    for item in std::mem::take(&mut module.items) {
+        items.extend(transform.transform_module_item(item)?);
+    }
+    module.items = items;
+
+    Ok(())
+}
+
+pub fn fold_module_item<SpanT: Eq + Clone, T: Transform<SpanT> + ?Sized>(
+    transform: &mut T,
+    item: ModuleItem<SpanT>,
+) -> Result<ModuleItem<SpanT>, T::Error> {
+    Ok(match item {
+        ModuleItem::Constant(mut constant) => {
+            constant.value = transform.transform_value(constant.value)?;
+            ModuleItem::Constant(constant)
+        }
+        ModuleItem::Component(component) => {
+            ModuleItem::Component(fold_component(transform, component)?)
+        }
+        ModuleItem::ComponentDefinition(mut definition) => {
+            definition.children = fold_components(transform, definition.children)?;
+            ModuleItem::ComponentDefinition(definition)
+        }
+    })
+}
+
+/// Transforms values of the properties, children and inline components of the component
+pub fn fold_component<SpanT: Eq + Clone, T: Transform<SpanT> + ?Sized>(
+    transform: &mut T,
+    mut component: Component<SpanT>,
+) -> Result<Component<SpanT>, T::Error> {
+    let properties = &mut component.properties;
+    properties.default = properties
+        .default
+        .take()
+        .map(|value| transform.transform_value(value))
+        .transpose()?;
+    properties.named_properties = std::mem::take(&mut properties.named_properties)
+        .into_iter()
+        .map(|mut property| {
+            property.value = transform.transform_value(property.value)?;
+            Ok(property)
+        })
+        .collect::<Result<_, _>>()?;
+    component.children = fold_components(transform, component.children)?;
+    if let Some(text) = &mut component.text {
+        let mut segments = Vec::with_capacity(text.segments.len());
+        for segment in std::mem::take(&mut text.segments) {
+            match segment.kind {
+                InterpolationSegmentKind::Component(inline) => {
+                    for inline in transform.transform_component(*inline)? {
+                        segments.push(InterpolationSegment {
+                            span: segment.span.clone(),
+                            kind: InterpolationSegmentKind::Component(Box::new(inline)),
+                        });
+                    }
+                }
+                _ => segments.push(segment),
+            }
+        }
+        text.segments = segments;
+    }
+
+    Ok(component)
+}
+
+pub fn fold_components<SpanT: Eq + Clone, T: Transform<SpanT> + ?Sized>(
+    transform: &mut T,
+    components: Vec<Component<SpanT>>,
+) -> Result<Vec<Component<SpanT>>, T::Error> {
+    let mut result = Vec::with_capacity(components.len());
+    for component in components {
+        result.extend(transform.transform_component(component)?);
+    }
+
+    Ok(result)
+}
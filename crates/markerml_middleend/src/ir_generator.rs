@@ -5,14 +5,26 @@ use markerml_frontend::parser::Span;
 use std::collections::{HashMap, HashSet};
 
 /// Intermediate Representation generator
+#[derive(Default)]
 pub struct IrGenerator {
     ast: Option<ast::Module<Span>>,
+    /// Names of the already generated constants along with their spans
+    constants: HashMap<String, Span>,
+    /// Names of the already generated component definitions along with their spans
+    definitions: HashMap<String, Span>,
+    /// Names of the identifiers, shared by all their occurrences
+    interner: ir::Interner,
 }
 
 impl IrGenerator {
     /// Creates new instance from the given AST
     pub fn new(ast: ast::Module<Span>) -> Self {
-        IrGenerator { ast: Some(ast) }
+        IrGenerator {
+            ast: Some(ast),
+            constants: HashMap::new(),
+            definitions: HashMap::new(),
+            interner: ir::Interner::default(),
+        }
     }
 
     /// Generates Intermediate Representation from the stored AST
@@ -41,6 +53,9 @@ impl IrGenerator {
         item: ast::ModuleItem<Span>,
     ) -> Result<ir::ModuleItem<Span>, IrGeneratorError> {
         Ok(match item {
+            ast::ModuleItem::Constant(constant) => {
+                ir::ModuleItem::Constant(self.generate_constant(constant)?)
+            }
             ast::ModuleItem::Component(component) => {
                 ir::ModuleItem::Component(self.generate_component(component)?)
             }
@@ -50,7 +65,34 @@ impl IrGenerator {
         })
     }
 
-    fn generate_component(
+    /// Generates Intermediate Representation of the module-level constant.
+    /// Every constant must have unique name
+    pub fn generate_constant(
+        &mut self,
+        constant: ast::Constant<Span>,
+    ) -> Result<ir::Constant<Span>, IrGeneratorError> {
+        let name = self.generate_identifier(constant.name)?;
+        if let Some(span) = self.constants.get(name.as_str()) {
+            return Err(DuplicatedConstantError {
+                name: name.clone().into(),
+                first: span.clone(),
+                second: name.span.clone(),
+            }
+            .into());
+        }
+        self.constants
+            .insert(name.as_str().to_owned(), name.span.clone());
+
+        Ok(ir::Constant {
+            span: constant.span,
+            name,
+            value: self.generate_value(constant.value)?,
+        })
+    }
+
+    /// Generates Intermediate Representation of the single component,
+    /// so that components can be processed one by one without storing the whole module
+    pub fn generate_component(
         &mut self,
         component: ast::Component<Span>,
     ) -> Result<ir::Component<Span>, IrGeneratorError> {
@@ -58,12 +100,13 @@ impl IrGenerator {
         let name = self.generate_identifier(component.name)?;
         let properties = component
             .properties
-            .map(|properties| self.generate_properties(properties))
+            .map(|properties| self.generate_properties(*properties))
             .unwrap_or_else(|| {
                 Ok(ir::Properties {
                     default: None,
                     named_properties: HashSet::new(),
                     flag_properties: HashSet::new(),
+                    negated_flags: HashSet::new(),
                 })
             })?;
 
@@ -105,8 +148,10 @@ impl IrGenerator {
         let mut names: HashMap<String, Span> = HashMap::new();
         let mut named_properties = HashSet::new();
         let mut flag_properties = HashSet::new();
+        let mut negated_flags = HashSet::new();
 
         for property in properties.properties {
+            let is_negated = matches!(property.kind, ast::PropertyKind::NegatedFlag { .. });
             match property.kind {
                 ast::PropertyKind::KeyValue { key, value } => {
                     let key = self.generate_identifier(key)?;
@@ -126,7 +171,7 @@ impl IrGenerator {
                         value: self.generate_value(value)?,
                     });
                 }
-                ast::PropertyKind::Flag { key } => {
+                ast::PropertyKind::Flag { key } | ast::PropertyKind::NegatedFlag { key } => {
                     let key = self.generate_identifier(key)?;
                     if let Some(span) = names.get(key.as_str()) {
                         return Err(DuplicatedPropertyError {
@@ -138,7 +183,11 @@ impl IrGenerator {
                     }
 
                     names.insert(key.as_str().to_owned(), key.span.clone());
-                    flag_properties.insert(key);
+                    if is_negated {
+                        negated_flags.insert(key);
+                    } else {
+                        flag_properties.insert(key);
+                    }
                 }
             }
         }
@@ -147,14 +196,26 @@ impl IrGenerator {
             default,
             named_properties,
             flag_properties,
+            negated_flags,
         })
     }
 
-    fn generate_component_definition(
+    /// Generates Intermediate Representation of the single component definition
+    pub fn generate_component_definition(
         &mut self,
         def: ast::ComponentDefinition<Span>,
     ) -> Result<ir::ComponentDefinition<Span>, IrGeneratorError> {
         let name = self.generate_identifier(def.name.clone())?;
+        if let Some(span) = self.definitions.get(name.as_str()) {
+            return Err(DuplicatedComponentDefinitionError {
+                name: name.clone().into(),
+                first: span.clone(),
+                second: name.span.clone(),
+            }
+            .into());
+        }
+        self.definitions
+            .insert(name.as_str().to_owned(), name.span.clone());
         let children = def
             .children
             .map(|children| self.generate_children(children))
@@ -177,7 +238,7 @@ impl IrGenerator {
             name: self.generate_identifier(def.name.clone())?,
             properties: def
                 .properties
-                .map(|props| self.generate_properties_definition(props))
+                .map(|props| self.generate_properties_definition(*props))
                 .transpose()?
                 .unwrap_or_else(|| ir::PropertiesDefinition {
                     span: def.name.span,
@@ -198,6 +259,24 @@ impl IrGenerator {
         let mut properties = HashSet::new();
         let mut names = HashMap::<String, Span>::new();
 
+        let mut slots = def.properties.iter().filter(|property| {
+            let ty = match &property.kind {
+                ast::PropertyDefinitionKind::Default(def) => &def.ty,
+                ast::PropertyDefinitionKind::Named(def) => &def.ty,
+                ast::PropertyDefinitionKind::Text(_) => return false,
+            };
+
+            matches!(ty.kind, ast::TypeKind::Slot | ast::TypeKind::SlotList)
+        });
+        if let (Some(first), Some(second)) = (slots.next(), slots.next()) {
+            return Err(MultipleSlotPropertiesError {
+                properties: def.span,
+                first: first.span.clone(),
+                second: second.span.clone(),
+            }
+            .into());
+        }
+
         for property in def.properties {
             match property.kind {
                 ast::PropertyDefinitionKind::Default(def) => {
@@ -320,13 +399,18 @@ impl IrGenerator {
 
     fn generate_text(&mut self, text: ast::Text<Span>) -> Result<ir::Text<Span>, IrGeneratorError> {
         let span = text.span;
+        let raw = text.raw;
         let segments = text
             .segments
             .into_iter()
             .map(|segment| self.generate_interpolation_segment(segment))
             .collect::<Result<_, _>>()?;
 
-        Ok(ir::Text { span, segments })
+        Ok(ir::Text {
+            span,
+            segments,
+            raw,
+        })
     }
 
     fn generate_string_value(
@@ -354,6 +438,21 @@ impl IrGenerator {
             ast::InterpolationSegmentKind::Variable(identifier) => {
                 ir::InterpolationSegmentKind::Variable(self.generate_identifier(identifier)?)
             }
+            ast::InterpolationSegmentKind::Formatted(variable) => {
+                ir::InterpolationSegmentKind::Formatted(Box::new(ir::FormattedVariable {
+                    name: self.generate_identifier(variable.name)?,
+                    spec: variable.spec,
+                }))
+            }
+            ast::InterpolationSegmentKind::Message(key) => {
+                ir::InterpolationSegmentKind::Message(key)
+            }
+            ast::InterpolationSegmentKind::Component(component) => {
+                ir::InterpolationSegmentKind::Component(Box::new(
+                    self.generate_component(*component)?,
+                ))
+            }
+            ast::InterpolationSegmentKind::Newline => ir::InterpolationSegmentKind::Newline,
         };
 
         Ok(kind.spanned(segment.span))
@@ -377,7 +476,7 @@ impl IrGenerator {
     ) -> Result<ir::Identifier<Span>, IrGeneratorError> {
         Ok(ir::Identifier {
             span: identifier.span,
-            name: identifier.name,
+            name: self.interner.intern(&identifier.name),
         })
     }
 }
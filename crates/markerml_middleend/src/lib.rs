@@ -4,18 +4,60 @@
 //! For the full grammar overview,
 //! refer to the [`markerml`](https://crates.io/crates/markerml) crate.
 
+pub mod analysis;
+pub mod component_expander;
 pub mod error;
+pub mod format;
 pub mod ir;
 pub mod ir_generator;
+pub mod suggestions;
+pub mod typecheck;
 
 /// IR generator error
 pub use error::IrGeneratorError;
+/// IR analysis warning
+pub use error::IrWarning;
 /// Source code span. Used for error reporting
 pub use markerml_frontend::parser::Span;
+/// Converts byte offsets of the spans into lines and columns
+pub use markerml_frontend::span::SourceMap;
 
 use markerml_frontend::ast;
+use std::collections::HashMap;
 
 /// Generates IR from the given AST
 pub fn generate_ir(ast: ast::Module<Span>) -> Result<ir::Module<Span>, IrGeneratorError> {
     ir_generator::IrGenerator::new(ast).generate()
 }
+
+/// Checks that properties of the custom components in the given IR match their definitions
+pub fn typecheck(ir: &ir::Module<Span>) -> Result<(), IrGeneratorError> {
+    typecheck::TypeChecker::check_module(ir)
+}
+
+/// Replaces instances of custom components in the given IR with their definitions
+pub fn expand_components(ir: ir::Module<Span>) -> Result<ir::Module<Span>, IrGeneratorError> {
+    component_expander::ComponentExpander::new(ir).expand()
+}
+
+/// Replaces instances of custom components in the given IR with their definitions,
+/// making given top-level variables available for interpolation
+pub fn expand_components_with_variables(
+    ir: ir::Module<Span>,
+    variables: HashMap<String, ir::Value<Span>>,
+) -> Result<ir::Module<Span>, IrGeneratorError> {
+    component_expander::ComponentExpander::with_variables(ir, variables).expand()
+}
+
+/// Replaces instances of custom components in the given IR with their definitions,
+/// making given top-level variables available for interpolation
+/// and resolving localized messages from the given catalog
+pub fn expand_components_with_messages(
+    ir: ir::Module<Span>,
+    variables: HashMap<String, ir::Value<Span>>,
+    messages: HashMap<String, String>,
+) -> Result<ir::Module<Span>, IrGeneratorError> {
+    component_expander::ComponentExpander::with_variables(ir, variables)
+        .with_messages(messages)
+        .expand()
+}
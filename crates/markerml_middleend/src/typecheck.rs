@@ -0,0 +1,230 @@
+use crate::error::*;
+use crate::{ir, IrGeneratorError};
+use markerml_frontend::parser::Span;
+use std::collections::HashMap;
+
+/// Checks that properties of the custom component instances match their definitions:
+/// values have declared types, required properties are specified and there are no unknown ones
+pub struct TypeChecker<'a> {
+    definitions: HashMap<&'a str, &'a ir::ComponentDefinition<Span>>,
+}
+
+impl<'a> TypeChecker<'a> {
+    /// Creates new instance from the given definitions
+    pub fn new(definitions: impl IntoIterator<Item = &'a ir::ComponentDefinition<Span>>) -> Self {
+        TypeChecker {
+            definitions: definitions
+                .into_iter()
+                .map(|def| (def.name.as_str(), def))
+                .collect(),
+        }
+    }
+
+    /// Checks definitions and components of the module
+    pub fn check_module(module: &ir::Module<Span>) -> Result<(), IrGeneratorError> {
+        let definitions = module.items.iter().filter_map(|item| match item {
+            ir::ModuleItem::ComponentDefinition(def) => Some(def),
+            ir::ModuleItem::Constant(_) | ir::ModuleItem::Component(_) => None,
+        });
+        let checker = TypeChecker::new(definitions);
+
+        for item in &module.items {
+            match item {
+                ir::ModuleItem::Constant(_) => {}
+                ir::ModuleItem::Component(component) => checker.check_component(component)?,
+                ir::ModuleItem::ComponentDefinition(def) => checker.check_definition(def)?,
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Checks default values of the definition properties and components inside of it.
+    /// Properties of the definition are available as variables to these components
+    pub fn check_definition(
+        &self,
+        def: &ir::ComponentDefinition<Span>,
+    ) -> Result<(), IrGeneratorError> {
+        for property in &def.properties.properties {
+            if let Some(value) = &property.default_value {
+                Self::check_type(&property.ty.kind, value, property, None)?;
+            }
+        }
+        for child in &def.children {
+            self.check_instance(child, Some(def))?;
+        }
+
+        Ok(())
+    }
+
+    /// Checks top-level component, its children and inline components
+    pub fn check_component(&self, component: &ir::Component<Span>) -> Result<(), IrGeneratorError> {
+        self.check_instance(component, None)
+    }
+
+    fn check_instance(
+        &self,
+        component: &ir::Component<Span>,
+        scope: Option<&ir::ComponentDefinition<Span>>,
+    ) -> Result<(), IrGeneratorError> {
+        let inline = component.text.iter().flat_map(|text| text.components());
+        for child in component.children.iter().chain(inline) {
+            self.check_instance(child, scope)?;
+        }
+
+        let Some(def) = self.definitions.get(component.name.as_str()) else {
+            return Ok(());
+        };
+        let properties = &component.properties;
+        let find = |name: &str| {
+            def.properties
+                .properties
+                .iter()
+                .find(|property| property.name.as_str() == name)
+        };
+
+        if let Some(value) = &properties.default {
+            let Some(property) = &def.properties.default_property else {
+                return Err(UnexpectedDefaultPropertyError {
+                    name: def.name.clone().into(),
+                    definition: def.name.span.clone(),
+                    value: value.span.clone(),
+                }
+                .into());
+            };
+            Self::check_type(&property.ty.kind, value, property, scope)?;
+        }
+        for property in &properties.named_properties {
+            let Some(property_def) = find(property.key.as_str()) else {
+                return Err(Self::unknown_property(def, &property.key));
+            };
+            Self::check_type(&property_def.ty.kind, &property.value, property_def, scope)?;
+        }
+        for flag in properties
+            .flag_properties
+            .iter()
+            .chain(&properties.negated_flags)
+        {
+            let Some(property_def) = find(flag.as_str()) else {
+                return Err(Self::unknown_property(def, flag));
+            };
+            if !matches!(property_def.ty.kind, ir::TypeKind::Bool) {
+                return Err(TypeMismatchError {
+                    expected: type_name(&property_def.ty.kind),
+                    found: "flag",
+                    value: flag.span.clone(),
+                    property: property_def.span.clone(),
+                }
+                .into());
+            }
+        }
+
+        for property_def in &def.properties.properties {
+            let name = property_def.name.as_str();
+            let is_default = def
+                .properties
+                .default_property
+                .as_ref()
+                .is_some_and(|default| default.name.as_str() == name);
+            let is_specified = (is_default && properties.default.is_some())
+                || properties
+                    .named_properties
+                    .iter()
+                    .any(|property| property.key.as_str() == name)
+                || properties
+                    .flag_properties
+                    .iter()
+                    .chain(&properties.negated_flags)
+                    .any(|flag| flag.as_str() == name);
+            let is_slot = matches!(
+                property_def.ty.kind,
+                ir::TypeKind::Slot | ir::TypeKind::SlotList
+            );
+
+            if !is_specified && !is_slot && property_def.default_value.is_none() {
+                return Err(MissingPropertyError {
+                    name: name.to_owned(),
+                    component: component.span.clone(),
+                    property: property_def.span.clone(),
+                }
+                .into());
+            }
+        }
+
+        Ok(())
+    }
+
+    fn check_type(
+        expected: &ir::TypeKind,
+        value: &ir::Value<Span>,
+        property: &ir::PropertyDefinition<Span>,
+        scope: Option<&ir::ComponentDefinition<Span>>,
+    ) -> Result<(), IrGeneratorError> {
+        let Some(found) = value_type(value, scope) else {
+            return Ok(());
+        };
+        if found == type_name(expected) {
+            return Ok(());
+        }
+
+        Err(TypeMismatchError {
+            expected: type_name(expected),
+            found,
+            value: value.span.clone(),
+            property: property.span.clone(),
+        }
+        .into())
+    }
+
+    fn unknown_property(
+        def: &ir::ComponentDefinition<Span>,
+        key: &ir::Identifier<Span>,
+    ) -> IrGeneratorError {
+        UnknownPropertyError {
+            name: key.clone().into(),
+            component: def.name.clone().into(),
+            property: key.span.clone(),
+            definition: def.name.span.clone(),
+        }
+        .into()
+    }
+}
+
+/// Returns type of the value. Types of the variables are known
+/// only inside of the definition, whose properties they refer to
+fn value_type(
+    value: &ir::Value<Span>,
+    scope: Option<&ir::ComponentDefinition<Span>>,
+) -> Option<&'static str> {
+    match &value.kind {
+        ir::ValueKind::String(_) => Some("string"),
+        ir::ValueKind::Integer(_) => Some("int"),
+        ir::ValueKind::Bool(_) => Some("bool"),
+        ir::ValueKind::Variable(name) => {
+            let properties = &scope?.properties;
+            if properties
+                .text_property
+                .as_ref()
+                .is_some_and(|text| text.as_str() == name.as_str())
+            {
+                return Some("string");
+            }
+
+            properties
+                .properties
+                .iter()
+                .find(|property| property.name.as_str() == name.as_str())
+                .map(|property| type_name(&property.ty.kind))
+        }
+    }
+}
+
+fn type_name(ty: &ir::TypeKind) -> &'static str {
+    match ty {
+        ir::TypeKind::String => "string",
+        ir::TypeKind::Integer => "int",
+        ir::TypeKind::Bool => "bool",
+        ir::TypeKind::Slot => "slot",
+        ir::TypeKind::SlotList => "slot[]",
+    }
+}
@@ -0,0 +1,759 @@
+#[cfg(test)]
+mod test {
+    use anyhow::Result;
+    use markerml_middleend::ir::*;
+    use markerml_middleend::{IrGeneratorError, Span};
+    use std::collections::HashMap;
+
+    fn expand(code: &str) -> Result<Module<Span>> {
+        let ast = markerml_frontend::parse(code)?;
+        let ir = markerml_middleend::generate_ir(ast)?;
+        let ir = markerml_middleend::expand_components(ir)?;
+
+        Ok(ir)
+    }
+
+    fn components(module: &Module<Span>) -> Vec<&Component<Span>> {
+        module
+            .items
+            .iter()
+            .filter_map(|item| match item {
+                ModuleItem::Component(component) => Some(component),
+                ModuleItem::Constant(_) | ModuleItem::ComponentDefinition(_) => None,
+            })
+            .collect()
+    }
+
+    fn literal(segments: &[InterpolationSegment<Span>]) -> String {
+        segments
+            .iter()
+            .map(|segment| match &segment.kind {
+                InterpolationSegmentKind::Literal(literal) => literal.clone(),
+                InterpolationSegmentKind::Variable(name) => format!("${{{}}}", name.as_str()),
+                InterpolationSegmentKind::Formatted(variable) => {
+                    format!("${{{}:{}}}", variable.name.as_str(), variable.spec)
+                }
+                InterpolationSegmentKind::Message(key) => format!("${{t:{key}}}"),
+                InterpolationSegmentKind::Component(component) => {
+                    let text = component.text.as_ref().map(|text| literal(&text.segments));
+                    format!("${}({})", component.name.as_str(), text.unwrap_or_default())
+                }
+                InterpolationSegmentKind::Newline => " ".to_owned(),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn expand_custom_component() -> Result<()> {
+        let code = r#"
+            component card[default title: string, text body, level: int = 2] {
+                header[level = ${level}](${title})
+                paragraph(${body}!)
+            }
+            card["Title"](Body)
+        "#;
+        let module = expand(code)?;
+        let components = components(&module);
+
+        assert_eq!(components.len(), 2);
+        assert_eq!(components[0].name.as_str(), "header");
+        assert_eq!(
+            literal(&components[0].text.as_ref().unwrap().segments),
+            "Title"
+        );
+        let level = components[0].properties.named_properties.get("level");
+        assert_eq!(level.unwrap().value.kind, ValueKind::Integer(2));
+        assert_eq!(components[1].name.as_str(), "paragraph");
+        assert_eq!(
+            literal(&components[1].text.as_ref().unwrap().segments),
+            "Body!"
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn raw_text() -> Result<()> {
+        let code = r#"
+            component art { paragraph `  /\_/\` }
+            art
+            paragraph(Plain)
+        "#;
+        let module = expand(code)?;
+        let components = components(&module);
+
+        let text = components[0].text.as_ref().unwrap();
+        assert!(text.raw);
+        assert_eq!(literal(&text.segments), "  /\\_/\\");
+        assert!(!components[1].text.as_ref().unwrap().raw);
+
+        Ok(())
+    }
+
+    #[test]
+    fn expand_nested_custom_components() -> Result<()> {
+        let code = r#"
+            component inner[default value: string] {
+                @(${value})
+            }
+            component outer[name: string = "default"] {
+                box {
+                    inner[${name}]
+                }
+            }
+            outer[name = "custom"]
+            outer
+        "#;
+        let module = expand(code)?;
+        let components = components(&module);
+
+        let texts = components
+            .iter()
+            .map(|component| literal(&component.children[0].text.as_ref().unwrap().segments))
+            .collect::<Vec<_>>();
+        assert_eq!(texts, vec!["custom", "default"]);
+
+        Ok(())
+    }
+
+    #[test]
+    fn expand_repeated_instances() -> Result<()> {
+        let code = r#"
+            component item[default value: string, bold: bool = false] {
+                @(${value} ${bold})
+            }
+            item["a"]
+            item["b"]
+            item["a"]
+            item["a", bold]
+        "#;
+        let module = expand(code)?;
+
+        let texts = components(&module)
+            .iter()
+            .map(|component| literal(&component.text.as_ref().unwrap().segments))
+            .collect::<Vec<_>>();
+        assert_eq!(texts, vec!["a false", "b false", "a false", "a true"]);
+
+        Ok(())
+    }
+
+    #[test]
+    fn repeated_instance_spans() -> Result<()> {
+        let code = r#"
+            component item[default value: string] { @(${value}) }
+            item["a"]
+            item["a"]
+        "#;
+        let module = expand(code)?;
+
+        // First match is the definition
+        let instances = code.match_indices("item[").skip(1);
+        for (component, (offset, _)) in components(&module).iter().zip(instances) {
+            let segment = &component.text.as_ref().unwrap().segments[0];
+            assert_eq!(segment.span.start.0 as usize, offset + r#"item[""#.len());
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn inline_components() -> Result<()> {
+        let code = r#"
+            component term[default name: string] { @[bold](${name}) }
+            component note[text body] { paragraph(Note: ${body}) }
+            let site = "example.com"
+            note(See $#["${site}"]($term["the site"]) or $note(nested))
+        "#;
+        let module = expand(code)?;
+        let components = components(&module);
+
+        assert_eq!(components.len(), 1);
+        let text = components[0].text.as_ref().unwrap();
+        assert_eq!(
+            literal(&text.segments),
+            "Note: See $#($@(the site)) or $paragraph(Note: nested)"
+        );
+        let link = text.components().next().unwrap();
+        let url = &link.properties.default.as_ref().unwrap().kind;
+        let ValueKind::String(url) = url else {
+            panic!("URL isn't a string: {url:?}");
+        };
+        assert_eq!(literal(&url.segments), "example.com");
+
+        let ast = markerml_frontend::parse(
+            r#"component term[default name: string] { @(${name}) } paragraph($term[size = 1])"#,
+        )?;
+        let ir = markerml_middleend::generate_ir(ast)?;
+        assert!(matches!(
+            markerml_middleend::typecheck(&ir),
+            Err(IrGeneratorError::UnknownProperty(_))
+        ));
+        assert!(matches!(
+            expand("paragraph($x(${undefined}))"),
+            Err(error) if matches!(
+                error.downcast_ref(),
+                Some(IrGeneratorError::UndefinedVariable(_))
+            )
+        ));
+
+        Ok(())
+    }
+
+    #[test]
+    fn top_level_variables() -> Result<()> {
+        let code = r#"
+            component greeting[text name] {
+                @(${greeting}, ${name})
+            }
+            greeting(${user})
+        "#;
+        let ast = markerml_frontend::parse(code)?;
+        let ir = markerml_middleend::generate_ir(ast)?;
+        let variables = HashMap::from([
+            (
+                "greeting".to_owned(),
+                ValueKind::String(StringValue::from_literal("Hello")).spanned(Span::default()),
+            ),
+            (
+                "user".to_owned(),
+                ValueKind::String(StringValue::from_literal("User")).spanned(Span::default()),
+            ),
+        ]);
+        let module = markerml_middleend::expand_components_with_variables(ir, variables)?;
+        let components = components(&module);
+
+        assert_eq!(
+            literal(&components[0].text.as_ref().unwrap().segments),
+            "Hello, User"
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn formatted_variables() -> Result<()> {
+        let code = r#"
+            component price[default amount: string, count: int = 7, sale: bool = true] {
+                @([${amount:.2}] [${count:04}] [${count:>4}] [${sale:^8}] [${amount:<8}])
+            }
+            price["19.995"]
+            price["-0.5"]
+            @(${missing:.2})
+        "#;
+        let expanded = |code: &str| -> Result<Vec<String>> {
+            let module = expand(code)?;
+            Ok(components(&module)
+                .iter()
+                .map(|component| literal(&component.text.as_ref().unwrap().segments))
+                .collect())
+        };
+
+        let res = [
+            "[20.00] [0007] [   7] [  true  ] [19.995  ]",
+            "[-0.50] [0007] [   7] [  true  ] [-0.5    ]",
+        ];
+        let lines = code.lines().collect::<Vec<_>>();
+        assert_eq!(expanded(&lines[..6].join("\n"))?, res);
+        assert!(matches!(
+            expand(code),
+            Err(error) if matches!(
+                error.downcast_ref(),
+                Some(IrGeneratorError::UndefinedVariable(_))
+            )
+        ));
+        assert_eq!(
+            expanded(r#"let n = 5 let s = "a" @(${n:.1}|${n:<3}|${s:>3}|${n:0})"#)?,
+            ["5.0|5  |  a|5"]
+        );
+
+        for code in [r#"let s = "abc" @(${s:.2})"#, r#"let b = true @(${b:04})"#] {
+            assert!(matches!(
+                expand(code),
+                Err(error) if matches!(
+                    error.downcast_ref(),
+                    Some(IrGeneratorError::InvalidFormat(_))
+                )
+            ));
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn module_constants() -> Result<()> {
+        let code = r#"
+            component greeting[text name] {
+                @(${greeting}, ${name})
+            }
+            greeting(${user})
+            let greeting = "Hello"
+            let user = "${greeting} user"
+        "#;
+        let module = expand(code)?;
+        let components = components(&module);
+
+        assert_eq!(module.items.len(), 2);
+        assert_eq!(
+            literal(&components[0].text.as_ref().unwrap().segments),
+            "Hello, Hello user"
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn duplicated_constant() -> Result<()> {
+        let code = r#"
+            let accent = "red"
+            let accent = "blue"
+        "#;
+        let ast = markerml_frontend::parse(code)?;
+
+        assert!(matches!(
+            markerml_middleend::generate_ir(ast),
+            Err(IrGeneratorError::DuplicatedConstant(_))
+        ));
+
+        Ok(())
+    }
+
+    #[test]
+    fn duplicated_component_definition() -> Result<()> {
+        let code = r#"
+            component card { box }
+            component card { divider }
+        "#;
+        let ast = markerml_frontend::parse(code)?;
+
+        let Err(IrGeneratorError::DuplicatedComponentDefinition(err)) =
+            markerml_middleend::generate_ir(ast)
+        else {
+            panic!("Expected duplicated definition");
+        };
+        assert_eq!(err.name, "card");
+        assert!(err.first.start.offset() < err.second.start.offset());
+
+        Ok(())
+    }
+
+    #[test]
+    fn undefined_variable() -> Result<()> {
+        let code = r#"
+            let accent = "red"
+            component card[text title] {
+                @(${title} ${subtitle})
+            }
+            card(Title)
+        "#;
+
+        match expand(code).unwrap_err().downcast::<IrGeneratorError>()? {
+            IrGeneratorError::UndefinedVariable(error) => {
+                assert_eq!(error.name, "subtitle");
+                assert_eq!(error.available, vec!["accent".to_owned()]);
+            }
+            error => panic!("Unexpected error: {error}"),
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn fill_slots() -> Result<()> {
+        let code = r#"
+            component card[default title: string, content: slot[]] {
+                box { header(${title}) content }
+            }
+            component page[body: slot] {
+                card["Page"] { body @(Footer) }
+            }
+            page { card["Inner"] { @(a) @(b) } }
+            card["Empty"]
+        "#;
+        let module = expand(code)?;
+
+        fn names(component: &Component<Span>) -> String {
+            let children = component.children.iter().map(names).collect::<Vec<_>>();
+            match &component.text {
+                Some(text) => literal(&text.segments),
+                None => format!("{}[{}]", component.name.as_str(), children.join(" ")),
+            }
+        }
+        let components = components(&module);
+        assert_eq!(names(components[0]), "box[Page box[Inner a b] Footer]");
+        assert_eq!(names(components[1]), "box[Empty]");
+
+        Ok(())
+    }
+
+    #[test]
+    fn invalid_slot_children() -> Result<()> {
+        let code = r#"
+            component single[content: slot] { content }
+            component none { @(text) }
+        "#;
+        let expand = |instance: &str| -> Result<_> {
+            let ast = markerml_frontend::parse(&format!("{code}{instance}"))?;
+            let ir = markerml_middleend::generate_ir(ast)?;
+
+            Ok(markerml_middleend::expand_components(ir))
+        };
+
+        assert!(matches!(
+            expand("single { @(a) @(b) }")?,
+            Err(IrGeneratorError::MultipleSlotChildren(_))
+        ));
+        assert!(matches!(
+            expand("none { @(a) }")?,
+            Err(IrGeneratorError::UnexpectedChildren(_))
+        ));
+
+        Ok(())
+    }
+
+    #[test]
+    fn typecheck_properties() -> Result<()> {
+        let code = r#"
+            component card[default title: string, level: int = 2, bold: bool = false] {
+                header[${level}](${title})
+            }
+            component wrapper[size: int] { card["Wrapped", level = ${size}] }
+        "#;
+        let typecheck = |instance: &str| -> Result<_> {
+            let ast = markerml_frontend::parse(&format!("{code}{instance}"))?;
+            let ir = markerml_middleend::generate_ir(ast)?;
+
+            Ok(markerml_middleend::typecheck(&ir))
+        };
+
+        assert!(typecheck(r#"card["Title", bold] wrapper[size = 3]"#)?.is_ok());
+        assert!(typecheck(r#"card["Title", !bold]"#)?.is_ok());
+        assert!(matches!(
+            typecheck(r#"card["Title", !level]"#)?,
+            Err(IrGeneratorError::TypeMismatch(_))
+        ));
+        assert!(matches!(
+            typecheck(r#"card["Title", level = "2"]"#)?,
+            Err(IrGeneratorError::TypeMismatch(_))
+        ));
+        assert!(matches!(
+            typecheck("card[level = 1]")?,
+            Err(IrGeneratorError::MissingProperty(_))
+        ));
+        assert!(matches!(
+            typecheck(r#"card["Title", color = "red"]"#)?,
+            Err(IrGeneratorError::UnknownProperty(_))
+        ));
+        assert!(matches!(
+            typecheck(r#"wrapper["Title", size = 1]"#)?,
+            Err(IrGeneratorError::UnexpectedDefaultProperty(_))
+        ));
+
+        Ok(())
+    }
+
+    #[test]
+    fn recursive_expansion() -> Result<()> {
+        let code = r#"
+            component a { b }
+            component b { a }
+            a
+        "#;
+        let ast = markerml_frontend::parse(code)?;
+        let ir = markerml_middleend::generate_ir(ast)?;
+
+        assert!(matches!(
+            markerml_middleend::expand_components(ir),
+            Err(IrGeneratorError::RecursiveExpansion(_))
+        ));
+
+        Ok(())
+    }
+
+    #[test]
+    fn localized_messages() -> Result<()> {
+        let code = r#"
+            component greeting[name: string] { @(${t:greeting.hello}, ${name}!) }
+            greeting[name="${t:world}"]
+        "#;
+        let ast = markerml_frontend::parse(code)?;
+        let ir = markerml_middleend::generate_ir(ast)?;
+        let messages = HashMap::from([
+            ("greeting.hello".to_owned(), "Hallo".to_owned()),
+            ("world".to_owned(), "Welt".to_owned()),
+        ]);
+        let ir = markerml_middleend::expand_components_with_messages(ir, HashMap::new(), messages)?;
+        let components = components(&ir);
+
+        assert_eq!(
+            literal(&components[0].text.as_ref().unwrap().segments),
+            "Hallo, Welt!"
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn missing_message() -> Result<()> {
+        let ast = markerml_frontend::parse("@(${t:missing})")?;
+        let ir = markerml_middleend::generate_ir(ast)?;
+
+        assert!(matches!(
+            markerml_middleend::expand_components_with_messages(ir, HashMap::new(), HashMap::new()),
+            Err(IrGeneratorError::MissingMessage(_))
+        ));
+
+        Ok(())
+    }
+
+    #[test]
+    fn repeat_children() -> Result<()> {
+        let code = r#"
+            component row[default cells: int] {
+                box { repeat[${cells}, index = "i"] { repeat[2] { @(${i}.${index}) } } }
+            }
+            row[2]
+        "#;
+        let ir = expand(code)?;
+        let components = components(&ir);
+        let cells = components[0]
+            .children
+            .iter()
+            .map(|cell| literal(&cell.text.as_ref().unwrap().segments))
+            .collect::<Vec<_>>();
+
+        assert_eq!(cells, ["0.0", "0.1", "1.0", "1.1"]);
+
+        Ok(())
+    }
+
+    #[test]
+    fn invalid_repeat() -> Result<()> {
+        for code in [r#"repeat["3"] { @(text) }"#, r#"repeat[-1] { @(text) }"#] {
+            assert!(matches!(
+                expand(code).unwrap_err().downcast::<IrGeneratorError>()?,
+                IrGeneratorError::InvalidRepeatCount(_)
+            ));
+        }
+        assert!(matches!(
+            expand(r#"repeat[1, index = "a b"] { @(text) }"#)
+                .unwrap_err()
+                .downcast::<IrGeneratorError>()?,
+            IrGeneratorError::InvalidRepeatIndex(_)
+        ));
+
+        Ok(())
+    }
+
+    #[test]
+    fn repeat_limit() -> Result<()> {
+        for code in [
+            "repeat[100000000] { @(text) }",
+            "repeat[10000] { repeat[10000] { @(text) } }",
+        ] {
+            assert!(matches!(
+                expand(code).unwrap_err().downcast::<IrGeneratorError>()?,
+                IrGeneratorError::RepeatLimit(_)
+            ));
+        }
+        assert_eq!(expand("repeat[10000] { @(text) }")?.items.len(), 10000);
+
+        Ok(())
+    }
+
+    #[test]
+    fn effective_properties() -> Result<()> {
+        let code = r#"
+            component card[default title: string, level: int = 2, width: int = 10] {
+                header[level = ${level}](${title})
+            }
+            card["Title", level = 3, wide]
+        "#;
+        let ast = markerml_frontend::parse(code)?;
+        let module = markerml_middleend::generate_ir(ast)?;
+        let Some(ModuleItem::ComponentDefinition(definition)) = module.items.first() else {
+            panic!("Expected component definition");
+        };
+        let component = components(&module)[0];
+        let properties =
+            EffectiveProperties::from_definition(&definition.properties, &component.properties);
+
+        let value = |name| properties.get(name).map(|value| &value.kind);
+        assert!(matches!(value("title"), Some(ValueKind::String(_))));
+        assert_eq!(value("level"), Some(&ValueKind::Integer(3)));
+        assert_eq!(value("width"), Some(&ValueKind::Integer(10)));
+        assert_eq!(value("body"), None);
+        assert!(properties.has_flag("wide"));
+
+        Ok(())
+    }
+
+    #[test]
+    fn negated_flags() -> Result<()> {
+        let code = r#"
+            component card[shadow: bool = true, wide: bool = false] {
+                paragraph(${shadow} ${wide})
+            }
+            card
+            card[!shadow, wide]
+        "#;
+        let module = expand(code)?;
+        let texts = components(&module)
+            .iter()
+            .map(|component| literal(&component.text.as_ref().unwrap().segments))
+            .collect::<Vec<_>>();
+
+        assert_eq!(texts, vec!["true false", "false true"]);
+
+        let ast = markerml_frontend::parse("box[!vertical, horizontal = true]")?;
+        let module = markerml_middleend::generate_ir(ast)?;
+        let properties = EffectiveProperties::resolve(&components(&module)[0].properties, None, []);
+        assert_eq!(properties.flag("vertical"), Some(false));
+        assert_eq!(properties.flag("horizontal"), Some(true));
+        assert_eq!(properties.flag("ordered"), None);
+
+        Ok(())
+    }
+
+    #[test]
+    fn visitors() -> Result<()> {
+        use markerml_middleend::ir::visit;
+
+        #[derive(Default)]
+        struct Names(Vec<String>);
+
+        impl Visitor<Span> for Names {
+            fn visit_component(&mut self, component: &Component<Span>) {
+                self.0.push(component.name.as_str().to_owned());
+                visit::walk_component(self, component);
+            }
+        }
+
+        struct Rename;
+
+        impl VisitorMut<Span> for Rename {
+            fn visit_identifier_mut(&mut self, identifier: &mut Identifier<Span>) {
+                identifier.name = identifier.name.replace("box", "frame").into();
+            }
+        }
+
+        struct Flatten;
+
+        impl Transform<Span> for Flatten {
+            type Error = ();
+
+            fn transform_component(
+                &mut self,
+                component: Component<Span>,
+            ) -> Result<Vec<Component<Span>>, ()> {
+                let component = visit::fold_component(self, component)?;
+                match component.name.as_str() {
+                    "divider" => Ok(Vec::new()),
+                    "frame" => Ok(component.children),
+                    _ => Ok(vec![component]),
+                }
+            }
+
+            fn transform_value(&mut self, mut value: Value<Span>) -> Result<Value<Span>, ()> {
+                if let ValueKind::Integer(number) = &mut value.kind {
+                    *number *= 2;
+                }
+                Ok(value)
+            }
+        }
+
+        let mut module = expand("root { box { header[2](A) divider box { paragraph(B) } } }")?;
+        let mut names = Names::default();
+        names.visit_module(&module);
+        assert_eq!(
+            names.0,
+            ["root", "box", "header", "divider", "box", "paragraph"]
+        );
+
+        Rename.visit_module_mut(&mut module);
+        Flatten.transform_module(&mut module).unwrap();
+        let mut names = Names::default();
+        names.visit_module(&module);
+        assert_eq!(names.0, ["root", "header", "paragraph"]);
+        let header = &components(&module)[0].children[0];
+        let level = header.properties.default.as_ref().unwrap();
+        assert_eq!(level.kind, ValueKind::Integer(4));
+
+        Ok(())
+    }
+
+    #[test]
+    fn unused_definitions() -> Result<()> {
+        use markerml_middleend::analysis::lint_module;
+        use markerml_middleend::IrWarning;
+
+        let code = r#"
+            component inner { paragraph(Inner) }
+            component outer { box { inner } }
+            component orphan { orphan_child }
+            component orphan_child { divider }
+            outer
+        "#;
+        let ast = markerml_frontend::parse(code)?;
+        let module = markerml_middleend::generate_ir(ast)?;
+        let names = lint_module(&module)
+            .into_iter()
+            .filter_map(|warning| match warning {
+                IrWarning::UnusedDefinition(warning) => Some(warning.name),
+                IrWarning::AmbiguousFlag(_) => None,
+            })
+            .collect::<Vec<_>>();
+
+        assert_eq!(names, ["orphan", "orphan_child"]);
+
+        Ok(())
+    }
+
+    #[test]
+    fn ambiguous_flags() -> Result<()> {
+        use markerml_middleend::analysis::lint_module;
+        use markerml_middleend::IrWarning;
+
+        let code = r#"
+            let wide = true
+            component card[text body, title: string, shadow: bool = true] {
+                box[title, vertical] {
+                    paragraph[!shadow](${body})
+                    @[wide]($code[body](inline))
+                }
+            }
+            card[shadow, title = "Title"](Text)
+        "#;
+        let ast = markerml_frontend::parse(code)?;
+        let module = markerml_middleend::generate_ir(ast)?;
+        let flags = lint_module(&module)
+            .into_iter()
+            .filter_map(|warning| match warning {
+                IrWarning::AmbiguousFlag(warning) => Some((warning.name, warning.definition)),
+                IrWarning::UnusedDefinition(_) => None,
+            })
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            flags,
+            [
+                ("title".to_owned(), "card".to_owned()),
+                ("wide".to_owned(), "card".to_owned()),
+                ("body".to_owned(), "card".to_owned()),
+            ]
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn closest_name() {
+        use markerml_middleend::suggestions::{closest_name, edit_distance};
+
+        assert_eq!(edit_distance("boxx", "box"), 1);
+        assert_eq!(edit_distance("header", "heading"), 3);
+        assert_eq!(closest_name("boxx", ["text", "box", "list"]), Some("box"));
+        assert_eq!(closest_name("imgae", ["image", "link"]), Some("image"));
+        assert_eq!(closest_name("table", ["text", "box"]), None);
+    }
+}